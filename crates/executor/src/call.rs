@@ -1,6 +1,8 @@
+use std::collections::btree_map::Entry;
 use std::sync::Arc;
 
-use blockifier::context::TransactionContext;
+use blockifier::context::{BlockContext, TransactionContext};
+use blockifier::execution::call_info::CallInfo;
 use blockifier::execution::entry_point::{
     CallEntryPoint,
     EntryPointExecutionContext,
@@ -10,16 +12,27 @@ use blockifier::execution::stack_trace::{
     extract_trailing_cairo1_revert_trace,
     Cairo1RevertHeader,
 };
-use blockifier::state::state_api::StateReader;
+use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::{State, StateReader};
 use blockifier::transaction::objects::{DeprecatedTransactionInfo, TransactionInfo};
 use blockifier::versioned_constants::VersionedConstants;
-use pathfinder_common::{felt, CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_common::{
+    felt,
+    CallParam,
+    CallResultValue,
+    ContractAddress,
+    EntryPoint,
+    StorageValue,
+};
 use starknet_api::contract_class::EntryPointType;
 use starknet_api::core::PatriciaKey;
+use starknet_api::transaction::fields::GasVectorComputationMode;
 
 use super::error::CallError;
 use super::execution_state::ExecutionState;
-use super::felt::{IntoFelt, IntoStarkFelt};
+use super::felt::{storage_location, IntoFelt, IntoStarkFelt};
+use super::recording::RecordingStateReader;
+use super::types::{CallTrace, FunctionInvocation, StorageAccess};
 
 pub fn call(
     execution_state: ExecutionState<'_>,
@@ -27,8 +40,133 @@ pub fn call(
     entry_point_selector: EntryPoint,
     calldata: Vec<CallParam>,
 ) -> Result<Vec<CallResultValue>, CallError> {
+    let cancellation = execution_state.cancellation();
     let (mut state, block_context) = execution_state.starknet_state()?;
+    // Setting up the state can take a while, check before executing the call.
+    cancellation.check()?;
+
+    let call_info = execute_call(
+        &mut state,
+        &block_context,
+        contract_address,
+        entry_point_selector,
+        calldata,
+    )?;
+
+    // In Starknet 0.13.4 calls return a failure which is not an error.
+    if call_info.execution.failed {
+        check_entry_point_found(&call_info)?;
+
+        let revert_trace =
+            extract_trailing_cairo1_revert_trace(&call_info, Cairo1RevertHeader::Execution);
+
+        return Err(CallError::ContractError(
+            anyhow::Error::msg(revert_trace.to_string()),
+            revert_trace.into(),
+        ));
+    }
+
+    let result = call_info
+        .execution
+        .retdata
+        .0
+        .iter()
+        .map(|f| CallResultValue(f.into_felt()))
+        .collect();
+
+    Ok(result)
+}
+
+/// Executes a call like [call] but returns the full invocation tree and all
+/// storage slots accessed during execution.
+///
+/// Unlike [call], a call that fails (as opposed to one that cannot be
+/// executed) is not an error, the failure is reported in the trace instead.
+pub fn trace_call(
+    execution_state: ExecutionState<'_>,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+) -> Result<CallTrace, CallError> {
+    let cancellation = execution_state.cancellation();
+    let (state, block_context) = execution_state.starknet_state()?;
+    cancellation.check()?;
+
+    // Reads are recorded below a separate cache so that we get the values as they
+    // were before the call was executed.
+    let recording_state = RecordingStateReader::new(&state);
+    let mut call_state = CachedState::new(recording_state);
 
+    let call_info = execute_call(
+        &mut call_state,
+        &block_context,
+        contract_address,
+        entry_point_selector,
+        calldata,
+    )?;
+
+    let revert_reason = if call_info.execution.failed {
+        check_entry_point_found(&call_info)?;
+
+        Some(
+            extract_trailing_cairo1_revert_trace(&call_info, Cairo1RevertHeader::Execution)
+                .to_string(),
+        )
+    } else {
+        None
+    };
+
+    let storage_writes = call_state.to_state_diff()?.state_maps.storage;
+    let mut storage_accesses = call_state.state.storage_reads();
+    for (address, key) in storage_writes.keys() {
+        if let Entry::Vacant(entry) = storage_accesses.entry(storage_location(address, key)?) {
+            entry.insert(StorageValue(
+                state.get_storage_at(*address, *key)?.into_felt(),
+            ));
+        }
+    }
+
+    let storage_accesses = storage_accesses
+        .into_iter()
+        .map(|((contract_address, key), pre_value)| {
+            let post_value = call_state.get_storage_at(
+                starknet_api::core::ContractAddress(PatriciaKey::try_from(
+                    contract_address.0.into_starkfelt(),
+                )?),
+                starknet_api::state::StorageKey(PatriciaKey::try_from(key.0.into_starkfelt())?),
+            )?;
+
+            Ok(StorageAccess {
+                contract_address,
+                key,
+                pre_value,
+                post_value: StorageValue(post_value.into_felt()),
+            })
+        })
+        .collect::<Result<Vec<_>, CallError>>()?;
+
+    // Calls are not transactions, there is no fee to compute, so we report all
+    // gas kinds as they are.
+    let invocation = FunctionInvocation::from_call_info(
+        call_info,
+        block_context.versioned_constants(),
+        &GasVectorComputationMode::All,
+    );
+
+    Ok(CallTrace {
+        invocation,
+        revert_reason,
+        storage_accesses,
+    })
+}
+
+fn execute_call(
+    state: &mut dyn State,
+    block_context: &BlockContext,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+) -> Result<CallInfo, CallError> {
     let starknet_api_contract_address = starknet_api::core::ContractAddress(PatriciaKey::try_from(
         contract_address.0.into_starkfelt(),
     )?);
@@ -58,7 +196,7 @@ pub fn call(
 
     let mut context = EntryPointExecutionContext::new_invoke(
         Arc::new(TransactionContext {
-            block_context,
+            block_context: block_context.clone(),
             tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
         }),
         false,
@@ -66,8 +204,8 @@ pub fn call(
     );
 
     let mut remaining_gas = call_entry_point.initial_gas;
-    let call_info = call_entry_point
-        .execute(&mut state, &mut context, &mut remaining_gas)
+    call_entry_point
+        .execute(state, &mut context, &mut remaining_gas)
         .map_err(|e| {
             CallError::from_entry_point_execution_error(
                 e,
@@ -75,38 +213,19 @@ pub fn call(
                 &class_hash,
                 &starknet_api_entry_point_selector,
             )
-        })?;
+        })
+}
 
-    // In Starknet 0.13.4 calls return a failure which is not an error.
-    if call_info.execution.failed {
-        match call_info.execution.retdata.0.as_slice() {
-            [error_code]
-                if error_code.into_felt()
-                    == felt!(
-                        blockifier::execution::syscalls::hint_processor::ENTRYPOINT_NOT_FOUND_ERROR
-                    ) =>
-            {
-                return Err(CallError::InvalidMessageSelector);
-            }
-            _ => {
-                let revert_trace =
-                    extract_trailing_cairo1_revert_trace(&call_info, Cairo1RevertHeader::Execution);
-
-                return Err(CallError::ContractError(
-                    anyhow::Error::msg(revert_trace.to_string()),
-                    revert_trace.into(),
-                ));
-            }
+fn check_entry_point_found(call_info: &CallInfo) -> Result<(), CallError> {
+    match call_info.execution.retdata.0.as_slice() {
+        [error_code]
+            if error_code.into_felt()
+                == felt!(
+                    blockifier::execution::syscalls::hint_processor::ENTRYPOINT_NOT_FOUND_ERROR
+                ) =>
+        {
+            Err(CallError::InvalidMessageSelector)
         }
+        _ => Ok(()),
     }
-
-    let result = call_info
-        .execution
-        .retdata
-        .0
-        .iter()
-        .map(|f| CallResultValue(f.into_felt()))
-        .collect();
-
-    Ok(result)
 }
@@ -22,6 +22,7 @@ pub fn estimate(
     epsilon: Percentage,
 ) -> Result<Vec<FeeEstimate>, TransactionExecutionError> {
     let block_number = execution_state.header.number;
+    let cancellation = execution_state.cancellation();
 
     let (mut state, block_context) = execution_state.starknet_state()?;
 
@@ -29,6 +30,8 @@ pub fn estimate(
         .into_iter()
         .enumerate()
         .map(|(tx_index, mut tx)| {
+            cancellation.check()?;
+
             let _span = tracing::debug_span!(
                 "estimate",
                 block_number = %block_number,
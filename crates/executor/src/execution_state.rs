@@ -2,19 +2,22 @@ use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use anyhow::Context;
 use blockifier::blockifier::block::pre_process_block;
 use blockifier::bouncer::BouncerConfig;
 use blockifier::context::{BlockContext, ChainInfo};
 use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::State;
 use blockifier::versioned_constants::VersionedConstants;
 use pathfinder_common::prelude::*;
 use pathfinder_common::L1DataAvailabilityMode;
 use starknet_api::block::{BlockHashAndNumber, BlockInfo, GasPrice, NonzeroGasPrice};
 use starknet_api::core::PatriciaKey;
+use util::task::CancellationToken;
 
+use super::overrides::OverrideStateReader;
 use super::pending::PendingStateReader;
 use super::state_reader::PathfinderStateReader;
+use super::types::StateOverrides;
 use crate::state_reader::NativeClassCache;
 use crate::IntoStarkFelt;
 
@@ -204,42 +207,87 @@ pub struct ExecutionState<'tx> {
     eth_fee_address: ContractAddress,
     strk_fee_address: ContractAddress,
     native_class_cache: Option<NativeClassCache>,
+    state_overrides: StateOverrides,
+    cancellation: CancellationToken,
 }
 
 impl<'tx> ExecutionState<'tx> {
+    pub(super) fn storage_transaction(&self) -> &'tx pathfinder_storage::Transaction<'tx> {
+        self.transaction
+    }
+
+    pub(super) fn cancellation(&self) -> Cancellation {
+        Cancellation(self.cancellation.clone())
+    }
+
     pub(super) fn starknet_state(
-        self,
+        mut self,
     ) -> anyhow::Result<(
-        CachedState<PendingStateReader<PathfinderStateReader<'tx>>>,
+        CachedState<OverrideStateReader<PendingStateReader<PathfinderStateReader<'tx>>>>,
         BlockContext,
     )> {
+        let mut cached_state = self.cached_state();
+        let block_context = self.block_context(&mut cached_state, &self.header)?;
+
+        Ok((cached_state, block_context))
+    }
+
+    /// Returns the state execution happens on, without any of the system
+    /// contract updates performed at the start of a block.
+    pub(super) fn cached_state(
+        &mut self,
+    ) -> CachedState<OverrideStateReader<PendingStateReader<PathfinderStateReader<'tx>>>> {
         let block_number = if self.execute_on_parent_state {
             self.header.number.parent()
         } else {
             Some(self.header.number)
         };
 
-        let chain_info = self.chain_info()?;
-        let block_info = self.block_info()?;
-
         let raw_reader = PathfinderStateReader::new(
             self.transaction,
             block_number,
             self.pending_state.is_some(),
-            self.native_class_cache,
+            self.native_class_cache.take(),
         );
         let pending_state_reader = PendingStateReader::new(raw_reader, self.pending_state.clone());
-        let mut cached_state = CachedState::new(pending_state_reader);
+        let override_state_reader = OverrideStateReader::new(
+            pending_state_reader,
+            std::mem::take(&mut self.state_overrides),
+            self.eth_fee_address,
+            self.strk_fee_address,
+        );
+        CachedState::new(override_state_reader)
+    }
+
+    /// Performs the system contract updates at the start of the block described
+    /// by `header` and returns the context to execute its transactions in.
+    ///
+    /// `header` is either this state's header or the header of a virtual block
+    /// on top of it.
+    pub(super) fn block_context(
+        &self,
+        state: &mut dyn State,
+        header: &BlockHeader,
+    ) -> anyhow::Result<BlockContext> {
+        let chain_info = self.chain_info()?;
+        let block_info = self.block_info(header)?;
 
         // Perform system contract updates if we are executing ontop of a parent block.
         // Currently this is only the block hash from 10 blocks ago.
-        let old_block_number_and_hash = if self.header.number.get() >= 10 {
+        let old_block_number_and_hash = if header.number.get() >= 10 {
             let block_number_whose_hash_becomes_available =
-                pathfinder_common::BlockNumber::new_or_panic(self.header.number.get() - 10);
-            let block_hash = self
+                pathfinder_common::BlockNumber::new_or_panic(header.number.get() - 10);
+            let block_hash = match self
                 .transaction
                 .block_hash(block_number_whose_hash_becomes_available.into())?
-                .context("Getting historical block hash")?;
+            {
+                Some(block_hash) => block_hash,
+                // Virtual blocks don't have a hash.
+                None if block_number_whose_hash_becomes_available > self.header.number => {
+                    BlockHash::ZERO
+                }
+                None => anyhow::bail!("Historical block hash missing"),
+            };
 
             tracing::trace!(%block_number_whose_hash_becomes_available, %block_hash, "Setting historical block hash");
 
@@ -255,23 +303,21 @@ impl<'tx> ExecutionState<'tx> {
 
         let versioned_constants = self
             .versioned_constants_map
-            .for_version(&self.header.starknet_version);
+            .for_version(&header.starknet_version);
 
         pre_process_block(
-            &mut cached_state,
+            state,
             old_block_number_and_hash,
             block_info.block_number,
             &versioned_constants.os_constants,
         )?;
 
-        let block_context = BlockContext::new(
+        Ok(BlockContext::new(
             block_info,
             chain_info,
             versioned_constants.into_owned(),
             BouncerConfig::max(),
-        );
-
-        Ok((cached_state, block_context))
+        ))
     }
 
     fn chain_info(&self) -> anyhow::Result<ChainInfo> {
@@ -308,61 +354,59 @@ impl<'tx> ExecutionState<'tx> {
         })
     }
 
-    fn block_info(&self) -> anyhow::Result<BlockInfo> {
-        let eth_l1_gas_price =
-            NonzeroGasPrice::new(GasPrice(if self.header.eth_l1_gas_price.0 == 0 {
-                // Bad API design - the genesis block has 0 gas price, but
-                // blockifier doesn't allow for it. This isn't critical for
-                // consensus, so we just use 1.
-                1
-            } else {
-                self.header.eth_l1_gas_price.0
-            }))?;
+    fn block_info(&self, header: &BlockHeader) -> anyhow::Result<BlockInfo> {
+        let eth_l1_gas_price = NonzeroGasPrice::new(GasPrice(if header.eth_l1_gas_price.0 == 0 {
+            // Bad API design - the genesis block has 0 gas price, but
+            // blockifier doesn't allow for it. This isn't critical for
+            // consensus, so we just use 1.
+            1
+        } else {
+            header.eth_l1_gas_price.0
+        }))?;
         let strk_l1_gas_price =
-            NonzeroGasPrice::new(GasPrice(if self.header.strk_l1_gas_price.0 == 0 {
+            NonzeroGasPrice::new(GasPrice(if header.strk_l1_gas_price.0 == 0 {
                 // Bad API design - the genesis block has 0 gas price, but
                 // blockifier doesn't allow for it. This isn't critical for
                 // consensus, so we just use 1.
                 1
             } else {
-                self.header.strk_l1_gas_price.0
+                header.strk_l1_gas_price.0
             }))?;
         let eth_l1_data_gas_price =
-            NonzeroGasPrice::new(GasPrice(if self.header.eth_l1_data_gas_price.0 == 0 {
+            NonzeroGasPrice::new(GasPrice(if header.eth_l1_data_gas_price.0 == 0 {
                 // Bad API design - pre-v0.13.1 blocks have 0 data gas price, but
                 // blockifier doesn't allow for it. This value is ignored for those
                 // transactions.
                 1
             } else {
-                self.header.eth_l1_data_gas_price.0
+                header.eth_l1_data_gas_price.0
             }))?;
         let strk_l1_data_gas_price =
-            NonzeroGasPrice::new(GasPrice(if self.header.strk_l1_data_gas_price.0 == 0 {
+            NonzeroGasPrice::new(GasPrice(if header.strk_l1_data_gas_price.0 == 0 {
                 // Bad API design - pre-v0.13.1 blocks have 0 data gas price, but
                 // blockifier doesn't allow for it. This value is ignored for those
                 // transactions.
                 1
             } else {
-                self.header.strk_l1_data_gas_price.0
-            }))?;
-        let eth_l2_gas_price =
-            NonzeroGasPrice::new(GasPrice(if self.header.eth_l2_gas_price.0 == 0 {
-                1
-            } else {
-                self.header.eth_l2_gas_price.0
+                header.strk_l1_data_gas_price.0
             }))?;
+        let eth_l2_gas_price = NonzeroGasPrice::new(GasPrice(if header.eth_l2_gas_price.0 == 0 {
+            1
+        } else {
+            header.eth_l2_gas_price.0
+        }))?;
         let strk_l2_gas_price =
-            NonzeroGasPrice::new(GasPrice(if self.header.strk_l2_gas_price.0 == 0 {
+            NonzeroGasPrice::new(GasPrice(if header.strk_l2_gas_price.0 == 0 {
                 1
             } else {
-                self.header.strk_l2_gas_price.0
+                header.strk_l2_gas_price.0
             }))?;
 
         Ok(BlockInfo {
-            block_number: starknet_api::block::BlockNumber(self.header.number.get()),
-            block_timestamp: starknet_api::block::BlockTimestamp(self.header.timestamp.get()),
+            block_number: starknet_api::block::BlockNumber(header.number.get()),
+            block_timestamp: starknet_api::block::BlockTimestamp(header.timestamp.get()),
             sequencer_address: starknet_api::core::ContractAddress(
-                PatriciaKey::try_from(self.header.sequencer_address.0.into_starkfelt())
+                PatriciaKey::try_from(header.sequencer_address.0.into_starkfelt())
                     .expect("Sequencer address overflow"),
             ),
             gas_prices: starknet_api::block::GasPrices {
@@ -378,7 +422,7 @@ impl<'tx> ExecutionState<'tx> {
                 },
             },
             use_kzg_da: self.allow_use_kzg_data
-                && self.header.l1_da_mode == L1DataAvailabilityMode::Blob,
+                && header.l1_da_mode == L1DataAvailabilityMode::Blob,
         })
     }
 
@@ -404,6 +448,8 @@ impl<'tx> ExecutionState<'tx> {
             eth_fee_address,
             strk_fee_address,
             native_class_cache,
+            state_overrides: Default::default(),
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -430,6 +476,40 @@ impl<'tx> ExecutionState<'tx> {
             eth_fee_address,
             strk_fee_address,
             native_class_cache,
+            state_overrides: Default::default(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Executes on top of the state with `state_overrides` applied.
+    pub fn with_state_overrides(self, state_overrides: StateOverrides) -> Self {
+        Self {
+            state_overrides,
+            ..self
+        }
+    }
+
+    /// Stops executing transactions or calls once `cancellation` is cancelled.
+    /// A transaction or call which has already started is executed to
+    /// completion.
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation,
+            ..self
+        }
+    }
+}
+
+/// Checked between transactions to stop execution that nobody is waiting for
+/// anymore.
+pub(super) struct Cancellation(CancellationToken);
+
+impl Cancellation {
+    pub(super) fn check(&self) -> anyhow::Result<()> {
+        if self.0.is_cancelled() {
+            Err(anyhow::anyhow!("Execution cancelled"))
+        } else {
+            Ok(())
         }
     }
 }
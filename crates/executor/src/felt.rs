@@ -1,4 +1,7 @@
+use pathfinder_common::{ContractAddress, StorageAddress};
 use pathfinder_crypto::Felt;
+use starknet_api::state::StorageKey;
+use starknet_api::StarknetApiError;
 use starknet_types_core::felt::Felt as CoreFelt;
 
 pub trait IntoFelt {
@@ -35,3 +38,27 @@ impl IntoStarkFelt for pathfinder_common::transaction::DataAvailabilityMode {
         }
     }
 }
+
+/// Converts a contract address used by blockifier, which may be out of range
+/// for [ContractAddress].
+pub fn contract_address(
+    address: &starknet_api::core::ContractAddress,
+) -> Result<ContractAddress, StarknetApiError> {
+    ContractAddress::new(address.0.key().into_felt()).ok_or_else(|| StarknetApiError::OutOfRange {
+        string: "Contract address out of range".to_owned(),
+    })
+}
+
+/// Converts a storage slot used by blockifier, see [contract_address].
+pub fn storage_location(
+    address: &starknet_api::core::ContractAddress,
+    key: &StorageKey,
+) -> Result<(ContractAddress, StorageAddress), StarknetApiError> {
+    let key = StorageAddress::new(key.0.key().into_felt()).ok_or_else(|| {
+        StarknetApiError::OutOfRange {
+            string: "Storage key out of range".to_owned(),
+        }
+    })?;
+
+    Ok((contract_address(address)?, key))
+}
@@ -6,7 +6,9 @@ pub(crate) mod estimate;
 pub(crate) mod execution_state;
 pub(crate) mod felt;
 pub(crate) mod lru_cache;
+pub(crate) mod overrides;
 pub(crate) mod pending;
+pub(crate) mod recording;
 pub(crate) mod simulate;
 pub(crate) mod state_reader;
 pub(crate) mod transaction;
@@ -19,13 +21,13 @@ pub use blockifier::transaction::account_transaction::{
 };
 pub use blockifier::transaction::transaction_execution::Transaction;
 pub use blockifier::versioned_constants::VersionedConstants;
-pub use call::call;
+pub use call::{call, trace_call};
 pub use class::{parse_casm_definition, parse_deprecated_class_definition};
 pub use error::{CallError, TransactionExecutionError};
 pub use error_stack::{CallFrame, ErrorStack, Frame};
 pub use estimate::estimate;
 pub use execution_state::{ExecutionState, L1BlobDataAvailability, VersionedConstantsMap};
 pub use felt::{IntoFelt, IntoStarkFelt};
-pub use simulate::{simulate, trace, TraceCache};
+pub use simulate::{simulate, simulate_blocks, trace, TraceCache, STORED_TRACES_VERSION};
 pub use starknet_api::contract_class::ClassInfo;
 pub use state_reader::NativeClassCache;
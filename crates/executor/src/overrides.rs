@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use blockifier::execution::contract_class::RunnableCompiledClass;
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::StateReader;
+use pathfinder_common::{ClassHash, ContractAddress, ContractNonce, StorageAddress, StorageValue};
+use pathfinder_crypto::Felt;
+use starknet_api::state::StorageKey;
+
+use super::felt::{self, storage_location, IntoStarkFelt};
+use super::types::StateOverrides;
+
+/// A state reader which returns overridden values instead of the ones in the
+/// underlying state.
+///
+/// Overrides are not writes, so they do not show up in state diffs.
+pub(super) struct OverrideStateReader<S: StateReader> {
+    state: S,
+    storage: BTreeMap<(ContractAddress, StorageAddress), StorageValue>,
+    nonces: BTreeMap<ContractAddress, ContractNonce>,
+    class_hashes: BTreeMap<ContractAddress, ClassHash>,
+}
+
+impl<S: StateReader> OverrideStateReader<S> {
+    /// Balance overrides are resolved into storage overrides of the fee token
+    /// contracts.
+    pub(super) fn new(
+        state: S,
+        overrides: StateOverrides,
+        eth_fee_address: ContractAddress,
+        strk_fee_address: ContractAddress,
+    ) -> Self {
+        let mut storage = BTreeMap::new();
+        let mut nonces = BTreeMap::new();
+        let mut class_hashes = BTreeMap::new();
+
+        for (contract_address, contract_override) in overrides {
+            for (key, value) in contract_override.storage {
+                storage.insert((contract_address, key), value);
+            }
+            if let Some(nonce) = contract_override.nonce {
+                nonces.insert(contract_address, nonce);
+            }
+            if let Some(class_hash) = contract_override.class_hash {
+                class_hashes.insert(contract_address, class_hash);
+            }
+
+            let balances = [
+                (eth_fee_address, contract_override.eth_balance),
+                (strk_fee_address, contract_override.strk_balance),
+            ];
+            for (fee_token_address, balance) in balances {
+                let Some(balance) = balance else {
+                    continue;
+                };
+
+                // Fee token balances are u256 values stored as two felts, low
+                // word first.
+                let low_key =
+                    StorageAddress::from_map_name_and_key(b"ERC20_balances", contract_address.0);
+                let high_key = next_storage_key(low_key);
+
+                storage.insert(
+                    (fee_token_address, low_key),
+                    StorageValue(Felt::from_u128(balance.low_u128())),
+                );
+                storage.insert(
+                    (fee_token_address, high_key),
+                    StorageValue(Felt::from_u128((balance >> 128).low_u128())),
+                );
+            }
+        }
+
+        Self {
+            state,
+            storage,
+            nonces,
+            class_hashes,
+        }
+    }
+}
+
+fn next_storage_key(key: StorageAddress) -> StorageAddress {
+    let key = primitive_types::U256::from_big_endian(key.0.as_be_bytes()) + 1;
+    let mut bytes = [0u8; 32];
+    key.to_big_endian(&mut bytes);
+    // Map keys are reduced modulo a bound well below the maximum storage address,
+    // so this cannot overflow.
+    StorageAddress::new_or_panic(Felt::from_be_bytes(bytes).expect("Storage key should fit"))
+}
+
+impl<S: StateReader> StateReader for OverrideStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+        key: StorageKey,
+    ) -> blockifier::state::state_api::StateResult<starknet_types_core::felt::Felt> {
+        if !self.storage.is_empty() {
+            let location =
+                storage_location(&contract_address, &key).map_err(StateError::StarknetApiError)?;
+            if let Some(value) = self.storage.get(&location) {
+                return Ok(value.0.into_starkfelt());
+            }
+        }
+
+        self.state.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::Nonce> {
+        let pathfinder_contract_address =
+            felt::contract_address(&contract_address).map_err(StateError::StarknetApiError)?;
+
+        match self.nonces.get(&pathfinder_contract_address) {
+            Some(nonce) => Ok(starknet_api::core::Nonce(nonce.0.into_starkfelt())),
+            None => self.state.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::ClassHash> {
+        let pathfinder_contract_address =
+            felt::contract_address(&contract_address).map_err(StateError::StarknetApiError)?;
+
+        match self.class_hashes.get(&pathfinder_contract_address) {
+            Some(class_hash) => Ok(starknet_api::core::ClassHash(class_hash.0.into_starkfelt())),
+            None => self.state.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_compiled_class(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> blockifier::state::state_api::StateResult<RunnableCompiledClass> {
+        self.state.get_compiled_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::CompiledClassHash> {
+        self.state.get_compiled_class_hash(class_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use starknet_types_core::felt::Felt as CoreFelt;
+
+    use super::*;
+    use crate::types::ContractStateOverride;
+
+    struct DummyStateReader {}
+
+    impl StateReader for DummyStateReader {
+        fn get_storage_at(
+            &self,
+            _contract_address: starknet_api::core::ContractAddress,
+            _key: StorageKey,
+        ) -> blockifier::state::state_api::StateResult<CoreFelt> {
+            Ok(CoreFelt::from(1u8))
+        }
+
+        fn get_nonce_at(
+            &self,
+            _contract_address: starknet_api::core::ContractAddress,
+        ) -> blockifier::state::state_api::StateResult<starknet_api::core::Nonce> {
+            Ok(starknet_api::core::Nonce(CoreFelt::from(2u8)))
+        }
+
+        fn get_class_hash_at(
+            &self,
+            _contract_address: starknet_api::core::ContractAddress,
+        ) -> blockifier::state::state_api::StateResult<starknet_api::core::ClassHash> {
+            Ok(starknet_api::core::ClassHash(CoreFelt::from(3u8)))
+        }
+
+        fn get_compiled_class(
+            &self,
+            _class_hash: starknet_api::core::ClassHash,
+        ) -> blockifier::state::state_api::StateResult<RunnableCompiledClass> {
+            unimplemented!()
+        }
+
+        fn get_compiled_class_hash(
+            &self,
+            _class_hash: starknet_api::core::ClassHash,
+        ) -> blockifier::state::state_api::StateResult<starknet_api::core::CompiledClassHash>
+        {
+            unimplemented!()
+        }
+    }
+
+    fn api_address(address: ContractAddress) -> starknet_api::core::ContractAddress {
+        starknet_api::core::ContractAddress(address.0.into_starkfelt().try_into().unwrap())
+    }
+
+    fn api_key(key: StorageAddress) -> StorageKey {
+        StorageKey(key.0.into_starkfelt().try_into().unwrap())
+    }
+
+    #[test]
+    fn overridden_values_are_returned() {
+        let eth = contract_address!("0xe");
+        let strk = contract_address!("0x5");
+        let account = contract_address!("0xa");
+        let other = contract_address!("0xb");
+
+        let overrides = StateOverrides::from([(
+            account,
+            ContractStateOverride {
+                storage: [(storage_address!("0x10"), storage_value!("0x11"))].into(),
+                nonce: Some(contract_nonce!("0x12")),
+                class_hash: Some(class_hash!("0x13")),
+                eth_balance: None,
+                strk_balance: Some((primitive_types::U256::from(2) << 128) + 7),
+            },
+        )]);
+        let reader = OverrideStateReader::new(DummyStateReader {}, overrides, eth, strk);
+
+        assert_eq!(
+            reader
+                .get_storage_at(api_address(account), api_key(storage_address!("0x10")))
+                .unwrap(),
+            CoreFelt::from(17u8)
+        );
+        assert_eq!(
+            reader.get_nonce_at(api_address(account)).unwrap().0,
+            CoreFelt::from(18u8)
+        );
+        assert_eq!(
+            reader.get_class_hash_at(api_address(account)).unwrap().0,
+            CoreFelt::from(19u8)
+        );
+
+        // Balance shortcuts end up in the fee token's storage.
+        let balance_key = StorageAddress::from_map_name_and_key(b"ERC20_balances", account.0);
+        assert_eq!(
+            reader
+                .get_storage_at(api_address(strk), api_key(balance_key))
+                .unwrap(),
+            CoreFelt::from(7u8)
+        );
+        assert_eq!(
+            reader
+                .get_storage_at(api_address(strk), api_key(next_storage_key(balance_key)))
+                .unwrap(),
+            CoreFelt::from(2u8)
+        );
+
+        // Everything else comes from the underlying state.
+        assert_eq!(
+            reader.get_nonce_at(api_address(other)).unwrap().0,
+            CoreFelt::from(2u8)
+        );
+        assert_eq!(
+            reader
+                .get_storage_at(api_address(eth), api_key(balance_key))
+                .unwrap(),
+            CoreFelt::from(1u8)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use blockifier::execution::contract_class::RunnableCompiledClass;
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::StateReader;
+use pathfinder_common::{ContractAddress, StorageAddress, StorageValue};
+use starknet_api::state::StorageKey;
+
+use super::felt::{storage_location, IntoFelt};
+
+/// A state reader which records the storage slots read from the underlying
+/// state.
+///
+/// Used below a [CachedState](blockifier::state::cached_state::CachedState),
+/// each slot is read at most once, so the recorded value is the value of the
+/// slot before execution.
+pub(super) struct RecordingStateReader<'a, S: StateReader> {
+    state: &'a S,
+    storage_reads: RefCell<BTreeMap<(ContractAddress, StorageAddress), StorageValue>>,
+}
+
+impl<'a, S: StateReader> RecordingStateReader<'a, S> {
+    pub(super) fn new(state: &'a S) -> Self {
+        Self {
+            state,
+            storage_reads: Default::default(),
+        }
+    }
+
+    pub(super) fn storage_reads(
+        &self,
+    ) -> BTreeMap<(ContractAddress, StorageAddress), StorageValue> {
+        self.storage_reads.borrow().clone()
+    }
+}
+
+impl<S: StateReader> StateReader for RecordingStateReader<'_, S> {
+    fn get_storage_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+        key: StorageKey,
+    ) -> blockifier::state::state_api::StateResult<starknet_types_core::felt::Felt> {
+        let value = self.state.get_storage_at(contract_address, key)?;
+
+        let location =
+            storage_location(&contract_address, &key).map_err(StateError::StarknetApiError)?;
+        self.storage_reads
+            .borrow_mut()
+            .entry(location)
+            .or_insert(StorageValue(value.into_felt()));
+
+        Ok(value)
+    }
+
+    fn get_nonce_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::Nonce> {
+        self.state.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::ClassHash> {
+        self.state.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_class(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> blockifier::state::state_api::StateResult<RunnableCompiledClass> {
+        self.state.get_compiled_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::CompiledClassHash> {
+        self.state.get_compiled_class_hash(class_hash)
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
+use blockifier::context::BlockContext;
 use blockifier::state::cached_state::CachedState;
 use blockifier::state::errors::StateError;
 use blockifier::transaction::transaction_execution::Transaction;
@@ -10,11 +11,19 @@ use blockifier::versioned_constants::VersionedConstants;
 use cached::{Cached, SizedCache};
 use pathfinder_common::prelude::*;
 use starknet_api::transaction::fields::GasVectorComputationMode;
+use tokio::sync::broadcast::error::RecvError;
 use util::percentage::Percentage;
 
 use super::error::TransactionExecutionError;
-use super::execution_state::ExecutionState;
-use super::types::{FeeEstimate, TransactionSimulation, TransactionTrace};
+use super::execution_state::{Cancellation, ExecutionState};
+use super::types::{
+    BlockSimulation,
+    BlocksSimulation,
+    FeeEstimate,
+    TransactionSimulation,
+    TransactionTrace,
+    VirtualBlock,
+};
 use crate::error_stack::ErrorStack;
 use crate::transaction::{
     execute_transaction,
@@ -63,14 +72,90 @@ impl From<ExecutionError> for TransactionExecutionError {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct TraceCache(Arc<Mutex<SizedCache<BlockHash, CacheItem>>>);
+#[derive(Clone)]
+pub struct TraceCache {
+    cache: Arc<Mutex<SizedCache<BlockHash, CacheItem>>>,
+    /// Optional database which traces are persisted to, so that they survive
+    /// restarts and cache evictions.
+    store: Option<pathfinder_storage::Storage>,
+}
 
 type Traces = Vec<(TransactionHash, TransactionTrace)>;
 
+/// Format version of persisted traces, see
+/// [insert_block_traces](pathfinder_storage::Transaction::insert_block_traces).
+///
+/// Must be bumped whenever [TransactionTrace] or any of the types it contains
+/// change, so that traces persisted by an earlier version are ignored rather
+/// than decoded incorrectly.
+pub const STORED_TRACES_VERSION: u32 = 1;
+
 impl Default for TraceCache {
     fn default() -> Self {
-        Self(Arc::new(Mutex::new(SizedCache::with_size(128))))
+        Self {
+            cache: Arc::new(Mutex::new(SizedCache::with_size(128))),
+            store: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for TraceCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceCache")
+            .field("cache", &self.cache)
+            .field("persisted", &self.store.is_some())
+            .finish()
+    }
+}
+
+impl TraceCache {
+    /// Persists computed block traces to `store`, which must be writable.
+    pub fn with_store(self, store: pathfinder_storage::Storage) -> Self {
+        Self {
+            store: Some(store),
+            ..self
+        }
+    }
+
+    /// Returns the traces of a block if they are cached or have been
+    /// persisted, so that callers can skip setting up its execution.
+    pub fn get(
+        &self,
+        db: &pathfinder_storage::Transaction<'_>,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    ) -> Option<Vec<(TransactionHash, TransactionTrace)>> {
+        if let Some(CacheItem::CachedOk(cached)) = self.cache.lock().unwrap().cache_get(&block_hash)
+        {
+            return Some(cached.clone());
+        }
+
+        let traces = self.stored(db, block_number, block_hash)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
+        Some(traces)
+    }
+
+    /// Traces may have been persisted earlier, possibly by a previous run of
+    /// the node. The store is only consulted if traces are being persisted.
+    fn stored(
+        &self,
+        db: &pathfinder_storage::Transaction<'_>,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    ) -> Option<Traces> {
+        self.store.as_ref()?;
+
+        match db.block_traces::<Traces>(block_number, block_hash, STORED_TRACES_VERSION) {
+            Ok(traces) => traces,
+            Err(error) => {
+                // The block is re-executed and the stored traces are replaced.
+                tracing::debug!(block=%block_hash, %error, "Failed to read stored traces");
+                None
+            }
+        }
     }
 }
 
@@ -79,14 +164,106 @@ pub fn simulate(
     transactions: Vec<Transaction>,
     epsilon: Percentage,
 ) -> Result<Vec<TransactionSimulation>, TransactionExecutionError> {
-    let block_number = execution_state.header.number;
-
+    let cancellation = execution_state.cancellation();
     let (mut state, block_context) = execution_state.starknet_state()?;
 
+    simulate_transactions(
+        &mut state,
+        &block_context,
+        transactions,
+        0,
+        epsilon,
+        &cancellation,
+    )
+}
+
+/// Simulates the transactions of a sequence of virtual blocks, each executed in
+/// its own block context on top of the state left behind by the previous one.
+///
+/// Transaction indices in errors refer to the position of the transaction
+/// among the transactions of all blocks.
+pub fn simulate_blocks(
+    mut execution_state: ExecutionState<'_>,
+    blocks: Vec<VirtualBlock>,
+    epsilon: Percentage,
+) -> Result<BlocksSimulation, TransactionExecutionError> {
+    let cancellation = execution_state.cancellation();
+    let mut state = execution_state.cached_state();
+
+    let mut parent_number = execution_state.header.number;
+    let mut parent_timestamp = execution_state.header.timestamp;
+    let mut first_tx_index = 0;
+    let mut deprecated_declared_classes = Vec::new();
+    let mut simulations = Vec::with_capacity(blocks.len());
+
+    for block in blocks {
+        if block.header.number <= parent_number {
+            return Err(TransactionExecutionError::Custom(anyhow::anyhow!(
+                "Block number {} is not greater than the number of the previous block {}",
+                block.header.number,
+                parent_number
+            )));
+        }
+        if block.header.timestamp.get() < parent_timestamp.get() {
+            return Err(TransactionExecutionError::Custom(anyhow::anyhow!(
+                "Timestamp of block {} is earlier than the timestamp of the previous block",
+                block.header.number
+            )));
+        }
+        parent_number = block.header.number;
+        parent_timestamp = block.header.timestamp;
+
+        let block_context = execution_state.block_context(&mut state, &block.header)?;
+
+        let transaction_count = block.transactions.len();
+        deprecated_declared_classes.extend(
+            block
+                .transactions
+                .iter()
+                .filter_map(transaction_declared_deprecated_class),
+        );
+
+        let transactions = simulate_transactions(
+            &mut state,
+            &block_context,
+            block.transactions,
+            first_tx_index,
+            epsilon,
+            &cancellation,
+        )?;
+        first_tx_index += transaction_count;
+
+        simulations.push(BlockSimulation {
+            block_number: block.header.number,
+            transactions,
+        });
+    }
+
+    let state_diff = to_state_diff(&mut state, deprecated_declared_classes)?;
+
+    Ok(BlocksSimulation {
+        blocks: simulations,
+        state_diff,
+    })
+}
+
+fn simulate_transactions<S: blockifier::state::state_api::StateReader>(
+    state: &mut CachedState<S>,
+    block_context: &BlockContext,
+    transactions: Vec<Transaction>,
+    first_tx_index: usize,
+    epsilon: Percentage,
+    cancellation: &Cancellation,
+) -> Result<Vec<TransactionSimulation>, TransactionExecutionError> {
+    let block_number = block_context.block_info().block_number.0;
+
     transactions
         .into_iter()
         .enumerate()
         .map(|(tx_index, mut tx)| {
+            cancellation.check()?;
+
+            let tx_index = first_tx_index + tx_index;
             let _span = tracing::debug_span!(
                 "simulate",
                 block_number = %block_number,
@@ -96,23 +273,23 @@ pub fn simulate(
             .entered();
 
             let gas_vector_computation_mode = super::transaction::gas_vector_computation_mode(&tx);
-            let mut tx_state = CachedState::<_>::create_transactional(&mut state);
+            let mut tx_state = CachedState::<_>::create_transactional(state);
             let tx_info = if l2_gas_accounting_enabled(
                 &tx,
                 &tx_state,
-                &block_context,
+                block_context,
                 &gas_vector_computation_mode,
             )? {
                 find_l2_gas_limit_and_execute_transaction(
                     &mut tx,
                     tx_index,
                     &mut tx_state,
-                    &block_context,
+                    block_context,
                     ExecutionBehaviorOnRevert::Continue,
                     epsilon,
                 )?
             } else {
-                execute_transaction(&tx, tx_index, &mut tx_state, &block_context, &ExecutionBehaviorOnRevert::Continue)?
+                execute_transaction(&tx, tx_index, &mut tx_state, block_context, &ExecutionBehaviorOnRevert::Continue)?
             };
             let state_diff = to_state_diff(&mut tx_state, transaction_declared_deprecated_class(&tx))?;
             tx_state.commit();
@@ -124,7 +301,7 @@ pub fn simulate(
                     &tx,
                     &tx_info,
                     &gas_vector_computation_mode,
-                    &block_context,
+                    block_context,
                 ),
                 trace: to_trace(
                     transaction_type(&tx),
@@ -144,10 +321,12 @@ pub fn trace(
     block_hash: BlockHash,
     transactions: Vec<Transaction>,
 ) -> Result<Vec<(TransactionHash, TransactionTrace)>, TransactionExecutionError> {
-    let (mut state, block_context) = execution_state.starknet_state()?;
+    let db = execution_state.storage_transaction();
+    let block_number = execution_state.header.number;
+    let cancellation = execution_state.cancellation();
 
-    let sender = {
-        let mut cache = cache.0.lock().unwrap();
+    let sender = loop {
+        let mut cache = cache.cache.lock().unwrap();
         match cache.cache_get(&block_hash) {
             Some(CacheItem::CachedOk(cached)) => {
                 tracing::trace!(block=%block_hash, "trace cache hit: ok");
@@ -162,20 +341,46 @@ pub fn trace(
                 let mut receiver = receiver.resubscribe();
                 drop(cache);
 
-                let trace = receiver.blocking_recv().context("Trace error")?;
-                return trace.map_err(Into::into);
+                match receiver.blocking_recv() {
+                    Ok(trace) => return trace.map_err(Into::into),
+                    // The block was not traced to completion, e.g. because that request was
+                    // cancelled. Its entry has been removed, so we try again.
+                    Err(RecvError::Closed) => continue,
+                    Err(e @ RecvError::Lagged(_)) => {
+                        return Err(anyhow::Error::from(e).context("Trace error").into())
+                    }
+                }
             }
             None => {
                 tracing::trace!(block=%block_hash, "trace cache miss");
                 let (sender, receiver) = tokio::sync::broadcast::channel(1);
                 cache.cache_set(block_hash, CacheItem::Inflight(receiver));
-                sender
+                break sender;
             }
         }
     };
+    // Declared after the sender so that the entry is gone by the time waiters
+    // see the channel closing.
+    let _inflight = InflightGuard {
+        cache: &cache,
+        block_hash,
+    };
+
+    if let Some(traces) = cache.stored(db, block_number, block_hash) {
+        tracing::trace!(block=%block_hash, "trace store hit");
+        let mut cache = cache.cache.lock().unwrap();
+        let _ = sender.send(Ok(traces.clone()));
+        cache.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
+        return Ok(traces);
+    }
+
+    let (mut state, block_context) = execution_state.starknet_state()?;
 
     let mut traces = Vec::with_capacity(transactions.len());
     for (transaction_idx, tx) in transactions.into_iter().enumerate() {
+        // Cancellation is not an outcome of the block, so it is not cached.
+        cancellation.check()?;
+
         let hash = TransactionHash(Transaction::tx_hash(&tx).0.into_felt());
         let _span =
             tracing::debug_span!("trace", transaction_hash=%hash, %transaction_idx).entered();
@@ -193,17 +398,12 @@ pub fn trace(
                 error: e.to_string(),
                 error_stack: e.into(),
             };
-            let mut cache = cache.0.lock().unwrap();
+            let mut cache = cache.cache.lock().unwrap();
             let _ = sender.send(Err(err.clone()));
             cache.cache_set(block_hash, CacheItem::CachedErr(err.clone()));
             err
         })?;
-        let state_diff = to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash)
-            .inspect_err(|_| {
-                // Remove the cache entry so it's no longer inflight.
-                let mut cache = cache.0.lock().unwrap();
-                cache.cache_remove(&block_hash);
-            })?;
+        let state_diff = to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash)?;
         tx_state.commit();
 
         tracing::trace!("Transaction tracing finished");
@@ -218,14 +418,50 @@ pub fn trace(
         traces.push((hash, trace));
     }
 
+    if let Some(store) = &cache.store {
+        if let Err(error) = persist_traces(store, block_number, block_hash, &traces) {
+            tracing::warn!(block=%block_hash, %error, "Failed to persist block traces");
+        }
+    }
+
     // Lock the cache before sending to avoid race conditions between senders and
     // receivers.
-    let mut cache = cache.0.lock().unwrap();
+    let mut cache = cache.cache.lock().unwrap();
     let _ = sender.send(Ok(traces.clone()));
     cache.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
     Ok(traces)
 }
 
+/// Removes the inflight cache entry of a block if tracing it stops without a
+/// result, so that requests waiting for it trace the block themselves.
+struct InflightGuard<'a> {
+    cache: &'a TraceCache,
+    block_hash: BlockHash,
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        let Ok(mut cache) = self.cache.cache.lock() else {
+            return;
+        };
+        if let Some(CacheItem::Inflight(_)) = cache.cache_get(&self.block_hash) {
+            cache.cache_remove(&self.block_hash);
+        }
+    }
+}
+
+fn persist_traces(
+    store: &pathfinder_storage::Storage,
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    traces: &Traces,
+) -> anyhow::Result<()> {
+    let mut db = store.connection().context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+    db.insert_block_traces(block_number, block_hash, STORED_TRACES_VERSION, traces)?;
+    db.commit().context("Committing database transaction")
+}
+
 enum TransactionType {
     Declare,
     DeployAccount,
@@ -271,7 +507,7 @@ fn transaction_declared_deprecated_class(transaction: &Transaction) -> Option<Cl
 
 fn to_state_diff<S: blockifier::state::state_api::StateReader>(
     state: &mut blockifier::state::cached_state::CachedState<S>,
-    old_declared_contract: Option<ClassHash>,
+    old_declared_contracts: impl IntoIterator<Item = ClassHash>,
 ) -> Result<StateDiff, StateError> {
     let state_diff = state.to_state_diff()?;
 
@@ -332,7 +568,7 @@ fn to_state_diff<S: blockifier::state::state_api::StateReader>(
         storage_diffs,
         deployed_contracts,
         // This info is not present in the state diff, so we need to pass it separately.
-        deprecated_declared_classes: old_declared_contract.into_iter().collect(),
+        deprecated_declared_classes: old_declared_contracts.into_iter().collect(),
         declared_classes: state_diff
             .state_maps
             .compiled_class_hashes
@@ -444,3 +680,37 @@ fn to_trace(
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflight_guard_only_removes_inflight_entries() {
+        let cache = TraceCache::default();
+        let inflight = BlockHash(pathfinder_crypto::Felt::from_u64(1));
+        let cached = BlockHash(pathfinder_crypto::Felt::from_u64(2));
+        {
+            let mut cache = cache.cache.lock().unwrap();
+            let (_sender, receiver) = tokio::sync::broadcast::channel(1);
+            cache.cache_set(inflight, CacheItem::Inflight(receiver));
+            cache.cache_set(cached, CacheItem::CachedOk(vec![]));
+        }
+
+        drop(InflightGuard {
+            cache: &cache,
+            block_hash: inflight,
+        });
+        drop(InflightGuard {
+            cache: &cache,
+            block_hash: cached,
+        });
+
+        let mut cache = cache.cache.lock().unwrap();
+        assert!(cache.cache_get(&inflight).is_none());
+        assert!(matches!(
+            cache.cache_get(&cached),
+            Some(CacheItem::CachedOk(_))
+        ));
+    }
+}
@@ -4,6 +4,7 @@ use blockifier::execution::call_info::OrderedL2ToL1Message;
 use blockifier::transaction::objects::TransactionExecutionInfo;
 use pathfinder_common::prelude::*;
 use pathfinder_crypto::Felt;
+use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockInfo, FeeType};
 use starknet_api::execution_resources::GasVector;
 
@@ -87,7 +88,7 @@ impl From<FeeType> for PriceUnit {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum EntryPointType {
     Constructor,
     External,
@@ -106,7 +107,30 @@ impl TransactionSimulation {
     }
 }
 
-#[derive(Debug, Clone)]
+/// A block executed on top of the state of a real block, or the virtual
+/// block before it.
+///
+/// Only the header fields making up the block context (number, timestamp,
+/// sequencer address and gas prices) are used.
+pub struct VirtualBlock {
+    pub header: BlockHeader,
+    pub transactions: Vec<blockifier::transaction::transaction_execution::Transaction>,
+}
+
+#[derive(Debug)]
+pub struct BlockSimulation {
+    pub block_number: BlockNumber,
+    pub transactions: Vec<TransactionSimulation>,
+}
+
+#[derive(Debug)]
+pub struct BlocksSimulation {
+    pub blocks: Vec<BlockSimulation>,
+    /// The combined state diff of all blocks.
+    pub state_diff: StateDiff,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TransactionTrace {
     Declare(DeclareTransactionTrace),
     DeployAccount(DeployAccountTransactionTrace),
@@ -126,7 +150,47 @@ impl TransactionTrace {
     }
 }
 
+/// State overrides applied on top of the state execution happens on, keyed by
+/// contract address.
+pub type StateOverrides = BTreeMap<ContractAddress, ContractStateOverride>;
+
+/// Overrides for a single contract's state. Fields left empty are read from
+/// the actual state.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContractStateOverride {
+    pub storage: BTreeMap<StorageAddress, StorageValue>,
+    pub nonce: Option<ContractNonce>,
+    pub class_hash: Option<ClassHash>,
+    /// Shortcut for overriding the contract's balance in the ETH fee token
+    /// contract.
+    pub eth_balance: Option<primitive_types::U256>,
+    /// Shortcut for overriding the contract's balance in the STRK fee token
+    /// contract.
+    pub strk_balance: Option<primitive_types::U256>,
+}
+
+/// The trace of a call executed outside of a transaction.
 #[derive(Debug, Clone)]
+pub struct CallTrace {
+    pub invocation: FunctionInvocation,
+    pub revert_reason: Option<String>,
+    /// Every storage slot read or written by the call, ordered by contract
+    /// address and key.
+    pub storage_accesses: Vec<StorageAccess>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StorageAccess {
+    pub contract_address: ContractAddress,
+    pub key: StorageAddress,
+    /// The value of the slot before the call.
+    pub pre_value: StorageValue,
+    /// The value of the slot after the call. Equals `pre_value` for slots that
+    /// were only read.
+    pub post_value: StorageValue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeclareTransactionTrace {
     pub validate_invocation: Option<FunctionInvocation>,
     pub fee_transfer_invocation: Option<FunctionInvocation>,
@@ -134,7 +198,7 @@ pub struct DeclareTransactionTrace {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeployAccountTransactionTrace {
     pub validate_invocation: Option<FunctionInvocation>,
     pub constructor_invocation: Option<FunctionInvocation>,
@@ -143,14 +207,14 @@ pub struct DeployAccountTransactionTrace {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(clippy::large_enum_variant)]
 pub enum ExecuteInvocation {
     FunctionInvocation(Option<FunctionInvocation>),
     RevertedReason(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InvokeTransactionTrace {
     pub validate_invocation: Option<FunctionInvocation>,
     pub execute_invocation: ExecuteInvocation,
@@ -159,27 +223,27 @@ pub struct InvokeTransactionTrace {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct L1HandlerTransactionTrace {
     pub function_invocation: Option<FunctionInvocation>,
     pub state_diff: StateDiff,
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum CallType {
     Call,
     Delegate,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Event {
     pub order: i64,
     pub data: Vec<Felt>,
     pub keys: Vec<Felt>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionInvocation {
     pub calldata: Vec<Felt>,
     pub contract_address: ContractAddress,
@@ -197,7 +261,7 @@ pub struct FunctionInvocation {
     pub is_reverted: bool,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct MsgToL1 {
     pub order: usize,
     pub payload: Vec<Felt>,
@@ -205,13 +269,13 @@ pub struct MsgToL1 {
     pub from_address: Felt,
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct InnerCallExecutionResources {
     pub l1_gas: u128,
     pub l2_gas: u128,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct StateDiff {
     pub storage_diffs: BTreeMap<ContractAddress, Vec<StorageDiff>>,
     pub deployed_contracts: Vec<DeployedContract>,
@@ -221,31 +285,31 @@ pub struct StateDiff {
     pub replaced_classes: Vec<ReplacedClass>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct StorageDiff {
     pub key: StorageAddress,
     pub value: StorageValue,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct DeployedContract {
     pub address: ContractAddress,
     pub class_hash: ClassHash,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct DeclaredSierraClass {
     pub class_hash: SierraHash,
     pub compiled_class_hash: CasmHash,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ReplacedClass {
     pub contract_address: ContractAddress,
     pub class_hash: ClassHash,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ExecutionResources {
     pub computation_resources: ComputationResources,
     pub data_availability: DataAvailabilityResources,
@@ -254,7 +318,7 @@ pub struct ExecutionResources {
     pub l2_gas: u128,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ComputationResources {
     pub steps: usize,
     pub memory_holes: usize,
@@ -294,7 +358,7 @@ impl std::ops::Add for ComputationResources {
     }
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct DataAvailabilityResources {
     pub l1_gas: u128,
     pub l1_data_gas: u128,
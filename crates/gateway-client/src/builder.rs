@@ -16,6 +16,7 @@ use pathfinder_common::{BlockId, ClassHash, TransactionHash};
 use starknet_gateway_types::error::SequencerError;
 
 use crate::metrics::{with_metrics, BlockTag, RequestMetadata};
+use crate::record::{Archive, Capture};
 
 const X_THROTTLING_BYPASS: &str = "X-Throttling-Bypass";
 
@@ -25,6 +26,7 @@ pub struct Request<'a, S: RequestState> {
     url: reqwest::Url,
     api_key: Option<String>,
     client: &'a reqwest::Client,
+    archive: Option<&'a Archive>,
 }
 
 pub mod stage {
@@ -79,11 +81,13 @@ impl<'a> Request<'a, stage::Init> {
         client: &'a reqwest::Client,
         url: reqwest::Url,
         api_key: Option<String>,
+        archive: Option<&'a Archive>,
     ) -> Request<'a, stage::Method> {
         Request {
             url,
             client,
             api_key,
+            archive,
             state: stage::Method,
         }
     }
@@ -162,6 +166,7 @@ impl<'a> Request<'a, stage::Method> {
             url: self.url,
             client: self.client,
             api_key: self.api_key,
+            archive: self.archive,
             state: stage::Params {
                 meta: RequestMetadata::new(method),
             },
@@ -220,6 +225,7 @@ impl<'a> Request<'a, stage::Params> {
             url: self.url,
             client: self.client,
             api_key: self.api_key,
+            archive: self.archive,
             state: stage::Final {
                 meta: self.state.meta,
                 retry,
@@ -240,6 +246,7 @@ impl Request<'_, stage::Final> {
             api_key: Option<String>,
             client: &reqwest::Client,
             meta: RequestMetadata,
+            capture: Option<&Capture>,
         ) -> Result<T, SequencerError> {
             with_metrics(meta, async move {
                 tracing::trace!(%url, "Fetching data from feeder gateway");
@@ -248,26 +255,48 @@ impl Request<'_, stage::Final> {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
                 };
-                let response = request.send().await?;
+                let response = send(request, capture).await?;
                 parse::<T>(response).await
             })
             .await
         }
 
-        match self.state.retry {
-            false => send_request(self.url, self.api_key, self.client, self.state.meta).await,
+        let capture = match self.archive {
+            Some(Archive::Replay(replayer)) => {
+                let response = replayer.response(&self.url).await?;
+                return parse::<T>(response).await;
+            }
+            Some(Archive::Record(_)) => Some(Capture::default()),
+            None => None,
+        };
+
+        let result = match self.state.retry {
+            false => {
+                send_request(
+                    self.url.clone(),
+                    self.api_key.clone(),
+                    self.client,
+                    self.state.meta,
+                    capture.as_ref(),
+                )
+                .await
+            }
             true => {
                 retry0(
                     || async {
                         let url = self.url.clone();
                         let api_key = self.api_key.clone();
-                        send_request(url, api_key, self.client, self.state.meta).await
+                        send_request(url, api_key, self.client, self.state.meta, capture.as_ref())
+                            .await
                     },
                     retry_condition,
                 )
                 .await
             }
-        }
+        };
+
+        self.record(capture);
+        result
     }
 
     /// Sends the Sequencer request as a REST `GET` operation and returns the
@@ -278,6 +307,7 @@ impl Request<'_, stage::Final> {
             api_key: Option<String>,
             client: &reqwest::Client,
             meta: RequestMetadata,
+            capture: Option<&Capture>,
         ) -> Result<bytes::Bytes, SequencerError> {
             with_metrics(meta, async {
                 tracing::trace!(%url, "Fetching binary data from feeder gateway");
@@ -286,7 +316,7 @@ impl Request<'_, stage::Final> {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
                 };
-                let response = request.send().await?;
+                let response = send(request, capture).await?;
                 let response = parse_raw(response).await?;
                 let bytes = response.bytes().await?;
                 Ok(bytes)
@@ -294,20 +324,49 @@ impl Request<'_, stage::Final> {
             .await
         }
 
-        match self.state.retry {
-            false => get_as_bytes_inner(self.url, self.api_key, self.client, self.state.meta).await,
+        let capture = match self.archive {
+            Some(Archive::Replay(replayer)) => {
+                let response = replayer.response(&self.url).await?;
+                let response = parse_raw(response).await?;
+                return Ok(response.bytes().await?);
+            }
+            Some(Archive::Record(_)) => Some(Capture::default()),
+            None => None,
+        };
+
+        let result = match self.state.retry {
+            false => {
+                get_as_bytes_inner(
+                    self.url.clone(),
+                    self.api_key.clone(),
+                    self.client,
+                    self.state.meta,
+                    capture.as_ref(),
+                )
+                .await
+            }
             true => {
                 retry0(
                     || async {
                         let url = self.url.clone();
                         let api_key = self.api_key.clone();
-                        get_as_bytes_inner(url, api_key, self.client, self.state.meta).await
+                        get_as_bytes_inner(
+                            url,
+                            api_key,
+                            self.client,
+                            self.state.meta,
+                            capture.as_ref(),
+                        )
+                        .await
                     },
                     retry_condition,
                 )
                 .await
             }
-        }
+        };
+
+        self.record(capture);
+        result
     }
 
     /// Sends the Sequencer request as a REST `POST` operation, in addition to
@@ -331,6 +390,7 @@ impl Request<'_, stage::Final> {
             meta: RequestMetadata,
             json: &J,
             timeout: Option<std::time::Duration>,
+            capture: Option<&Capture>,
         ) -> Result<T, SequencerError>
         where
             T: serde::de::DeserializeOwned,
@@ -346,21 +406,31 @@ impl Request<'_, stage::Final> {
                     Some(timeout) => request.timeout(timeout),
                     None => request,
                 };
-                let response = request.json(json).send().await?;
+                let response = send(request.json(json), capture).await?;
                 parse::<T>(response).await
             })
             .await
         }
 
-        match self.state.retry {
+        let capture = match self.archive {
+            Some(Archive::Replay(replayer)) => {
+                let response = replayer.response(&self.url).await?;
+                return parse::<T>(response).await;
+            }
+            Some(Archive::Record(_)) => Some(Capture::default()),
+            None => None,
+        };
+
+        let result = match self.state.retry {
             false => {
                 post_with_json_inner(
-                    self.url,
-                    self.api_key,
+                    self.url.clone(),
+                    self.api_key.clone(),
                     self.client,
                     self.state.meta,
                     json,
                     timeout,
+                    capture.as_ref(),
                 )
                 .await
             }
@@ -377,6 +447,7 @@ impl Request<'_, stage::Final> {
                             self.state.meta,
                             json,
                             timeout,
+                            capture.as_ref(),
                         )
                         .await
                     },
@@ -384,10 +455,36 @@ impl Request<'_, stage::Final> {
                 )
                 .await
             }
+        };
+
+        self.record(capture);
+        result
+    }
+}
+
+impl Request<'_, stage::Final> {
+    /// Appends the final response captured for this request to the recording,
+    /// if there is one.
+    fn record(&self, capture: Option<Capture>) {
+        if let (Some(Archive::Record(recorder)), Some(response)) =
+            (self.archive, capture.and_then(Capture::into_response))
+        {
+            recorder.record(&self.url, response);
         }
     }
 }
 
+/// Sends the request, capturing the response if it is being recorded.
+async fn send(
+    request: reqwest::RequestBuilder,
+    capture: Option<&Capture>,
+) -> Result<reqwest::Response, SequencerError> {
+    match capture {
+        Some(capture) => capture.send(request).await,
+        None => Ok(request.send().await?),
+    }
+}
+
 async fn parse<T>(response: reqwest::Response) -> Result<T, SequencerError>
 where
     T: ::serde::de::DeserializeOwned,
@@ -485,6 +582,7 @@ fn retry_condition(e: &SequencerError) -> bool {
             error!(reason=%e, "Request failed, retrying");
             true
         }
+        SequencerError::LocalDumpError(_) => false,
     }
 }
 
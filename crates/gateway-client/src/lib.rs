@@ -1,6 +1,8 @@
 //! Starknet L2 sequencer client.
 use std::fmt::Debug;
+use std::path::Path;
 use std::result::Result;
+use std::sync::Arc;
 use std::time::Duration;
 
 use pathfinder_common::prelude::*;
@@ -12,7 +14,11 @@ use starknet_gateway_types::trace::{BlockTrace, TransactionTrace};
 use starknet_gateway_types::{reply, request};
 
 mod builder;
+mod local;
 mod metrics;
+mod record;
+
+pub use local::LocalGateway;
 
 #[allow(unused_variables)]
 #[mockall::automock]
@@ -111,7 +117,7 @@ pub trait GatewayApi: Sync {
 }
 
 #[async_trait::async_trait]
-impl<T: GatewayApi + Sync + Send> GatewayApi for std::sync::Arc<T> {
+impl<T: GatewayApi + Sync + Send + ?Sized> GatewayApi for std::sync::Arc<T> {
     async fn pending_block(&self) -> Result<(PendingBlock, StateUpdate), SequencerError> {
         self.as_ref().pending_block().await
     }
@@ -223,6 +229,9 @@ pub struct Client {
     /// Api key added to each request as a value for 'X-Throttling-Bypass'
     /// header.
     api_key: Option<String>,
+    /// Recording of the feeder gateway traffic, either being written or
+    /// replayed instead of querying the feeder gateway.
+    archive: Option<record::Archive>,
 }
 
 impl Client {
@@ -273,6 +282,29 @@ impl Client {
             feeder_gateway,
             retry: true,
             api_key: None,
+            archive: None,
+        })
+    }
+
+    /// Creates a [Client] which serves the feeder gateway responses recorded
+    /// at `path` by [with_recording](Client::with_recording), without any
+    /// network access.
+    ///
+    /// Repeated requests are answered in the order they were recorded. If
+    /// `timing` is set, each response is delayed until the time it was
+    /// originally received, relative to the start of the recording.
+    /// Requests to the gateway fail.
+    pub fn replay(path: &Path, timing: bool) -> anyhow::Result<Self> {
+        let replayer = record::Replayer::open(path, timing)?;
+        // No requests are sent by a replaying client.
+        let client = Self::with_base_url(
+            Url::parse("http://replay.invalid/").unwrap(),
+            Duration::ZERO,
+        )?;
+
+        Ok(Self {
+            archive: Some(record::Archive::Replay(Arc::new(replayer))),
+            ..client
         })
     }
 
@@ -283,6 +315,15 @@ impl Client {
         self
     }
 
+    /// Records every feeder gateway response received by this client, and
+    /// its clones, to a new archive at `path`, for use with
+    /// [replay](Client::replay).
+    pub fn with_recording(mut self, path: &Path) -> anyhow::Result<Self> {
+        let recorder = record::Recorder::create(path)?;
+        self.archive = Some(record::Archive::Record(Arc::new(recorder)));
+        Ok(self)
+    }
+
     /// Use this method to disable retry logic for all __non write__ requests
     /// when testing.
     pub fn disable_retry_for_tests(self) -> Self {
@@ -293,7 +334,12 @@ impl Client {
     }
 
     fn gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
-        builder::Request::builder(&self.inner, self.gateway.clone(), self.api_key.clone())
+        builder::Request::builder(
+            &self.inner,
+            self.gateway.clone(),
+            self.api_key.clone(),
+            self.archive.as_ref(),
+        )
     }
 
     fn feeder_gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
@@ -301,6 +347,7 @@ impl Client {
             &self.inner,
             self.feeder_gateway.clone(),
             self.api_key.clone(),
+            self.archive.as_ref(),
         )
     }
 }
@@ -0,0 +1,446 @@
+//! A [GatewayApi] serving feeder gateway replies from a local directory, for
+//! syncing where the feeder gateway cannot be reached and for replaying
+//! captured chains in tests.
+//!
+//! The directory holds one file per reply, exactly as returned by the feeder
+//! gateway:
+//!
+//! ```text
+//! <directory>
+//! ├── public_key.json                        get_public_key
+//! ├── contract_addresses.json                get_contract_addresses
+//! ├── state_update_with_block/<number>.json  get_state_update?includeBlock=true
+//! ├── signature/<number>.json                get_signature
+//! ├── class/<class hash>.json                get_class_by_hash
+//! └── compiled_class/<class hash>.json       get_compiled_class_by_class_hash
+//! ```
+//!
+//! Class hashes are written as `0x` followed by 64 lower case hex digits. The
+//! latest block is the highest numbered state update. The directory is scanned
+//! once when opened, after which dumps added for the blocks following the
+//! latest one are picked up as well.
+//!
+//! Only the requests made by L2 sync and at startup are served, and there is
+//! never a pending block. Blocks can only be looked up by number, looking them
+//! up by hash fails with [SequencerError::LocalDumpError].
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use pathfinder_common::prelude::*;
+use pathfinder_common::BlockId;
+use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError, StarknetError};
+use starknet_gateway_types::reply;
+use starknet_gateway_types::reply::PendingBlock;
+
+use crate::GatewayApi;
+
+const STATE_UPDATE_WITH_BLOCK: &str = "state_update_with_block";
+const SIGNATURE: &str = "signature";
+const CLASS: &str = "class";
+const COMPILED_CLASS: &str = "compiled_class";
+const PUBLIC_KEY: &str = "public_key.json";
+const CONTRACT_ADDRESSES: &str = "contract_addresses.json";
+
+#[derive(Debug, Clone)]
+pub struct LocalGateway {
+    directory: PathBuf,
+    /// The highest numbered state update found so far.
+    latest: Arc<Mutex<Option<BlockNumber>>>,
+}
+
+impl LocalGateway {
+    /// Serves the dumps in `directory`.
+    pub fn new(directory: PathBuf) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            directory.is_dir(),
+            "Gateway dump directory {} does not exist",
+            directory.display()
+        );
+
+        let latest = scan_latest(&directory.join(STATE_UPDATE_WITH_BLOCK))?;
+
+        Ok(Self {
+            directory,
+            latest: Arc::new(Mutex::new(latest)),
+        })
+    }
+
+    fn block_path(&self, kind: &str, block: BlockNumber) -> PathBuf {
+        self.directory.join(kind).join(format!("{block}.json"))
+    }
+
+    fn class_path(&self, kind: &str, class_hash: ClassHash) -> PathBuf {
+        self.directory
+            .join(kind)
+            .join(format!("0x{:x}.json", class_hash.0))
+    }
+
+    /// The highest numbered state update, if any, taking dumps added since
+    /// the last call into account.
+    async fn latest(&self) -> Result<Option<BlockNumber>, SequencerError> {
+        let mut latest = *self.latest.lock().unwrap();
+        loop {
+            let next = latest.map_or(BlockNumber::GENESIS, |latest| latest + 1);
+            let path = self.block_path(STATE_UPDATE_WITH_BLOCK, next);
+            match tokio::fs::try_exists(&path).await {
+                Ok(true) => latest = Some(next),
+                Ok(false) => break,
+                Err(e) => return Err(io_error(e, &path)),
+            }
+        }
+
+        let mut cached = self.latest.lock().unwrap();
+        *cached = std::cmp::max(*cached, latest);
+        Ok(*cached)
+    }
+
+    /// Blocks cannot be looked up by hash since the dumps are named by block
+    /// number, and indexing them by hash would mean parsing all of them.
+    async fn block_number(&self, block: BlockId) -> Result<BlockNumber, SequencerError> {
+        match block {
+            BlockId::Number(number) => Ok(number),
+            BlockId::Latest => self.latest().await?.ok_or_else(block_not_found),
+            BlockId::Pending => Err(block_not_found()),
+            BlockId::Hash(_) => Err(SequencerError::LocalDumpError(anyhow::anyhow!(
+                "Blocks can only be looked up by number in gateway dumps"
+            ))),
+        }
+    }
+}
+
+/// The highest numbered state update in `directory`, if any.
+fn scan_latest(directory: &Path) -> anyhow::Result<Option<BlockNumber>> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Reading {}", directory.display())),
+    };
+
+    let mut latest = None;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("Reading {}", directory.display()))?
+            .path();
+        let number = path
+            .extension()
+            .filter(|extension| *extension == "json")
+            .and(path.file_stem())
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+            .and_then(BlockNumber::new);
+        latest = std::cmp::max(latest, number);
+    }
+
+    Ok(latest)
+}
+
+/// Reads a file, or [None] if it does not exist.
+async fn read(path: &Path) -> Result<Option<Vec<u8>>, SequencerError> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io_error(e, path)),
+    }
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(
+    path: &Path,
+) -> Result<Option<T>, SequencerError> {
+    let Some(bytes) = read(path).await? else {
+        return Ok(None);
+    };
+
+    serde_json::from_slice(&bytes)
+        .with_context(|| format!("Parsing {}", path.display()))
+        .map(Some)
+        .map_err(SequencerError::LocalDumpError)
+}
+
+fn io_error(error: std::io::Error, path: &Path) -> SequencerError {
+    SequencerError::LocalDumpError(
+        anyhow::Error::from(error).context(format!("Reading {}", path.display())),
+    )
+}
+
+fn block_not_found() -> SequencerError {
+    StarknetError {
+        code: KnownStarknetErrorCode::BlockNotFound.into(),
+        message: "Block not found in gateway dumps".to_owned(),
+    }
+    .into()
+}
+
+fn undeclared_class(class_hash: ClassHash) -> SequencerError {
+    StarknetError {
+        code: KnownStarknetErrorCode::UndeclaredClass.into(),
+        message: format!("Class {class_hash} not found in gateway dumps"),
+    }
+    .into()
+}
+
+#[async_trait::async_trait]
+impl GatewayApi for LocalGateway {
+    async fn pending_block(&self) -> Result<(PendingBlock, StateUpdate), SequencerError> {
+        Err(block_not_found())
+    }
+
+    async fn block_header(
+        &self,
+        block: BlockId,
+    ) -> Result<(BlockNumber, BlockHash), SequencerError> {
+        #[derive(serde::Deserialize)]
+        struct Dto {
+            block: BlockHeader,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct BlockHeader {
+            block_hash: BlockHash,
+            block_number: BlockNumber,
+        }
+
+        let number = self.block_number(block).await?;
+        let dto: Dto = read_json(&self.block_path(STATE_UPDATE_WITH_BLOCK, number))
+            .await?
+            .ok_or_else(block_not_found)?;
+
+        Ok((dto.block.block_number, dto.block.block_hash))
+    }
+
+    async fn pending_class_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        read(&self.class_path(CLASS, class_hash))
+            .await?
+            .map(Into::into)
+            .ok_or_else(|| undeclared_class(class_hash))
+    }
+
+    async fn pending_casm_by_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        read(&self.class_path(COMPILED_CLASS, class_hash))
+            .await?
+            .map(Into::into)
+            .ok_or_else(|| undeclared_class(class_hash))
+    }
+
+    async fn state_update_with_block(
+        &self,
+        block: BlockNumber,
+    ) -> Result<(reply::Block, StateUpdate), SequencerError> {
+        #[derive(serde::Deserialize)]
+        struct Dto {
+            block: reply::Block,
+            state_update: reply::StateUpdate,
+        }
+
+        let dto: Dto = read_json(&self.block_path(STATE_UPDATE_WITH_BLOCK, block))
+            .await?
+            .ok_or_else(block_not_found)?;
+
+        Ok((dto.block, dto.state_update.into()))
+    }
+
+    async fn signature(&self, block: BlockId) -> Result<reply::BlockSignature, SequencerError> {
+        let number = self.block_number(block).await?;
+        read_json(&self.block_path(SIGNATURE, number))
+            .await?
+            .ok_or_else(block_not_found)
+    }
+
+    async fn public_key(&self) -> Result<PublicKey, SequencerError> {
+        let path = self.directory.join(PUBLIC_KEY);
+        read_json(&path).await?.ok_or_else(|| {
+            SequencerError::LocalDumpError(anyhow::anyhow!("{} is missing", path.display()))
+        })
+    }
+
+    async fn eth_contract_addresses(&self) -> Result<reply::EthContractAddresses, SequencerError> {
+        let path = self.directory.join(CONTRACT_ADDRESSES);
+        read_json(&path).await?.ok_or_else(|| {
+            SequencerError::LocalDumpError(anyhow::anyhow!("{} is missing", path.display()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use starknet_gateway_test_fixtures::{v0_13_1, v0_13_2};
+
+    use super::*;
+
+    const BLOCK: BlockNumber = BlockNumber::new_or_panic(9703);
+
+    fn write(directory: &Path, path: &str, contents: impl AsRef<[u8]>) {
+        let path = directory.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn setup() -> (tempfile::TempDir, LocalGateway) {
+        let directory = tempfile::tempdir().unwrap();
+        write(
+            directory.path(),
+            "state_update_with_block/9703.json",
+            v0_13_1::state_update_with_block::SEPOLIA_INTEGRATION_NUMBER_9703,
+        );
+        write(
+            directory.path(),
+            "signature/9703.json",
+            v0_13_2::signature::SEPOLIA_INTEGRATION_35748,
+        );
+        write(directory.path(), "public_key.json", r#""0x1234""#);
+        write(
+            directory.path(),
+            "class/0x0000000000000000000000000000000000000000000000000000000000000abc.json",
+            "class definition",
+        );
+        // Files which are not state updates are ignored when looking for the
+        // latest block.
+        write(directory.path(), "state_update_with_block/README", "");
+
+        let gateway = LocalGateway::new(directory.path().to_owned()).unwrap();
+        (directory, gateway)
+    }
+
+    #[tokio::test]
+    async fn state_update_with_block() {
+        let (_directory, gateway) = setup();
+
+        let (block, state_update) = gateway.state_update_with_block(BLOCK).await.unwrap();
+        assert_eq!(block.block_number, BLOCK);
+        assert_eq!(state_update.block_hash, block.block_hash);
+
+        let (number, hash) = gateway.block_header(BlockId::Latest).await.unwrap();
+        assert_eq!(number, BLOCK);
+        assert_eq!(hash, block.block_hash);
+        assert_eq!(gateway.head().await.unwrap(), (number, hash));
+    }
+
+    #[tokio::test]
+    async fn picks_up_new_dumps() {
+        let (directory, gateway) = setup();
+        assert_eq!(gateway.latest().await.unwrap(), Some(BLOCK));
+
+        write(directory.path(), "state_update_with_block/9704.json", "");
+        write(directory.path(), "state_update_with_block/9705.json", "");
+        assert_eq!(gateway.latest().await.unwrap(), Some(BLOCK + 2));
+    }
+
+    #[tokio::test]
+    async fn lookup_by_hash_is_unsupported() {
+        let (_directory, gateway) = setup();
+
+        let error = gateway
+            .block_header(BlockId::Hash(block_hash!("0x1")))
+            .await
+            .unwrap_err();
+        assert_matches!(error, SequencerError::LocalDumpError(_));
+    }
+
+    #[tokio::test]
+    async fn missing_block() {
+        let (_directory, gateway) = setup();
+
+        let error = gateway
+            .state_update_with_block(BLOCK + 1)
+            .await
+            .unwrap_err();
+        assert_matches!(error, SequencerError::StarknetError(e) => {
+            assert_eq!(e.code, KnownStarknetErrorCode::BlockNotFound.into())
+        });
+        let error = gateway.signature((BLOCK + 1).into()).await.unwrap_err();
+        assert_matches!(error, SequencerError::StarknetError(e) => {
+            assert_eq!(e.code, KnownStarknetErrorCode::BlockNotFound.into())
+        });
+        assert_matches!(
+            gateway.pending_block().await.unwrap_err(),
+            SequencerError::StarknetError(_)
+        );
+    }
+
+    #[tokio::test]
+    async fn signature_and_public_key() {
+        let (_directory, gateway) = setup();
+
+        let signature = gateway.signature(BLOCK.into()).await.unwrap();
+        let expected: reply::BlockSignature =
+            serde_json::from_str(v0_13_2::signature::SEPOLIA_INTEGRATION_35748).unwrap();
+        assert_eq!(signature, expected);
+
+        assert_eq!(gateway.public_key().await.unwrap(), public_key!("0x1234"));
+    }
+
+    #[tokio::test]
+    async fn contract_addresses() {
+        let (directory, gateway) = setup();
+
+        assert_matches!(
+            gateway.eth_contract_addresses().await.unwrap_err(),
+            SequencerError::LocalDumpError(_)
+        );
+
+        write(
+            directory.path(),
+            "contract_addresses.json",
+            r#"{
+                "Starknet": "0x4737c0c1B4D5b1A687B42610DdabEE781152359c",
+                "strk_l2_token_address": "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d"
+            }"#,
+        );
+        let addresses = gateway.eth_contract_addresses().await.unwrap();
+        assert_eq!(
+            addresses.starknet.0.as_bytes(),
+            [
+                0x47, 0x37, 0xc0, 0xc1, 0xb4, 0xd5, 0xb1, 0xa6, 0x87, 0xb4, 0x26, 0x10, 0xdd, 0xab,
+                0xee, 0x78, 0x11, 0x52, 0x35, 0x9c
+            ]
+        );
+        assert_eq!(
+            addresses.strk_l2_token_address,
+            Some(contract_address!(
+                "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d"
+            ))
+        );
+        assert_eq!(addresses.eth_l2_token_address, None);
+    }
+
+    #[tokio::test]
+    async fn classes() {
+        let (_directory, gateway) = setup();
+
+        let class = gateway
+            .pending_class_by_hash(class_hash!("0xabc"))
+            .await
+            .unwrap();
+        assert_eq!(class.as_ref(), b"class definition");
+
+        let error = gateway
+            .pending_casm_by_hash(class_hash!("0xabc"))
+            .await
+            .unwrap_err();
+        assert_matches!(error, SequencerError::StarknetError(e) => {
+            assert_eq!(e.code, KnownStarknetErrorCode::UndeclaredClass.into())
+        });
+    }
+
+    #[tokio::test]
+    async fn corrupt_dump() {
+        let (directory, gateway) = setup();
+        write(directory.path(), "signature/9703.json", "{");
+
+        let error = gateway.signature(BLOCK.into()).await.unwrap_err();
+        assert_matches!(error, SequencerError::LocalDumpError(_));
+    }
+
+    #[test]
+    fn missing_directory() {
+        LocalGateway::new(PathBuf::from("/does/not/exist")).unwrap_err();
+    }
+}
@@ -189,7 +189,7 @@ pub async fn with_metrics<T>(
             SequencerError::ReqwestError(e) if e.is_timeout() => {
                 increment_failed(meta, REASON_TIMEOUT);
             }
-            SequencerError::ReqwestError(_) => {}
+            SequencerError::ReqwestError(_) | SequencerError::LocalDumpError(_) => {}
         }
     })
 }
@@ -0,0 +1,500 @@
+//! Recording and replaying of gateway traffic, to reproduce what a node saw
+//! without network access.
+//!
+//! An archive is a zstd compressed stream of JSON lines, one per gateway or
+//! feeder gateway request made by the [Client](crate::Client). Each line is
+//! compressed into a zstd frame of its own, so that the lines can be
+//! decompressed individually when replaying. Only the final
+//! response to a request is recorded, after any retries, so that replaying the
+//! archive gives the same results to the node. Response bodies are stored
+//! base64 encoded, as they need not be valid UTF-8. Requests are identified by
+//! method and query, e.g. `get_signature?blockNumber=10`, but not by their
+//! body, and repeated requests are answered in the order they were recorded.
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use reqwest::Url;
+use starknet_gateway_types::error::SequencerError;
+
+/// Where a [Client](crate::Client) sends its feeder gateway requests, other
+/// than to the feeder gateway only.
+#[derive(Clone)]
+pub(crate) enum Archive {
+    Record(Arc<Recorder>),
+    Replay(Arc<Replayer>),
+}
+
+impl std::fmt::Debug for Archive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Archive::Record(_) => f.write_str("Record"),
+            Archive::Replay(_) => f.write_str("Replay"),
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Exchange {
+    /// Method and query of the request.
+    request: String,
+    /// Time from the start of the recording to the response.
+    elapsed_ms: u64,
+    #[serde(flatten)]
+    response: Response,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Response {
+    Http {
+        status: u16,
+        #[serde(with = "base64_body")]
+        body: Vec<u8>,
+    },
+    /// The request failed without a response, e.g. because it timed out.
+    Error(String),
+}
+
+impl Response {
+    fn into_reqwest(self) -> Result<reqwest::Response, SequencerError> {
+        match self {
+            Response::Http { status, body } => Ok(http::Response::builder()
+                .status(status)
+                .body(body)
+                .context("Creating response")
+                .map_err(SequencerError::LocalDumpError)?
+                .into()),
+            Response::Error(error) => Err(SequencerError::LocalDumpError(anyhow::anyhow!(
+                "Recorded error: {error}"
+            ))),
+        }
+    }
+}
+
+mod base64_body {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(body))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let body = String::deserialize(deserializer)?;
+        base64::decode(body).map_err(serde::de::Error::custom)
+    }
+}
+
+fn request_key(url: &Url) -> String {
+    let method = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default();
+    match url.query() {
+        Some(query) => format!("{method}?{query}"),
+        None => method.to_owned(),
+    }
+}
+
+pub(crate) struct Recorder {
+    /// Exchanges are compressed and written by a dedicated thread, so that
+    /// requests are not held up by file I/O.
+    sender: Option<mpsc::Sender<Exchange>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Creating gateway recording {}", path.display()))?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("gateway-recorder".to_owned())
+            .spawn(move || write_exchanges(BufWriter::new(file), receiver))
+            .context("Spawning gateway recording writer")?;
+
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            started: Instant::now(),
+        })
+    }
+
+    /// Appends the final response to the request for `url`.
+    pub fn record(&self, url: &Url, response: Response) {
+        let exchange = Exchange {
+            request: request_key(url),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            response,
+        };
+
+        let sent = self
+            .sender
+            .as_ref()
+            .is_some_and(|sender| sender.send(exchange).is_ok());
+        if !sent {
+            tracing::warn!("Gateway recording writer has stopped");
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Closing the channel stops the writer once everything recorded has been
+        // written, so that the archive is complete once the recorder is gone.
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                tracing::warn!("Gateway recording writer panicked");
+            }
+        }
+    }
+}
+
+/// Writes each exchange as a zstd frame of its own. The archive is flushed
+/// after each exchange so that a recording cut short by a crash can still be
+/// replayed up to that point.
+fn write_exchanges(mut writer: BufWriter<File>, exchanges: mpsc::Receiver<Exchange>) {
+    for exchange in exchanges {
+        let result = serde_json::to_vec(&exchange)
+            .map_err(std::io::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                zstd::stream::encode_all(line.as_slice(), 0)
+            })
+            .and_then(|frame| writer.write_all(&frame))
+            .and_then(|_| writer.flush());
+        if let Err(error) = result {
+            tracing::warn!(%error, request=%exchange.request, "Failed to record gateway response");
+        }
+    }
+}
+
+/// Captures the response of the latest attempt at a request, for the
+/// [Recorder].
+#[derive(Default)]
+pub(crate) struct Capture(Mutex<Option<Response>>);
+
+impl Capture {
+    /// Sends the request and captures the response, which is returned as if
+    /// it had not been read yet.
+    pub async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, SequencerError> {
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                self.set(Response::Error(e.to_string()));
+                return Err(e.into());
+            }
+        };
+
+        let status = response.status().as_u16();
+        let body = match response.bytes().await {
+            Ok(body) => body.to_vec(),
+            Err(e) => {
+                self.set(Response::Error(e.to_string()));
+                return Err(e.into());
+            }
+        };
+
+        let response = Response::Http { status, body };
+        self.set(response.clone());
+        response.into_reqwest()
+    }
+
+    fn set(&self, response: Response) {
+        *self.0.lock().unwrap() = Some(response);
+    }
+
+    pub fn into_response(self) -> Option<Response> {
+        self.0.into_inner().unwrap()
+    }
+}
+
+/// Where an exchange is in the archive.
+#[derive(Clone, Copy)]
+struct Indexed {
+    /// Offset of the exchange's frame.
+    offset: u64,
+    elapsed_ms: u64,
+}
+
+/// Serves the responses of a recording.
+pub(crate) struct Replayer {
+    path: PathBuf,
+    /// Only the location of each exchange is kept in memory, responses are read
+    /// from the archive when they are served.
+    index: Mutex<HashMap<String, VecDeque<Indexed>>>,
+    /// Start of the replay, if responses are delayed to their original timing.
+    started: Option<Instant>,
+}
+
+impl Replayer {
+    pub fn open(path: &Path, timing: bool) -> anyhow::Result<Self> {
+        /// The fields of an [Exchange] needed to index it.
+        #[derive(serde::Deserialize)]
+        struct Key {
+            request: String,
+            elapsed_ms: u64,
+        }
+
+        let file = File::open(path)
+            .with_context(|| format!("Opening gateway recording {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+
+        let mut index = HashMap::<_, VecDeque<_>>::new();
+        for number in 0.. {
+            let offset = reader
+                .stream_position()
+                .context("Reading gateway recording")?;
+            if reader
+                .fill_buf()
+                .context("Reading gateway recording")?
+                .is_empty()
+            {
+                break;
+            }
+
+            let line = match read_frame(&mut reader) {
+                Ok(line) => line,
+                Err(error) => {
+                    // The recording was probably cut short.
+                    tracing::warn!(%error, "Gateway recording ends with unreadable data");
+                    break;
+                }
+            };
+            let key: Key = serde_json::from_slice(&line)
+                .with_context(|| format!("Parsing exchange {number} of gateway recording"))?;
+            index.entry(key.request).or_default().push_back(Indexed {
+                offset,
+                elapsed_ms: key.elapsed_ms,
+            });
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            index: Mutex::new(index),
+            started: timing.then(Instant::now),
+        })
+    }
+
+    /// The next recorded response to the request for `url`. The last one is
+    /// served again once all have been served.
+    pub async fn response(&self, url: &Url) -> Result<reqwest::Response, SequencerError> {
+        let key = request_key(url);
+        let indexed = {
+            let mut index = self.index.lock().unwrap();
+            let queue = index.get_mut(&key).ok_or_else(|| {
+                SequencerError::LocalDumpError(anyhow::anyhow!("{key} was not recorded"))
+            })?;
+            if queue.len() > 1 {
+                queue.pop_front().expect("Queue is not empty")
+            } else {
+                queue[0]
+            }
+        };
+
+        if let Some(started) = self.started {
+            let elapsed = Duration::from_millis(indexed.elapsed_ms);
+            tokio::time::sleep_until((started + elapsed).into()).await;
+        }
+
+        let path = self.path.clone();
+        let exchange = tokio::task::spawn_blocking(move || read_exchange(&path, indexed.offset))
+            .await
+            .context("Joining gateway recording reader")
+            .and_then(|exchange| exchange)
+            .map_err(SequencerError::LocalDumpError)?;
+
+        exchange.response.into_reqwest()
+    }
+}
+
+/// Decompresses the frame `reader` is at, leaving it at the start of the next
+/// frame.
+fn read_frame(reader: &mut BufReader<File>) -> std::io::Result<Vec<u8>> {
+    let mut decoder = zstd::stream::read::Decoder::with_buffer(reader)?.single_frame();
+    let mut line = Vec::new();
+    decoder.read_to_end(&mut line)?;
+    Ok(line)
+}
+
+fn read_exchange(path: &Path, offset: u64) -> anyhow::Result<Exchange> {
+    let file = File::open(path)
+        .with_context(|| format!("Opening gateway recording {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    reader
+        .seek(SeekFrom::Start(offset))
+        .context("Seeking in gateway recording")?;
+    let line = read_frame(&mut reader).context("Reading gateway recording")?;
+    serde_json::from_slice(&line).context("Parsing gateway recording")
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use gateway_test_utils::*;
+    use pathfinder_common::prelude::*;
+    use pathfinder_common::BlockId;
+    use starknet_gateway_test_fixtures::v0_13_2;
+    use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError};
+
+    use super::{Exchange, Response};
+    use crate::{Client, GatewayApi};
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let (_jh, url) = setup([
+            (
+                "/feeder_gateway/get_signature?blockNumber=35748",
+                (
+                    v0_13_2::signature::SEPOLIA_INTEGRATION_35748.to_owned(),
+                    200,
+                ),
+            ),
+            (
+                "/feeder_gateway/get_signature?blockNumber=35749",
+                response_from(KnownStarknetErrorCode::BlockNotFound),
+            ),
+        ]);
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("recording.zst");
+
+        let block = BlockId::Number(BlockNumber::new_or_panic(35748));
+        let next = BlockId::Number(BlockNumber::new_or_panic(35749));
+
+        let client = Client::with_base_url(url, GATEWAY_TIMEOUT)
+            .unwrap()
+            .with_recording(&path)
+            .unwrap();
+        let signature = client.signature(block).await.unwrap();
+        let error = client.signature(next).await.unwrap_err();
+        drop(client);
+
+        let replay = Client::replay(&path, false).unwrap();
+        assert_eq!(replay.signature(block).await.unwrap(), signature);
+        // Served again once exhausted.
+        assert_eq!(replay.signature(block).await.unwrap(), signature);
+        assert_matches!(
+            (error, replay.signature(next).await.unwrap_err()),
+            (SequencerError::StarknetError(recorded), SequencerError::StarknetError(replayed)) => {
+                assert_eq!(recorded, replayed)
+            }
+        );
+
+        // Requests which were not recorded fail.
+        assert_matches!(
+            replay.signature(BlockId::Latest).await.unwrap_err(),
+            SequencerError::LocalDumpError(_)
+        );
+    }
+
+    #[tokio::test]
+    async fn gateway_requests_are_recorded() {
+        use starknet_gateway_types::request::add_transaction::{
+            InvokeFunction,
+            InvokeFunctionV0V1,
+        };
+
+        let (_jh, url) = setup([(
+            "/gateway/add_transaction",
+            (
+                r#"{"code":"TRANSACTION_RECEIVED","transaction_hash":"0x1234"}"#,
+                200,
+            ),
+        )]);
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("recording.zst");
+
+        let invoke = || {
+            InvokeFunction::V1(InvokeFunctionV0V1 {
+                max_fee: Fee::ZERO,
+                signature: vec![],
+                nonce: Some(TransactionNonce::ZERO),
+                sender_address: ContractAddress::ONE,
+                entry_point_selector: None,
+                calldata: vec![],
+            })
+        };
+
+        let client = Client::with_base_url(url, GATEWAY_TIMEOUT)
+            .unwrap()
+            .with_recording(&path)
+            .unwrap();
+        let response = client.add_invoke_transaction(invoke()).await.unwrap();
+        drop(client);
+
+        let replay = Client::replay(&path, false).unwrap();
+        assert_eq!(
+            replay.add_invoke_transaction(invoke()).await.unwrap(),
+            response
+        );
+    }
+
+    #[tokio::test]
+    async fn recording_cut_short() {
+        let (_jh, url) = setup([
+            (
+                "/feeder_gateway/get_signature?blockNumber=35748",
+                (
+                    v0_13_2::signature::SEPOLIA_INTEGRATION_35748.to_owned(),
+                    200,
+                ),
+            ),
+            (
+                "/feeder_gateway/get_signature?blockNumber=35749",
+                response_from(KnownStarknetErrorCode::BlockNotFound),
+            ),
+        ]);
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("recording.zst");
+
+        let block = BlockId::Number(BlockNumber::new_or_panic(35748));
+        let next = BlockId::Number(BlockNumber::new_or_panic(35749));
+
+        let client = Client::with_base_url(url, GATEWAY_TIMEOUT)
+            .unwrap()
+            .with_recording(&path)
+            .unwrap();
+        let signature = client.signature(block).await.unwrap();
+        client.signature(next).await.unwrap_err();
+        drop(client);
+
+        // Cut the last exchange short, as a crash while writing it would.
+        let recording = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &recording[..recording.len() - 4]).unwrap();
+
+        let replay = Client::replay(&path, false).unwrap();
+        assert_eq!(replay.signature(block).await.unwrap(), signature);
+        assert_matches!(
+            replay.signature(next).await.unwrap_err(),
+            SequencerError::LocalDumpError(_)
+        );
+    }
+
+    #[test]
+    fn bodies_are_recorded_as_bytes() {
+        let body = vec![0x00, 0x9f, 0x92, 0x96, 0xff];
+        let exchange = Exchange {
+            request: "get_class_by_hash?classHash=0x1".to_owned(),
+            elapsed_ms: 0,
+            response: Response::Http {
+                status: 200,
+                body: body.clone(),
+            },
+        };
+
+        let line = serde_json::to_string(&exchange).unwrap();
+        let exchange: Exchange = serde_json::from_str(&line).unwrap();
+        assert_matches!(exchange.response, Response::Http { status: 200, body: recorded } => {
+            assert_eq!(recorded, body)
+        });
+    }
+}
@@ -14,6 +14,9 @@ pub enum SequencerError {
     /// not informative enough or bloated
     #[error("error decoding response body: invalid error variant")]
     InvalidStarknetErrorVariant,
+    /// Errors reading gateway replies dumped to local files
+    #[error(transparent)]
+    LocalDumpError(anyhow::Error),
 }
 
 /// Used for deserializing specific Starknet sequencer error data.
@@ -104,6 +104,22 @@ impl<'tx> ClassCommitmentTree<'tx> {
 
         MerkleTree::<PoseidonHash, 251>::get_proofs(root, &storage, &keys)
     }
+
+    /// Checks the hashes of the nodes added after `newer_than`. See
+    /// [`MerkleTree::verify_nodes`].
+    pub fn verify_nodes(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        root: TrieStorageIndex,
+        newer_than: Option<TrieStorageIndex>,
+    ) -> anyhow::Result<Option<TrieStorageIndex>> {
+        let storage = ClassStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PoseidonHash, 251>::verify_nodes(root, &storage, newer_than)
+    }
 }
 
 struct ClassStorage<'tx> {
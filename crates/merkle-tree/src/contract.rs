@@ -110,6 +110,24 @@ impl<'tx> ContractsStorageTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proofs(root, &storage, &keys)
     }
 
+    /// Checks the hashes of the nodes added after `newer_than`. See
+    /// [`MerkleTree::verify_nodes`].
+    pub fn verify_nodes(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        block: BlockNumber,
+        root: TrieStorageIndex,
+        newer_than: Option<TrieStorageIndex>,
+    ) -> anyhow::Result<Option<TrieStorageIndex>> {
+        let storage = ContractStorage {
+            tx,
+            block: Some(block),
+            contract,
+        };
+
+        MerkleTree::<PedersenHash, 251>::verify_nodes(root, &storage, newer_than)
+    }
+
     pub fn set(&mut self, address: StorageAddress, value: StorageValue) -> anyhow::Result<()> {
         let key = address.view_bits().to_owned();
         self.tree.set(&self.storage, key, value.0)
@@ -237,6 +255,22 @@ impl<'tx> StorageCommitmentTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proofs(root, &storage, &keys)
     }
 
+    /// Checks the hashes of the nodes added after `newer_than`. See
+    /// [`MerkleTree::verify_nodes`].
+    pub fn verify_nodes(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        root: TrieStorageIndex,
+        newer_than: Option<TrieStorageIndex>,
+    ) -> anyhow::Result<Option<TrieStorageIndex>> {
+        let storage = StorageTrieStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PedersenHash, 251>::verify_nodes(root, &storage, newer_than)
+    }
+
     /// See [`MerkleTree::dfs`]
     pub fn dfs<B, F: FnMut(&InternalNode, &BitSlice<u8, Msb0>) -> ControlFlow<B, Visit>>(
         &mut self,
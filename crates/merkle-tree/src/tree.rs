@@ -668,6 +668,125 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         Ok(proofs)
     }
 
+    /// Recomputes the hashes of the nodes reachable from `root` from the stored
+    /// hashes of their children and the leaf values, and compares them to the
+    /// stored hashes. This detects corrupted trie data.
+    ///
+    /// Only nodes with a storage index greater than `newer_than` are checked,
+    /// older nodes are assumed to have been checked already. Since nodes are
+    /// stored children first, passing the root index of the previous version
+    /// of the trie checks exactly the nodes added since.
+    ///
+    /// Returns the index of the first node whose hash does not match, or which
+    /// is missing any data required to compute its hash.
+    pub fn verify_nodes(
+        root: TrieStorageIndex,
+        storage: &impl Storage,
+        newer_than: Option<TrieStorageIndex>,
+    ) -> anyhow::Result<Option<TrieStorageIndex>> {
+        let is_new = |index: TrieStorageIndex| newer_than.is_none_or(|old| index.0 > old.0);
+        if !is_new(root) {
+            return Ok(None);
+        }
+
+        let mut to_visit = vec![(root, BitVec::<u8, Msb0>::new())];
+        while let Some((index, path)) = to_visit.pop() {
+            let Some(node) = storage.get(index).context("Resolving node")? else {
+                return Ok(Some(index));
+            };
+
+            // Paths which don't end at the tree height are corrupt, and leaves
+            // can only be looked up at full height.
+            let height = path.len();
+            let valid_height = match &node {
+                StoredNode::Binary { .. } => height + 1 < HEIGHT,
+                StoredNode::Edge { path, .. } => height + path.len() < HEIGHT,
+                StoredNode::LeafBinary => height + 1 == HEIGHT,
+                StoredNode::LeafEdge { path } => height + path.len() == HEIGHT,
+            };
+            if !valid_height {
+                return Ok(Some(index));
+            }
+
+            let node = match node {
+                StoredNode::Binary { left, right } => {
+                    let (Some(left_hash), Some(right_hash)) = (
+                        storage.hash(left).context("Querying left child's hash")?,
+                        storage.hash(right).context("Querying right child's hash")?,
+                    ) else {
+                        return Ok(Some(index));
+                    };
+
+                    for (child, direction) in [(left, Direction::Left), (right, Direction::Right)] {
+                        if is_new(child) {
+                            let mut child_path = path.clone();
+                            child_path.push(direction.into());
+                            to_visit.push((child, child_path));
+                        }
+                    }
+
+                    TrieNode::Binary {
+                        left: left_hash,
+                        right: right_hash,
+                    }
+                }
+                StoredNode::Edge {
+                    child,
+                    path: edge_path,
+                } => {
+                    let Some(child_hash) = storage.hash(child).context("Querying child's hash")?
+                    else {
+                        return Ok(Some(index));
+                    };
+
+                    if is_new(child) {
+                        let mut child_path = path.clone();
+                        child_path.extend_from_bitslice(&edge_path);
+                        to_visit.push((child, child_path));
+                    }
+
+                    TrieNode::Edge {
+                        child: child_hash,
+                        path: edge_path,
+                    }
+                }
+                StoredNode::LeafBinary => {
+                    let mut leaf_path = path.clone();
+                    leaf_path.push(Direction::Left.into());
+                    let left = storage.leaf(&leaf_path).context("Querying left leaf")?;
+                    leaf_path.pop();
+                    leaf_path.push(Direction::Right.into());
+                    let right = storage.leaf(&leaf_path).context("Querying right leaf")?;
+
+                    let (Some(left), Some(right)) = (left, right) else {
+                        return Ok(Some(index));
+                    };
+
+                    TrieNode::Binary { left, right }
+                }
+                StoredNode::LeafEdge { path: edge_path } => {
+                    let mut leaf_path = path.clone();
+                    leaf_path.extend_from_bitslice(&edge_path);
+                    let Some(child) = storage.leaf(&leaf_path).context("Querying leaf")? else {
+                        return Ok(Some(index));
+                    };
+
+                    TrieNode::Edge {
+                        child,
+                        path: edge_path,
+                    }
+                }
+            };
+
+            let stored_hash = storage.hash(index).context("Querying node hash")?;
+            if stored_hash != Some(node.hash::<H>()) {
+                return Ok(Some(index));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Traverses from the current root towards destination node.
     /// Returns the list of nodes along the path.
     ///
@@ -2563,4 +2682,85 @@ mod tests {
             }
         }
     }
+
+    mod verify_nodes {
+        use super::*;
+
+        fn setup(storage: &mut TestStorage) -> TrieStorageIndex {
+            let mut uut = TestTree::empty();
+            for (key, value) in [
+                (felt!("0x1"), felt!("0x10")),
+                (felt!("0x2"), felt!("0x20")),
+                (felt!("0x99cadc82"), felt!("0x30")),
+            ] {
+                uut.set(storage, key.view_bits().to_bitvec(), value)
+                    .unwrap();
+            }
+            commit_and_persist_without_pruning(uut, storage).1
+        }
+
+        #[test]
+        fn intact() {
+            let mut storage = TestStorage::default();
+            let root = setup(&mut storage);
+            assert_eq!(TestTree::verify_nodes(root, &storage, None).unwrap(), None);
+
+            let mut uut = TestTree::new(root);
+            uut.set(
+                &storage,
+                felt!("0x3").view_bits().to_bitvec(),
+                felt!("0x40"),
+            )
+            .unwrap();
+            let (_, new_root) = commit_and_persist_without_pruning(uut, &mut storage);
+            assert_eq!(
+                TestTree::verify_nodes(new_root, &storage, Some(root)).unwrap(),
+                None
+            );
+            assert_eq!(
+                TestTree::verify_nodes(new_root, &storage, None).unwrap(),
+                None
+            );
+        }
+
+        #[test]
+        fn corrupt_node_hash() {
+            let mut storage = TestStorage::default();
+            let root = setup(&mut storage);
+            storage.nodes.get_mut(&TrieStorageIndex(0)).unwrap().0 = felt!("0xdead");
+
+            // Reported at the node itself or at its parent, whose hash is computed
+            // from the corrupt one.
+            assert!(TestTree::verify_nodes(root, &storage, None)
+                .unwrap()
+                .is_some());
+            // Nodes which are not newer are not checked.
+            assert_eq!(
+                TestTree::verify_nodes(root, &storage, Some(root)).unwrap(),
+                None
+            );
+        }
+
+        #[test]
+        fn corrupt_leaf() {
+            let mut storage = TestStorage::default();
+            let root = setup(&mut storage);
+            storage.leaves.insert(felt!("0x2"), felt!("0x21"));
+
+            assert!(TestTree::verify_nodes(root, &storage, None)
+                .unwrap()
+                .is_some());
+        }
+
+        #[test]
+        fn missing_node() {
+            let mut storage = TestStorage::default();
+            let root = setup(&mut storage);
+            storage.nodes.remove(&TrieStorageIndex(0));
+
+            assert!(TestTree::verify_nodes(root, &storage, None)
+                .unwrap()
+                .is_some());
+        }
+    }
 }
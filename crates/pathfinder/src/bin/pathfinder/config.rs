@@ -11,8 +11,10 @@ use clap::{ArgAction, CommandFactory, Parser};
 use ipnet::IpNet;
 #[cfg(feature = "p2p")]
 use p2p::libp2p::Multiaddr;
-use pathfinder_common::{AllowedOrigins, StarknetVersion};
+use pathfinder_common::{AllowedOrigins, BlockNumber, StarknetVersion};
 use pathfinder_executor::VersionedConstantsMap;
+use pathfinder_rpc::middleware::rate_limit::{Quota, RateLimiter};
+use pathfinder_rpc::{Listener, MethodFilter};
 use pathfinder_storage::JournalMode;
 use reqwest::Url;
 use util::percentage::Percentage;
@@ -24,7 +26,11 @@ use util::percentage::Percentage;
 #[command(
     about = "A Starknet node implemented by Equilibrium Labs. Submit bug reports and issues at https://github.com/eqlabs/pathfinder."
 )]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(
         long,
         value_name = "DIR", 
@@ -53,8 +59,9 @@ Examples:
         value_name = "HTTP(s) URL",
         value_hint = clap::ValueHint::Url,
         env = "PATHFINDER_ETHEREUM_API_URL", 
+        required = true
     )]
-    ethereum_url: Url,
+    ethereum_url: Option<Url>,
 
     #[arg(
         long = "http-rpc",
@@ -195,6 +202,9 @@ Examples:
     #[clap(flatten)]
     websocket: WebsocketConfig,
 
+    #[clap(flatten)]
+    trace_precomputation: TracePrecomputationConfig,
+
     #[arg(
         long = "sync.verify_tree_node_data",
         long_help = r"When enabled, state tree node hashes are verified when loaded from disk.
@@ -255,6 +265,17 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     gateway_timeout: std::num::NonZeroU64,
 
+    #[arg(
+        long = "gateway.record",
+        value_name = "FILE",
+        long_help = "Record every feeder gateway and gateway response, including those to \
+                     submitted transactions, to this file, which is overwritten. The \
+                     recording can be served back with `--sync.gateway-replay` to reproduce what \
+                     this node saw without network access.",
+        env = "PATHFINDER_GATEWAY_RECORD"
+    )]
+    gateway_record: Option<PathBuf>,
+
     #[arg(
         long = "gateway.fetch-concurrency",
         long_help = "How many concurrent requests to send to the feeder gateway when fetching \
@@ -343,6 +364,53 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     fetch_casm_from_fgw: bool,
 
+    #[arg(
+        long = "sync.gateway-dump-dir",
+        value_name = "DIR",
+        long_help = "Sync from feeder gateway replies dumped to this directory instead of from \
+                     the feeder gateway, for example where the feeder gateway cannot be reached. \
+                     The directory holds one file per reply, as returned by the feeder gateway: \
+                     'public_key.json', 'contract_addresses.json' (custom networks only), \
+                     'state_update_with_block/<block number>.json', \
+                     'signature/<block number>.json', 'class/<class hash>.json' and \
+                     'compiled_class/<class hash>.json'.",
+        env = "PATHFINDER_SYNC_GATEWAY_DUMP_DIR"
+    )]
+    gateway_dump_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "sync.gateway-replay",
+        value_name = "FILE",
+        long_help = "Sync from feeder gateway responses recorded with `--gateway.record` instead \
+                     of from the feeder gateway. Requests which were not recorded fail.",
+        env = "PATHFINDER_SYNC_GATEWAY_REPLAY",
+        conflicts_with = "gateway_dump_dir"
+    )]
+    gateway_replay: Option<PathBuf>,
+
+    #[arg(
+        long = "sync.gateway-replay-timing",
+        long_help = "Delay each replayed response until the time it was received in the \
+                     recording, instead of replaying as fast as possible.",
+        env = "PATHFINDER_SYNC_GATEWAY_REPLAY_TIMING",
+        default_value = "false",
+        action=ArgAction::Set,
+        requires = "gateway_replay"
+    )]
+    gateway_replay_timing: bool,
+
+    #[arg(
+        long = "sync.stop-at-block",
+        value_name = "BLOCK_NUMBER",
+        long_help = "Stop syncing once this block is stored, and keep serving the state at \
+                     this block. Pending data and L1 are no longer polled, and the node reports \
+                     itself as synced. Pathfinder exits with an error if the database is \
+                     already past this block.",
+        env = "PATHFINDER_SYNC_STOP_AT_BLOCK",
+        value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64)
+    )]
+    sync_stop_at_block: Option<u64>,
+
     #[arg(
         long = "shutdown.grace-period",
         value_name = "Seconds",
@@ -363,6 +431,378 @@ This should only be enabled for debugging purposes as it adds substantial proces
         value_parser = parse_fee_estimation_epsilon
     )]
     fee_estimation_epsilon: Percentage,
+
+    #[arg(
+        long = "rpc.archive-fallback-url",
+        long_help = "URL of an archive pathfinder node's JSON-RPC server. Requests for blocks which \
+                     have been pruned locally (see `--storage.blockchain-history`) are forwarded \
+                     to this node instead of failing.",
+        value_name = "HTTP(s) URL",
+        value_hint = clap::ValueHint::Url,
+        env = "PATHFINDER_RPC_ARCHIVE_FALLBACK_URL"
+    )]
+    rpc_archive_fallback_url: Option<Url>,
+
+    #[arg(
+        long = "rpc.store-traces",
+        long_help = "Persist block traces computed by `starknet_traceBlockTransactions` and \
+                     `starknet_traceTransaction` to the database, so that they are served \
+                     without re-executing the block, even after a restart.",
+        env = "PATHFINDER_RPC_STORE_TRACES",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_store_traces: bool,
+
+    #[arg(
+        long = "rpc.rate-limit",
+        long_help = "Request cost units per second which each client without an API key may \
+                     spend, per IP address. Cheap methods like `starknet_blockNumber` cost one \
+                     unit, while methods like `starknet_getEvents` and \
+                     `starknet_traceBlockTransactions` cost more. Unlimited if not set.",
+        env = "PATHFINDER_RPC_RATE_LIMIT"
+    )]
+    rpc_rate_limit: Option<NonZeroU32>,
+
+    #[arg(
+        long = "rpc.rate-limit-burst",
+        long_help = "Maximum number of request cost units which a client without an API key may \
+                     spend at once. Defaults to the value of `--rpc.rate-limit`.",
+        env = "PATHFINDER_RPC_RATE_LIMIT_BURST",
+        requires = "rpc_rate_limit"
+    )]
+    rpc_rate_limit_burst: Option<NonZeroU32>,
+
+    #[arg(
+        long = "rpc.api-keys-file",
+        long_help = r#"Path to a JSON file with the API keys accepted by the RPC server in the `X-API-Key` header, and their rate limits. Clients without a rate limit are unlimited.
+
+Example:
+    [
+        {"name": "alice", "key": "secret", "rate": 100, "burst": 500},
+        {"name": "bob", "key": "another-secret"}
+    ]"#,
+        value_name = "PATH",
+        env = "PATHFINDER_RPC_API_KEYS_FILE"
+    )]
+    rpc_api_keys_file: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.require-api-key",
+        long_help = "Reject RPC requests which don't have a valid API key.",
+        env = "PATHFINDER_RPC_REQUIRE_API_KEY",
+        default_value = "false",
+        action=ArgAction::Set,
+        requires = "rpc_api_keys_file"
+    )]
+    rpc_require_api_key: bool,
+
+    #[arg(
+        long = "rpc.disabled-methods",
+        long_help = "Comma separated list of RPC methods which are not served on the `--http-rpc` \
+                     address. `*` matches any sequence of characters, e.g. \
+                     `starknet_add*Transaction`.",
+        value_name = "METHOD LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_RPC_DISABLED_METHODS"
+    )]
+    rpc_disabled_methods: Vec<String>,
+
+    #[arg(
+        long = "rpc.listeners-file",
+        long_help = r#"Path to a JSON file with additional addresses for the RPC server to listen on, each serving a subset of the API versions and methods. Versions are any of `v06`, `v07`, `v08` and `pathfinder`, and default to all of them. Methods are selected with either `enabled_methods` or `disabled_methods`, in which `*` matches any sequence of characters.
+
+Example:
+    [
+        {
+            "address": "0.0.0.0:9546",
+            "versions": ["v07", "v08"],
+            "disabled_methods": ["starknet_add*Transaction", "pathfinder_getProof"]
+        }
+    ]"#,
+        value_name = "PATH",
+        env = "PATHFINDER_RPC_LISTENERS_FILE"
+    )]
+    rpc_listeners_file: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.request-max-size",
+        value_name = "Bytes",
+        long_help = "Maximum size of an RPC request body or WebSocket message",
+        env = "PATHFINDER_RPC_REQUEST_MAX_SIZE",
+        default_value = "10485760"
+    )]
+    rpc_request_max_size: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.request-timeout",
+        value_name = "Seconds",
+        long_help = "Time after which RPC requests are aborted. Execution of transactions for an \
+                     aborted request is stopped as well.",
+        env = "PATHFINDER_RPC_REQUEST_TIMEOUT",
+        default_value = "120"
+    )]
+    rpc_request_timeout: std::num::NonZeroU64,
+
+    #[arg(
+        long = "rpc.method-timeouts",
+        long_help = "Comma separated list of `method=seconds` pairs overriding \
+                     `--rpc.request-timeout` for individual method calls. Calls still end \
+                     with the request they are part of, so longer timeouts have no effect.
+
+Example:
+    starknet_simulateTransactions=30,starknet_blockNumber=5",
+        value_name = "METHOD=SECONDS LIST",
+        value_delimiter = ',',
+        value_parser = parse_method_timeout,
+        env = "PATHFINDER_RPC_METHOD_TIMEOUTS"
+    )]
+    rpc_method_timeouts: Vec<(String, Duration)>,
+
+    #[arg(
+        long = "rpc.response-cache-size",
+        value_name = "Bytes",
+        long_help = "Enables caching of RPC responses for blocks accepted on L1, using at most \
+                     this many bytes of serialized JSON. Disabled by default.",
+        env = "PATHFINDER_RPC_RESPONSE_CACHE_SIZE"
+    )]
+    rpc_response_cache_size: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.compression",
+        long_help = "Compress RPC responses with gzip, brotli or zstd if the client accepts it, \
+                     and accept request bodies compressed the same way.",
+        env = "PATHFINDER_RPC_COMPRESSION",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_compression: bool,
+
+    #[arg(
+        long = "rpc.http2",
+        long_help = "Serve RPC requests over HTTP/2 without TLS (h2c) in addition to HTTP/1.1.",
+        env = "PATHFINDER_RPC_HTTP2",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_http2: bool,
+}
+
+/// Offline and maintenance tools which run instead of the node.
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Export or import a compressed database snapshot.
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+    /// Database maintenance. The node must not be running.
+    #[command(subcommand)]
+    Db(DbCommand),
+}
+
+#[derive(clap::Subcommand)]
+pub enum SnapshotCommand {
+    /// Export a consistent, compressed and checksummed snapshot of a
+    /// database. This is safe to run while the node is syncing.
+    Export(SnapshotExportArgs),
+    /// Restore a snapshot created by `snapshot export` into a new database
+    /// file.
+    Import(SnapshotImportArgs),
+}
+
+#[derive(clap::Args)]
+pub struct SnapshotExportArgs {
+    #[arg(
+        long = "database",
+        long_help = "Path to the database file to export, e.g. `mainnet.sqlite` in the node's \
+                     data directory",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub database: PathBuf,
+
+    #[arg(
+        long = "output",
+        long_help = "Path of the snapshot file to create. A manifest describing the snapshot is \
+                     written next to it with a `.manifest.json` suffix. Requires free space \
+                     for an uncompressed copy of the database in the same directory while the \
+                     snapshot is being created.",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub output: PathBuf,
+
+    #[arg(
+        long = "block",
+        long_help = "Block number to create the snapshot at. Defaults to the latest block. Blocks \
+                     after this one are rolled back in the copy, the source database is not \
+                     modified.",
+        value_name = "BLOCK_NUMBER"
+    )]
+    pub block: Option<u64>,
+
+    #[arg(
+        long = "chain-id",
+        long_help = "Chain ID to record in the snapshot manifest (e.g. SN_MAIN). Required for \
+                     custom networks, otherwise it is derived from the genesis block hash.",
+        value_name = "CHAIN ID"
+    )]
+    pub chain_id: Option<String>,
+
+    #[arg(
+        long = "compression-level",
+        long_help = "Zstandard compression level",
+        default_value = "3",
+        value_parser = clap::value_parser!(i32).range(1..=22)
+    )]
+    pub compression_level: i32,
+}
+
+#[derive(clap::Args)]
+pub struct SnapshotImportArgs {
+    #[arg(
+        long = "input",
+        long_help = "Path to the snapshot file. The manifest is expected next to it with a \
+                     `.manifest.json` suffix.",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub input: PathBuf,
+
+    #[arg(
+        long = "database",
+        long_help = "Path of the database file to restore into, e.g. `mainnet.sqlite` in the \
+                     node's data directory. Must not exist.",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub database: PathBuf,
+
+    #[arg(
+        long = "chain-id",
+        long_help = "The chain ID the snapshot is expected to be for (e.g. SN_MAIN)",
+        value_name = "CHAIN ID"
+    )]
+    pub chain_id: String,
+}
+
+#[derive(clap::Subcommand)]
+pub enum DbCommand {
+    /// Convert an archive database into a pruned one in place, deleting
+    /// historical blockchain data and/or Merkle trie nodes.
+    Prune(DbPruneArgs),
+    /// Roll the database back to an earlier block, removing all blocks after
+    /// it.
+    Rollback(DbRollbackArgs),
+    /// Recompute hashes and commitments from the stored data to detect
+    /// database corruption. This is safe to run while the node is syncing.
+    Verify(DbVerifyArgs),
+}
+
+#[derive(clap::Args)]
+#[command(group(
+    clap::ArgGroup::new("prune")
+        .required(true)
+        .multiple(true)
+        .args(["blockchain_history", "state_tries"])
+))]
+pub struct DbPruneArgs {
+    #[arg(
+        long = "database",
+        long_help = "Path to the database file to convert, e.g. `mainnet.sqlite` in the node's \
+                     data directory",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub database: PathBuf,
+
+    #[arg(
+        long = "blockchain-history",
+        long_help = "Enable blockchain history pruning, keeping only the last N+1 blocks. The \
+                     node must then be started with the same `--storage.blockchain-history` value.",
+        value_name = "N"
+    )]
+    pub blockchain_history: Option<u64>,
+
+    #[arg(
+        long = "state-tries",
+        long_help = "Enable Merkle trie pruning, keeping only the last N+1 states of the tries. \
+                     The node should then be started with the same `--storage.state-tries` value.",
+        value_name = "N"
+    )]
+    pub state_tries: Option<u64>,
+
+    #[arg(
+        long = "vacuum",
+        long_help = "Rebuild the database file afterwards to return the freed space to the \
+                     operating system. Requires free space for a full copy of the pruned database."
+    )]
+    pub vacuum: bool,
+}
+
+#[derive(clap::Args)]
+pub struct DbRollbackArgs {
+    #[arg(
+        long = "database",
+        long_help = "Path to the database file to roll back, e.g. `mainnet.sqlite` in the node's \
+                     data directory",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub database: PathBuf,
+
+    #[arg(
+        long = "to",
+        long_help = "Block number to roll back to. This block is kept, all blocks after it are \
+                     removed along with their state. Neither the block nor its Merkle trie \
+                     state may have been pruned.",
+        value_name = "BLOCK_NUMBER",
+        value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64)
+    )]
+    pub to: u64,
+}
+
+#[derive(clap::Args)]
+pub struct DbVerifyArgs {
+    #[arg(
+        long = "database",
+        long_help = "Path to the database file to verify, e.g. `mainnet.sqlite` in the node's \
+                     data directory",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub database: PathBuf,
+
+    #[arg(
+        long = "from",
+        long_help = "First block to verify. Defaults to the oldest block in the database.",
+        value_name = "BLOCK_NUMBER",
+        value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64)
+    )]
+    pub from: Option<u64>,
+
+    #[arg(
+        long = "to",
+        long_help = "Last block to verify. Defaults to the latest block in the database.",
+        value_name = "BLOCK_NUMBER",
+        value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64)
+    )]
+    pub to: Option<u64>,
+
+    #[arg(
+        long = "threads",
+        long_help = "Number of blocks verified in parallel. Defaults to the number of CPUs.",
+        value_name = "N"
+    )]
+    pub threads: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "progress",
+        long_help = "File to record progress and mismatches found in. If it exists, \
+                     verification resumes where it left off.",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub progress: Option<PathBuf>,
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
@@ -429,6 +869,20 @@ fn parse_state_tries(s: &str) -> Result<StateTries, String> {
     }
 }
 
+fn parse_method_timeout(s: &str) -> Result<(String, Duration), String> {
+    let (method, seconds) = s
+        .split_once('=')
+        .ok_or_else(|| "Expected `method=seconds`".to_string())?;
+    if !pathfinder_rpc::is_rpc_method(method) {
+        return Err(format!("Unknown RPC method `{method}`"));
+    }
+    let seconds: std::num::NonZeroU64 = seconds
+        .parse()
+        .map_err(|_| "Expected a positive number of seconds".to_string())?;
+
+    Ok((method.to_owned(), Duration::from_secs(seconds.get())))
+}
+
 fn parse_fee_estimation_epsilon(s: &str) -> Result<Percentage, String> {
     let value: u8 = s
         .parse()
@@ -842,6 +1296,166 @@ enum ParseVersionedConstantsError {
     Parse(#[from] serde_json::Error),
 }
 
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKey {
+    name: String,
+    key: String,
+    rate: Option<NonZeroU32>,
+    burst: Option<NonZeroU32>,
+}
+
+fn quota(rate: Option<NonZeroU32>, burst: Option<NonZeroU32>) -> Option<Quota> {
+    rate.map(|rate| Quota {
+        rate,
+        burst: burst.unwrap_or(rate),
+    })
+}
+
+fn parse_rate_limiter(
+    rate_limit: Option<NonZeroU32>,
+    rate_limit_burst: Option<NonZeroU32>,
+    api_keys_file: Option<PathBuf>,
+    require_api_key: bool,
+) -> Result<Option<RateLimiter>, ParseApiKeysError> {
+    if rate_limit.is_none() && api_keys_file.is_none() {
+        return Ok(None);
+    }
+
+    let mut rate_limiter = RateLimiter::new(quota(rate_limit, rate_limit_burst))
+        .with_required_api_key(require_api_key);
+
+    if let Some(path) = api_keys_file {
+        let file = File::open(path)?;
+        let reader = std::io::BufReader::new(file);
+        let api_keys: Vec<ApiKey> = serde_json::from_reader(reader)?;
+
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        for api_key in api_keys {
+            if !names.insert(api_key.name.clone()) {
+                return Err(ParseApiKeysError::DuplicateName(api_key.name));
+            }
+            if !keys.insert(api_key.key.clone()) {
+                return Err(ParseApiKeysError::DuplicateKey(api_key.name));
+            }
+            rate_limiter = rate_limiter.with_api_key(
+                api_key.name,
+                api_key.key,
+                quota(api_key.rate, api_key.burst),
+            );
+        }
+    }
+
+    Ok(Some(rate_limiter))
+}
+
+fn parse_rate_limiter_or_exit(
+    rate_limit: Option<NonZeroU32>,
+    rate_limit_burst: Option<NonZeroU32>,
+    api_keys_file: Option<PathBuf>,
+    require_api_key: bool,
+) -> Option<RateLimiter> {
+    use clap::error::ErrorKind;
+
+    match parse_rate_limiter(rate_limit, rate_limit_burst, api_keys_file, require_api_key) {
+        Ok(rate_limiter) => rate_limiter,
+        Err(error) => Cli::command()
+            .error(ErrorKind::ValueValidation, error)
+            .exit(),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ParseApiKeysError {
+    #[error("IO error while reading API keys: {0}.")]
+    Io(#[from] std::io::Error),
+    #[error("Parse error while loading API keys: {0}.")]
+    Parse(#[from] serde_json::Error),
+    #[error("API key name {0} is used more than once.")]
+    DuplicateName(String),
+    #[error("The API key of {0} is used by another client.")]
+    DuplicateKey(String),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListenerConfig {
+    address: SocketAddr,
+    versions: Option<Vec<ListenerVersion>>,
+    enabled_methods: Option<Vec<String>>,
+    disabled_methods: Option<Vec<String>>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ListenerVersion {
+    V06,
+    V07,
+    V08,
+    Pathfinder,
+}
+
+impl From<ListenerVersion> for pathfinder_rpc::RpcVersion {
+    fn from(value: ListenerVersion) -> Self {
+        match value {
+            ListenerVersion::V06 => pathfinder_rpc::RpcVersion::V06,
+            ListenerVersion::V07 => pathfinder_rpc::RpcVersion::V07,
+            ListenerVersion::V08 => pathfinder_rpc::RpcVersion::V08,
+            ListenerVersion::Pathfinder => pathfinder_rpc::RpcVersion::PathfinderV01,
+        }
+    }
+}
+
+fn parse_listeners(path: PathBuf) -> Result<Vec<Listener>, ParseListenersError> {
+    let file = File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+    let configs: Vec<ListenerConfig> = serde_json::from_reader(reader)?;
+
+    configs
+        .into_iter()
+        .map(|config| {
+            let method_filter = match (config.enabled_methods, config.disabled_methods) {
+                (Some(_), Some(_)) => {
+                    return Err(ParseListenersError::BothMethodLists(config.address))
+                }
+                (Some(enabled), None) => MethodFilter::Allow(enabled),
+                (None, Some(disabled)) => MethodFilter::Deny(disabled),
+                (None, None) => MethodFilter::All,
+            };
+
+            let listener = Listener::new(config.address).with_method_filter(method_filter);
+            Ok(match config.versions {
+                Some(versions) => {
+                    listener.with_versions(versions.into_iter().map(Into::into).collect())
+                }
+                None => listener,
+            })
+        })
+        .collect()
+}
+
+fn parse_listeners_or_exit(path: PathBuf) -> Vec<Listener> {
+    use clap::error::ErrorKind;
+
+    match parse_listeners(path) {
+        Ok(listeners) => listeners,
+        Err(error) => Cli::command()
+            .error(ErrorKind::ValueValidation, error)
+            .exit(),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ParseListenersError {
+    #[error("IO error while reading RPC listeners: {0}.")]
+    Io(#[from] std::io::Error),
+    #[error("Parse error while loading RPC listeners: {0}.")]
+    Parse(#[from] serde_json::Error),
+    #[error("RPC listener {0} has both enabled and disabled methods, specify only one of them.")]
+    BothMethodLists(SocketAddr),
+}
+
 pub struct Config {
     pub data_directory: PathBuf,
     pub ethereum: Ethereum,
@@ -849,6 +1463,7 @@ pub struct Config {
     pub rpc_cors_domains: Option<AllowedOrigins>,
     pub rpc_root_version: RootRpcVersion,
     pub websocket: WebsocketConfig,
+    pub trace_precomputation: TracePrecomputationConfig,
     pub monitor_address: Option<SocketAddr>,
     pub network: Option<NetworkConfig>,
     pub execution_concurrency: Option<std::num::NonZeroU32>,
@@ -867,6 +1482,7 @@ pub struct Config {
     pub is_rpc_enabled: bool,
     pub gateway_api_key: Option<String>,
     pub gateway_timeout: Duration,
+    pub gateway_record: Option<PathBuf>,
     pub event_filter_cache_size: NonZeroUsize,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_event_filters_to_load: NonZeroUsize,
@@ -875,8 +1491,23 @@ pub struct Config {
     pub versioned_constants_map: VersionedConstantsMap,
     pub feeder_gateway_fetch_concurrency: NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
+    pub gateway_dump_dir: Option<PathBuf>,
+    pub gateway_replay: Option<PathBuf>,
+    pub gateway_replay_timing: bool,
+    pub sync_stop_at_block: Option<BlockNumber>,
     pub shutdown_grace_period: Duration,
     pub fee_estimation_epsilon: Percentage,
+    pub rpc_archive_fallback_url: Option<Url>,
+    pub rpc_store_traces: bool,
+    pub rpc_rate_limiter: Option<RateLimiter>,
+    pub rpc_method_filter: MethodFilter,
+    pub rpc_listeners: Vec<Listener>,
+    pub rpc_request_max_size: NonZeroUsize,
+    pub rpc_request_timeout: Duration,
+    pub rpc_method_timeouts: HashMap<String, Duration>,
+    pub rpc_response_cache_size: Option<NonZeroUsize>,
+    pub rpc_compression: bool,
+    pub rpc_http2: bool,
     pub native_execution: NativeExecutionConfig,
 }
 
@@ -1082,6 +1713,50 @@ impl P2PConfig {
     }
 }
 
+#[cfg(not(feature = "p2p"))]
+fn check_sync_options_or_exit(_: &Cli) {}
+
+/// Exits if options which only apply to feeder gateway sync are used with p2p
+/// sync.
+#[cfg(feature = "p2p")]
+fn check_sync_options_or_exit(cli: &Cli) {
+    use clap::error::ErrorKind;
+
+    if cli.p2p.proxy {
+        return;
+    }
+
+    if cli.trace_precomputation.enabled {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--sync.trace-precomputation is only supported with feeder gateway sync, enable \
+                 --p2p.proxy",
+            )
+            .exit()
+    }
+
+    if cli.gateway_dump_dir.is_some() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--sync.gateway-dump-dir is only supported with feeder gateway sync, enable \
+                 --p2p.proxy",
+            )
+            .exit()
+    }
+
+    if cli.gateway_replay.is_some() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--sync.gateway-replay is only supported with feeder gateway sync, enable \
+                 --p2p.proxy",
+            )
+            .exit()
+    }
+}
+
 #[cfg(feature = "p2p")]
 fn parse_l1_checkpoint_or_exit(
     l1_checkpoint_override: Option<String>,
@@ -1170,23 +1845,42 @@ impl NativeExecutionConfig {
     }
 }
 
+/// What the binary was asked to do: run the node, or one of the offline
+/// [commands](Command).
+pub enum Mode {
+    Node(Box<Config>),
+    Command(Command),
+}
+
+/// Parses the command line, exiting on error.
+pub fn parse() -> Mode {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(command) => Mode::Command(command),
+        None => Mode::Node(Box::new(Config::from_cli(cli))),
+    }
+}
+
 impl Config {
     #[cfg_attr(not(feature = "p2p"), allow(clippy::unit_arg))]
-    pub fn parse() -> Self {
-        let cli = Cli::parse();
-
+    fn from_cli(cli: Cli) -> Self {
+        check_sync_options_or_exit(&cli);
         let network = NetworkConfig::from_components(cli.network);
 
         Config {
             data_directory: cli.data_directory,
             ethereum: Ethereum {
                 password: cli.ethereum_password,
-                url: cli.ethereum_url,
+                url: cli
+                    .ethereum_url
+                    .expect("Required by clap unless a subcommand is given"),
             },
             rpc_address: cli.rpc_address,
             rpc_cors_domains: parse_cors_or_exit(cli.rpc_cors_domains),
             rpc_root_version: cli.rpc_root_version,
             websocket: cli.websocket,
+            trace_precomputation: cli.trace_precomputation,
             monitor_address: cli.monitor_address,
             network,
             execution_concurrency: cli.execution_concurrency,
@@ -1212,6 +1906,7 @@ impl Config {
             get_events_max_uncached_event_filters_to_load: cli
                 .get_events_max_uncached_event_filters_to_load,
             gateway_timeout: Duration::from_secs(cli.gateway_timeout.get()),
+            gateway_record: cli.gateway_record,
             feeder_gateway_fetch_concurrency: cli.feeder_gateway_fetch_concurrency,
             blockchain_history: cli.blockchain_history,
             state_tries: cli.state_tries,
@@ -1220,8 +1915,36 @@ impl Config {
                 .map(parse_versioned_constants_or_exit)
                 .unwrap_or_default(),
             fetch_casm_from_fgw: cli.fetch_casm_from_fgw,
+            gateway_dump_dir: cli.gateway_dump_dir,
+            gateway_replay: cli.gateway_replay,
+            gateway_replay_timing: cli.gateway_replay_timing,
+            // Range is validated by clap.
+            sync_stop_at_block: cli.sync_stop_at_block.map(BlockNumber::new_or_panic),
             shutdown_grace_period: Duration::from_secs(cli.shutdown_grace_period.get()),
             fee_estimation_epsilon: cli.fee_estimation_epsilon,
+            rpc_archive_fallback_url: cli.rpc_archive_fallback_url,
+            rpc_store_traces: cli.rpc_store_traces,
+            rpc_rate_limiter: parse_rate_limiter_or_exit(
+                cli.rpc_rate_limit,
+                cli.rpc_rate_limit_burst,
+                cli.rpc_api_keys_file,
+                cli.rpc_require_api_key,
+            ),
+            rpc_method_filter: if cli.rpc_disabled_methods.is_empty() {
+                MethodFilter::All
+            } else {
+                MethodFilter::Deny(cli.rpc_disabled_methods)
+            },
+            rpc_listeners: cli
+                .rpc_listeners_file
+                .map(parse_listeners_or_exit)
+                .unwrap_or_default(),
+            rpc_request_max_size: cli.rpc_request_max_size,
+            rpc_request_timeout: Duration::from_secs(cli.rpc_request_timeout.get()),
+            rpc_method_timeouts: cli.rpc_method_timeouts.into_iter().collect(),
+            rpc_response_cache_size: cli.rpc_response_cache_size,
+            rpc_compression: cli.rpc_compression,
+            rpc_http2: cli.rpc_http2,
             native_execution: NativeExecutionConfig::parse(cli.native_execution),
         }
     }
@@ -1259,6 +1982,37 @@ pub struct WebsocketConfig {
     pub topic_sender_capacity: NonZeroUsize,
 }
 
+#[derive(clap::Args, Clone)]
+pub struct TracePrecomputationConfig {
+    #[arg(
+        long = "sync.trace-precomputation",
+        long_help = "Compute the traces of each new block in the background once it has been \
+                     synced, and store them in the database. Trace RPC methods are then served \
+                     from the database instead of executing the block.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_SYNC_TRACE_PRECOMPUTATION"
+    )]
+    pub enabled: bool,
+    #[arg(
+        long = "sync.trace-precomputation-concurrency",
+        long_help = "Maximum number of blocks whose traces are computed at the same time.",
+        value_name = "BLOCKS",
+        default_value = "2",
+        env = "PATHFINDER_SYNC_TRACE_PRECOMPUTATION_CONCURRENCY"
+    )]
+    pub concurrency: NonZeroUsize,
+    #[arg(
+        long = "sync.trace-precomputation-backfill",
+        long_help = "Number of blocks below the latest block at startup whose traces are computed \
+                     as well. Blocks which already have traces stored are skipped.",
+        value_name = "BLOCKS",
+        default_value = "0",
+        env = "PATHFINDER_SYNC_TRACE_PRECOMPUTATION_BACKFILL"
+    )]
+    pub backfill: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
@@ -1341,6 +2095,23 @@ mod tests {
         });
     }
 
+    #[test]
+    fn parse_method_timeouts() {
+        assert_eq!(
+            super::parse_method_timeout("starknet_blockNumber=5").unwrap(),
+            (
+                "starknet_blockNumber".to_owned(),
+                std::time::Duration::from_secs(5)
+            )
+        );
+        assert_eq!(
+            super::parse_method_timeout("starknet_blockNumbr=5").unwrap_err(),
+            "Unknown RPC method `starknet_blockNumbr`"
+        );
+        assert!(super::parse_method_timeout("starknet_subscribeEvents=5").is_err());
+        assert!(super::parse_method_timeout("starknet_blockNumber=0").is_err());
+    }
+
     #[test]
     fn parse_versioned_constants_fails_if_file_not_found() {
         assert_matches!(
@@ -0,0 +1,214 @@
+//! Offline database maintenance commands.
+
+use std::num::NonZeroU32;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_lib::state::revert::rollback_to_block;
+use pathfinder_storage::{BlockId, JournalMode, StorageBuilder};
+
+use crate::config::{DbPruneArgs, DbRollbackArgs};
+
+mod verify;
+
+pub use verify::verify;
+
+/// Converts an archive database into a pruned one in place.
+///
+/// Everything happens in a single database transaction, so an interrupted
+/// conversion leaves the database untouched.
+pub fn prune(args: DbPruneArgs) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.database.exists(),
+        "Database {} does not exist",
+        args.database.display()
+    );
+
+    // Use a rollback journal like migrations do, otherwise all deleted pages end up
+    // in the WAL.
+    let storage = StorageBuilder::file(args.database.clone())
+        .journal_mode(JournalMode::Rollback)
+        .migrate()
+        .context("Opening database")?
+        .create_pool(NonZeroU32::new(1).unwrap())?;
+
+    let start = std::time::Instant::now();
+    let mut connection = storage.connection()?;
+    let transaction = connection.transaction()?;
+    if let Some(num_blocks_kept) = args.blockchain_history {
+        tracing::info!(%num_blocks_kept, "Pruning blockchain history");
+        transaction
+            .convert_to_pruned_blockchain(num_blocks_kept)
+            .context("Pruning blockchain history")?;
+    }
+    if let Some(num_blocks_kept) = args.state_tries {
+        tracing::info!(%num_blocks_kept, "Pruning Merkle tries");
+        transaction
+            .convert_to_pruned_tries(num_blocks_kept)
+            .context("Pruning Merkle tries")?;
+    }
+    transaction.commit().context("Committing pruned database")?;
+    drop(connection);
+    tracing::info!(elapsed=?start.elapsed(), "Database pruned");
+
+    if args.vacuum {
+        tracing::info!("Vacuuming database");
+        storage.vacuum()?;
+    }
+
+    let mut flags = Vec::new();
+    if let Some(n) = args.blockchain_history {
+        flags.push(format!("--storage.blockchain-history {n}"));
+    }
+    if let Some(n) = args.state_tries {
+        flags.push(format!("--storage.state-tries {n}"));
+    }
+    tracing::info!("Start the node with `{}` from now on", flags.join(" "));
+
+    Ok(())
+}
+
+/// Rolls the database back to an earlier block, removing all blocks after it.
+///
+/// The Merkle tries are reverted first and the resulting state commitment is
+/// checked against the header of the target block, then the removed blocks are
+/// purged. Like [prune], this happens in a single database transaction.
+pub fn rollback(args: DbRollbackArgs) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.database.exists(),
+        "Database {} does not exist",
+        args.database.display()
+    );
+    let target = BlockNumber::new(args.to).context("Invalid block number")?;
+
+    let storage = StorageBuilder::file(args.database.clone())
+        .journal_mode(JournalMode::Rollback)
+        .migrate()
+        .context("Opening database")?
+        .create_pool(NonZeroU32::new(1).unwrap())?;
+
+    let start = std::time::Instant::now();
+    let mut connection = storage.connection()?;
+    let transaction = connection.transaction()?;
+    let head = transaction
+        .block_number(BlockId::Latest)
+        .context("Querying latest block number")?
+        .context("Database is empty")?;
+    anyhow::ensure!(
+        target <= head,
+        "Requested block {target} is after the latest block {head}"
+    );
+    if target == head {
+        tracing::info!(%head, "Database is already at the requested block");
+        return Ok(());
+    }
+
+    tracing::info!(%head, %target, "Rolling back database");
+    rollback_to_block(&transaction, target).context("Rolling back database")?;
+
+    let new_head = transaction
+        .block_number(BlockId::Latest)
+        .context("Querying latest block number")?;
+    anyhow::ensure!(
+        new_head == Some(target),
+        "Latest block is {new_head:?} after rolling back to {target}"
+    );
+
+    // The running event filter was rebuilt for the new head, store it so that
+    // the node does not start from the stale one.
+    transaction
+        .store_in_memory_state()
+        .context("Committing rolled back database")?;
+    let removed_blocks = head.get() - target.get();
+    tracing::info!(elapsed=?start.elapsed(), %removed_blocks, "Database rolled back");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+    use pathfinder_storage::fake;
+
+    use super::*;
+
+    /// Creates a database file with five blocks with consistent tries.
+    fn setup(dir: &Path) -> PathBuf {
+        let database = dir.join("db.sqlite");
+        // Contract updates are applied to the tries using additional connections.
+        let storage = StorageBuilder::file(database.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(32).unwrap())
+            .unwrap();
+        let blocks = fake::generate::with_config(
+            5,
+            fake::Config {
+                update_tries: Box::new(update_starknet_state),
+                ..Default::default()
+            },
+        );
+        fake::fill(&storage, &blocks, Some(Box::new(update_starknet_state)));
+
+        database
+    }
+
+    fn latest_block(database: PathBuf) -> Option<BlockNumber> {
+        let storage = StorageBuilder::file(database)
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        db.block_number(BlockId::Latest).unwrap()
+    }
+
+    #[test]
+    fn rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path());
+
+        super::rollback(DbRollbackArgs {
+            database: database.clone(),
+            to: 2,
+        })
+        .unwrap();
+
+        assert_eq!(latest_block(database), Some(BlockNumber::new_or_panic(2)));
+    }
+
+    #[test]
+    fn rollback_to_head_is_a_no_op() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path());
+
+        super::rollback(DbRollbackArgs {
+            database: database.clone(),
+            to: 4,
+        })
+        .unwrap();
+
+        assert_eq!(latest_block(database), Some(BlockNumber::new_or_panic(4)));
+    }
+
+    #[test]
+    fn rollback_past_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path());
+
+        let error = super::rollback(DbRollbackArgs {
+            database: database.clone(),
+            to: 5,
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Requested block 5 is after the latest block 4"
+        );
+        assert_eq!(latest_block(database), Some(BlockNumber::new_or_panic(4)));
+    }
+}
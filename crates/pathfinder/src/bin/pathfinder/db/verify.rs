@@ -0,0 +1,1021 @@
+//! Recomputes hashes and commitments from the data stored in a database and
+//! compares them to the stored ones, to detect corruption.
+//!
+//! Blocks are verified in parallel in batches. After each batch the progress
+//! and the first mismatch of each [check](Check) found so far can be recorded
+//! in a file, from which an interrupted verification resumes. Checks which
+//! have found a mismatch are not run for later blocks.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::Path;
+
+use anyhow::Context;
+use pathfinder_block_hashes::BlockHashDb;
+use pathfinder_class_hash::compute_class_hash;
+use pathfinder_common::consts::{
+    MAINNET_GENESIS_HASH,
+    SEPOLIA_INTEGRATION_GENESIS_HASH,
+    SEPOLIA_TESTNET_GENESIS_HASH,
+};
+use pathfinder_common::event::Event;
+use pathfinder_common::prelude::*;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::transaction::Transaction as StarknetTransaction;
+use pathfinder_common::Chain;
+use pathfinder_lib::state::block_hash::{
+    calculate_event_commitment,
+    calculate_receipt_commitment,
+    calculate_transaction_commitment,
+    compute_final_hash,
+    verify_block_hash,
+    BlockHeaderData,
+};
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
+use pathfinder_storage::{BlockId, Storage, StorageBuilder, Transaction};
+use rayon::prelude::*;
+
+use crate::config::DbVerifyArgs;
+
+/// Number of blocks verified between progress updates.
+const BATCH_SIZE: u64 = 1000;
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+enum Check {
+    BlockHash,
+    TransactionCommitment,
+    EventCommitment,
+    ReceiptCommitment,
+    StateDiffCommitment,
+    ClassHash,
+    CasmHash,
+    /// Hashes of the Merkle trie nodes added in the block.
+    TrieNodes,
+    /// The state commitment calculated from the trie roots.
+    StateCommitment,
+}
+
+impl Check {
+    const ALL: [Check; 9] = [
+        Check::BlockHash,
+        Check::TransactionCommitment,
+        Check::EventCommitment,
+        Check::ReceiptCommitment,
+        Check::StateDiffCommitment,
+        Check::ClassHash,
+        Check::CasmHash,
+        Check::TrieNodes,
+        Check::StateCommitment,
+    ];
+
+    fn needs_transactions(self) -> bool {
+        matches!(
+            self,
+            Check::BlockHash
+                | Check::TransactionCommitment
+                | Check::EventCommitment
+                | Check::ReceiptCommitment
+        )
+    }
+
+    fn is_trie(self) -> bool {
+        matches!(self, Check::TrieNodes | Check::StateCommitment)
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Check::BlockHash => "block hash",
+            Check::TransactionCommitment => "transaction commitment",
+            Check::EventCommitment => "event commitment",
+            Check::ReceiptCommitment => "receipt commitment",
+            Check::StateDiffCommitment => "state diff commitment",
+            Check::ClassHash => "class hash",
+            Check::CasmHash => "CASM hash",
+            Check::TrieNodes => "trie node",
+            Check::StateCommitment => "state commitment",
+        })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct Mismatch {
+    block: BlockNumber,
+    details: String,
+}
+
+/// The state of a verification, as recorded in the progress file.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Progress {
+    from: BlockNumber,
+    to: BlockNumber,
+    /// All blocks before this one have been verified.
+    next: BlockNumber,
+    /// The first mismatch found by each check.
+    mismatches: BTreeMap<Check, Mismatch>,
+}
+
+impl Progress {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Opening progress file {}", path.display()))?;
+        serde_json::from_reader(std::io::BufReader::new(file)).context("Parsing progress file")
+    }
+
+    /// Replaces the progress file, so that it is never left half written.
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let json = serde_json::to_vec_pretty(self).context("Serializing progress")?;
+        std::fs::write(&partial, json).context("Writing progress file")?;
+        std::fs::rename(&partial, path).context("Replacing progress file")
+    }
+}
+
+/// What is needed to verify any block.
+struct Verifier {
+    storage: Storage,
+    chain: Chain,
+    chain_id: ChainId,
+    block_hash_db: BlockHashDb,
+    /// Tries are only intact from this block on if they are pruned.
+    oldest_trie: BlockNumber,
+}
+
+/// Verifies a range of blocks, reporting the first mismatch found by each
+/// [check](Check). Fails if there are any mismatches.
+pub fn verify(args: DbVerifyArgs) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.database.exists(),
+        "Database {} does not exist",
+        args.database.display()
+    );
+
+    let threads = args
+        .threads
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, |threads| threads.get());
+    let storage = StorageBuilder::file(args.database.clone())
+        .open_read_only()
+        .context("Opening database")?
+        .create_read_only_pool(
+            NonZeroU32::new(threads.try_into().unwrap_or(u32::MAX)).expect("Threads is non-zero"),
+        )?;
+
+    let mut connection = storage.connection()?;
+    let transaction = connection.transaction()?;
+    let head = transaction
+        .block_number(BlockId::Latest)
+        .context("Querying latest block number")?
+        .context("Database is empty")?;
+    let oldest = transaction
+        .oldest_available_block()
+        .context("Querying oldest available block")?
+        .unwrap_or(BlockNumber::GENESIS);
+    let oldest_trie = transaction
+        .oldest_trie_block()
+        .context("Querying oldest block with trie state")?
+        .unwrap_or(BlockNumber::GENESIS);
+    let (chain, chain_id) = known_chain(&transaction)?;
+    drop(transaction);
+    drop(connection);
+
+    let resumed = match &args.progress {
+        Some(path) if path.exists() => Some(Progress::load(path)?),
+        _ => None,
+    };
+    let mut progress = match resumed {
+        Some(progress) => {
+            anyhow::ensure!(
+                args.from.is_none_or(|from| from == progress.from.get())
+                    && args.to.is_none_or(|to| to == progress.to.get()),
+                "The progress file is for blocks {} to {}, remove it to verify a different range",
+                progress.from,
+                progress.to
+            );
+            tracing::info!(from=%progress.from, to=%progress.to, next=%progress.next, "Resuming verification");
+            progress
+        }
+        None => {
+            let from = match args.from {
+                Some(from) => BlockNumber::new(from).context("Invalid block number")?,
+                None => oldest,
+            };
+            let to = match args.to {
+                Some(to) => BlockNumber::new(to).context("Invalid block number")?,
+                None => head,
+            };
+            Progress {
+                from,
+                to,
+                next: from,
+                mismatches: Default::default(),
+            }
+        }
+    };
+    anyhow::ensure!(
+        progress.from <= progress.to,
+        "First block {} is after the last block {}",
+        progress.from,
+        progress.to
+    );
+    anyhow::ensure!(
+        progress.from >= oldest,
+        "Block {} has been pruned, the oldest available block is {oldest}",
+        progress.from
+    );
+    anyhow::ensure!(
+        progress.to <= head,
+        "Block {} is after the latest block {head}",
+        progress.to
+    );
+    if oldest_trie > progress.next {
+        tracing::info!(%oldest_trie, "Merkle tries are pruned, they are only verified from block {oldest_trie} on");
+    }
+
+    let verifier = Verifier {
+        storage,
+        chain,
+        chain_id,
+        block_hash_db: BlockHashDb::new(chain),
+        oldest_trie,
+    };
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .context("Creating thread pool")?;
+
+    let start = std::time::Instant::now();
+    while progress.next <= progress.to {
+        let checks: Vec<_> = Check::ALL
+            .into_iter()
+            .filter(|check| !progress.mismatches.contains_key(check))
+            .collect();
+        if checks.is_empty() {
+            break;
+        }
+
+        let first = progress.next;
+        let last = std::cmp::min(first + (BATCH_SIZE - 1), progress.to);
+        let batch: Vec<Vec<(Check, Mismatch)>> = pool.install(|| {
+            (first.get()..=last.get())
+                .into_par_iter()
+                .map(|block| {
+                    let block = BlockNumber::new_or_panic(block);
+                    verify_block(&verifier, block, &checks)
+                        .with_context(|| format!("Verifying block {block}"))
+                })
+                .collect::<anyhow::Result<_>>()
+        })?;
+
+        // Blocks are in order, so the first mismatch of each check is kept.
+        for (check, mismatch) in batch.into_iter().flatten() {
+            progress.mismatches.entry(check).or_insert_with(|| {
+                tracing::warn!(block=%mismatch.block, details=%mismatch.details, "{check} mismatch");
+                mismatch
+            });
+        }
+
+        progress.next = last + 1;
+        if let Some(path) = &args.progress {
+            progress.save(path)?;
+        }
+        tracing::info!(block=%last, to=%progress.to, elapsed=?start.elapsed(), "Verified blocks");
+    }
+
+    for check in Check::ALL {
+        match progress.mismatches.get(&check) {
+            Some(mismatch) => {
+                tracing::warn!(block=%mismatch.block, details=%mismatch.details, "First {check} mismatch")
+            }
+            None => tracing::info!("No {check} mismatches"),
+        }
+    }
+    anyhow::ensure!(
+        progress.mismatches.is_empty(),
+        "Found mismatches in {} of {} checks",
+        progress.mismatches.len(),
+        Check::ALL.len()
+    );
+
+    Ok(())
+}
+
+/// The chain of a database, determined from its genesis block. Other than
+/// for the known chains, the chain ID is not needed to verify block hashes.
+fn known_chain(transaction: &Transaction<'_>) -> anyhow::Result<(Chain, ChainId)> {
+    let genesis = transaction
+        .block_hash(BlockNumber::GENESIS.into())
+        .context("Fetching genesis block hash")?;
+
+    Ok(match genesis {
+        Some(MAINNET_GENESIS_HASH) => (Chain::Mainnet, ChainId::MAINNET),
+        Some(SEPOLIA_TESTNET_GENESIS_HASH) => (Chain::SepoliaTestnet, ChainId::SEPOLIA_TESTNET),
+        Some(SEPOLIA_INTEGRATION_GENESIS_HASH) => {
+            (Chain::SepoliaIntegration, ChainId::SEPOLIA_INTEGRATION)
+        }
+        _ => (Chain::Custom, ChainId(Default::default())),
+    })
+}
+
+/// Runs `checks` for `block`, returning a [Mismatch] for each one that fails.
+fn verify_block(
+    verifier: &Verifier,
+    block: BlockNumber,
+    checks: &[Check],
+) -> anyhow::Result<Vec<(Check, Mismatch)>> {
+    let mut connection = verifier
+        .storage
+        .connection()
+        .context("Creating database connection")?;
+    let transaction = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    let mut mismatches = Vec::new();
+    let mut mismatch = |check: Check, details: String| {
+        if checks.contains(&check) {
+            mismatches.push((check, Mismatch { block, details }));
+        }
+    };
+
+    let Some(header) = transaction
+        .block_header(block.into())
+        .context("Fetching block header")?
+    else {
+        for check in checks {
+            mismatch(*check, "Block header is missing".to_owned());
+        }
+        return Ok(mismatches);
+    };
+
+    if checks.iter().any(|check| check.needs_transactions()) {
+        match transaction
+            .transaction_data_for_block(block.into())
+            .context("Fetching transactions")?
+        {
+            Some(data) => verify_transactions(&header, &data, verifier, &mut mismatch)?,
+            None => {
+                for check in checks.iter().filter(|check| check.needs_transactions()) {
+                    mismatch(*check, "Transactions are missing".to_owned());
+                }
+            }
+        }
+    }
+
+    let Some(state_update) = transaction
+        .state_update(block.into())
+        .context("Fetching state update")?
+    else {
+        for check in checks.iter().filter(|check| !check.needs_transactions()) {
+            mismatch(*check, "State update is missing".to_owned());
+        }
+        return Ok(mismatches);
+    };
+
+    // Commitments missing from the header (zero) cannot be verified.
+    if header.state_diff_commitment != StateDiffCommitment::ZERO {
+        let computed = state_update.compute_state_diff_commitment();
+        if computed != header.state_diff_commitment {
+            mismatch(
+                Check::StateDiffCommitment,
+                format!(
+                    "Computed {computed}, stored {}",
+                    header.state_diff_commitment
+                ),
+            );
+        } else if state_update.state_diff_length() != header.state_diff_length {
+            mismatch(
+                Check::StateDiffCommitment,
+                format!(
+                    "State diff length is {}, stored {}",
+                    state_update.state_diff_length(),
+                    header.state_diff_length
+                ),
+            );
+        }
+    }
+
+    if checks.contains(&Check::ClassHash) {
+        if let Some(details) = verify_class_hashes(&transaction, &state_update)? {
+            mismatch(Check::ClassHash, details);
+        }
+    }
+
+    if checks.contains(&Check::CasmHash) {
+        if let Some(details) = verify_casm_hashes(&transaction, &state_update)? {
+            mismatch(Check::CasmHash, details);
+        }
+    }
+
+    if block >= verifier.oldest_trie && checks.iter().any(|check| check.is_trie()) {
+        if checks.contains(&Check::TrieNodes) {
+            if let Some(details) = verify_trie_nodes(&transaction, block, &state_update)? {
+                mismatch(Check::TrieNodes, details);
+            }
+        }
+
+        if let Some(details) = verify_state_commitment(&transaction, &header)? {
+            mismatch(Check::StateCommitment, details);
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Verifies the transaction, event and receipt commitments as well as the
+/// block hash.
+fn verify_transactions(
+    header: &BlockHeader,
+    data: &[(StarknetTransaction, Receipt, Vec<Event>)],
+    verifier: &Verifier,
+    mismatch: &mut impl FnMut(Check, String),
+) -> anyhow::Result<()> {
+    let transactions: Vec<_> = data.iter().map(|(tx, ..)| tx.clone()).collect();
+    let receipts: Vec<_> = data.iter().map(|(_, receipt, _)| receipt.clone()).collect();
+    let events: Vec<_> = data
+        .iter()
+        .map(|(tx, _, events)| (tx.hash, events.as_slice()))
+        .collect();
+
+    // Headers hold the 0.13.2 variants of the commitments of older blocks.
+    let version = header.starknet_version.max(StarknetVersion::V_0_13_2);
+
+    // Commitments missing from the header (zero) cannot be verified.
+    if header.transaction_commitment != TransactionCommitment::ZERO {
+        let computed = calculate_transaction_commitment(&transactions, version)?;
+        if computed != header.transaction_commitment {
+            mismatch(
+                Check::TransactionCommitment,
+                format!(
+                    "Computed {computed}, stored {}",
+                    header.transaction_commitment
+                ),
+            );
+        }
+    }
+    if header.event_commitment != EventCommitment::ZERO {
+        let computed = calculate_event_commitment(&events, version)?;
+        if computed != header.event_commitment {
+            mismatch(
+                Check::EventCommitment,
+                format!("Computed {computed}, stored {}", header.event_commitment),
+            );
+        }
+    }
+    if header.receipt_commitment != ReceiptCommitment::ZERO {
+        let computed = calculate_receipt_commitment(&receipts)?;
+        if computed != header.receipt_commitment {
+            mismatch(
+                Check::ReceiptCommitment,
+                format!("Computed {computed}, stored {}", header.receipt_commitment),
+            );
+        }
+    }
+
+    // The hashes of older blocks commit to the original variants.
+    let mut header_data = BlockHeaderData::from_header(header);
+    if header.starknet_version < StarknetVersion::V_0_13_2 {
+        header_data.transaction_commitment =
+            calculate_transaction_commitment(&transactions, header.starknet_version)?;
+        header_data.event_commitment =
+            calculate_event_commitment(&events, header.starknet_version)?;
+    }
+    let matches = verify_block_hash(header_data, verifier.chain, verifier.chain_id)?.is_match()
+        // Like P2P sync, accept the hashes of blocks which can only be verified with the
+        // latest algorithm, as long as the stored hash is the expected one.
+        || verifier
+            .block_hash_db
+            .block_hash(header.number)
+            .is_some_and(|expected| {
+                header.hash == expected
+                    && compute_final_hash(&BlockHeaderData::from_header(header)) == expected
+            });
+    if !matches {
+        mismatch(
+            Check::BlockHash,
+            format!("Stored hash {} does not match the block", header.hash),
+        );
+    }
+
+    Ok(())
+}
+
+/// Recomputes the hashes of the classes declared in a block, returning the
+/// first mismatch.
+fn verify_class_hashes(
+    transaction: &Transaction<'_>,
+    state_update: &StateUpdate,
+) -> anyhow::Result<Option<String>> {
+    let mut classes: Vec<_> = state_update
+        .declared_cairo_classes
+        .iter()
+        .copied()
+        .chain(
+            state_update
+                .declared_sierra_classes
+                .keys()
+                .map(|sierra| ClassHash(sierra.0)),
+        )
+        .collect();
+    classes.sort();
+
+    for class_hash in classes {
+        let Some(definition) = transaction
+            .class_definition(class_hash)
+            .context("Fetching class definition")?
+        else {
+            return Ok(Some(format!("Class {class_hash} is missing")));
+        };
+
+        match compute_class_hash(&definition) {
+            Ok(computed) if computed.hash() == class_hash => {}
+            Ok(computed) => {
+                return Ok(Some(format!(
+                    "Class {class_hash} hashes to {}",
+                    computed.hash()
+                )))
+            }
+            Err(error) => {
+                return Ok(Some(format!(
+                    "Class {class_hash} could not be hashed: {error:#}"
+                )))
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Recomputes the CASM hashes of the Sierra classes declared in a block,
+/// returning the first mismatch.
+///
+/// CASM which the node compiled itself need not hash to the declared CASM hash,
+/// which is that of the sequencer's compilation. It is compiled again instead.
+fn verify_casm_hashes(
+    transaction: &Transaction<'_>,
+    state_update: &StateUpdate,
+) -> anyhow::Result<Option<String>> {
+    let mut classes: Vec<_> = state_update.declared_sierra_classes.iter().collect();
+    classes.sort();
+
+    for (sierra_hash, casm_hash) in classes {
+        let class_hash = ClassHash(sierra_hash.0);
+        let Some(casm_definition) = transaction
+            .casm_definition(class_hash)
+            .context("Fetching CASM definition")?
+        else {
+            return Ok(Some(format!("CASM of class {sierra_hash} is missing")));
+        };
+
+        let stored_hash = transaction
+            .casm_hash(class_hash)
+            .context("Fetching CASM hash")?;
+        if stored_hash != Some(*casm_hash) {
+            return Ok(Some(format!(
+                "CASM hash of class {sierra_hash} is {stored_hash:?}, declared {casm_hash}"
+            )));
+        }
+
+        if pathfinder_compiler::casm_class_hash(&casm_definition)
+            .is_ok_and(|computed| computed == *casm_hash)
+        {
+            continue;
+        }
+
+        let Some(sierra_definition) = transaction
+            .class_definition(class_hash)
+            .context("Fetching class definition")?
+        else {
+            return Ok(Some(format!("Class {sierra_hash} is missing")));
+        };
+        let compiled = pathfinder_compiler::compile_to_casm(&sierra_definition);
+        if !compiled.is_ok_and(|compiled| compiled == casm_definition) {
+            return Ok(Some(format!(
+                "CASM of class {sierra_hash} does not hash to {casm_hash} and differs from a \
+                 fresh compilation"
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Verifies the hashes of the trie nodes added in `block`, returning the first
+/// mismatch.
+fn verify_trie_nodes(
+    transaction: &Transaction<'_>,
+    block: BlockNumber,
+    state_update: &StateUpdate,
+) -> anyhow::Result<Option<String>> {
+    let parent = block.parent();
+
+    let root = transaction
+        .storage_root_index(block)
+        .context("Querying storage root index")?;
+    let previous = match parent {
+        Some(parent) => transaction
+            .storage_root_index(parent)
+            .context("Querying storage root index")?,
+        None => None,
+    };
+    if let Some(root) = root.filter(|root| Some(*root) != previous) {
+        if let Some(node) = StorageCommitmentTree::verify_nodes(transaction, block, root, previous)?
+        {
+            return Ok(Some(format!("Storage trie node {node}")));
+        }
+    }
+
+    let root = transaction
+        .class_root_index(block)
+        .context("Querying class root index")?;
+    let previous = match parent {
+        Some(parent) => transaction
+            .class_root_index(parent)
+            .context("Querying class root index")?,
+        None => None,
+    };
+    if let Some(root) = root.filter(|root| Some(*root) != previous) {
+        if let Some(node) = ClassCommitmentTree::verify_nodes(transaction, block, root, previous)? {
+            return Ok(Some(format!("Class trie node {node}")));
+        }
+    }
+
+    let mut contracts: Vec<_> = state_update
+        .contract_updates
+        .iter()
+        .filter(|(_, update)| !update.storage.is_empty())
+        .map(|(address, _)| *address)
+        .chain(state_update.system_contract_updates.keys().copied())
+        .collect();
+    contracts.sort();
+
+    for contract in contracts {
+        let root = transaction
+            .contract_root_index(block, contract)
+            .context("Querying contract root index")?;
+        let previous = match parent {
+            Some(parent) => transaction
+                .contract_root_index(parent, contract)
+                .context("Querying contract root index")?,
+            None => None,
+        };
+        if let Some(root) = root.filter(|root| Some(*root) != previous) {
+            if let Some(node) =
+                ContractsStorageTree::verify_nodes(transaction, contract, block, root, previous)?
+            {
+                return Ok(Some(format!(
+                    "Storage trie node {node} of contract {contract}"
+                )));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Compares the state commitment calculated from the roots of the storage and
+/// class tries to the one in the block header.
+fn verify_state_commitment(
+    transaction: &Transaction<'_>,
+    header: &BlockHeader,
+) -> anyhow::Result<Option<String>> {
+    let storage_commitment = match transaction
+        .storage_root_index(header.number)
+        .context("Querying storage root index")?
+    {
+        Some(index) => match transaction
+            .storage_trie_node_hash(index)
+            .context("Fetching storage root hash")?
+        {
+            Some(hash) => StorageCommitment(hash),
+            None => return Ok(Some(format!("Storage trie root {index} is missing"))),
+        },
+        None => StorageCommitment::ZERO,
+    };
+    let class_commitment = match transaction
+        .class_root_index(header.number)
+        .context("Querying class root index")?
+    {
+        Some(index) => match transaction
+            .class_trie_node_hash(index)
+            .context("Fetching class root hash")?
+        {
+            Some(hash) => ClassCommitment(hash),
+            None => return Ok(Some(format!("Class trie root {index} is missing"))),
+        },
+        None => ClassCommitment::ZERO,
+    };
+
+    let computed = StateCommitment::calculate(storage_commitment, class_commitment);
+    Ok((computed != header.state_commitment).then(|| {
+        format!(
+            "Calculated {computed} from the trie roots, stored {}",
+            header.state_commitment
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use pathfinder_common::state_update::{StateUpdateError, StateUpdateRef};
+    use pathfinder_crypto::Felt;
+    use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+    use pathfinder_storage::fake::{self, Block};
+    use pathfinder_storage::{JournalMode, Node, NodeRef, StoredNode, TrieUpdate};
+    use starknet_gateway_test_fixtures::class_definitions::{
+        CAIRO_1_1_0_RC0_SIERRA,
+        CONTRACT_DEFINITION,
+        CONTRACT_DEFINITION_CLASS_HASH,
+        DUMMY_ACCOUNT,
+    };
+
+    use super::*;
+
+    const NUM_BLOCKS: usize = 8;
+    /// The block declaring a Cairo and a Sierra class.
+    const CLASSES: usize = 5;
+
+    /// The first block corrupted for each check, see [setup].
+    const CORRUPTED: [(Check, u64); 9] = [
+        (Check::BlockHash, 0),
+        (Check::TransactionCommitment, 1),
+        (Check::EventCommitment, 2),
+        (Check::ReceiptCommitment, 3),
+        (Check::StateDiffCommitment, 4),
+        (Check::ClassHash, CLASSES as u64),
+        (Check::CasmHash, CLASSES as u64),
+        (Check::StateCommitment, 6),
+        (Check::TrieNodes, 7),
+    ];
+
+    /// Blocks in which everything verifies.
+    fn blocks() -> Vec<Block> {
+        let mut blocks = fake::generate::with_config(
+            NUM_BLOCKS,
+            fake::Config {
+                calculate_block_hash: Box::new(|header: &BlockHeader| {
+                    compute_final_hash(&BlockHeaderData::from_header(header))
+                }),
+                calculate_transaction_commitment: Box::new(calculate_transaction_commitment),
+                calculate_receipt_commitment: Box::new(calculate_receipt_commitment),
+                calculate_event_commitment: Box::new(calculate_event_commitment),
+                update_tries: Box::new(update_starknet_state),
+                // Fake class definitions don't hash to their class hashes.
+                occurrence: fake::OccurrencePerBlock {
+                    cairo: 0..=0,
+                    sierra: 0..=0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let sierra_hash = SierraHash(compute_class_hash(CAIRO_1_1_0_RC0_SIERRA).unwrap().hash().0);
+        let casm = pathfinder_compiler::compile_to_casm(CAIRO_1_1_0_RC0_SIERRA).unwrap();
+        let casm_hash = pathfinder_compiler::casm_class_hash(&casm).unwrap();
+
+        let block = &mut blocks[CLASSES];
+        let state_update = block.state_update.as_mut().unwrap();
+        state_update
+            .declared_cairo_classes
+            .insert(CONTRACT_DEFINITION_CLASS_HASH);
+        state_update
+            .declared_sierra_classes
+            .insert(sierra_hash, casm_hash);
+        block.header.header.state_diff_length = state_update.state_diff_length();
+        block.header.header.state_diff_commitment = state_update.compute_state_diff_commitment();
+        block
+            .cairo_defs
+            .push((CONTRACT_DEFINITION_CLASS_HASH, CONTRACT_DEFINITION.to_vec()));
+        block
+            .sierra_defs
+            .push((sierra_hash, CAIRO_1_1_0_RC0_SIERRA.to_vec(), casm));
+
+        // The Sierra class changes the class trie, so the state commitments and
+        // block hashes have to be computed again.
+        let scratch = StorageBuilder::in_memory().unwrap();
+        fake::fill(&scratch, &blocks, Some(Box::new(update_starknet_state)));
+        let mut db = scratch.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let mut parent = (BlockHash::ZERO, StateCommitment::ZERO);
+        for block in &mut blocks {
+            let header = &mut block.header.header;
+            let storage_commitment = db
+                .storage_root_index(header.number)
+                .unwrap()
+                .map(|index| StorageCommitment(db.storage_trie_node_hash(index).unwrap().unwrap()))
+                .unwrap_or_default();
+            let class_commitment = db.class_root(header.number).unwrap().unwrap_or_default();
+            header.state_commitment =
+                StateCommitment::calculate(storage_commitment, class_commitment);
+            header.parent_hash = parent.0;
+            header.hash = compute_final_hash(&BlockHeaderData::from_header(header));
+
+            let state_update = block.state_update.as_mut().unwrap();
+            state_update.block_hash = header.hash;
+            state_update.state_commitment = header.state_commitment;
+            state_update.parent_state_commitment = parent.1;
+
+            parent = (header.hash, header.state_commitment);
+        }
+
+        blocks
+    }
+
+    /// Creates a database from [blocks], corrupting one item for each
+    /// [CORRUPTED] check if `corrupt` is set.
+    fn setup(dir: &Path, corrupt: bool) -> PathBuf {
+        let mut blocks = blocks();
+        if corrupt {
+            blocks[0].header.header.hash = BlockHash(Felt::ONE);
+            blocks[1].header.header.transaction_commitment = TransactionCommitment(Felt::ONE);
+            blocks[2].transaction_data[0].2.push(Event {
+                data: vec![],
+                from_address: ContractAddress(Felt::ONE),
+                keys: vec![],
+            });
+            blocks[3].transaction_data[0].1.actual_fee = Fee(Felt::ONE);
+            blocks[4].header.header.state_diff_commitment = StateDiffCommitment(Felt::ONE);
+            blocks[CLASSES].cairo_defs[0].1 = DUMMY_ACCOUNT.to_vec();
+            blocks[CLASSES].sierra_defs[0].2 = b"corrupt".to_vec();
+            blocks[6].header.header.state_commitment = StateCommitment(Felt::ONE);
+        }
+
+        let database = dir.join("db.sqlite");
+        let storage = StorageBuilder::file(database.clone())
+            .journal_mode(JournalMode::Rollback)
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let update_tries: fake::UpdateTriesFn = if corrupt {
+            Box::new(update_and_corrupt_tries)
+        } else {
+            Box::new(update_starknet_state)
+        };
+        fake::fill(&storage, &blocks, Some(update_tries));
+
+        database
+    }
+
+    /// Updates the tries like [update_starknet_state], and then replaces the
+    /// storage trie root of block 7 with a copy with a wrong hash.
+    fn update_and_corrupt_tries(
+        tx: &Transaction<'_>,
+        state_update: StateUpdateRef<'_>,
+        verify_hashes: bool,
+        block: BlockNumber,
+        storage: Storage,
+    ) -> Result<(StorageCommitment, ClassCommitment), StateUpdateError> {
+        let commitments = update_starknet_state(tx, state_update, verify_hashes, block, storage)?;
+        if block.get() != 7 {
+            return Ok(commitments);
+        }
+
+        let root = tx.storage_root_index(block).unwrap().unwrap();
+        let node = match tx.storage_trie_node(root).unwrap().unwrap() {
+            StoredNode::Binary { left, right } => Node::Binary {
+                left: NodeRef::StorageIndex(left),
+                right: NodeRef::StorageIndex(right),
+            },
+            StoredNode::Edge { child, path } => Node::Edge {
+                child: NodeRef::StorageIndex(child),
+                path,
+            },
+            leaf => unreachable!("Trie root {leaf:?} is a leaf"),
+        };
+        let update = TrieUpdate {
+            nodes_added: vec![(Felt::ONE, node)],
+            nodes_removed: vec![],
+            root_commitment: Felt::ONE,
+        };
+        let root = tx.insert_storage_trie(&update, block).unwrap();
+        tx.insert_storage_root(block, root).unwrap();
+
+        Ok(commitments)
+    }
+
+    fn args(database: PathBuf, progress: PathBuf, threads: usize) -> DbVerifyArgs {
+        DbVerifyArgs {
+            database,
+            from: None,
+            to: None,
+            threads: NonZeroUsize::new(threads),
+            progress: Some(progress),
+        }
+    }
+
+    fn first_mismatches(progress: &Path) -> BTreeMap<Check, u64> {
+        Progress::load(progress)
+            .unwrap()
+            .mismatches
+            .into_iter()
+            .map(|(check, mismatch)| (check, mismatch.block.get()))
+            .collect()
+    }
+
+    #[test]
+    fn no_mismatches() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path(), false);
+        let progress = dir.path().join("progress.json");
+
+        verify(args(database, progress.clone(), 1)).unwrap();
+
+        let progress = Progress::load(&progress).unwrap();
+        assert_eq!(progress.next.get(), NUM_BLOCKS as u64);
+        assert!(progress.mismatches.is_empty());
+    }
+
+    #[test]
+    fn first_mismatch_of_each_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path(), true);
+
+        for threads in [1, 4] {
+            let progress = dir.path().join(format!("progress-{threads}.json"));
+
+            let error = verify(args(database.clone(), progress.clone(), threads)).unwrap_err();
+
+            assert_eq!(error.to_string(), "Found mismatches in 9 of 9 checks");
+            assert_eq!(
+                first_mismatches(&progress),
+                BTreeMap::from(CORRUPTED),
+                "{threads} threads"
+            );
+        }
+    }
+
+    #[test]
+    fn resumes_from_the_progress_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path(), true);
+        let progress = dir.path().join("progress.json");
+        Progress {
+            from: BlockNumber::GENESIS,
+            to: BlockNumber::new_or_panic(NUM_BLOCKS as u64 - 1),
+            next: BlockNumber::new_or_panic(CLASSES as u64),
+            mismatches: BTreeMap::from([(
+                Check::BlockHash,
+                Mismatch {
+                    block: BlockNumber::GENESIS,
+                    details: "Found before resuming".to_owned(),
+                },
+            )]),
+        }
+        .save(&progress)
+        .unwrap();
+
+        verify(args(database, progress.clone(), 1)).unwrap_err();
+
+        // Blocks before the one to resume from are not verified again.
+        let expected: BTreeMap<_, _> = CORRUPTED
+            .into_iter()
+            .filter(|(check, block)| *check == Check::BlockHash || *block >= CLASSES as u64)
+            .collect();
+        assert_eq!(first_mismatches(&progress), expected);
+        let progress = Progress::load(&progress).unwrap();
+        assert_eq!(
+            progress.mismatches[&Check::BlockHash].details,
+            "Found before resuming"
+        );
+    }
+
+    #[test]
+    fn progress_file_for_another_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = setup(dir.path(), false);
+        let progress = dir.path().join("progress.json");
+        Progress {
+            from: BlockNumber::GENESIS,
+            to: BlockNumber::new_or_panic(3),
+            next: BlockNumber::new_or_panic(2),
+            mismatches: Default::default(),
+        }
+        .save(&progress)
+        .unwrap();
+
+        let error = verify(DbVerifyArgs {
+            from: Some(1),
+            ..args(database, progress.clone(), 1)
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The progress file is for blocks 0 to 3, remove it to verify a different range"
+        );
+        assert_eq!(
+            Progress::load(&progress).unwrap().next,
+            BlockNumber::new_or_panic(2)
+        );
+    }
+}
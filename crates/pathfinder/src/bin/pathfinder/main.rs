@@ -26,6 +26,8 @@ use tracing::{info, warn};
 use crate::config::{NetworkConfig, StateTries};
 
 mod config;
+mod db;
+mod snapshot;
 mod update;
 
 // The Cairo VM allocates felts on the stack, so during execution it's making
@@ -41,19 +43,47 @@ fn main() -> anyhow::Result<()> {
         .build()
         .unwrap()
         .block_on(async {
-            async_main().await?;
+            if std::env::var_os("RUST_LOG").is_none() {
+                // Disable all dependency logs by default.
+                std::env::set_var("RUST_LOG", "pathfinder=info");
+            }
+
+            match config::parse() {
+                config::Mode::Node(config) => {
+                    async_main(*config).await?;
+                }
+                config::Mode::Command(command) => {
+                    setup_tracing(config::Color::Auto, false, false);
+                    run_command(command).await?;
+                }
+            }
+
             Ok(())
         })
 }
 
-async fn async_main() -> anyhow::Result<Storage> {
-    if std::env::var_os("RUST_LOG").is_none() {
-        // Disable all dependency logs by default.
-        std::env::set_var("RUST_LOG", "pathfinder=info");
+/// Runs one of the offline [commands](config::Command) instead of the node.
+async fn run_command(command: config::Command) -> anyhow::Result<()> {
+    match command {
+        config::Command::Snapshot(config::SnapshotCommand::Export(args)) => {
+            util::task::spawn_blocking(move |_| snapshot::export(args)).await?
+        }
+        config::Command::Snapshot(config::SnapshotCommand::Import(args)) => {
+            util::task::spawn_blocking(move |_| snapshot::import(args)).await?
+        }
+        config::Command::Db(config::DbCommand::Prune(args)) => {
+            util::task::spawn_blocking(move |_| db::prune(args)).await?
+        }
+        config::Command::Db(config::DbCommand::Rollback(args)) => {
+            util::task::spawn_blocking(move |_| db::rollback(args)).await?
+        }
+        config::Command::Db(config::DbCommand::Verify(args)) => {
+            util::task::spawn_blocking(move |_| db::verify(args)).await?
+        }
     }
+}
 
-    let config = config::Config::parse();
-
+async fn async_main(config: config::Config) -> anyhow::Result<Storage> {
     setup_tracing(
         config.color,
         config.debug.pretty_log,
@@ -105,19 +135,45 @@ async fn async_main() -> anyhow::Result<Storage> {
         NetworkConfig::Custom { .. } => "custom",
     };
 
-    let pathfinder_context = PathfinderContext::configure_and_proxy_check(
+    let gateway_dumps = config
+        .gateway_dump_dir
+        .clone()
+        .map(starknet_gateway_client::LocalGateway::new)
+        .transpose()
+        .context("Opening gateway dumps")?;
+
+    let mut pathfinder_context = PathfinderContext::configure_and_proxy_check(
         network,
         &config.data_directory,
         config.gateway_api_key.clone(),
         config.gateway_timeout,
+        gateway_dumps.as_ref(),
     )
     .await
     .context("Configuring pathfinder")?;
 
+    if let Some(path) = &config.gateway_record {
+        pathfinder_context.gateway = pathfinder_context
+            .gateway
+            .with_recording(path)
+            .context("Creating gateway recording")?;
+    }
+
     verify_networks(pathfinder_context.network, ethereum.chain)?;
 
-    let gateway_public_key = pathfinder_context
-        .gateway
+    // Sync reads L2 data from local gateway dumps or a recording instead of the
+    // feeder gateway, if configured.
+    let sync_gateway: Arc<dyn GatewayApi + Send + Sync> =
+        match (gateway_dumps, &config.gateway_replay) {
+            (Some(gateway_dumps), _) => Arc::new(gateway_dumps),
+            (None, Some(path)) => Arc::new(
+                starknet_gateway_client::Client::replay(path, config.gateway_replay_timing)
+                    .context("Opening gateway recording")?,
+            ),
+            (None, None) => Arc::new(pathfinder_context.gateway.clone()),
+        };
+
+    let gateway_public_key = sync_gateway
         .public_key()
         .await
         .context("Fetching Starknet gateway public key")?;
@@ -196,7 +252,7 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     verify_database(
         &sync_storage,
         pathfinder_context.network,
-        &pathfinder_context.gateway,
+        sync_gateway.as_ref(),
     )
     .await
     .context("Verifying database")?;
@@ -226,6 +282,9 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         versioned_constants_map: config.versioned_constants_map.clone(),
         native_execution: config.native_execution.is_enabled(),
         native_class_cache_size: config.native_execution.class_cache_size(),
+        request_max_size: config.rpc_request_max_size.get(),
+        request_timeout: config.rpc_request_timeout,
+        method_timeouts: config.rpc_method_timeouts.clone(),
     };
 
     let notifications = Notifications::default();
@@ -252,6 +311,61 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         context
     };
 
+    let context = match config.rpc_archive_fallback_url.clone() {
+        Some(url) => context.with_archive_fallback(
+            pathfinder_rpc::ArchiveFallback::new(url).context("Creating archive fallback")?,
+        ),
+        None => context,
+    };
+
+    let context = match config.rpc_response_cache_size {
+        Some(capacity) => context.with_response_cache(capacity),
+        None => context,
+    };
+
+    // Pre-computed traces are only useful if trace methods read them.
+    let context = if config.rpc_store_traces || config.trace_precomputation.enabled {
+        let trace_storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context(
+                r"Creating database connection pool for trace storage
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+            )?;
+        context.with_trace_store(trace_storage)
+    } else {
+        context
+    };
+
+    let trace_precomputation = if config.trace_precomputation.enabled {
+        let concurrency = config.trace_precomputation.concurrency;
+        // One connection per block being traced, and one for finding the blocks.
+        let pool_size = u32::try_from(concurrency.get() + 1)
+            .ok()
+            .and_then(NonZeroU32::new)
+            .context("Trace pre-computation concurrency is too large")?;
+        let storage = storage_manager.create_pool(pool_size).context(
+            r"Creating database connection pool for trace pre-computation
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+        )?;
+
+        Some(state::traces::TracePrecomputationConfig {
+            storage,
+            chain_id: pathfinder_context.network_id,
+            eth_fee_address: pathfinder_context.contract_addresses.eth_l2_token_address,
+            strk_fee_address: pathfinder_context.contract_addresses.strk_l2_token_address,
+            versioned_constants_map: config.versioned_constants_map.clone(),
+            native_class_cache: context.native_class_cache.clone(),
+            concurrency,
+            backfill: config.trace_precomputation.backfill,
+        })
+    } else {
+        None
+    };
+
     let default_version = match config.rpc_root_version {
         config::RootRpcVersion::V06 => pathfinder_rpc::RpcVersion::V06,
         config::RootRpcVersion::V07 => pathfinder_rpc::RpcVersion::V07,
@@ -263,6 +377,17 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         Some(ref allowed_origins) => rpc_server.with_cors(allowed_origins.clone()),
         None => rpc_server,
     };
+    let rpc_server = match config.rpc_rate_limiter {
+        Some(ref rate_limiter) => rpc_server.with_rate_limiter(rate_limiter.clone()),
+        None => rpc_server,
+    };
+    let mut rpc_server = rpc_server
+        .with_compression(config.rpc_compression)
+        .with_http2(config.rpc_http2)
+        .with_method_filter(config.rpc_method_filter.clone());
+    for listener in &config.rpc_listeners {
+        rpc_server = rpc_server.with_listener(listener.clone());
+    }
 
     // Spawn monitoring if configured.
     if let Some(address) = config.monitor_address {
@@ -302,6 +427,7 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         start_sync(
             sync_storage,
             pathfinder_context,
+            sync_gateway,
             ethereum.client,
             sync_state.clone(),
             &config,
@@ -311,6 +437,7 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
             gateway_public_key,
             p2p_client,
             config.verify_tree_hashes,
+            trace_precomputation,
         )
     } else {
         tokio::task::spawn(futures::future::pending())
@@ -322,8 +449,10 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
             .spawn()
             .await
         {
-            Ok((rpc_handle, on)) => {
-                info!(%on, "📡 RPC server started");
+            Ok((rpc_handle, addresses)) => {
+                for on in addresses {
+                    info!(%on, "📡 RPC server started");
+                }
                 rpc_handle
             }
             Err(error) => tokio::task::spawn(std::future::ready(Err(
@@ -556,6 +685,7 @@ async fn start_p2p(
 fn start_sync(
     storage: Storage,
     pathfinder_context: PathfinderContext,
+    sync_gateway: Arc<dyn GatewayApi + Send + Sync>,
     ethereum_client: EthereumClient,
     sync_state: Arc<SyncState>,
     config: &config::Config,
@@ -565,11 +695,13 @@ fn start_sync(
     gateway_public_key: pathfinder_common::PublicKey,
     p2p_client: Option<p2p::client::peer_agnostic::Client>,
     verify_tree_hashes: bool,
+    trace_precomputation: Option<state::traces::TracePrecomputationConfig>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     if config.p2p.proxy {
         start_feeder_gateway_sync(
             storage,
             pathfinder_context,
+            sync_gateway,
             ethereum_client,
             sync_state,
             config,
@@ -577,6 +709,7 @@ fn start_sync(
             websocket_txs,
             notifications,
             gateway_public_key,
+            trace_precomputation,
         )
     } else {
         let p2p_client = p2p_client.expect("P2P client is expected with the p2p feature enabled");
@@ -585,9 +718,11 @@ fn start_sync(
             pathfinder_context,
             ethereum_client,
             p2p_client,
+            sync_state,
             gateway_public_key,
             config.p2p.l1_checkpoint_override,
             verify_tree_hashes,
+            config.sync_stop_at_block,
         )
     }
 }
@@ -597,6 +732,7 @@ fn start_sync(
 fn start_sync(
     storage: Storage,
     pathfinder_context: PathfinderContext,
+    sync_gateway: Arc<dyn GatewayApi + Send + Sync>,
     ethereum_client: EthereumClient,
     sync_state: Arc<SyncState>,
     config: &config::Config,
@@ -606,10 +742,12 @@ fn start_sync(
     gateway_public_key: pathfinder_common::PublicKey,
     _p2p_client: Option<p2p::client::peer_agnostic::Client>,
     _verify_tree_hashes: bool,
+    trace_precomputation: Option<state::traces::TracePrecomputationConfig>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     start_feeder_gateway_sync(
         storage,
         pathfinder_context,
+        sync_gateway,
         ethereum_client,
         sync_state,
         config,
@@ -617,6 +755,7 @@ fn start_sync(
         websocket_txs,
         notifications,
         gateway_public_key,
+        trace_precomputation,
     )
 }
 
@@ -624,6 +763,7 @@ fn start_sync(
 fn start_feeder_gateway_sync(
     storage: Storage,
     pathfinder_context: PathfinderContext,
+    sync_gateway: Arc<dyn GatewayApi + Send + Sync>,
     ethereum_client: EthereumClient,
     sync_state: Arc<SyncState>,
     config: &config::Config,
@@ -631,6 +771,7 @@ fn start_feeder_gateway_sync(
     websocket_txs: Option<pathfinder_rpc::TopicBroadcasters>,
     notifications: Notifications,
     gateway_public_key: pathfinder_common::PublicKey,
+    trace_precomputation: Option<state::traces::TracePrecomputationConfig>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     let sync_context = SyncContext {
         storage,
@@ -638,7 +779,7 @@ fn start_feeder_gateway_sync(
         chain: pathfinder_context.network,
         chain_id: pathfinder_context.network_id,
         core_address: pathfinder_context.contract_addresses.l1_contract_address,
-        sequencer: pathfinder_context.gateway,
+        sequencer: sync_gateway,
         state: sync_state.clone(),
         head_poll_interval: config.poll_interval,
         l1_poll_interval: config.l1_poll_interval,
@@ -652,9 +793,16 @@ fn start_feeder_gateway_sync(
         sequencer_public_key: gateway_public_key,
         fetch_concurrency: config.feeder_gateway_fetch_concurrency,
         fetch_casm_from_fgw: config.fetch_casm_from_fgw,
+        trace_precomputation,
+        stop_at_block: config.sync_stop_at_block,
     };
 
-    util::task::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync))
+    let storage = sync_context.storage.clone();
+    util::task::spawn(sync_until_stopped(
+        state::sync(sync_context, state::l1::sync, state::l2::sync),
+        storage,
+        sync_state,
+    ))
 }
 
 #[cfg(feature = "p2p")]
@@ -663,14 +811,16 @@ fn start_p2p_sync(
     pathfinder_context: PathfinderContext,
     ethereum_client: EthereumClient,
     p2p_client: p2p::client::peer_agnostic::Client,
+    sync_state: Arc<SyncState>,
     gateway_public_key: pathfinder_common::PublicKey,
     l1_checkpoint_override: Option<pathfinder_ethereum::EthereumStateUpdate>,
     verify_tree_hashes: bool,
+    stop_at_block: Option<BlockNumber>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     use pathfinder_block_hashes::BlockHashDb;
 
     let sync = pathfinder_lib::sync::Sync {
-        storage,
+        storage: storage.clone(),
         p2p: p2p_client,
         eth_client: ethereum_client,
         eth_address: pathfinder_context.contract_addresses.l1_contract_address,
@@ -680,8 +830,53 @@ fn start_p2p_sync(
         l1_checkpoint_override,
         verify_tree_hashes,
         block_hash_db: Some(BlockHashDb::new(pathfinder_context.network)),
+        stop_at_block,
     };
-    util::task::spawn(sync.run())
+    util::task::spawn(sync_until_stopped(sync.run(), storage, sync_state))
+}
+
+/// Runs sync, which only ends without an error once `--sync.stop-at-block` is
+/// reached. The node then keeps serving the state at the stop block, reports
+/// itself as synced and marks [SyncState::stopped_at].
+async fn sync_until_stopped(
+    sync: impl std::future::Future<Output = anyhow::Result<()>>,
+    storage: Storage,
+    sync_state: Arc<SyncState>,
+) -> anyhow::Result<()> {
+    use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
+
+    sync.await?;
+
+    let (number, hash) = util::task::spawn_blocking(move |_| {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.block_id(pathfinder_storage::BlockId::Latest)
+            .context("Querying latest block")
+    })
+    .await
+    .context("Joining blocking task")??
+    .context("Sync stopped without any blocks")?;
+    let stopped_at = NumberedBlock::from((hash, number));
+
+    {
+        let mut status = sync_state.status.write().await;
+        let starting = match &*status {
+            Syncing::Status(status) => status.starting,
+            Syncing::False => stopped_at,
+        };
+        *status = Syncing::Status(Status {
+            starting,
+            current: stopped_at,
+            highest: stopped_at,
+        });
+    }
+    *sync_state.stopped_at.write().await = Some(number);
+    metrics::gauge!("sync_stopped_at_block", number.get() as f64);
+
+    info!(block=%number, "Sync stopped, serving the state at the stop block");
+    std::future::pending().await
 }
 
 /// Spawns the monitoring task at the given address.
@@ -780,7 +975,7 @@ mod pathfinder_context {
     use pathfinder_ethereum::core_addr;
     use pathfinder_rpc::context::EthContractAddresses;
     use reqwest::Url;
-    use starknet_gateway_client::Client as GatewayClient;
+    use starknet_gateway_client::{Client as GatewayClient, GatewayApi, LocalGateway};
 
     use super::PathfinderContext;
     use crate::config::NetworkConfig;
@@ -791,6 +986,7 @@ mod pathfinder_context {
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
+            gateway_dumps: Option<&LocalGateway>,
         ) -> anyhow::Result<Self> {
             let context = match cfg {
                 NetworkConfig::Mainnet => Self {
@@ -828,6 +1024,7 @@ mod pathfinder_context {
                     data_directory,
                     api_key,
                     gateway_timeout,
+                    gateway_dumps,
                 )
                 .await
                 .context("Configuring custom network")?,
@@ -840,6 +1037,9 @@ mod pathfinder_context {
         /// additional verification by checking for a proxy gateway by
         /// comparing against L1 starknet address against of
         /// the known networks.
+        ///
+        /// The L1 addresses are read from the gateway dumps instead of the
+        /// feeder gateway if there are any.
         async fn configure_custom(
             gateway: Url,
             feeder: Url,
@@ -847,9 +1047,9 @@ mod pathfinder_context {
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
+            gateway_dumps: Option<&LocalGateway>,
         ) -> anyhow::Result<Self> {
             use pathfinder_crypto::Felt;
-            use starknet_gateway_client::GatewayApi;
 
             let gateway = GatewayClient::with_urls(gateway, feeder, gateway_timeout)
                 .context("Creating gateway client")?
@@ -858,7 +1058,11 @@ mod pathfinder_context {
             let network_id =
                 ChainId(Felt::from_be_slice(chain_id.as_bytes()).context("Parsing chain ID")?);
 
-            let reply_contract_addresses = gateway
+            let source: &dyn GatewayApi = match gateway_dumps {
+                Some(gateway_dumps) => gateway_dumps,
+                None => &gateway,
+            };
+            let reply_contract_addresses = source
                 .eth_contract_addresses()
                 .await
                 .context("Downloading starknet L1 address from gateway for proxy check")?;
@@ -918,7 +1122,7 @@ fn verify_networks(starknet: Chain, ethereum: EthereumChain) -> anyhow::Result<(
 async fn verify_database(
     storage: &Storage,
     network: Chain,
-    gateway_client: &starknet_gateway_client::Client,
+    gateway_client: &dyn GatewayApi,
 ) -> anyhow::Result<()> {
     let storage = storage.clone();
 
@@ -1019,3 +1223,77 @@ impl From<BlockchainHistory> for pathfinder_storage::pruning::BlockchainHistoryM
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
+    use pathfinder_storage::StorageBuilder;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stopped_sync_reports_the_stop_block() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        pathfinder_storage::test_utils::fill_test_storage(&storage, 3, 1);
+        let (number, hash) = {
+            let mut db = storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.block_id(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap()
+        };
+        let stopped_at = NumberedBlock::from((hash, number));
+        let starting = NumberedBlock::from((Default::default(), BlockNumber::GENESIS));
+
+        let sync_state = Arc::new(SyncState::default());
+        *sync_state.status.write().await = Syncing::Status(Status {
+            starting,
+            current: starting,
+            highest: NumberedBlock::from((Default::default(), BlockNumber::new_or_panic(100))),
+        });
+
+        let handle = tokio::spawn(sync_until_stopped(
+            async { Ok(()) },
+            storage,
+            sync_state.clone(),
+        ));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sync_state.stopped_at.read().await.is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Sync should be marked as stopped");
+
+        assert_eq!(*sync_state.stopped_at.read().await, Some(number));
+        assert_eq!(
+            *sync_state.status.read().await,
+            Syncing::Status(Status {
+                starting,
+                current: stopped_at,
+                highest: stopped_at,
+            })
+        );
+        // The node keeps serving the stop block.
+        assert!(!handle.is_finished());
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn sync_errors_are_returned() {
+        let sync_state = Arc::new(SyncState::default());
+
+        let result = sync_until_stopped(
+            async { Err(anyhow::anyhow!("Sync failed")) },
+            StorageBuilder::in_memory().unwrap(),
+            sync_state.clone(),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "Sync failed");
+        assert_eq!(*sync_state.stopped_at.read().await, None);
+    }
+}
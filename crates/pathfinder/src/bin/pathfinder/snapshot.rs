@@ -0,0 +1,646 @@
+//! Database snapshot export and import.
+//!
+//! A snapshot consists of two files:
+//! - the database itself, compressed with Zstandard, and
+//! - a JSON [manifest](Manifest) stored next to it with a `.manifest.json`
+//!   suffix, describing the snapshot contents and its checksum.
+//!
+//! Export takes a transactionally consistent copy of the database using
+//! `VACUUM INTO` so it can run against the database of a node that is still
+//! syncing. The copy is then optionally rolled back to the requested block,
+//! compressed and checksummed. Import verifies the checksum, the schema
+//! revision, the chain and the state commitment of the head block before
+//! moving the restored database into place.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use pathfinder_common::consts::{
+    MAINNET_GENESIS_HASH,
+    SEPOLIA_INTEGRATION_GENESIS_HASH,
+    SEPOLIA_TESTNET_GENESIS_HASH,
+};
+use pathfinder_common::prelude::*;
+use pathfinder_lib::state::revert::rollback_to_block;
+use pathfinder_storage::{BlockId, JournalMode, StorageBuilder, Transaction};
+use sha3::{Digest, Sha3_256};
+
+use crate::config::{SnapshotExportArgs, SnapshotImportArgs};
+
+/// Describes the contents of a snapshot file.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    /// The database schema revision (SQLite `user_version`).
+    schema_revision: usize,
+    /// Human readable chain ID, e.g. `SN_MAIN`.
+    chain_id: String,
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    state_commitment: StateCommitment,
+    /// Hex encoded SHA3-256 digest of the compressed snapshot file.
+    sha3_256: String,
+    /// Size of the uncompressed database in bytes.
+    database_size: u64,
+}
+
+fn manifest_path(snapshot: &Path) -> PathBuf {
+    let mut path = snapshot.as_os_str().to_owned();
+    path.push(".manifest.json");
+    path.into()
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".partial");
+    path.into()
+}
+
+pub fn export(args: SnapshotExportArgs) -> anyhow::Result<()> {
+    let manifest_path = manifest_path(&args.output);
+    anyhow::ensure!(
+        !args.output.exists(),
+        "Output file {} already exists",
+        args.output.display()
+    );
+    anyhow::ensure!(
+        !manifest_path.exists(),
+        "Manifest file {} already exists",
+        manifest_path.display()
+    );
+
+    let copy_path = partial_path(&args.output);
+
+    tracing::info!(database=%args.database.display(), "Copying database");
+    let started = std::time::Instant::now();
+    let source = StorageBuilder::file(args.database.clone())
+        .open_read_only()
+        .context("Opening source database")?
+        .create_read_only_pool(NonZeroU32::new(1).unwrap())
+        .context("Creating source database connection pool")?;
+    source
+        .vacuum_into(&copy_path)
+        .context("Copying source database")?;
+    drop(source);
+    tracing::info!(elapsed=?started.elapsed(), "Database copied");
+
+    let result = export_copy(&args, &copy_path, &manifest_path);
+
+    // The uncompressed copy is only needed while the snapshot is being created.
+    if let Err(error) = std::fs::remove_file(&copy_path) {
+        tracing::warn!(%error, path=%copy_path.display(), "Failed to remove database copy");
+    }
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&args.output);
+    }
+
+    result
+}
+
+fn export_copy(
+    args: &SnapshotExportArgs,
+    copy_path: &Path,
+    manifest_path: &Path,
+) -> anyhow::Result<()> {
+    let storage = StorageBuilder::file(copy_path.to_path_buf())
+        .journal_mode(JournalMode::Rollback)
+        .migrate()
+        .context("Opening database copy")?
+        .create_pool(NonZeroU32::new(1).unwrap())
+        .context("Creating database copy connection pool")?;
+    let mut connection = storage
+        .connection()
+        .context("Creating database connection")?;
+    let transaction = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    if let Some(target) = args.block {
+        let target = BlockNumber::new(target).context("Invalid block number")?;
+        let head = transaction
+            .block_number(BlockId::Latest)
+            .context("Querying latest block number")?
+            .context("Database is empty")?;
+        anyhow::ensure!(
+            target <= head,
+            "Requested block {target} is after the latest block {head}"
+        );
+
+        if target < head {
+            tracing::info!(%head, %target, "Rolling back database copy");
+            rollback_to_block(&transaction, target).context("Rolling back database copy")?;
+        }
+    }
+
+    let header = transaction
+        .block_header(BlockId::Latest)
+        .context("Fetching latest block header")?
+        .context("Database is empty")?;
+    let chain_id = match &args.chain_id {
+        Some(chain_id) => chain_id.clone(),
+        None => known_chain_id(&transaction)?
+            .context("Custom network detected, please specify the chain ID")?
+            .as_str()
+            .to_owned(),
+    };
+
+    // Persists the running event filter so that the imported database does not
+    // have to rebuild it on first start.
+    transaction
+        .store_in_memory_state()
+        .context("Committing database copy")?;
+    drop(connection);
+    drop(storage);
+
+    let schema_revision = pathfinder_storage::database_schema_revision(copy_path)?;
+    let database_size = std::fs::metadata(copy_path)
+        .context("Reading database copy size")?
+        .len();
+
+    tracing::info!(output=%args.output.display(), block=%header.number, "Compressing snapshot");
+    let started = std::time::Instant::now();
+    let input = File::open(copy_path).context("Opening database copy")?;
+    let output = File::create_new(&args.output).context("Creating snapshot file")?;
+    let mut output = HashingWriter::new(BufWriter::new(output));
+    zstd::stream::copy_encode(BufReader::new(input), &mut output, args.compression_level)
+        .context("Compressing database")?;
+    let (output, digest, compressed_size) = output.finish();
+    output
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Flushing snapshot file")?
+        .sync_all()
+        .context("Syncing snapshot file")?;
+
+    let manifest = Manifest {
+        schema_revision,
+        chain_id,
+        block_number: header.number,
+        block_hash: header.hash,
+        state_commitment: header.state_commitment,
+        sha3_256: digest,
+        database_size,
+    };
+    let manifest_file = File::create_new(manifest_path).context("Creating manifest file")?;
+    serde_json::to_writer_pretty(manifest_file, &manifest).context("Writing manifest")?;
+
+    tracing::info!(
+        block=%manifest.block_number,
+        %database_size,
+        %compressed_size,
+        elapsed=?started.elapsed(),
+        "Snapshot exported"
+    );
+
+    Ok(())
+}
+
+pub fn import(args: SnapshotImportArgs) -> anyhow::Result<()> {
+    let manifest_path = manifest_path(&args.input);
+    let manifest = File::open(&manifest_path)
+        .with_context(|| format!("Opening manifest file {}", manifest_path.display()))?;
+    let manifest: Manifest =
+        serde_json::from_reader(BufReader::new(manifest)).context("Parsing manifest")?;
+
+    anyhow::ensure!(
+        !args.database.exists(),
+        "Database file {} already exists, snapshots can only be imported into a fresh data \
+         directory",
+        args.database.display()
+    );
+    anyhow::ensure!(
+        manifest.chain_id == args.chain_id,
+        "Snapshot is for chain {} but {} was expected",
+        manifest.chain_id,
+        args.chain_id
+    );
+
+    let latest_revision = pathfinder_storage::latest_schema_revision();
+    anyhow::ensure!(
+        manifest.schema_revision <= latest_revision,
+        "Snapshot schema revision {} is newer than the latest revision supported by this version \
+         of pathfinder ({latest_revision})",
+        manifest.schema_revision
+    );
+
+    let partial = partial_path(&args.database);
+    let result = import_into(&args, &manifest, &partial);
+    match result {
+        Ok(()) => std::fs::rename(&partial, &args.database).context("Moving database into place"),
+        Err(error) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(error)
+        }
+    }
+}
+
+fn import_into(
+    args: &SnapshotImportArgs,
+    manifest: &Manifest,
+    partial: &Path,
+) -> anyhow::Result<()> {
+    tracing::info!(input=%args.input.display(), block=%manifest.block_number, "Decompressing snapshot");
+    let started = std::time::Instant::now();
+    let input = File::open(&args.input).context("Opening snapshot file")?;
+    let mut input = HashingReader::new(BufReader::new(input));
+    let output = File::create_new(partial).context("Creating database file")?;
+    let mut output = BufWriter::new(output);
+    zstd::stream::copy_decode(&mut input, &mut output).context("Decompressing snapshot")?;
+    output
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Flushing database file")?
+        .sync_all()
+        .context("Syncing database file")?;
+
+    let digest = input.finish().context("Reading snapshot")?;
+    anyhow::ensure!(
+        digest == manifest.sha3_256,
+        "Snapshot checksum mismatch: expected {}, got {digest}",
+        manifest.sha3_256
+    );
+    tracing::info!(elapsed=?started.elapsed(), "Snapshot decompressed and checksum verified");
+
+    let schema_revision = pathfinder_storage::database_schema_revision(partial)?;
+    anyhow::ensure!(
+        schema_revision == manifest.schema_revision,
+        "Database schema revision {schema_revision} does not match the manifest ({})",
+        manifest.schema_revision
+    );
+
+    let storage = StorageBuilder::file(partial.to_path_buf())
+        .journal_mode(JournalMode::Rollback)
+        .migrate()
+        .context("Opening restored database")?
+        .create_pool(NonZeroU32::new(1).unwrap())
+        .context("Creating database connection pool")?;
+    let mut connection = storage
+        .connection()
+        .context("Creating database connection")?;
+    let transaction = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    let header = transaction
+        .block_header(BlockId::Latest)
+        .context("Fetching latest block header")?
+        .context("Restored database is empty")?;
+    anyhow::ensure!(
+        header.number == manifest.block_number && header.hash == manifest.block_hash,
+        "Latest block {} ({}) does not match the manifest {} ({})",
+        header.number,
+        header.hash,
+        manifest.block_number,
+        manifest.block_hash
+    );
+
+    if let Some(chain_id) = known_chain_id(&transaction)? {
+        anyhow::ensure!(
+            chain_id.as_str() == manifest.chain_id,
+            "Genesis block belongs to {} but the manifest claims {}",
+            chain_id.as_str(),
+            manifest.chain_id
+        );
+    }
+
+    let state_commitment = state_commitment_from_tries(&transaction, header.number)?;
+    anyhow::ensure!(
+        state_commitment == header.state_commitment
+            && state_commitment == manifest.state_commitment,
+        "State commitment mismatch: header {}, manifest {}, calculated from tries {}",
+        header.state_commitment,
+        manifest.state_commitment,
+        state_commitment
+    );
+
+    tracing::info!(
+        block=%header.number,
+        %state_commitment,
+        database=%args.database.display(),
+        "Snapshot imported"
+    );
+
+    Ok(())
+}
+
+/// Returns the chain ID of a known network based on the genesis block hash,
+/// or [None] for custom networks.
+fn known_chain_id(transaction: &Transaction<'_>) -> anyhow::Result<Option<ChainId>> {
+    let genesis = transaction
+        .block_hash(BlockNumber::GENESIS.into())
+        .context("Fetching genesis block hash")?;
+
+    Ok(match genesis {
+        Some(MAINNET_GENESIS_HASH) => Some(ChainId::MAINNET),
+        Some(SEPOLIA_TESTNET_GENESIS_HASH) => Some(ChainId::SEPOLIA_TESTNET),
+        Some(SEPOLIA_INTEGRATION_GENESIS_HASH) => Some(ChainId::SEPOLIA_INTEGRATION),
+        _ => None,
+    })
+}
+
+/// Calculates the state commitment from the storage and class trie root nodes
+/// stored for `block`.
+fn state_commitment_from_tries(
+    transaction: &Transaction<'_>,
+    block: BlockNumber,
+) -> anyhow::Result<StateCommitment> {
+    let storage_commitment = match transaction
+        .storage_root_index(block)
+        .context("Querying storage root index")?
+    {
+        Some(index) => transaction
+            .storage_trie_node_hash(index)
+            .context("Fetching storage root hash")?
+            .map(StorageCommitment)
+            .context("Storage root node is missing")?,
+        None => StorageCommitment::ZERO,
+    };
+    let class_commitment = transaction
+        .class_root(block)
+        .context("Fetching class root hash")?
+        .unwrap_or(ClassCommitment::ZERO);
+
+    Ok(StateCommitment::calculate(
+        storage_commitment,
+        class_commitment,
+    ))
+}
+
+/// A [Write] adapter which calculates the SHA3-256 digest of the data written.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha3_256,
+    bytes: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha3_256::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Returns the inner writer, the hex encoded digest and the number of bytes
+    /// written.
+    fn finish(self) -> (W, String, u64) {
+        (
+            self.inner,
+            format!("{:x}", self.hasher.finalize()),
+            self.bytes,
+        )
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A [Read] adapter which calculates the SHA3-256 digest of the data read.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha3_256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha3_256::new(),
+        }
+    }
+
+    /// Consumes the rest of the input and returns the hex encoded digest of
+    /// everything read.
+    fn finish(mut self) -> std::io::Result<String> {
+        // The decoder may stop before the end of input, e.g. on trailing
+        // garbage. Make sure the digest covers the whole file.
+        std::io::copy(&mut self, &mut std::io::sink())?;
+        Ok(format!("{:x}", self.hasher.finalize()))
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+    use pathfinder_storage::fake::{self, Block};
+
+    use super::*;
+
+    const CHAIN_ID: &str = "SN_TEST";
+
+    /// Creates `source.sqlite` in `directory` holding five blocks with
+    /// consistent tries.
+    fn source_database(directory: &Path) -> (PathBuf, Vec<Block>) {
+        let path = directory.join("source.sqlite");
+        // Contract updates are applied to the tries using additional connections.
+        let storage = StorageBuilder::file(path.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(32).unwrap())
+            .unwrap();
+        let blocks = fake::generate::with_config(
+            5,
+            fake::Config {
+                update_tries: Box::new(update_starknet_state),
+                occurrence: fake::OccurrencePerBlock {
+                    cairo: 1..=3,
+                    sierra: 1..=3,
+                    storage: 1..=3,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        fake::fill(&storage, &blocks, Some(Box::new(update_starknet_state)));
+
+        (path, blocks)
+    }
+
+    fn export_at(directory: &Path, database: PathBuf, block: Option<u64>) -> PathBuf {
+        let output = directory.join("snapshot.zst");
+        export(SnapshotExportArgs {
+            database,
+            output: output.clone(),
+            block,
+            chain_id: Some(CHAIN_ID.to_owned()),
+            compression_level: 3,
+        })
+        .unwrap();
+        output
+    }
+
+    fn import_args(input: PathBuf, database: PathBuf) -> SnapshotImportArgs {
+        SnapshotImportArgs {
+            input,
+            database,
+            chain_id: CHAIN_ID.to_owned(),
+        }
+    }
+
+    fn latest_block(database: &Path) -> Option<(BlockNumber, BlockHash)> {
+        let storage = StorageBuilder::file(database.to_path_buf())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.block_id(BlockId::Latest).unwrap()
+    }
+
+    #[test]
+    fn round_trip_at_block() {
+        let directory = tempfile::tempdir().unwrap();
+        let (source, blocks) = source_database(directory.path());
+        let snapshot = export_at(directory.path(), source.clone(), Some(2));
+
+        let manifest = File::open(manifest_path(&snapshot)).unwrap();
+        let manifest: Manifest = serde_json::from_reader(manifest).unwrap();
+        let target = &blocks[2].header.header;
+        assert_eq!(manifest.chain_id, CHAIN_ID);
+        assert_eq!(manifest.block_number, target.number);
+        assert_eq!(manifest.block_hash, target.hash);
+        assert_eq!(manifest.state_commitment, target.state_commitment);
+        assert_eq!(
+            manifest.schema_revision,
+            pathfinder_storage::latest_schema_revision()
+        );
+
+        // The source database is not rolled back, nor is the copy left behind.
+        let head = &blocks.last().unwrap().header.header;
+        assert_eq!(latest_block(&source), Some((head.number, head.hash)));
+        assert!(!partial_path(&snapshot).exists());
+
+        let restored = directory.path().join("restored.sqlite");
+        import(import_args(snapshot, restored.clone())).unwrap();
+        assert_eq!(latest_block(&restored), Some((target.number, target.hash)));
+        assert!(!partial_path(&restored).exists());
+    }
+
+    #[test]
+    fn corrupted_snapshot_is_rejected() {
+        let directory = tempfile::tempdir().unwrap();
+        let (source, _) = source_database(directory.path());
+        let snapshot = export_at(directory.path(), source, None);
+
+        let mut contents = std::fs::read(&snapshot).unwrap();
+        let middle = contents.len() / 2;
+        contents[middle] ^= 0xff;
+        std::fs::write(&snapshot, contents).unwrap();
+
+        let restored = directory.path().join("restored.sqlite");
+        import(import_args(snapshot, restored.clone())).unwrap_err();
+        assert!(!restored.exists());
+        assert!(!partial_path(&restored).exists());
+    }
+
+    #[test]
+    fn wrong_chain_is_rejected() {
+        let directory = tempfile::tempdir().unwrap();
+        let (source, _) = source_database(directory.path());
+        let snapshot = export_at(directory.path(), source, None);
+
+        let restored = directory.path().join("restored.sqlite");
+        let error = import(SnapshotImportArgs {
+            chain_id: "SN_MAIN".to_owned(),
+            ..import_args(snapshot, restored.clone())
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Snapshot is for chain SN_TEST but SN_MAIN was expected"
+        );
+        assert!(!restored.exists());
+    }
+
+    #[test]
+    fn block_after_head_is_rejected() {
+        let directory = tempfile::tempdir().unwrap();
+        let (source, _) = source_database(directory.path());
+        let output = directory.path().join("snapshot.zst");
+
+        let error = export(SnapshotExportArgs {
+            database: source,
+            output: output.clone(),
+            block: Some(5),
+            chain_id: Some(CHAIN_ID.to_owned()),
+            compression_level: 3,
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Requested block 5 is after the latest block 4"
+        );
+        assert!(!output.exists());
+        assert!(!manifest_path(&output).exists());
+        assert!(!partial_path(&output).exists());
+    }
+
+    #[test]
+    fn hashing_reader_and_writer_agree() {
+        let data = b"pathfinder snapshot".repeat(1000);
+
+        let mut writer = HashingWriter::new(Vec::new());
+        writer.write_all(&data).unwrap();
+        let (written, write_digest, bytes) = writer.finish();
+        assert_eq!(written, data);
+        assert_eq!(bytes, data.len() as u64);
+
+        let mut reader = HashingReader::new(&data[..]);
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).unwrap();
+        assert_eq!(reader.finish().unwrap(), write_digest);
+    }
+
+    #[test]
+    fn hashing_reader_propagates_read_errors() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::UnexpectedEof.into())
+            }
+        }
+
+        let partial: &[u8] = b"partial";
+        let reader = HashingReader::new(partial.chain(Failing));
+        assert_eq!(
+            reader.finish().unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn sidecar_paths() {
+        assert_eq!(
+            manifest_path(Path::new("/snapshots/mainnet.zst")),
+            PathBuf::from("/snapshots/mainnet.zst.manifest.json")
+        );
+        assert_eq!(
+            partial_path(Path::new("/data/mainnet.sqlite")),
+            PathBuf::from("/data/mainnet.sqlite.partial")
+        );
+    }
+}
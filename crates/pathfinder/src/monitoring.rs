@@ -55,12 +55,24 @@ async fn ready_route(axum::extract::State(state): axum::extract::State<State>) -
     }
 }
 
-/// Returns `Ok` if `readiness == true`, or `SERVICE_UNAVAILABLE` otherwise.
+/// Returns `Ok` if `readiness == true` and sync is within 6 blocks of the
+/// chain tip, or `SERVICE_UNAVAILABLE` otherwise.
+///
+/// Once sync has stopped at `--sync.stop-at-block`, the stop block is reported
+/// as the chain tip, so the node is synced. The response body then says where
+/// sync stopped, to tell such a node apart from one following the chain.
 async fn synced_route(
     axum::extract::State(state): axum::extract::State<State>,
-) -> http::StatusCode {
+) -> (http::StatusCode, String) {
     if !state.readiness.load(std::sync::atomic::Ordering::Relaxed) {
-        return http::StatusCode::SERVICE_UNAVAILABLE;
+        return (http::StatusCode::SERVICE_UNAVAILABLE, String::new());
+    }
+
+    if let Some(stopped_at) = *state.sync.stopped_at.read().await {
+        return (
+            http::StatusCode::OK,
+            format!("Sync stopped at block {stopped_at}"),
+        );
     }
 
     let status = { state.sync.status.read().await.clone() };
@@ -68,9 +80,9 @@ async fn synced_route(
         Syncing::Status(status)
             if status.highest.number.get() - status.current.number.get() < 6 =>
         {
-            http::StatusCode::OK
+            (http::StatusCode::OK, String::new())
         }
-        _ => http::StatusCode::SERVICE_UNAVAILABLE,
+        _ => (http::StatusCode::SERVICE_UNAVAILABLE, String::new()),
     }
 }
 
@@ -160,6 +172,8 @@ mod tests {
         let handle = PrometheusBuilder::new().build_recorder().handle();
         let sync_state = Arc::new(SyncState {
             status: RwLock::new(Syncing::False),
+            stopped_at: RwLock::new(None),
+            l1_accepted: RwLock::new(None),
         });
         let (addr, _) = super::spawn_server(
             ([127, 0, 0, 1], 0),
@@ -217,6 +231,14 @@ mod tests {
         });
         let resp = client.get(url.clone()).send().await.unwrap();
         assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert_eq!(resp.text().await.unwrap(), "");
+
+        // A stopped node reports itself as synced, and says where it stopped.
+        *sync_state.status.write().await = Syncing::False;
+        *sync_state.stopped_at.write().await = Some(BlockNumber::new_or_panic(42));
+        let resp = client.get(url.clone()).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert_eq!(resp.text().await.unwrap(), "Sync stopped at block 42");
     }
 
     #[tokio::test]
@@ -1,4 +1,4 @@
 pub mod block_hash;
 mod sync;
 
-pub use sync::{l1, l2, revert, sync, SyncContext, RESET_DELAY_ON_FAILURE};
+pub use sync::{l1, l2, revert, sync, traces, SyncContext, RESET_DELAY_ON_FAILURE};
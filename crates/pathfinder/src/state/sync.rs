@@ -3,6 +3,7 @@ pub mod l1;
 pub mod l2;
 mod pending;
 pub mod revert;
+pub mod traces;
 
 use std::future::Future;
 use std::sync::Arc;
@@ -88,6 +89,10 @@ pub struct SyncContext<G, E> {
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
+    /// Pre-compute and store block traces in the background.
+    pub trace_precomputation: Option<traces::TracePrecomputationConfig>,
+    /// Sync stops once this block is committed, see [sync].
+    pub stop_at_block: Option<BlockNumber>,
 }
 
 impl<G, E> From<&SyncContext<G, E>> for L1SyncContext<E>
@@ -118,11 +123,16 @@ where
             sequencer_public_key: value.sequencer_public_key,
             fetch_concurrency: value.fetch_concurrency,
             fetch_casm_from_fgw: value.fetch_casm_from_fgw,
+            stop_at_block: value.stop_at_block,
         }
     }
 }
 
 /// Implements the main sync loop, where L1 and L2 sync results are combined.
+///
+/// Only returns without an error if a stop block is configured. No blocks past
+/// it are synced, pending data is not polled, and once it is committed all
+/// other sync tasks are stopped.
 pub async fn sync<Ethereum, SequencerClient, F1, F2, L1Sync, L2Sync>(
     context: SyncContext<SequencerClient, Ethereum>,
     mut l1_sync: L1Sync,
@@ -166,6 +176,8 @@ where
         sequencer_public_key: _,
         fetch_concurrency: _,
         fetch_casm_from_fgw,
+        trace_precomputation,
+        stop_at_block,
     } = context;
 
     let mut db_conn = storage
@@ -185,6 +197,17 @@ where
         Ok(l2_head)
     })?;
 
+    if let (Some(stop), Some((head, ..))) = (stop_at_block, l2_head) {
+        anyhow::ensure!(
+            head <= stop,
+            "Latest block {head} in the database is past the stop block {stop}"
+        );
+        if head == stop {
+            tracing::info!(block=%stop, "Already synced up to the stop block");
+            return Ok(());
+        }
+    }
+
     // Get the latest block from the sequencer
     let gateway_latest = sequencer
         .head()
@@ -236,6 +259,16 @@ where
 
     let (current_num, current_hash, _) = l2_head.unwrap_or_default();
     let (tx_current, rx_current) = tokio::sync::watch::channel((current_num, current_hash));
+
+    if let Some(config) = trace_precomputation {
+        let traces =
+            traces::precompute_traces(config, rx_current.clone(), notifications.reorgs.subscribe());
+        util::task::spawn(async move {
+            if let Err(error) = traces.await {
+                tracing::error!(?error, "Trace pre-computation terminated");
+            }
+        });
+    }
     let consumer_context = ConsumerContext {
         storage: storage.clone(),
         state,
@@ -247,18 +280,44 @@ where
     let mut consumer_handle =
         util::task::spawn(consumer(event_receiver, consumer_context, tx_current));
 
-    let mut pending_handle = util::task::spawn(pending::poll_pending(
-        event_sender.clone(),
-        sequencer.clone(),
-        Duration::from_secs(2),
-        storage.clone(),
-        rx_latest.clone(),
-        rx_current.clone(),
-        fetch_casm_from_fgw,
-    ));
+    let mut pending_handle = match stop_at_block {
+        // Pending data would be past the stop block.
+        Some(_) => util::task::spawn(std::future::pending()),
+        None => util::task::spawn(pending::poll_pending(
+            event_sender.clone(),
+            sequencer.clone(),
+            Duration::from_secs(2),
+            storage.clone(),
+            rx_latest.clone(),
+            rx_current.clone(),
+            fetch_casm_from_fgw,
+        )),
+    };
 
     loop {
         tokio::select! {
+            stop = stop_block_committed(rx_current.clone(), stop_at_block) => {
+                tracing::info!(block=%stop, "Synced up to the stop block, stopping sync");
+
+                l1_handle.abort();
+                l2_handle.abort();
+                pending_handle.abort();
+                latest_handle.abort();
+
+                _ = l1_handle.await;
+                _ = l2_handle.await;
+                _ = pending_handle.await;
+                _ = latest_handle.await;
+
+                // Let the consumer process what is left, such as L1 updates.
+                drop(event_sender);
+                consumer_handle
+                    .await
+                    .context("Joining sync consumer task")?
+                    .context("Sync consumer task")?;
+
+                return Ok(());
+            },
             _ = &mut pending_handle => {
                 tracing::error!("Pending tracking task ended unexpectedly");
 
@@ -438,6 +497,8 @@ async fn consumer(
         .with_retry()
         .context("Enabling retries for database connection")?;
 
+    publish_l1_accepted(&mut db_conn, &state).await?;
+
     let (mut latest_timestamp, mut next_number) = tokio::task::block_in_place(|| {
         let tx = db_conn
             .transaction()
@@ -458,6 +519,7 @@ async fn consumer(
             L1Update(update) => {
                 tracing::trace!("Updating L1 sync to block {}", update.block_number);
                 l1_update(&mut db_conn, &update).await?;
+                publish_l1_accepted(&mut db_conn, &state).await?;
                 tracing::info!("L1 sync updated to block {}", update.block_number);
             }
             Block(
@@ -498,6 +560,7 @@ async fn consumer(
                 )
                 .await
                 .with_context(|| format!("Update L2 state to {block_number}"))?;
+                publish_l1_accepted(&mut db_conn, &state).await?;
                 let block_time = last_block_start.elapsed();
                 let update_t = update_t.elapsed();
                 last_block_start = std::time::Instant::now();
@@ -573,6 +636,7 @@ async fn consumer(
                 l2_reorg(&mut db_conn, reorg_tail, &mut notifications)
                     .await
                     .with_context(|| format!("Reorg L2 state to {reorg_tail:?}"))?;
+                publish_l1_accepted(&mut db_conn, &state).await?;
 
                 next_number = reorg_tail;
 
@@ -693,6 +757,28 @@ async fn latest_n_blocks(
     })
 }
 
+/// Resolves to the stop block once it is committed, or never if there is none.
+async fn stop_block_committed(
+    mut current: tokio::sync::watch::Receiver<(BlockNumber, BlockHash)>,
+    stop_at_block: Option<BlockNumber>,
+) -> BlockNumber {
+    let Some(stop) = stop_at_block else {
+        return std::future::pending().await;
+    };
+
+    // The hash is zero until the first block is committed to an empty database.
+    if current
+        .wait_for(|(number, hash)| *number >= stop && *hash != BlockHash::ZERO)
+        .await
+        .is_err()
+    {
+        // The consumer has exited, which is handled separately.
+        return std::future::pending().await;
+    }
+
+    stop
+}
+
 /// Periodically updates sync state with the latest block height.
 ///
 /// If feature `p2p` is enabled and node type is `proxy`
@@ -752,6 +838,21 @@ async fn update_sync_status_latest(
     tracing::info!("Channel closed, exiting latest poll task");
 }
 
+/// Makes the latest L1-accepted block available to RPC, see
+/// [SyncState::l1_accepted]. It can change with L1 updates, new blocks and
+/// reorgs.
+async fn publish_l1_accepted(connection: &mut Connection, state: &SyncState) -> anyhow::Result<()> {
+    let l1_accepted = tokio::task::block_in_place(|| {
+        connection
+            .transaction()
+            .context("Creating database transaction")?
+            .l1_l2_pointer()
+            .context("Querying L1-L2 pointer")
+    })?;
+    *state.l1_accepted.write().await = l1_accepted;
+    Ok(())
+}
+
 async fn l1_update(
     connection: &mut Connection,
     update: &EthereumStateUpdate,
@@ -984,7 +1085,7 @@ async fn l2_reorg(
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        let mut head = transaction
+        let head = transaction
             .block_id(pathfinder_storage::BlockId::Latest)
             .context("Querying latest block number")?
             .context("Latest block number is none during reorg")?
@@ -1000,87 +1101,59 @@ Blockchain history must include the reorg tail and its parent block to perform a
             );
         };
 
-        // Roll back Merkle trie updates.
-        //
-        // If we're rolling back genesis then there will be no blocks left so state will
-        // be empty.
-        if let Some(target_block) = reorg_tail.parent() {
-            let Some(target_header) = transaction
-                .block_header(target_block.into())
-                .context("Fetching target block header")?
-            else {
-                anyhow::bail!(
-                    r"Reorg tail parent (block number: {target_block}) does not exist (likely due to blockchain history pruning).
-Blockchain history must include the reorg tail and its parent block to perform a reorg."
-                );
-            };
-            revert::revert_starknet_state(&transaction, head, target_block, target_header)?;
-        }
-
+        let last_block_number = head;
         let head_hash = transaction
             .block_hash(head.into())
             .context("Fetching last block hash")?
             .context("Expected last block hash to exist because reorg tail exists")?;
 
-        // Purge each block one at a time.
-        //
-        // This is done 1-by-1 to allow sending the reorg'd block data
-        // to websocket subscriptions while keeping a constant memory footprint.
-        //
-        // This is acceptable performance because reorgs are rare and need not be
-        // 100% optimal. However a large reorg could cause a massive memory spike
-        // which is not acceptable.
-        while head >= reorg_tail {
-            transaction
-                .purge_block(head)
-                .with_context(|| format!("Purging block {head} from database"))?;
-
-            // No further blocks to purge if we just purged genesis.
-            if head == BlockNumber::GENESIS {
-                break;
-            }
-
-            head -= 1;
-        }
-
-        transaction
-            .reset_in_memory_state(head)
-            .context("Resetting in-memory DB state after reorg")?;
-
-        // Track combined L1 and L2 state.
-        let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
-        if let Some(l1_l2_head) = l1_l2_head {
-            if reorg_tail == BlockNumber::GENESIS {
-                // If we purged genesis then unset the L1 L2 pointer as well since there
-                // are now no blocks remaining.
+        // Remembered so that subscriptions resuming from a reorged block can be
+        // told about the reorg.
+        let orphaned = (reorg_tail.get()..=head.get())
+            .map(|number| {
                 transaction
-                    .update_l1_l2_pointer(None)
-                    .context("Unsetting L1-L2 head")?;
-            } else if l1_l2_head >= reorg_tail {
-                transaction
-                    .update_l1_l2_pointer(Some(reorg_tail - 1))
-                    .context("Updating L1-L2 head")?;
+                    .block_hash(BlockNumber::new_or_panic(number).into())
+                    .context("Fetching reorged block hash")?
+                    .context("Expected reorged block hash to exist because reorg tail exists")
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // If we're rolling back genesis then there will be no blocks left so state will
+        // be empty.
+        let new_head = match reorg_tail.parent() {
+            Some(target_block) => {
+                let Some(target_hash) = transaction
+                    .block_hash(target_block.into())
+                    .context("Fetching target block hash")?
+                else {
+                    anyhow::bail!(
+                        r"Reorg tail parent (block number: {target_block}) does not exist (likely due to blockchain history pruning).
+Blockchain history must include the reorg tail and its parent block to perform a reorg."
+                    );
+                };
+                revert::rollback_to_block(&transaction, target_block)?;
+                Some((target_block, target_hash))
             }
-        }
+            None => {
+                revert::purge_blocks(&transaction, head, reorg_tail)?;
+                None
+            }
+        };
 
         transaction
             .commit()
             .context("Commit database transaction")?;
 
-        notifications
-            .reorgs
-            .send(
-                Reorg {
-                    first_block_number: reorg_tail,
-                    first_block_hash: reorg_tail_hash,
-                    last_block_number: head,
-                    last_block_hash: head_hash,
-                }
-                .into(),
-            )
-            // Ignore errors in case nobody is listening. New listeners may subscribe in the
-            // future.
-            .ok();
+        notifications.send_reorg(
+            Reorg {
+                first_block_number: reorg_tail,
+                first_block_hash: reorg_tail_hash,
+                last_block_number,
+                last_block_hash: head_hash,
+                new_head,
+            },
+            orphaned,
+        );
 
         Ok(())
     })
@@ -1878,6 +1951,253 @@ mod tests {
         consumer(event_rx, context, tx).await.unwrap();
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn consumer_publishes_l1_accepted_block() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(5);
+
+        let blocks = generate_block_data();
+        let (a, b, c, d, e) = blocks[0].clone();
+        let block_hash = a.0.block_hash;
+        let state_root = a.0.state_commitment;
+
+        event_tx
+            .send(SyncEvent::Block(a, b, c, d, e))
+            .await
+            .unwrap();
+        event_tx
+            .send(SyncEvent::L1Update(
+                pathfinder_ethereum::EthereumStateUpdate {
+                    state_root,
+                    block_number: BlockNumber::GENESIS,
+                    block_hash,
+                },
+            ))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let state = Arc::new(SyncState::default());
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage,
+            state: state.clone(),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            notifications: Default::default(),
+        };
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        assert_eq!(*state.l1_accepted.read().await, Some(BlockNumber::GENESIS));
+    }
+
+    mod stop_at_block {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        use pathfinder_common::{Chain, ChainId, EthereumChain, L1TransactionHash};
+        use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
+        use primitive_types::H160;
+        use starknet_gateway_client::GatewayApi;
+        use starknet_gateway_types::error::SequencerError;
+        use starknet_gateway_types::reply::PendingBlock;
+        use tokio::sync::mpsc;
+
+        use super::*;
+        use crate::state::l1::L1SyncContext;
+        use crate::state::l2::{BlockChain, L2SyncContext};
+        use crate::state::sync::{sync, SyncContext};
+
+        #[derive(Clone)]
+        struct FakeEthereum;
+
+        #[async_trait::async_trait]
+        impl EthereumApi for FakeEthereum {
+            async fn get_starknet_state(&self, _: &H160) -> anyhow::Result<EthereumStateUpdate> {
+                unimplemented!()
+            }
+
+            async fn get_chain(&self) -> anyhow::Result<EthereumChain> {
+                unimplemented!()
+            }
+
+            async fn get_l1_handler_txs(
+                &self,
+                _: &H160,
+                _: &L1TransactionHash,
+            ) -> anyhow::Result<Vec<pathfinder_common::transaction::L1HandlerTransaction>>
+            {
+                unimplemented!()
+            }
+
+            async fn sync_and_listen<F, Fut>(
+                &mut self,
+                _: &H160,
+                _: Duration,
+                _: F,
+            ) -> anyhow::Result<()>
+            where
+                F: Fn(EthereumStateUpdate) -> Fut + Send + 'static,
+                Fut: std::future::Future<Output = ()> + Send + 'static,
+            {
+                unimplemented!()
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct FakeFgw {
+            pending_requests: Arc<AtomicUsize>,
+        }
+
+        #[async_trait::async_trait]
+        impl GatewayApi for FakeFgw {
+            async fn block_header(
+                &self,
+                _: pathfinder_common::BlockId,
+            ) -> Result<(BlockNumber, BlockHash), SequencerError> {
+                let (a, ..) = generate_block_data().pop().unwrap();
+                Ok((a.0.block_number, a.0.block_hash))
+            }
+
+            async fn pending_block(&self) -> Result<(PendingBlock, StateUpdate), SequencerError> {
+                self.pending_requests.fetch_add(1, Ordering::Relaxed);
+                Err(SequencerError::InvalidStarknetErrorVariant)
+            }
+        }
+
+        fn context(
+            storage: pathfinder_storage::Storage,
+            sequencer: FakeFgw,
+            stop_at_block: BlockNumber,
+        ) -> SyncContext<FakeFgw, FakeEthereum> {
+            let (pending_data, _) = tokio::sync::watch::channel(Default::default());
+            SyncContext {
+                storage,
+                ethereum: FakeEthereum,
+                chain: Chain::SepoliaTestnet,
+                chain_id: ChainId::SEPOLIA_TESTNET,
+                core_address: H160::zero(),
+                sequencer,
+                state: Arc::new(SyncState::default()),
+                head_poll_interval: Duration::from_millis(10),
+                l1_poll_interval: Duration::from_millis(10),
+                pending_data,
+                block_validation_mode: l2::BlockValidationMode::AllowMismatch,
+                websocket_txs: None,
+                notifications: Default::default(),
+                block_cache_size: 10,
+                restart_delay: Duration::from_millis(10),
+                verify_tree_hashes: false,
+                sequencer_public_key: Default::default(),
+                fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                fetch_casm_from_fgw: false,
+                trace_precomputation: None,
+                stop_at_block: Some(stop_at_block),
+            }
+        }
+
+        /// Sends the blocks up to and including the stop block, and then waits
+        /// like the real L2 sync would.
+        async fn l2_sync(
+            tx: mpsc::Sender<SyncEvent>,
+            context: L2SyncContext<FakeFgw>,
+            _: Option<(BlockNumber, BlockHash, StateCommitment)>,
+            _: BlockChain,
+            _: tokio::sync::watch::Receiver<(BlockNumber, BlockHash)>,
+        ) -> anyhow::Result<()> {
+            let stop = context.stop_at_block.unwrap().get() as usize;
+            for (a, b, c, d, e) in generate_block_data().into_iter().take(stop + 1) {
+                tx.send(SyncEvent::Block(a, b, c, d, e)).await?;
+            }
+            std::future::pending().await
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn sync_ends_once_the_stop_block_is_committed() {
+            let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+                pathfinder_storage::TriePruneMode::Archive,
+                std::num::NonZeroU32::new(5).unwrap(),
+            )
+            .unwrap();
+            let sequencer = FakeFgw::default();
+            let stop = BlockNumber::new_or_panic(2);
+
+            // Dropped once the L1 sync task is aborted.
+            let (l1_alive, l1_aborted) = tokio::sync::oneshot::channel::<()>();
+            let mut l1_alive = Some(l1_alive);
+            let l1_sync = move |_: mpsc::Sender<SyncEvent>, _: L1SyncContext<FakeEthereum>| {
+                let alive = l1_alive.take();
+                async move {
+                    let _alive = alive;
+                    std::future::pending::<anyhow::Result<()>>().await
+                }
+            };
+
+            tokio::time::timeout(
+                Duration::from_secs(10),
+                sync(
+                    context(storage.clone(), sequencer.clone(), stop),
+                    l1_sync,
+                    l2_sync,
+                ),
+            )
+            .await
+            .expect("Sync should stop")
+            .unwrap();
+
+            assert!(l1_aborted.await.is_err());
+            assert_eq!(sequencer.pending_requests.load(Ordering::Relaxed), 0);
+
+            let mut db = storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            let latest = db.block_id(BlockId::Latest).unwrap().unwrap();
+            assert_eq!(latest.0, stop);
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn database_at_or_past_the_stop_block() {
+            let storage = StorageBuilder::in_memory().unwrap();
+            pathfinder_storage::test_utils::fill_test_storage(&storage, 3, 1);
+
+            let l1_sync = |_: mpsc::Sender<SyncEvent>, _: L1SyncContext<FakeEthereum>| async {
+                Err::<(), _>(anyhow::anyhow!("L1 sync should not start"))
+            };
+
+            // Nothing is left to sync.
+            sync(
+                context(
+                    storage.clone(),
+                    FakeFgw::default(),
+                    BlockNumber::new_or_panic(2),
+                ),
+                l1_sync,
+                l2_sync,
+            )
+            .await
+            .unwrap();
+
+            let error = sync(
+                context(storage, FakeFgw::default(), BlockNumber::new_or_panic(1)),
+                l1_sync,
+                l2_sync,
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "Latest block 2 in the database is past the stop block 1"
+            );
+        }
+    }
+
     mod blockchain_pruning {
         use super::*;
 
@@ -2093,6 +2413,76 @@ mod tests {
             assert!(!transactions.is_empty() && !transaction_hashes.is_empty());
         }
 
+        #[tokio::test(flavor = "multi_thread")]
+        async fn convert_archive_to_pruned() {
+            let storage = StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
+                pathfinder_storage::pruning::BlockchainHistoryMode::Archive,
+                std::num::NonZeroU32::new(5).unwrap(),
+            )
+            .unwrap();
+            let mut conn = storage.connection().unwrap();
+
+            let (event_tx, event_rx) = tokio::sync::mpsc::channel(5);
+
+            let blocks = block_data_with_state_updates(one_non_prunable_block());
+            for (a, b, c, d, e) in blocks {
+                event_tx
+                    .send(SyncEvent::Block(a, b, c, d, e))
+                    .await
+                    .unwrap();
+            }
+            drop(event_tx);
+
+            let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+            let context = ConsumerContext {
+                storage: storage.clone(),
+                state: Arc::new(SyncState::default()),
+                pending_data: tx,
+                verify_tree_hashes: false,
+                websocket_txs: None,
+                notifications: Default::default(),
+            };
+
+            let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+            consumer(event_rx, context, tx).await.unwrap();
+
+            let tx = conn.transaction().unwrap();
+            // Keep only the latest block, the result must match what
+            // `blockchain_history_pruning` ends up with.
+            tx.convert_to_pruned_blockchain(0).unwrap();
+
+            for block in [0, 1, 3] {
+                let block_id = BlockId::Number(BlockNumber::new_or_panic(block));
+                assert!(tx.transactions_for_block(block_id).unwrap().is_none());
+                assert!(!tx.block_exists(block_id).unwrap());
+            }
+            let block_id = BlockId::Number(BlockNumber::new_or_panic(2));
+            assert!(tx
+                .transactions_for_block(block_id)
+                .unwrap()
+                .unwrap()
+                .is_empty());
+            assert!(tx.block_exists(block_id).unwrap());
+
+            let latest = tx.block_number(BlockId::Latest).unwrap().unwrap();
+            assert_eq!(latest, BlockNumber::new_or_panic(4));
+            let transactions = tx.transactions_for_block(latest.into()).unwrap().unwrap();
+            assert!(!transactions.is_empty());
+
+            // Latest state is still available.
+            let contract1 = contract_address_bytes!(b"contract 1");
+            let storage_address1 = storage_address_bytes!(b"storage address 1");
+            assert_eq!(
+                tx.storage_value(latest.into(), contract1, storage_address1)
+                    .unwrap(),
+                Some(storage_value!("0x200"))
+            );
+            assert_eq!(
+                tx.contract_nonce(contract1, latest.into()).unwrap(),
+                Some(contract_nonce!("0x3"))
+            );
+        }
+
         #[tokio::test(flavor = "multi_thread")]
         async fn non_prunable_blocks() {
             let storage = StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
@@ -93,6 +93,8 @@ pub struct L2SyncContext<GatewayClient> {
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
+    /// No blocks past this one are downloaded.
+    pub stop_at_block: Option<BlockNumber>,
 }
 
 pub async fn sync<GatewayClient>(
@@ -107,6 +109,10 @@ where
 {
     // Phase 1: catch up to the latest block
     let bulk_tail = latest.borrow().0;
+    let bulk_tail = match context.stop_at_block {
+        Some(stop) => std::cmp::min(bulk_tail, stop),
+        None => bulk_tail,
+    };
     bulk_sync(
         tx_event.clone(),
         context.clone(),
@@ -125,6 +131,7 @@ where
         sequencer_public_key,
         fetch_concurrency: _,
         fetch_casm_from_fgw,
+        stop_at_block,
     } = context;
 
     // Start polling head of chain
@@ -135,6 +142,12 @@ where
             None => (BlockNumber::GENESIS, None),
         };
 
+        if stop_at_block.is_some_and(|stop| next > stop) {
+            tracing::info!(block=%next - 1, "Reached the stop block, no more blocks are downloaded");
+            // Wait to be shut down once the stop block is committed.
+            return std::future::pending().await;
+        }
+
         // We start downloading the signature for the block
         let signature_handle = util::task::spawn({
             let sequencer = sequencer.clone();
@@ -602,6 +615,7 @@ where
         sequencer_public_key,
         fetch_concurrency,
         fetch_casm_from_fgw,
+        stop_at_block: _,
     } = context;
 
     let mut start = match head {
@@ -1169,7 +1183,7 @@ mod tests {
         use pathfinder_common::{BlockId, Chain};
         use pathfinder_crypto::Felt;
         use pathfinder_storage::StorageBuilder;
-        use starknet_gateway_client::MockGatewayApi;
+        use starknet_gateway_client::{GatewayApi, MockGatewayApi};
         use starknet_gateway_types::error::{
             KnownStarknetErrorCode,
             SequencerError,
@@ -1303,6 +1317,14 @@ mod tests {
         fn spawn_sync_default(
             tx_event: mpsc::Sender<SyncEvent>,
             sequencer: MockGatewayApi,
+        ) -> JoinHandle<anyhow::Result<()>> {
+            spawn_sync_with_stop(tx_event, sequencer, None)
+        }
+
+        fn spawn_sync_with_stop(
+            tx_event: mpsc::Sender<SyncEvent>,
+            sequencer: impl GatewayApi + Send + 'static,
+            stop_at_block: Option<BlockNumber>,
         ) -> JoinHandle<anyhow::Result<()>> {
             let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
                 pathfinder_storage::TriePruneMode::Archive,
@@ -1319,6 +1341,7 @@ mod tests {
                 sequencer_public_key: PublicKey::ZERO,
                 fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
                 fetch_casm_from_fgw: false,
+                stop_at_block,
             };
 
             let latest = tokio::sync::watch::channel(Default::default());
@@ -1351,6 +1374,7 @@ mod tests {
                 sequencer_public_key: PublicKey::ZERO,
                 fetch_concurrency: std::num::NonZeroUsize::new(2).unwrap(),
                 fetch_casm_from_fgw: false,
+                stop_at_block: None,
             };
 
             tokio::spawn(async move {
@@ -1785,6 +1809,148 @@ mod tests {
                 });
             }
 
+            #[tokio::test]
+            async fn stops_at_block() {
+                let (tx_event, mut rx_event) = tokio::sync::mpsc::channel(1);
+                let mut mock = MockGatewayApi::new();
+                let mut seq = mockall::Sequence::new();
+                let mut signature_seq = mockall::Sequence::new();
+
+                // Only the genesis block is downloaded, block #1 is never requested
+                expect_state_update_with_block(
+                    &mut mock,
+                    &mut seq,
+                    BLOCK0_NUMBER,
+                    Ok((BLOCK0.clone(), STATE_UPDATE0.clone())),
+                );
+                expect_class_by_hash(
+                    &mut mock,
+                    &mut seq,
+                    CONTRACT0_HASH,
+                    Ok(CONTRACT0_DEF.clone()),
+                );
+                expect_signature(
+                    &mut mock,
+                    &mut signature_seq,
+                    BLOCK0_NUMBER.into(),
+                    Ok(BLOCK0_SIGNATURE.clone()),
+                );
+
+                // Let's run the UUT
+                let jh = spawn_sync_with_stop(tx_event, mock, Some(BLOCK0_NUMBER));
+
+                assert_matches!(rx_event.recv().await.unwrap(),
+                    SyncEvent::CairoClass { hash, .. } => {
+                        assert_eq!(hash, CONTRACT0_HASH);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(), SyncEvent::Block((block, _), _, _, _, _) => {
+                    assert_eq!(*block, *BLOCK0);
+                });
+
+                // Sync waits to be shut down instead of downloading more blocks.
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                assert!(!jh.is_finished());
+                assert!(rx_event.try_recv().is_err());
+            }
+
+            #[tokio::test]
+            async fn from_gateway_dumps() {
+                /// The feeder gateway reply for a state update, as written to
+                /// the dumps.
+                fn reply(state_update: &StateUpdate) -> reply::StateUpdate {
+                    use pathfinder_common::state_update::ContractClassUpdate;
+                    use reply::state_update::{DeployedContract, StateDiff, StorageDiff};
+
+                    let mut state_diff = StateDiff::default();
+                    for (address, update) in &state_update.contract_updates {
+                        state_diff.storage_diffs.insert(
+                            *address,
+                            update
+                                .storage
+                                .iter()
+                                .map(|(key, value)| StorageDiff {
+                                    key: *key,
+                                    value: *value,
+                                })
+                                .collect(),
+                        );
+                        if let Some(ContractClassUpdate::Deploy(class_hash)) = update.class {
+                            state_diff.deployed_contracts.push(DeployedContract {
+                                address: *address,
+                                class_hash,
+                            });
+                        }
+                    }
+
+                    reply::StateUpdate {
+                        block_hash: state_update.block_hash,
+                        new_root: state_update.state_commitment,
+                        old_root: state_update.parent_state_commitment,
+                        state_diff,
+                    }
+                }
+
+                let dumps = tempfile::tempdir().unwrap();
+                let write = |path: String, contents: &[u8]| {
+                    let path = dumps.path().join(path);
+                    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    std::fs::write(path, contents).unwrap();
+                };
+                for (block, state_update, signature) in [
+                    (&*BLOCK0, &*STATE_UPDATE0, &BLOCK0_SIGNATURE),
+                    (&*BLOCK1, &*STATE_UPDATE1, &BLOCK1_SIGNATURE),
+                ] {
+                    let dump = serde_json::json!({
+                        "block": block,
+                        "state_update": reply(state_update),
+                    });
+                    write(
+                        format!("state_update_with_block/{}.json", block.block_number),
+                        &serde_json::to_vec(&dump).unwrap(),
+                    );
+                    write(
+                        format!("signature/{}.json", block.block_number),
+                        &serde_json::to_vec(signature).unwrap(),
+                    );
+                }
+                for (class_hash, definition) in [
+                    (CONTRACT0_HASH, &*CONTRACT0_DEF),
+                    (CONTRACT1_HASH, &*CONTRACT1_DEF),
+                ] {
+                    write(format!("class/0x{:x}.json", class_hash.0), definition);
+                }
+
+                let (tx_event, mut rx_event) = tokio::sync::mpsc::channel(1);
+                let gateway =
+                    starknet_gateway_client::LocalGateway::new(dumps.path().to_owned()).unwrap();
+
+                // Let's run the UUT
+                let _jh = spawn_sync_with_stop(tx_event, gateway, None);
+
+                assert_matches!(rx_event.recv().await.unwrap(),
+                    SyncEvent::CairoClass { hash, .. } => {
+                        assert_eq!(hash, CONTRACT0_HASH);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(), SyncEvent::Block((block, _), state_update, signature, _, _) => {
+                    assert_eq!(*block, *BLOCK0);
+                    assert_eq_sorted!(*state_update, *STATE_UPDATE0);
+                    assert_eq!(*signature, BLOCK0_SIGNATURE.signature());
+                });
+                assert_matches!(rx_event.recv().await.unwrap(),
+                    SyncEvent::CairoClass { hash, .. } => {
+                    assert_eq!(hash, CONTRACT1_HASH);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(), SyncEvent::Block((block, _), state_update, signature, _, _) => {
+                    assert_eq!(*block, *BLOCK1);
+                    assert_eq_sorted!(*state_update, *STATE_UPDATE1);
+                    assert_eq!(*signature, BLOCK1_SIGNATURE.signature());
+                });
+
+                // Sync stays at the head of the dumps.
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                assert!(rx_event.try_recv().is_err());
+            }
+
             #[tokio::test]
             async fn resumed_after_genesis() {
                 let (tx_event, mut rx_event) = tokio::sync::mpsc::channel(1);
@@ -1847,6 +2013,7 @@ mod tests {
                     sequencer_public_key: PublicKey::ZERO,
                     fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
                     fetch_casm_from_fgw: false,
+                    stop_at_block: None,
                 };
                 let latest_track = tokio::sync::watch::channel(Default::default());
 
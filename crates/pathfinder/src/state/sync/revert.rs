@@ -46,6 +46,103 @@ pub fn revert_starknet_state(
     transaction.coalesce_trie_removals(target_block)
 }
 
+/// Roll the database back to `target_block`, removing all blocks above it.
+///
+/// This reverts the Starknet state using [revert_starknet_state] and then
+/// removes the blocks in the range `(target_block, head]` using
+/// [purge_blocks].
+///
+/// Returns the block number of the previous head. Does nothing if the head is
+/// already at or below the target. The caller is responsible for committing
+/// the transaction.
+pub fn rollback_to_block(
+    transaction: &Transaction<'_>,
+    target_block: BlockNumber,
+) -> anyhow::Result<BlockNumber> {
+    let head = transaction
+        .block_number(pathfinder_storage::BlockId::Latest)
+        .context("Querying latest block number")?
+        .context("Database is empty")?;
+
+    if head <= target_block {
+        return Ok(head);
+    }
+
+    if let Some(oldest) = transaction
+        .trie_pruned(target_block)
+        .context("Checking for trie pruning")?
+    {
+        anyhow::bail!(
+            "Merkle trie state of target block {target_block} has been pruned, the oldest block \
+             with trie state is {oldest}"
+        );
+    }
+
+    let target_header = transaction
+        .block_header(target_block.into())
+        .context("Fetching target block header")?
+        .with_context(|| {
+            format!(
+                "Target block {target_block} does not exist (likely due to blockchain history \
+                 pruning)"
+            )
+        })?;
+
+    revert_starknet_state(transaction, head, target_block, target_header)?;
+    purge_blocks(transaction, head, target_block + 1)?;
+
+    Ok(head)
+}
+
+/// Removes the blocks in the range `[tail, head]`, including their
+/// transactions, events, traces, state updates, class declarations and trie
+/// roots, without reverting the Starknet state.
+///
+/// The L1-L2 pointer is clamped to the parent of `tail`, or unset if genesis
+/// was removed.
+pub fn purge_blocks(
+    transaction: &Transaction<'_>,
+    head: BlockNumber,
+    tail: BlockNumber,
+) -> anyhow::Result<()> {
+    // Purge each block one at a time.
+    //
+    // This is done 1-by-1 to allow sending the reorg'd block data
+    // to websocket subscriptions while keeping a constant memory footprint.
+    //
+    // This is acceptable performance because reorgs are rare and need not be
+    // 100% optimal. However a large reorg could cause a massive memory spike
+    // which is not acceptable.
+    let mut block = head;
+    while block >= tail {
+        transaction
+            .purge_block(block)
+            .with_context(|| format!("Purging block {block} from database"))?;
+
+        // No further blocks to purge if we just purged genesis.
+        if block == BlockNumber::GENESIS {
+            break;
+        }
+
+        block -= 1;
+    }
+
+    let new_head = tail.parent();
+    transaction
+        .reset_in_memory_state(new_head.unwrap_or(BlockNumber::GENESIS))
+        .context("Resetting in-memory DB state after purging blocks")?;
+
+    // Track combined L1 and L2 state.
+    let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
+    if l1_l2_head.is_some_and(|l1_l2_head| l1_l2_head >= tail) {
+        transaction
+            .update_l1_l2_pointer(new_head)
+            .context("Updating L1-L2 head")?;
+    }
+
+    Ok(())
+}
+
 /// Revert all contract/global storage trie updates.
 ///
 /// Fetches reverse updates from the database and updates all tries, returning
@@ -142,3 +239,162 @@ fn revert_class_updates(
 
     Ok(class_commitment)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+    use pathfinder_storage::fake::{self, Block};
+    use pathfinder_storage::{BlockId, Storage, StorageBuilder, TriePruneMode};
+
+    use super::*;
+
+    /// Fills a database with five blocks with consistent tries, traces for
+    /// every block and the L1-L2 pointer at the head.
+    fn setup(trie_prune_mode: TriePruneMode) -> (Storage, Vec<Block>) {
+        // Contract updates are applied to the tries using additional connections.
+        let storage = StorageBuilder::in_tempdir_with_trie_pruning_and_pool_size(
+            trie_prune_mode,
+            NonZeroU32::new(32).unwrap(),
+        )
+        .unwrap();
+        let blocks = fake::generate::with_config(
+            5,
+            fake::Config {
+                update_tries: Box::new(update_starknet_state),
+                occurrence: fake::OccurrencePerBlock {
+                    cairo: 1..=3,
+                    sierra: 1..=3,
+                    storage: 1..=3,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        fake::fill(&storage, &blocks, Some(Box::new(update_starknet_state)));
+
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        for block in &blocks {
+            db.insert_block_traces(
+                block.header.header.number,
+                block.header.header.hash,
+                pathfinder_executor::STORED_TRACES_VERSION,
+                &"traces",
+            )
+            .unwrap();
+        }
+        db.update_l1_l2_pointer(Some(blocks.last().unwrap().header.header.number))
+            .unwrap();
+        db.commit().unwrap();
+
+        (storage, blocks)
+    }
+
+    fn state_commitment(tx: &Transaction<'_>, block: BlockNumber) -> StateCommitment {
+        let storage_commitment = tx
+            .storage_root_index(block)
+            .unwrap()
+            .map(|idx| StorageCommitment(tx.storage_trie_node_hash(idx).unwrap().unwrap()))
+            .unwrap_or_default();
+        let class_commitment = tx.class_root(block).unwrap().unwrap_or_default();
+        StateCommitment::calculate(storage_commitment, class_commitment)
+    }
+
+    #[test]
+    fn removes_blocks_above_the_target() {
+        let (storage, blocks) = setup(TriePruneMode::Archive);
+        let target = BlockNumber::new_or_panic(2);
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let head = rollback_to_block(&tx, target).unwrap();
+        assert_eq!(head, BlockNumber::new_or_panic(4));
+
+        let target_header = &blocks[2].header.header;
+        assert_eq!(
+            tx.block_id(BlockId::Latest).unwrap(),
+            Some((target, target_header.hash))
+        );
+        assert_eq!(tx.l1_l2_pointer().unwrap(), Some(target));
+        assert_eq!(
+            state_commitment(&tx, target),
+            target_header.state_commitment
+        );
+
+        for block in &blocks[..=2] {
+            let number = block.header.header.number;
+            assert!(tx.block_exists(number.into()).unwrap());
+            assert!(tx.block_traces_exist(number).unwrap());
+            assert!(tx.state_update(number.into()).unwrap().is_some());
+        }
+
+        for block in &blocks[3..] {
+            let number = block.header.header.number;
+            assert_eq!(tx.block_header(number.into()).unwrap(), None);
+            assert!(tx
+                .transaction_data_for_block(number.into())
+                .unwrap()
+                .is_none());
+            assert!(tx.events_for_block(number.into()).unwrap().is_none());
+            assert_eq!(tx.state_update(number.into()).unwrap(), None);
+            assert!(!tx.block_traces_exist(number).unwrap());
+            assert!(!tx.storage_root_exists(number).unwrap());
+            assert!(!tx.class_root_exists(number).unwrap());
+
+            let state_update = block.state_update.as_ref().unwrap();
+            for class in state_update.declared_cairo_classes.iter().copied().chain(
+                state_update
+                    .declared_sierra_classes
+                    .keys()
+                    .map(|h| ClassHash(h.0)),
+            ) {
+                assert_eq!(
+                    tx.class_definition_at(BlockId::Latest, class).unwrap(),
+                    None
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn target_at_or_past_head_is_a_no_op() {
+        let (storage, blocks) = setup(TriePruneMode::Archive);
+        let head = blocks.last().unwrap().header.header.number;
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        assert_eq!(rollback_to_block(&tx, head).unwrap(), head);
+        assert_eq!(rollback_to_block(&tx, head + 1).unwrap(), head);
+
+        assert_eq!(tx.block_number(BlockId::Latest).unwrap(), Some(head));
+        assert_eq!(tx.l1_l2_pointer().unwrap(), Some(head));
+        assert!(tx.block_traces_exist(head).unwrap());
+    }
+
+    #[test]
+    fn pruned_target() {
+        let (storage, _) = setup(TriePruneMode::Prune { num_blocks_kept: 1 });
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let error = rollback_to_block(&tx, BlockNumber::new_or_panic(1)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Merkle trie state of target block 1 has been pruned, the oldest block with trie \
+             state is 3"
+        );
+        assert_eq!(
+            tx.block_number(BlockId::Latest).unwrap(),
+            Some(BlockNumber::new_or_panic(4))
+        );
+
+        // Blocks with trie state left can still be rolled back to.
+        rollback_to_block(&tx, BlockNumber::new_or_panic(3)).unwrap();
+        assert_eq!(
+            tx.block_number(BlockId::Latest).unwrap(),
+            Some(BlockNumber::new_or_panic(3))
+        );
+    }
+}
@@ -0,0 +1,719 @@
+//! Background pre-computation of block traces.
+//!
+//! Traces are computed for each block committed by sync, and optionally for a
+//! range of historical blocks, and stored in the database where the trace RPC
+//! methods pick them up instead of executing the block. Traces of reorged
+//! blocks are purged together with the block, after which the replacement
+//! blocks are traced again.
+
+use std::collections::{BTreeMap, HashSet};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber, ChainId, ContractAddress};
+use pathfinder_executor::{
+    ExecutionState,
+    NativeClassCache,
+    TraceCache,
+    VersionedConstantsMap,
+    STORED_TRACES_VERSION,
+};
+use pathfinder_rpc::{
+    Reorg,
+    VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
+};
+use pathfinder_storage::Storage;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::{broadcast, watch, OwnedSemaphorePermit, Semaphore};
+
+/// Delay before tracing a failed block again, doubled after each failure.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Number of times a block is traced before giving up on it.
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Clone)]
+pub struct TracePrecomputationConfig {
+    /// Writable storage, used to read blocks and to store their traces.
+    pub storage: Storage,
+    pub chain_id: ChainId,
+    pub eth_fee_address: ContractAddress,
+    pub strk_fee_address: ContractAddress,
+    pub versioned_constants_map: VersionedConstantsMap,
+    pub native_class_cache: Option<NativeClassCache>,
+    /// Maximum number of blocks traced at the same time.
+    pub concurrency: NonZeroUsize,
+    /// Number of blocks below the head at startup to compute traces for.
+    pub backfill: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum BlockStatus {
+    /// The block has not been committed yet, or has been reorged away.
+    Missing,
+    /// Traces are already stored, or can't be computed locally.
+    Skip,
+    Trace(BlockHash),
+}
+
+/// Blocks being traced, and blocks given up on after failing to be traced
+/// [MAX_ATTEMPTS] times.
+#[derive(Default)]
+struct Tasks {
+    running: BTreeMap<BlockNumber, tokio::task::JoinHandle<()>>,
+    /// Keyed by hash as well, so that blocks replacing failed ones in a reorg
+    /// are traced.
+    failed: Arc<Mutex<HashSet<(BlockNumber, BlockHash)>>>,
+}
+
+impl Tasks {
+    /// Whether the block is being traced or has been given up on.
+    fn contains(&mut self, block_number: BlockNumber, block_hash: BlockHash) -> bool {
+        self.running.retain(|_, task| !task.is_finished());
+        self.running.contains_key(&block_number)
+            || self
+                .failed
+                .lock()
+                .unwrap()
+                .contains(&(block_number, block_hash))
+    }
+
+    fn spawn(
+        &mut self,
+        config: TracePrecomputationConfig,
+        semaphore: Arc<Semaphore>,
+        permit: OwnedSemaphorePermit,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    ) {
+        let task = util::task::spawn(trace_block(
+            config,
+            semaphore,
+            permit,
+            block_number,
+            block_hash,
+            self.failed.clone(),
+        ));
+        self.running.insert(block_number, task);
+    }
+
+    /// Stops tracing blocks from `block_number` onwards, which are traced
+    /// again after having been rewound to.
+    fn rewind(&mut self, block_number: BlockNumber) {
+        for task in self.running.split_off(&block_number).into_values() {
+            task.abort();
+        }
+    }
+}
+
+/// Computes and stores traces for blocks up to `head`, starting
+/// [TracePrecomputationConfig::backfill] blocks below the initial head.
+///
+/// `reorgs` is used to go back and trace the blocks replacing reorged ones.
+pub async fn precompute_traces(
+    config: TracePrecomputationConfig,
+    mut head: watch::Receiver<(BlockNumber, BlockHash)>,
+    mut reorgs: broadcast::Receiver<Arc<Reorg>>,
+) -> anyhow::Result<()> {
+    let mut db_conn = config
+        .storage
+        .connection()
+        .context("Creating database connection")?;
+
+    let start = BlockNumber::new_or_panic(head.borrow().0.get().saturating_sub(config.backfill));
+    let start = tokio::task::block_in_place(|| {
+        let db = db_conn
+            .transaction()
+            .context("Creating database transaction")?;
+        first_block(&db, start)
+    })?;
+    let mut next = start;
+    let semaphore = Arc::new(Semaphore::new(config.concurrency.get()));
+    let mut tasks = Tasks::default();
+
+    tracing::info!(%start, "Starting trace pre-computation");
+
+    loop {
+        let head_number = head.borrow_and_update().0;
+
+        while next <= head_number {
+            match reorgs.try_recv() {
+                Ok(reorg) => {
+                    next = rewind(next, start, &reorg);
+                    tasks.rewind(next);
+                }
+                Err(TryRecvError::Lagged(_)) => {
+                    next = start;
+                    tasks.rewind(next);
+                }
+                Err(TryRecvError::Empty | TryRecvError::Closed) => {}
+            }
+
+            let status = tokio::task::block_in_place(|| {
+                let db = db_conn
+                    .transaction()
+                    .context("Creating database transaction")?;
+                // Skip over blocks that have been pruned in the meantime, e.g. after
+                // going back to `start`.
+                next = std::cmp::max(next, first_block(&db, start)?);
+                block_status(&db, next)
+            })?;
+
+            metrics::gauge!(
+                "trace_precomputation_backlog",
+                (head_number.get() + 1).saturating_sub(next.get()) as f64
+            );
+
+            match status {
+                BlockStatus::Missing => break,
+                BlockStatus::Skip => {}
+                BlockStatus::Trace(hash) if tasks.contains(next, hash) => {}
+                BlockStatus::Trace(hash) => {
+                    let permit = semaphore
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .context("Acquiring trace permit")?;
+                    tasks.spawn(config.clone(), semaphore.clone(), permit, next, hash);
+                }
+            }
+
+            next += 1;
+        }
+
+        tokio::select! {
+            changed = head.changed() => {
+                if changed.is_err() {
+                    tracing::debug!("Sync stopped, stopping trace pre-computation");
+                    return Ok(());
+                }
+            }
+            reorg = reorgs.recv() => match reorg {
+                Ok(reorg) => {
+                    next = rewind(next, start, &reorg);
+                    tasks.rewind(next);
+                }
+                Err(RecvError::Lagged(_)) => {
+                    next = start;
+                    tasks.rewind(next);
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Blocks from the start of the reorged range onwards have to be traced again.
+fn rewind(next: BlockNumber, start: BlockNumber, reorg: &Reorg) -> BlockNumber {
+    std::cmp::max(std::cmp::min(next, reorg.first_block_number), start)
+}
+
+/// `start`, or the oldest block still available if `start` has been pruned.
+fn first_block(
+    db: &pathfinder_storage::Transaction<'_>,
+    start: BlockNumber,
+) -> anyhow::Result<BlockNumber> {
+    let oldest = db
+        .oldest_available_block()
+        .context("Querying oldest available block")?;
+    Ok(oldest.map_or(start, |oldest| std::cmp::max(start, oldest)))
+}
+
+fn block_status(
+    db: &pathfinder_storage::Transaction<'_>,
+    block_number: BlockNumber,
+) -> anyhow::Result<BlockStatus> {
+    // Checked first, since the headers of pruned blocks are gone as well.
+    if db
+        .block_pruned(block_number.into())
+        .context("Querying block pruned status")?
+        .is_some()
+    {
+        return Ok(BlockStatus::Skip);
+    }
+
+    let Some(header) = db
+        .block_header(block_number.into())
+        .context("Fetching block header")?
+    else {
+        return Ok(BlockStatus::Missing);
+    };
+
+    if header.starknet_version
+        < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
+    {
+        return Ok(BlockStatus::Skip);
+    }
+
+    if db
+        .block_traces_exist(block_number)
+        .context("Querying block traces")?
+    {
+        return Ok(BlockStatus::Skip);
+    }
+
+    Ok(BlockStatus::Trace(header.hash))
+}
+
+/// Traces the block, retrying with backoff until it succeeds, the block no
+/// longer needs to be traced, or it has failed [MAX_ATTEMPTS] times. Blocks
+/// given up on are added to `failed`.
+async fn trace_block(
+    config: TracePrecomputationConfig,
+    semaphore: Arc<Semaphore>,
+    mut permit: OwnedSemaphorePermit,
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    failed: Arc<Mutex<HashSet<(BlockNumber, BlockHash)>>>,
+) {
+    let mut retry_delay = RETRY_DELAY;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let started = std::time::Instant::now();
+        let result = {
+            let config = config.clone();
+            util::task::spawn_blocking(move |_| trace_block_impl(config, block_number, block_hash))
+                .await
+                .context("Joining trace task")
+                .and_then(|result| result)
+        };
+
+        match result {
+            Ok(()) => {
+                metrics::increment_counter!("trace_precomputation_blocks_total");
+                metrics::histogram!("trace_precomputation_duration_seconds", started.elapsed());
+                metrics::gauge!("trace_precomputation_block", block_number.get() as f64);
+                tracing::debug!(block=%block_number, elapsed=?started.elapsed(), "Block traces stored");
+                return;
+            }
+            Err(error) if attempt == MAX_ATTEMPTS => {
+                metrics::increment_counter!("trace_precomputation_failed_total");
+                tracing::error!(block=%block_number, ?error, "Failed to pre-compute block traces, giving up");
+            }
+            Err(error) => {
+                metrics::increment_counter!("trace_precomputation_failed_total");
+                tracing::warn!(block=%block_number, ?error, ?retry_delay, "Failed to pre-compute block traces, retrying");
+
+                // Let other blocks be traced in the meantime.
+                drop(permit);
+                tokio::time::sleep(retry_delay).await;
+                retry_delay *= 2;
+
+                permit = match semaphore.clone().acquire_owned().await {
+                    Ok(permit) => permit,
+                    Err(_) => return,
+                };
+            }
+        }
+    }
+
+    failed.lock().unwrap().insert((block_number, block_hash));
+}
+
+fn trace_block_impl(
+    config: TracePrecomputationConfig,
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+) -> anyhow::Result<()> {
+    let (hash, traces) = {
+        let mut db = config
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        // Reorged away, pruned or traced after a reorg while waiting to be retried.
+        if block_status(&db, block_number)? != BlockStatus::Trace(block_hash) {
+            return Ok(());
+        }
+
+        let header = db
+            .block_header(block_number.into())
+            .context("Fetching block header")?
+            .context("Block header missing")?;
+
+        let transactions = db
+            .transactions_for_block(block_number.into())
+            .context("Fetching transactions")?
+            .context("Transaction data missing")?
+            .iter()
+            .map(|transaction| pathfinder_rpc::compose_executor_transaction(transaction, &db))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let hash = header.hash;
+        let state = ExecutionState::trace(
+            &db,
+            config.chain_id,
+            header,
+            None,
+            config.versioned_constants_map,
+            config.eth_fee_address,
+            config.strk_fee_address,
+            config.native_class_cache,
+        );
+
+        // A fresh cache per block, since traced blocks are only needed in the
+        // database. The traces are stored below rather than by the cache so that
+        // failing to store them is retried as well.
+        let traces = pathfinder_executor::trace(state, TraceCache::default(), hash, transactions)
+            .map_err(|error| anyhow::anyhow!("Tracing block: {error:?}"))?;
+
+        (hash, traces)
+    };
+
+    let mut db = config
+        .storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+    db.insert_block_traces(block_number, hash, STORED_TRACES_VERSION, &traces)
+        .context("Storing block traces")?;
+    db.commit().context("Committing database transaction")
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::prelude::*;
+    use pathfinder_common::transaction::{L1HandlerTransaction, Transaction};
+    use pathfinder_common::{class_hash, felt, CallParam};
+    use pathfinder_executor::types::TransactionTrace;
+    use pathfinder_storage::pruning::BlockchainHistoryMode;
+    use pathfinder_storage::StorageBuilder;
+    use starknet_gateway_test_fixtures::class_definitions::INTEGRATION_TEST;
+
+    use super::*;
+
+    fn setup(blocks: u64) -> Storage {
+        setup_with_history(blocks, BlockchainHistoryMode::Archive)
+    }
+
+    fn setup_with_history(blocks: u64, history: BlockchainHistoryMode) -> Storage {
+        let storage = StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
+            history,
+            std::num::NonZeroU32::new(8).unwrap(),
+        )
+        .unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        for number in 0..blocks {
+            let header = BlockHeader::builder()
+                .number(BlockNumber::new_or_panic(number))
+                .starknet_version(StarknetVersion::new(0, 13, 2, 0))
+                .finalize_with_hash(BlockHash(pathfinder_crypto::Felt::from_u64(number + 1)));
+            tx.insert_block_header(&header).unwrap();
+        }
+
+        tx.commit().unwrap();
+        storage
+    }
+
+    fn config(storage: Storage, backfill: u64) -> TracePrecomputationConfig {
+        TracePrecomputationConfig {
+            storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
+            eth_fee_address: pathfinder_rpc::context::ETH_FEE_TOKEN_ADDRESS,
+            strk_fee_address: pathfinder_rpc::context::STRK_FEE_TOKEN_ADDRESS,
+            versioned_constants_map: Default::default(),
+            native_class_cache: None,
+            concurrency: NonZeroUsize::new(2).unwrap(),
+            backfill,
+        }
+    }
+
+    const CONTRACT: ContractAddress = ContractAddress::new_or_panic(felt!("0xc01"));
+    const INTEGRATION_TEST_CLASS_HASH: ClassHash =
+        class_hash!("0x031da92cf5f54bcb81b447e219e2b791b23f3052d12b6c9abd04ff2e5626576");
+
+    /// Block 1 holds an L1 handler transaction depositing 2 into the
+    /// integration test contract, which block 0 deploys if `deployed` is set.
+    /// See [deploy_contract] otherwise.
+    fn setup_with_transaction(deployed: bool) -> (Storage, BlockHeader, TransactionHash) {
+        let storage = setup(0);
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        tx.insert_cairo_class(INTEGRATION_TEST_CLASS_HASH, INTEGRATION_TEST)
+            .unwrap();
+
+        let genesis = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .starknet_version(StarknetVersion::new(0, 13, 2, 0))
+            .finalize_with_hash(BlockHash(felt!("0xb00")));
+        tx.insert_block_header(&genesis).unwrap();
+        if deployed {
+            deploy_contract(&tx, &genesis);
+        }
+
+        let header = BlockHeader::child_builder(&genesis)
+            .timestamp(BlockTimestamp::new_or_panic(1))
+            .starknet_version(StarknetVersion::new(0, 13, 2, 0))
+            .finalize_with_hash(BlockHash(felt!("0xb01")));
+        tx.insert_block_header(&header).unwrap();
+
+        let transaction = Transaction {
+            hash: TransactionHash(felt!("0x1")),
+            variant: L1HandlerTransaction {
+                contract_address: CONTRACT,
+                entry_point_selector: EntryPoint::hashed(b"deposit"),
+                nonce: TransactionNonce::ZERO,
+                calldata: vec![CallParam(felt!("0xf00")), CallParam(felt!("0x2"))],
+            }
+            .into(),
+        };
+        tx.insert_transaction_data(
+            header.number,
+            &[(transaction.clone(), Default::default())],
+            None,
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+        (storage, header, transaction.hash)
+    }
+
+    fn deploy_contract(tx: &pathfinder_storage::Transaction<'_>, genesis: &BlockHeader) {
+        let state_update = StateUpdate::default()
+            .with_block_hash(genesis.hash)
+            .with_declared_cairo_class(INTEGRATION_TEST_CLASS_HASH)
+            .with_deployed_contract(CONTRACT, INTEGRATION_TEST_CLASS_HASH);
+        tx.insert_state_update(genesis.number, &state_update)
+            .unwrap();
+    }
+
+    fn stored_traces(
+        storage: &Storage,
+        header: &BlockHeader,
+    ) -> Vec<(TransactionHash, TransactionTrace)> {
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.block_traces(header.number, header.hash, STORED_TRACES_VERSION)
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn wait_for_traces(storage: &Storage, block_number: BlockNumber) {
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            loop {
+                let exists = storage
+                    .connection()
+                    .unwrap()
+                    .transaction()
+                    .unwrap()
+                    .block_traces_exist(block_number)
+                    .unwrap();
+                if exists {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn backfills_and_follows_head() {
+        let storage = setup(4);
+        let (head_tx, head_rx) = watch::channel((BlockNumber::new_or_panic(2), BlockHash::ZERO));
+        let (_reorg_tx, reorg_rx) = broadcast::channel(1);
+
+        let _worker = tokio::spawn(precompute_traces(
+            config(storage.clone(), 1),
+            head_rx,
+            reorg_rx,
+        ));
+
+        wait_for_traces(&storage, BlockNumber::new_or_panic(1)).await;
+        wait_for_traces(&storage, BlockNumber::new_or_panic(2)).await;
+
+        head_tx
+            .send((BlockNumber::new_or_panic(3), BlockHash::ZERO))
+            .unwrap();
+        wait_for_traces(&storage, BlockNumber::new_or_panic(3)).await;
+
+        // Outside of the backfilled range.
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        assert!(!tx.block_traces_exist(BlockNumber::GENESIS).unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn starts_after_pruned_blocks() {
+        // Blocks 0 and 1 are pruned.
+        let storage = setup_with_history(4, BlockchainHistoryMode::Prune { num_blocks_kept: 1 });
+        let (_head_tx, head_rx) = watch::channel((BlockNumber::new_or_panic(3), BlockHash::ZERO));
+        let (_reorg_tx, reorg_rx) = broadcast::channel(1);
+
+        let _worker = tokio::spawn(precompute_traces(
+            config(storage.clone(), 3),
+            head_rx,
+            reorg_rx,
+        ));
+
+        wait_for_traces(&storage, BlockNumber::new_or_panic(2)).await;
+        wait_for_traces(&storage, BlockNumber::new_or_panic(3)).await;
+
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        assert!(!tx.block_traces_exist(BlockNumber::GENESIS).unwrap());
+        assert_eq!(
+            super::first_block(&tx, BlockNumber::GENESIS).unwrap(),
+            BlockNumber::new_or_panic(2)
+        );
+        assert_eq!(
+            super::block_status(&tx, BlockNumber::new_or_panic(1)).unwrap(),
+            BlockStatus::Skip
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn traces_transactions() {
+        let (storage, header, transaction_hash) = setup_with_transaction(true);
+        let (_head_tx, head_rx) = watch::channel((header.number, header.hash));
+        let (_reorg_tx, reorg_rx) = broadcast::channel(1);
+
+        let _worker = tokio::spawn(precompute_traces(
+            config(storage.clone(), 0),
+            head_rx,
+            reorg_rx,
+        ));
+
+        wait_for_traces(&storage, header.number).await;
+
+        let traces = stored_traces(&storage, &header);
+        assert_eq!(traces.len(), 1);
+        let (hash, trace) = &traces[0];
+        assert_eq!(*hash, transaction_hash);
+        let TransactionTrace::L1Handler(trace) = trace else {
+            panic!("Expected an L1 handler trace, got {trace:?}");
+        };
+        let invocation = trace.function_invocation.as_ref().unwrap();
+        assert_eq!(invocation.contract_address, CONTRACT);
+        assert_eq!(invocation.selector, EntryPoint::hashed(b"deposit").0);
+        assert!(!invocation.is_reverted);
+        // The deposited amount is stored under a key derived from the depositor.
+        let storage_diffs = &trace.state_diff.storage_diffs[&CONTRACT];
+        assert_eq!(storage_diffs.len(), 1);
+        assert_eq!(storage_diffs[0].value, StorageValue(felt!("0x2")));
+    }
+
+    #[tokio::test]
+    async fn retries_failed_blocks() {
+        tokio::time::pause();
+
+        // Tracing fails until the contract is deployed.
+        let (storage, header, transaction_hash) = setup_with_transaction(false);
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        let failed = Arc::new(Mutex::new(HashSet::new()));
+
+        let task = tokio::spawn(trace_block(
+            config(storage.clone(), 0),
+            semaphore.clone(),
+            permit,
+            header.number,
+            header.hash,
+            failed.clone(),
+        ));
+
+        // The permit is released once the first attempt has failed.
+        let permit = semaphore.acquire().await.unwrap();
+        {
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            assert!(!tx.block_traces_exist(header.number).unwrap());
+            let genesis = tx
+                .block_header(BlockNumber::GENESIS.into())
+                .unwrap()
+                .unwrap();
+            deploy_contract(&tx, &genesis);
+            tx.commit().unwrap();
+        }
+        drop(permit);
+
+        tokio::time::advance(RETRY_DELAY).await;
+        task.await.unwrap();
+
+        let traces = stored_traces(&storage, &header);
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].0, transaction_hash);
+        assert!(failed.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_failing_blocks() {
+        // Retry delays are skipped while waiting for the task.
+        tokio::time::pause();
+
+        // The contract is never deployed, so tracing always fails.
+        let (storage, header, _) = setup_with_transaction(false);
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        let mut tasks = Tasks::default();
+
+        tasks.spawn(
+            config(storage.clone(), 0),
+            semaphore,
+            permit,
+            header.number,
+            header.hash,
+        );
+        assert!(tasks.contains(header.number, header.hash));
+        tasks.running.remove(&header.number).unwrap().await.unwrap();
+
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        assert!(!tx.block_traces_exist(header.number).unwrap());
+        assert!(tasks.contains(header.number, header.hash));
+        // A different block at the same height, e.g. after a reorg.
+        assert!(!tasks.contains(header.number, BlockHash::ZERO));
+    }
+
+    #[tokio::test]
+    async fn rewind_stops_tracing_rewound_blocks() {
+        let mut tasks = Tasks::default();
+        for number in 0..3 {
+            tasks.running.insert(
+                BlockNumber::new_or_panic(number),
+                tokio::spawn(std::future::pending()),
+            );
+        }
+
+        tasks.rewind(BlockNumber::new_or_panic(1));
+
+        assert!(tasks.contains(BlockNumber::GENESIS, BlockHash::ZERO));
+        assert!(!tasks.contains(BlockNumber::new_or_panic(1), BlockHash::ZERO));
+        assert!(!tasks.contains(BlockNumber::new_or_panic(2), BlockHash::ZERO));
+    }
+
+    #[test]
+    fn classifies_blocks() {
+        let storage = setup(2);
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::new_or_panic(2))
+            .starknet_version(StarknetVersion::new(0, 13, 1, 0))
+            .finalize_with_hash(BlockHash(pathfinder_crypto::Felt::from_u64(3)));
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_block_traces(
+            BlockNumber::new_or_panic(1),
+            BlockHash(pathfinder_crypto::Felt::from_u64(2)),
+            STORED_TRACES_VERSION,
+            &(),
+        )
+        .unwrap();
+
+        let status = |number| super::block_status(&tx, BlockNumber::new_or_panic(number)).unwrap();
+        assert_eq!(
+            status(0),
+            BlockStatus::Trace(BlockHash(pathfinder_crypto::Felt::from_u64(1)))
+        );
+        assert_eq!(status(1), BlockStatus::Skip);
+        assert_eq!(status(2), BlockStatus::Skip);
+        assert_eq!(status(3), BlockStatus::Missing);
+    }
+}
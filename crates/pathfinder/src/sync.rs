@@ -53,6 +53,8 @@ pub struct Sync<P, G> {
     pub l1_checkpoint_override: Option<EthereumStateUpdate>,
     pub verify_tree_hashes: bool,
     pub block_hash_db: Option<BlockHashDb>,
+    /// Sync stops once this block is stored, see [Sync::run].
+    pub stop_at_block: Option<BlockNumber>,
 }
 
 impl<P, G> Sync<P, G>
@@ -68,12 +70,47 @@ where
         + 'static,
     G: GatewayApi + Clone + Send + 'static,
 {
+    /// Only returns without an error once the stop block is stored, if one is
+    /// configured.
     pub async fn run(self) -> anyhow::Result<()> {
+        if let Some(stop) = self.stop_at_block {
+            let (next, _) = self.next_in_db().await?;
+            anyhow::ensure!(
+                next <= stop + 1,
+                "Latest block {} in the database is past the stop block {stop}",
+                next - 1
+            );
+            if next > stop {
+                tracing::info!(block=%stop, "Already synced up to the stop block");
+                return Ok(());
+            }
+        }
+
         let (next, parent_hash) = self.checkpoint_sync().await?;
 
         self.track_sync(next, parent_hash).await
     }
 
+    /// The next block to sync and its parent hash, according to the database.
+    async fn next_in_db(&self) -> anyhow::Result<(BlockNumber, BlockHash)> {
+        let storage = self.storage.clone();
+        util::task::spawn_blocking(move |_| {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            let latest = db
+                .block_id(pathfinder_storage::BlockId::Latest)
+                .context("Querying latest block")?;
+
+            Ok(latest
+                .map(|(number, hash)| (number + 1, hash))
+                .unwrap_or((BlockNumber::GENESIS, BlockHash::ZERO)))
+        })
+        .await
+        .context("Joining blocking task")?
+    }
+
     async fn handle_recoverable_error(&self, err: &error::SyncError) {
         // TODO
         tracing::debug!(%err, "Log and punish as appropriate");
@@ -114,6 +151,12 @@ where
     /// with an error.
     async fn checkpoint_sync(&self) -> anyhow::Result<(BlockNumber, BlockHash)> {
         let mut checkpoint = self.get_checkpoint().await;
+        if self.past_stop_block(checkpoint.block_number) {
+            // Checkpoint sync would store headers past the stop block, so all blocks are
+            // left to track sync instead.
+            tracing::info!(checkpoint=%checkpoint.block_number, "Checkpoint is past the stop block, skipping checkpoint sync");
+            return self.next_in_db().await;
+        }
         let from = (checkpoint.block_number, checkpoint.block_hash);
 
         tracing::info!(?from, "Checkpoint sync started");
@@ -153,7 +196,9 @@ where
             // Initial sync might take so long that the latest checkpoint is actually far
             // ahead again. Repeat until we are within some margin of L1.
             let latest_checkpoint = self.get_checkpoint().await;
-            if checkpoint.block_number + CHECKPOINT_MARGIN < latest_checkpoint.block_number {
+            if checkpoint.block_number + CHECKPOINT_MARGIN < latest_checkpoint.block_number
+                && !self.past_stop_block(latest_checkpoint.block_number)
+            {
                 checkpoint = latest_checkpoint;
                 tracing::debug!(
                     local_checkpoint=%checkpoint.block_number, latest_checkpoint=%latest_checkpoint.block_number,
@@ -166,8 +211,12 @@ where
         }
     }
 
-    /// Run the track sync forever, requires the number and parent hash of the
-    /// first block to sync.
+    fn past_stop_block(&self, block: BlockNumber) -> bool {
+        self.stop_at_block.is_some_and(|stop| block > stop)
+    }
+
+    /// Run the track sync forever, or until the stop block is stored, requires
+    /// the number and parent hash of the first block to sync.
     ///
     /// ### Important
     ///
@@ -190,11 +239,16 @@ where
                 public_key: self.public_key,
                 verify_tree_hashes: self.verify_tree_hashes,
                 block_hash_db: self.block_hash_db.clone(),
+                stop_at_block: self.stop_at_block,
             }
             .run(&mut next, &mut parent_hash, self.fgw_client.clone())
             .await;
 
             match result {
+                Ok(_) if self.past_stop_block(next) => {
+                    tracing::info!(block=%next - 1, "Synced up to the stop block, stopping sync");
+                    return Ok(());
+                }
                 Ok(_) => tracing::debug!("Restarting track sync: unexpected end of Block stream"),
                 Err(SyncError::Fatal(mut error)) => {
                     tracing::error!(?error, "Stopping track sync");
@@ -494,6 +548,7 @@ mod tests {
             }),
             verify_tree_hashes: true,
             block_hash_db: None,
+            stop_at_block: None,
         };
 
         let sync_done = if error_setup.fatal_at.is_some() {
@@ -627,6 +682,42 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case::at_stop_block(3, true)]
+    #[case::past_stop_block(2, false)]
+    #[tokio::test]
+    async fn stop_block_already_stored(#[case] stop: u64, #[case] succeeds: bool) {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = generate::n_blocks(4);
+        pathfinder_storage::fake::fill(&storage, &blocks, None);
+        let (last_event_tx, _) = tokio::sync::mpsc::channel(1);
+
+        let sync = Sync {
+            storage: storage.clone(),
+            p2p: FakeP2PClient {
+                blocks: vec![],
+                error_trigger: ErrorTrigger::new(None),
+                storage,
+                last_event_tx,
+            },
+            eth_client: EthereumClient::new("https://unused.com").unwrap(),
+            eth_address: H160::zero(),
+            fgw_client: FakeFgw {
+                head: (BlockNumber::GENESIS, BlockHash::ZERO),
+            },
+            chain_id: ChainId::SEPOLIA_TESTNET,
+            public_key: PublicKey::ZERO,
+            l1_checkpoint_override: None,
+            verify_tree_hashes: true,
+            block_hash_db: None,
+            stop_at_block: Some(BlockNumber::new_or_panic(stop)),
+        };
+
+        // Neither L1 nor peers are queried, so sync ends straight away.
+        let result = tokio::time::timeout(TIMEOUT, sync.run()).await.unwrap();
+        assert_eq!(result.is_ok(), succeeds);
+    }
+
     #[derive(Clone)]
     struct FakeP2PClient {
         pub blocks: Vec<Block>,
@@ -40,6 +40,8 @@ pub struct Sync<L, P> {
     pub public_key: PublicKey,
     pub block_hash_db: Option<pathfinder_block_hashes::BlockHashDb>,
     pub verify_tree_hashes: bool,
+    /// The block stream ends once this block is stored.
+    pub stop_at_block: Option<BlockNumber>,
 }
 
 impl<L, P> Sync<L, P> {
@@ -63,6 +65,7 @@ impl<L, P> Sync<L, P> {
             p2p: self.p2p.clone(),
             latest_onchain: self.latest.clone(),
             start: *next,
+            stop: self.stop_at_block,
         }
         .spawn()
         .pipe(headers::ForwardContinuity::new(*next, *parent_hash), 100)
@@ -171,6 +174,7 @@ struct HeaderSource<L, P> {
     p2p: P,
     latest_onchain: L,
     start: BlockNumber,
+    stop: Option<BlockNumber>,
 }
 
 impl<L, P> HeaderSource<L, P> {
@@ -184,13 +188,17 @@ impl<L, P> HeaderSource<L, P> {
             p2p,
             latest_onchain,
             mut start,
+            stop,
         } = self;
 
         util::task::spawn(async move {
             let mut latest_onchain = Box::pin(latest_onchain);
             while let Some(latest_onchain) = latest_onchain.next().await {
-                let mut headers =
-                    Box::pin(p2p.clone().header_stream(start, latest_onchain.0, false));
+                let end = match stop {
+                    Some(stop) => std::cmp::min(latest_onchain.0, stop),
+                    None => latest_onchain.0,
+                };
+                let mut headers = Box::pin(p2p.clone().header_stream(start, end, false));
 
                 while let Some(header) = headers.next().await {
                     start = header.data.header.number + 1;
@@ -199,6 +207,11 @@ impl<L, P> HeaderSource<L, P> {
                         return;
                     }
                 }
+
+                // Ending the stream ends the sync once all blocks are stored.
+                if stop.is_some_and(|stop| start > stop) {
+                    return;
+                }
             }
         });
 
@@ -0,0 +1,138 @@
+//! Forwarding of requests for pruned blocks to an upstream archive node.
+//!
+//! A node running with blockchain history pruning cannot serve requests for
+//! blocks outside of its history window. If an archive fallback is configured,
+//! such requests are transparently forwarded to the upstream node and its
+//! response is returned to the client instead of a `BlockPruned` error.
+//!
+//! Responses are requested in the encoding the client asked for, so that felts
+//! in them are encoded the same way as in responses served locally.
+
+use anyhow::Context;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::jsonrpc::{Encoding, RpcError};
+use crate::RpcVersion;
+
+#[derive(Clone, Debug)]
+pub struct ArchiveFallback {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl ArchiveFallback {
+    /// `url` is the root URL of the upstream node's JSON-RPC server, requests
+    /// are sent to the path matching the version of the local endpoint they
+    /// were received on.
+    ///
+    /// Forwarded requests count towards the timeout of the method call, which
+    /// is enforced by the caller.
+    pub fn new(url: Url) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .build()
+            .context("Creating archive fallback HTTP client")?;
+
+        Ok(Self { client, url })
+    }
+
+    /// Sends the raw JSON-RPC `request` to the upstream node and returns its
+    /// output.
+    ///
+    /// Errors returned by the upstream node are passed through as-is, an error
+    /// is only returned if the upstream node could not be reached or its
+    /// response could not be parsed.
+    pub(crate) async fn forward(
+        &self,
+        request: &str,
+        version: RpcVersion,
+        encoding: Encoding,
+    ) -> anyhow::Result<Result<Value, RpcError>> {
+        let url = self
+            .url
+            .join(version_path(version))
+            .context("Building upstream URL")?;
+
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(reqwest::header::ACCEPT, encoding.content_type())
+            .body(request.to_owned())
+            .send()
+            .await
+            .context("Sending request to upstream")?
+            .error_for_status()
+            .context("Upstream response status")?;
+        // Nodes which don't support CBOR respond with JSON instead.
+        let is_cbor = crate::jsonrpc::is_cbor(response.headers());
+        let response = response
+            .bytes()
+            .await
+            .context("Reading upstream response")?;
+        let response = if is_cbor {
+            ciborium::from_reader::<Response<ciborium::Value>, _>(response.as_ref())
+                .context("Parsing upstream response")?
+                .into_json()?
+        } else {
+            serde_json::from_slice::<Response<Value>>(&response)
+                .context("Parsing upstream response")?
+        };
+
+        match response.error {
+            Some(error) => Ok(Err(RpcError::Upstream {
+                code: error.code,
+                message: error.message,
+                data: error.data,
+            })),
+            // A `null` result deserializes to `None`.
+            None => Ok(Ok(response.result.unwrap_or_default())),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<ResponseError<T>>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError<T> {
+    code: i32,
+    message: String,
+    data: Option<T>,
+}
+
+impl Response<ciborium::Value> {
+    fn into_json(self) -> anyhow::Result<Response<Value>> {
+        let convert = |value: Option<ciborium::Value>| {
+            value
+                .map(crate::jsonrpc::cbor_response_to_value)
+                .transpose()
+                .context("Converting upstream response")
+        };
+
+        Ok(Response {
+            result: convert(self.result)?,
+            error: match self.error {
+                Some(error) => Some(ResponseError {
+                    code: error.code,
+                    message: error.message,
+                    data: convert(error.data)?,
+                }),
+                None => None,
+            },
+        })
+    }
+}
+
+fn version_path(version: RpcVersion) -> &'static str {
+    match version {
+        RpcVersion::V06 => "rpc/v0_6",
+        RpcVersion::V07 => "rpc/v0_7",
+        RpcVersion::V08 => "rpc/v0_8",
+        RpcVersion::PathfinderV01 => "rpc/pathfinder/v0_1",
+    }
+}
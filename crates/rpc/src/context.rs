@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::time::Duration;
 
 use pathfinder_common::{contract_address, ChainId, ContractAddress};
 use pathfinder_ethereum::EthereumClient;
@@ -8,9 +10,11 @@ use pathfinder_storage::Storage;
 use primitive_types::H160;
 use util::percentage::Percentage;
 
+use crate::archive_fallback::ArchiveFallback;
 pub use crate::jsonrpc::websocket::WebsocketContext;
 use crate::jsonrpc::Notifications;
 use crate::pending::{PendingData, PendingWatcher};
+use crate::response_cache::ResponseCache;
 use crate::SyncState;
 
 type SequencerClient = starknet_gateway_client::Client;
@@ -71,6 +75,24 @@ pub struct RpcConfig {
     pub versioned_constants_map: VersionedConstantsMap,
     pub native_execution: bool,
     pub native_class_cache_size: NonZeroUsize,
+    /// Maximum size of a request body or WebSocket message in bytes.
+    pub request_max_size: usize,
+    /// Time after which a request, including all calls of a batch, is
+    /// aborted. Also the timeout of each method call, unless overridden in
+    /// `method_timeouts`.
+    pub request_timeout: Duration,
+    /// Timeouts of individual method calls, which can't extend past the
+    /// `request_timeout` of the request they are part of.
+    pub method_timeouts: HashMap<String, Duration>,
+}
+
+impl RpcConfig {
+    pub(crate) fn method_timeout(&self, method_name: &str) -> Duration {
+        self.method_timeouts
+            .get(method_name)
+            .copied()
+            .unwrap_or(self.request_timeout)
+    }
 }
 
 #[derive(Clone)]
@@ -88,6 +110,8 @@ pub struct RpcContext {
     pub ethereum: EthereumClient,
     pub config: RpcConfig,
     pub native_class_cache: Option<NativeClassCache>,
+    pub archive_fallback: Option<ArchiveFallback>,
+    pub response_cache: Option<ResponseCache>,
 }
 
 impl RpcContext {
@@ -124,6 +148,8 @@ impl RpcContext {
             ethereum,
             config,
             native_class_cache,
+            archive_fallback: None,
+            response_cache: None,
         }
     }
 
@@ -150,6 +176,34 @@ impl RpcContext {
         }
     }
 
+    /// Requests for blocks pruned from local storage are forwarded to
+    /// `archive_fallback` instead of failing.
+    pub fn with_archive_fallback(self, archive_fallback: ArchiveFallback) -> Self {
+        Self {
+            archive_fallback: Some(archive_fallback),
+            ..self
+        }
+    }
+
+    /// Responses to requests for L1-accepted blocks are cached, up to
+    /// `capacity` bytes.
+    pub fn with_response_cache(self, capacity: NonZeroUsize) -> Self {
+        let reorgs = self.notifications.reorgs.subscribe();
+        Self {
+            response_cache: Some(ResponseCache::new(capacity, reorgs)),
+            ..self
+        }
+    }
+
+    /// Block traces are persisted to `storage`, which must be writable, and
+    /// served from there instead of re-executing the block.
+    pub fn with_trace_store(self, storage: Storage) -> Self {
+        Self {
+            cache: self.cache.with_store(storage),
+            ..self
+        }
+    }
+
     #[cfg(test)]
     pub fn with_notifications(self, notifications: Notifications) -> Self {
         Self {
@@ -213,6 +267,9 @@ impl RpcContext {
             versioned_constants_map: Default::default(),
             native_execution: true,
             native_class_cache_size: NonZeroUsize::new(10).unwrap(),
+            request_max_size: 10 * 1024 * 1024,
+            request_timeout: Duration::from_secs(120),
+            method_timeouts: Default::default(),
         };
 
         let ethereum =
@@ -28,13 +28,48 @@ use crate::RpcVersion;
 #[cfg_attr(test, derive(Default))]
 pub struct Serializer {
     pub version: RpcVersion,
+    /// Felts are serialized as [binary felts](binary_felt) instead of hex
+    /// strings.
+    pub binary_felts: bool,
 }
 
 pub struct SerializeStruct {
     pub version: RpcVersion,
+    binary_felts: bool,
     fields: serde_json::Map<String, Ok>,
 }
 
+/// The single-entry object which stands in for a felt when serializing for a
+/// binary encoding, mapping [binary_felt_key] to the felt's hex string.
+///
+/// [serde_json::Value] has no byte strings, so binary encoders replace these
+/// objects with the raw bytes of the felt.
+pub(crate) fn binary_felt(hex_str: impl Into<String>) -> serde_json::Value {
+    let mut felt = serde_json::Map::with_capacity(1);
+    felt.insert(
+        binary_felt_key().to_owned(),
+        serde_json::Value::String(hex_str.into()),
+    );
+    serde_json::Value::Object(felt)
+}
+
+/// The key of [binary felts](binary_felt).
+///
+/// It holds a nonce chosen at random when the node starts and is never sent to
+/// clients, so that objects which are part of the data being serialized, e.g.
+/// class ABIs, can't be mistaken for felts.
+pub(crate) fn binary_felt_key() -> &'static str {
+    use std::hash::{BuildHasher, Hasher};
+
+    static KEY: std::sync::LazyLock<String> = std::sync::LazyLock::new(|| {
+        let nonce = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        format!("\0felt-{nonce:016x}")
+    });
+    &KEY
+}
+
 type BaseSerializer = serde_json::value::Serializer;
 pub(crate) type Ok = <BaseSerializer as serde::Serializer>::Ok;
 pub(crate) type Error = <BaseSerializer as serde::Serializer>::Error;
@@ -57,7 +92,18 @@ impl SerializeForVersion for &serde_json::Value {
 
 impl Serializer {
     pub fn new(version: RpcVersion) -> Self {
-        Self { version }
+        Self {
+            version,
+            binary_felts: false,
+        }
+    }
+
+    /// Serializes felts for binary encodings, see [binary_felt].
+    pub fn with_binary_felts(self, binary_felts: bool) -> Self {
+        Self {
+            binary_felts,
+            ..self
+        }
     }
 
     pub fn serialize(self, value: &dyn SerializeForVersion) -> Result<Ok, Error> {
@@ -107,6 +153,7 @@ impl Serializer {
     pub fn serialize_struct(self) -> Result<SerializeStruct, Error> {
         Ok(SerializeStruct {
             version: self.version,
+            binary_felts: self.binary_felts,
             fields: Default::default(),
         })
     }
@@ -129,12 +176,16 @@ impl Serializer {
 }
 
 impl SerializeStruct {
+    fn serializer(&self) -> Serializer {
+        Serializer::new(self.version).with_binary_felts(self.binary_felts)
+    }
+
     pub fn serialize_field(
         &mut self,
         key: &'static str,
         value: &dyn SerializeForVersion,
     ) -> Result<(), Error> {
-        let value = value.serialize(Serializer::new(self.version))?;
+        let value = value.serialize(self.serializer())?;
         self.fields.insert(key.to_owned(), value);
         Ok(())
     }
@@ -145,7 +196,7 @@ impl SerializeStruct {
         len: usize,
         values: &mut dyn Iterator<Item = impl SerializeForVersion>,
     ) -> Result<(), Error> {
-        let seq = Serializer::new(self.version).serialize_iter(len, values)?;
+        let seq = self.serializer().serialize_iter(len, values)?;
         self.serialize_field(key, &seq)
     }
 
@@ -178,7 +229,7 @@ impl SerializeStruct {
     }
 
     pub fn flatten(&mut self, value: &dyn SerializeForVersion) -> Result<(), Error> {
-        let value = value.serialize(Serializer::new(self.version))?;
+        let value = value.serialize(self.serializer())?;
 
         if let serde_json::Value::Object(value) = value {
             for (k, v) in value {
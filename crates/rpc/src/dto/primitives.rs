@@ -382,14 +382,16 @@ mod pathfinder_crypto {
     impl SerializeForVersion for pathfinder_crypto::Felt {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
             let hex_str = hex_str::bytes_to_hex_str_stripped(self.as_be_bytes());
+            if serializer.binary_felts {
+                return Ok(crate::dto::binary_felt(hex_str));
+            }
             serializer.serialize_str(&hex_str)
         }
     }
 
     impl SerializeForVersion for &pathfinder_crypto::Felt {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            (*self).serialize(serializer)
         }
     }
 
@@ -411,6 +413,9 @@ mod pathfinder_crypto {
 
     impl SerializeForVersion for crate::felt::RpcFelt {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
+            if serializer.binary_felts {
+                return self.0.serialize(serializer);
+            }
             // StarkHash has a leading "0x" and at most 64 digits
             let mut buf = [0u8; 2 + 64];
             let s = self.0.as_hex_str(&mut buf);
@@ -465,7 +470,7 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for &pathfinder_common::AccountDeploymentDataElem {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -477,8 +482,7 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::BlockHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
@@ -490,105 +494,91 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::ChainId {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::CallParam {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::CallResultValue {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::CasmHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::CasmHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::ClassCommitment {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::ClassHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::ClassHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::ContractAddress {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::ContractAddress {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::ContractAddressSalt {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::ContractNonce {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::ContractRoot {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::ConstructorParam {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::EntryPoint {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            let hex_str = hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes());
-            serializer.serialize_str(&hex_str)
+            self.0.serialize(serializer)
         }
     }
 
@@ -601,19 +591,19 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::EventCommitment {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::EventData {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::EventKey {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -637,7 +627,7 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::Fee {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -658,7 +648,7 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for &pathfinder_common::L2ToL1MessagePayloadElem {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -681,7 +671,7 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::ReceiptCommitment {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -703,7 +693,7 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::SierraHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -718,31 +708,31 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::StateCommitment {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::StorageAddress {
         fn serialize(&self, serializer: Serializer) -> Result<dto::Ok, dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::StorageCommitment {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::StorageValue {
         fn serialize(&self, serializer: Serializer) -> Result<dto::Ok, dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::SequencerAddress {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
@@ -762,43 +752,43 @@ mod pathfinder_common_types {
 
     impl SerializeForVersion for pathfinder_common::TransactionCommitment {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::TransactionHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::TransactionHash {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::TransactionNonce {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::TransactionSignatureElem {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for pathfinder_common::TransactionVersion {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 
     impl SerializeForVersion for &pathfinder_common::PaymasterDataElem {
         fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
-            serializer.serialize_str(&hex_str::bytes_to_hex_str_stripped(self.0.as_be_bytes()))
+            self.0.serialize(serializer)
         }
     }
 }
@@ -134,7 +134,7 @@ impl crate::dto::SerializeForVersion for &pathfinder_executor::types::FunctionIn
         serializer.serialize_iter("messages", self.messages.len(), &mut self.messages.iter())?;
         serializer.serialize_iter("result", self.result.len(), &mut self.result.iter())?;
         match serializer.version {
-            RpcVersion::V08 => {
+            RpcVersion::V08 | RpcVersion::PathfinderV01 => {
                 serializer.serialize_field(
                     "execution_resources",
                     &InnerCallExecutionResources(&self.execution_resources),
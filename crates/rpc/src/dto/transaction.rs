@@ -377,11 +377,7 @@ mod tests {
                 "signature": ["0xa1b1", "0x1a1b"],
                 "class_hash": "0x123",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -412,11 +408,7 @@ mod tests {
                 "class_hash": "0x123",
                 "nonce": "0xaabbcc",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -450,11 +442,7 @@ mod tests {
                 "nonce": "0xaabbcc",
                 "compiled_class_hash": "0xbbbbb",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -514,11 +502,7 @@ mod tests {
                 "paymaster_data": [],
                 "account_deployment_data": [],
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -547,11 +531,7 @@ mod tests {
                 "constructor_calldata": ["0xbbb0","0xbbb1"],
                 "version": "0x0",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -586,11 +566,7 @@ mod tests {
                 "constructor_calldata": ["0xbbb0","0xbbb1"],
                 "class_hash": "0x123",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -649,11 +625,7 @@ mod tests {
                 "tip": "0x5",
                 "paymaster_data": [],
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -686,11 +658,7 @@ mod tests {
                 "max_fee": "0x1111",
                 "signature": ["0xa1b1", "0x1a1b"],
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -722,11 +690,7 @@ mod tests {
                 "signature": ["0xa1b1", "0x1a1b"],
                 "nonce": "0xaabbcc",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -784,11 +748,7 @@ mod tests {
                 "paymaster_data": [],
                 "account_deployment_data": [],
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -815,11 +775,7 @@ mod tests {
                 "calldata": ["0xfff1","0xfff0"],
                 "version": "0x0",
             });
-            let result = uut
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap();
+            let result = uut.serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
             assert_eq!(result, expected);
         }
@@ -5,7 +5,7 @@
 //! be used by each JSON-RPC method to trivially create its subset of
 //! [ApplicationError] along with the boilerplate involved.
 #![macro_use]
-use pathfinder_common::TransactionHash;
+use pathfinder_common::{BlockNumber, TransactionHash};
 use serde_json::json;
 
 #[derive(serde::Serialize, Clone, Copy, Debug)]
@@ -25,6 +25,15 @@ pub enum ApplicationError {
     EntrypointNotFound,
     #[error("Block not found")]
     BlockNotFound,
+    /// The requested block's data has been pruned from the database. Holds
+    /// the oldest block that is still available.
+    ///
+    /// The Starknet specification has no such error, so the `starknet_*` API
+    /// versions use the code of [ApplicationError::BlockNotFound]. The message
+    /// and the oldest available block in the data are the same for all
+    /// versions.
+    #[error("Block has been pruned")]
+    BlockPruned(BlockNumber),
     #[error("Invalid transaction index in a block")]
     InvalidTxnIndex,
     #[error("Invalid transaction hash")]
@@ -108,6 +117,8 @@ pub enum ApplicationError {
     InvalidSubscriptionID,
     #[error("Too many addresses in filter sender_address filter")]
     TooManyAddressesInFilter,
+    #[error("Too many contract addresses in event filter")]
+    TooManyAddressesInEventFilter { limit: usize, requested: usize },
     /// Internal errors are errors whose details we don't want to show to the
     /// end user. These are logged, and a simple "internal error" message is
     /// shown to the end user.
@@ -165,6 +176,16 @@ impl ApplicationError {
             // specs/rpc/pathfinder_rpc_api.json
             ApplicationError::ProofLimitExceeded { .. } => 10000,
             ApplicationError::ProofMissing => 10001,
+            ApplicationError::BlockPruned(_) => match version {
+                RpcVersion::PathfinderV01 => 10002,
+                _ => ApplicationError::BlockNotFound.code(version),
+            },
+            // Filters by several addresses are a pathfinder extension, so the Starknet API
+            // versions report exceeding the limit as invalid params.
+            ApplicationError::TooManyAddressesInEventFilter { .. } => match version {
+                RpcVersion::PathfinderV01 => 10003,
+                _ => -32602,
+            },
             ApplicationError::SubscriptionTransactionHashNotFound { .. } => 10029,
             ApplicationError::SubscriptionGatewayDown { .. } => 10030,
             // specs/rpc/starknet_ws_api.json
@@ -206,6 +227,9 @@ impl ApplicationError {
             ApplicationError::ContractNotFound => None,
             ApplicationError::EntrypointNotFound => None,
             ApplicationError::BlockNotFound => None,
+            ApplicationError::BlockPruned(oldest_available_block) => Some(json!({
+                "oldest_available_block": oldest_available_block,
+            })),
             ApplicationError::InvalidTxnIndex => None,
             ApplicationError::InvalidTxnHash => None,
             ApplicationError::InvalidBlockHash => None,
@@ -284,6 +308,10 @@ impl ApplicationError {
                 "limit": limit,
                 "requested": requested,
             })),
+            ApplicationError::TooManyAddressesInEventFilter { limit, requested } => Some(json!({
+                "limit": limit,
+                "requested": requested,
+            })),
             ApplicationError::UnexpectedError { data } => Some(json!(data)),
             ApplicationError::ProofLimitExceeded { limit, requested } => Some(json!({
                 "limit": limit,
@@ -349,7 +377,9 @@ fn error_stack_frames_to_json(frames: &[pathfinder_executor::Frame]) -> serde_js
 /// Note that the variants __must__ match the [ApplicationError] variant names
 /// and that [ApplicationError::Internal] and [ApplicationError::Custom] are
 /// always included by default (and therefore should not be part of macro
-/// input).
+/// input). Variants with a single field, such as
+/// [ApplicationError::BlockPruned], are supported by spelling out the field
+/// type, e.g. `BlockPruned(BlockNumber)`. Struct variants are not supported.
 ///
 /// An `Internal` only variant can be generated using
 /// `generate_rpc_error_subset!(<enum_name>)`.
@@ -417,20 +447,20 @@ macro_rules! generate_rpc_error_subset {
         generate_rpc_error_subset!(@from_def, $enum_name,);
     };
     // Main entry-point for the macro
-    ($enum_name:ident: $($subset:tt),+) => {
-        generate_rpc_error_subset!(@enum_def, $enum_name, $($subset),+);
+    ($enum_name:ident: $($variant:ident $(($field:ty))?),+) => {
+        generate_rpc_error_subset!(@enum_def, $enum_name, $($variant $(($field))?),+);
         generate_rpc_error_subset!(@from_anyhow, $enum_name);
-        generate_rpc_error_subset!(@from_def, $enum_name, $($subset),+);
+        generate_rpc_error_subset!(@from_def, $enum_name, $($variant $(($field))?),+);
     };
     // Generates the enum definition, nothing tricky here.
-    (@enum_def, $enum_name:ident, $($subset:tt),*) => {
+    (@enum_def, $enum_name:ident, $($variant:ident $(($field:ty))?),*) => {
         #[derive(Debug)]
         pub enum $enum_name {
             /// See [`crate::error::ApplicationError::Internal`]
             Internal(anyhow::Error),
             /// See [`crate::error::ApplicationError::Custom`]
             Custom(anyhow::Error),
-            $($subset),*
+            $($variant $(($field))?),*
         }
     };
     // Generates From<anyhow::Error>, nothing tricky here.
@@ -455,10 +485,10 @@ macro_rules! generate_rpc_error_subset {
     //
     // By pushing the arms from this level downwards, and creating the match statement at the lowest
     // level, we guarantee that only valid valid Rust will bubble back up.
-    (@from_def, $enum_name:ident, $($variants:ident),*) => {
+    (@from_def, $enum_name:ident, $($variant:ident $(($field:ty))?),*) => {
         impl From<$enum_name> for crate::error::ApplicationError {
             fn from(x: $enum_name) -> Self {
-                generate_rpc_error_subset!(@parse, x, $enum_name, {}, $($variants),*)
+                generate_rpc_error_subset!(@parse, x, $enum_name, {}, $($variant $(($field))?),*)
             }
         }
     };
@@ -470,26 +500,28 @@ macro_rules! generate_rpc_error_subset {
             $enum_name::Custom(error) => Self::Custom(error),
         }
     };
-    // Special case for single variant. This could probably be folded into one of the other
-    // cases but I struggled to do so correctly.
-    (@parse, $var:ident, $enum_name:ident, {$($arms:tt)*}, $variant:ident) => {
+    // Append this single field tuple variant to arms. Continue parsing the remaining
+    // variants, if any. This must come before the unit variant case, which would
+    // otherwise fail on the field.
+    (@parse, $var:ident, $enum_name:ident, {$($arms:tt)*}, $variant:ident($field:ty) $(, $($tail:tt)*)?) => {
         generate_rpc_error_subset!(
             @parse, $var, $enum_name,
             {
                 $($arms)*
-                $enum_name::$variant => Self::$variant,
+                $enum_name::$variant(data) => Self::$variant(data),
             },
+            $($($tail)*)?
         )
     };
-    // Append this variant to arms. Continue parsing the remaining variants.
-    (@parse, $var:ident, $enum_name:ident, {$($arms:tt)*}, $variant:ident, $($tail:ident),*) => {
+    // Append this unit variant to arms. Continue parsing the remaining variants, if any.
+    (@parse, $var:ident, $enum_name:ident, {$($arms:tt)*}, $variant:ident $(, $($tail:tt)*)?) => {
         generate_rpc_error_subset!(
             @parse, $var, $enum_name,
             {
                 $($arms)*
                 $enum_name::$variant => Self::$variant,
             },
-            $($tail),*
+            $($($tail)*)?
         )
     };
 }
@@ -501,6 +533,54 @@ use crate::RpcVersion;
 
 #[cfg(test)]
 mod tests {
+    mod pathfinder_errors {
+        use pathfinder_common::BlockNumber;
+        use serde_json::json;
+
+        use super::super::ApplicationError;
+        use crate::RpcVersion;
+
+        #[test]
+        fn has_block_not_found_code_on_spec_versions() {
+            let error = ApplicationError::BlockPruned(BlockNumber::new_or_panic(5));
+
+            for version in [RpcVersion::V06, RpcVersion::V07, RpcVersion::V08] {
+                assert_eq!(error.code(version), 24);
+                assert_eq!(error.message(version), "Block has been pruned");
+                assert_eq!(
+                    error.data(version),
+                    Some(json!({"oldest_available_block": 5}))
+                );
+            }
+        }
+
+        #[test]
+        fn is_dedicated_on_pathfinder_api() {
+            let error = ApplicationError::BlockPruned(BlockNumber::new_or_panic(5));
+            let version = RpcVersion::PathfinderV01;
+
+            assert_eq!(error.code(version), 10002);
+            assert_eq!(error.message(version), "Block has been pruned");
+            assert_eq!(
+                error.data(version),
+                Some(json!({"oldest_available_block": 5}))
+            );
+        }
+
+        #[test]
+        fn too_many_addresses_is_invalid_params_on_spec_versions() {
+            let error = ApplicationError::TooManyAddressesInEventFilter {
+                limit: 64,
+                requested: 65,
+            };
+
+            for version in [RpcVersion::V06, RpcVersion::V07, RpcVersion::V08] {
+                assert_eq!(error.code(version), -32602);
+            }
+            assert_eq!(error.code(RpcVersion::PathfinderV01), 10003);
+        }
+    }
+
     mod rpc_error_subset {
         use assert_matches::assert_matches;
 
@@ -531,6 +611,22 @@ mod tests {
             assert_matches!(contract_not_found, ApplicationError::ContractNotFound);
             assert_matches!(no_blocks, ApplicationError::NoBlocks);
         }
+
+        #[test]
+        fn tuple_variant() {
+            use pathfinder_common::BlockNumber;
+
+            generate_rpc_error_subset!(Tuple: BlockNotFound, BlockPruned(BlockNumber), NoBlocks);
+
+            let block_pruned = ApplicationError::from(Tuple::BlockPruned(BlockNumber::GENESIS));
+            let no_blocks = ApplicationError::from(Tuple::NoBlocks);
+
+            assert_matches!(
+                block_pruned,
+                ApplicationError::BlockPruned(oldest) if oldest == BlockNumber::GENESIS
+            );
+            assert_matches!(no_blocks, ApplicationError::NoBlocks);
+        }
     }
 
     mod error_stack {
@@ -36,6 +36,20 @@ impl From<anyhow::Error> for ExecutionStateError {
     }
 }
 
+tokio::task_local! {
+    /// Cancelled once the request being served is dropped, e.g. because it
+    /// timed out.
+    pub(crate) static REQUEST_CANCELLATION: util::task::CancellationToken;
+}
+
+/// Returns the token to stop execution for the current request with, which has
+/// to be called before moving to a blocking task.
+pub(crate) fn request_cancellation() -> util::task::CancellationToken {
+    REQUEST_CANCELLATION
+        .try_with(Clone::clone)
+        .unwrap_or_default()
+}
+
 pub const VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY:
     StarknetVersion = StarknetVersion::new(0, 13, 1, 1);
 
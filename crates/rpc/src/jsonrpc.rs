@@ -1,17 +1,21 @@
+mod encoding;
 mod error;
 mod request;
 mod response;
 mod router;
 pub mod websocket;
 
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 
+pub(crate) use encoding::{cbor_response_to_value, is_cbor, Encoding};
 pub use error::RpcError;
 use pathfinder_common::{BlockHash, BlockNumber};
-pub use request::RpcRequest;
+pub use request::{RawParams, RpcRequest};
 pub use response::RpcResponse;
 #[cfg(test)]
 pub use router::handle_json_rpc_socket;
+pub(crate) use router::missed_reorgs;
 pub use router::{
     rpc_handler,
     CatchUp,
@@ -44,6 +48,65 @@ pub struct Notifications {
     pub block_headers: broadcast::Sender<Arc<pathfinder_common::BlockHeader>>,
     pub l2_blocks: broadcast::Sender<Arc<Block>>,
     pub reorgs: broadcast::Sender<Arc<Reorg>>,
+    pub reorg_log: ReorgLog,
+}
+
+impl Notifications {
+    /// Records the reorg in [Notifications::reorg_log] and broadcasts it.
+    /// `orphaned` holds the hashes of the reorged away blocks.
+    pub fn send_reorg(&self, reorg: Reorg, orphaned: Vec<BlockHash>) {
+        let reorg = Arc::new(reorg);
+        self.reorg_log.record(reorg.clone(), orphaned);
+        // Ignore errors in case nobody is listening. New listeners may subscribe in the
+        // future.
+        self.reorgs.send(reorg).ok();
+    }
+}
+
+/// The most recent reorgs, along with the hashes of the blocks they removed,
+/// so that clients resuming from a reorged away block can be told what they
+/// missed.
+#[derive(Debug, Clone, Default)]
+pub struct ReorgLog(Arc<Mutex<VecDeque<(Arc<Reorg>, Vec<BlockHash>)>>>);
+
+impl ReorgLog {
+    /// Number of reorged away blocks remembered. The oldest reorgs are
+    /// forgotten first.
+    const CAPACITY: usize = 10_000;
+
+    fn record(&self, reorg: Arc<Reorg>, orphaned: Vec<BlockHash>) {
+        let mut log = self.0.lock().unwrap();
+        log.push_back((reorg, orphaned));
+
+        let mut len = log.iter().map(|(_, orphaned)| orphaned.len()).sum::<usize>();
+        while len > Self::CAPACITY && log.len() > 1 {
+            if let Some((_, orphaned)) = log.pop_front() {
+                len -= orphaned.len();
+            }
+        }
+    }
+
+    /// The reorg which removed the block with `block_hash` and all reorgs
+    /// since, along with the first block of the current chain which differs
+    /// from the chain the block was part of.
+    ///
+    /// [None] if the block is not known to have been reorged away.
+    pub(crate) fn since(&self, block_hash: BlockHash) -> Option<(Vec<Arc<Reorg>>, BlockNumber)> {
+        let log = self.0.lock().unwrap();
+        let position = log
+            .iter()
+            .position(|(_, orphaned)| orphaned.contains(&block_hash))?;
+        let reorgs = log
+            .iter()
+            .skip(position)
+            .map(|(reorg, _)| reorg.clone())
+            .collect::<Vec<_>>();
+        let fork = reorgs
+            .iter()
+            .map(|reorg| reorg.first_block_number)
+            .min()?;
+        Some((reorgs, fork))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +115,9 @@ pub struct Reorg {
     pub first_block_hash: BlockHash,
     pub last_block_number: BlockNumber,
     pub last_block_hash: BlockHash,
+    /// The latest block left after the reorg, which the replacing blocks
+    /// build on. [`None`] if the reorg removed genesis.
+    pub new_head: Option<(BlockNumber, BlockHash)>,
 }
 
 impl Default for Notifications {
@@ -63,6 +129,7 @@ impl Default for Notifications {
             block_headers,
             l2_blocks,
             reorgs,
+            reorg_log: Default::default(),
         }
     }
 }
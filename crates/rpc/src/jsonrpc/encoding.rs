@@ -0,0 +1,439 @@
+//! Binary encoding of JSON-RPC requests and responses.
+//!
+//! Clients can send requests encoded as CBOR by setting the `Content-Type`
+//! header to `application/cbor`, and ask for CBOR responses with the `Accept`
+//! header. Messages have the same structure as their JSON counterparts, except
+//! that felts in responses are byte strings holding their 32 big-endian bytes.
+//! Byte strings in requests are accepted wherever a felt is expected.
+//!
+//! Responses are serialized to [serde_json::Value]s either way. For CBOR,
+//! felts are serialized as [binary felts](crate::dto::binary_felt), which are
+//! turned into byte strings when encoding the value.
+
+use anyhow::Context;
+use http::HeaderValue;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde_json::Value;
+
+use crate::dto::binary_felt_key;
+
+const APPLICATION_CBOR: &str = "application/cbor";
+
+/// CBOR tag of an unsigned bignum, see RFC 8949 section 3.4.3.
+const TAG_UNSIGNED_BIGNUM: u64 = 2;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) enum Encoding {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl Encoding {
+    /// The encoding requested for the response by the `Accept` header.
+    ///
+    /// CBOR is used if it is accepted with a quality at least as high as that
+    /// of `application/json`. Wildcards are ignored, as JSON is the default.
+    pub(crate) fn accepted(headers: &http::HeaderMap) -> Self {
+        let mut cbor = 0.0;
+        let mut json = 0.0;
+
+        let ranges = headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|range| range.trim().parse::<mime::Mime>().ok());
+        for range in ranges {
+            let quality = quality(&range);
+            match range.essence_str() {
+                APPLICATION_CBOR => cbor = f32::max(cbor, quality),
+                "application/json" => json = f32::max(json, quality),
+                _ => {}
+            }
+        }
+
+        if cbor > 0.0 && cbor >= json {
+            Self::Cbor
+        } else {
+            Self::Json
+        }
+    }
+
+    pub(crate) fn content_type(self) -> HeaderValue {
+        match self {
+            Self::Json => HeaderValue::from_static("application/json"),
+            Self::Cbor => HeaderValue::from_static(APPLICATION_CBOR),
+        }
+    }
+}
+
+/// The `q` parameter of a media range, which defaults to 1. Invalid values
+/// count as 0.
+fn quality(range: &mime::Mime) -> f32 {
+    match range.get_param("q") {
+        Some(q) => q
+            .as_str()
+            .parse::<f32>()
+            .ok()
+            .filter(|q| (0.0..=1.0).contains(q))
+            .unwrap_or(0.0),
+        None => 1.0,
+    }
+}
+
+pub(crate) fn is_cbor(headers: &http::HeaderMap) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<mime::Mime>().ok())
+        .is_some_and(|mime| mime.essence_str() == APPLICATION_CBOR)
+}
+
+/// Converts a CBOR request body to JSON.
+pub(crate) fn cbor_to_json(body: &[u8]) -> anyhow::Result<String> {
+    let value: ciborium::Value = ciborium::from_reader(body).context("Parsing CBOR")?;
+    let value = from_cbor(value, Bytes::Hex)?;
+    Ok(value.to_string())
+}
+
+/// Converts a CBOR response received from another node, such as the archive
+/// fallback, so that it is encoded the same way as our own responses. Its byte
+/// strings are felts, which become [binary felts](crate::dto::binary_felt).
+pub(crate) fn cbor_response_to_value(value: ciborium::Value) -> anyhow::Result<Value> {
+    from_cbor(value, Bytes::BinaryFelt)
+}
+
+/// Encodes a response as CBOR, turning [binary felts](crate::dto::binary_felt)
+/// into byte strings.
+pub(crate) fn json_to_cbor(value: &Value) -> Vec<u8> {
+    let mut buf = Vec::new();
+    ciborium::into_writer(&Cbor(value), &mut buf).expect("Writing to a Vec cannot fail");
+    buf
+}
+
+/// Writes a [Value] as CBOR, without converting it to a [ciborium::Value]
+/// first.
+struct Cbor<'a>(&'a Value);
+
+impl serde::Serialize for Cbor<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    serializer.serialize_u64(n)
+                } else if let Some(n) = n.as_i64() {
+                    serializer.serialize_i64(n)
+                } else if let Ok(n) = n.to_string().parse::<u128>() {
+                    let bytes = n.to_be_bytes();
+                    let start = bytes.iter().take_while(|b| **b == 0).count();
+                    ciborium::Value::Tag(
+                        TAG_UNSIGNED_BIGNUM,
+                        Box::new(ciborium::Value::Bytes(bytes[start..].to_vec())),
+                    )
+                    .serialize(serializer)
+                } else {
+                    serializer.serialize_f64(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(&Cbor(value))?;
+                }
+                seq.end()
+            }
+            Value::Object(map) => {
+                if let Some(felt) = binary_felt(map) {
+                    return serializer.serialize_bytes(&felt);
+                }
+                let mut entries = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    entries.serialize_entry(key, &Cbor(value))?;
+                }
+                entries.end()
+            }
+        }
+    }
+}
+
+fn binary_felt(map: &serde_json::Map<String, Value>) -> Option<[u8; 32]> {
+    if map.len() != 1 {
+        return None;
+    }
+    let Value::String(hex) = map.get(binary_felt_key())? else {
+        return None;
+    };
+    crate::dto::hex_str::bytes_from_hex_str_stripped::<32>(hex).ok()
+}
+
+/// What CBOR byte strings are converted to.
+#[derive(Copy, Clone)]
+enum Bytes {
+    /// Hex strings, as expected by the JSON API.
+    Hex,
+    /// [Binary felts](crate::dto::binary_felt), which are encoded as byte
+    /// strings again.
+    BinaryFelt,
+}
+
+fn from_cbor(value: ciborium::Value, bytes: Bytes) -> anyhow::Result<Value> {
+    use ciborium::Value as Cbor;
+
+    let value = match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(n) => {
+            let n = i128::from(n);
+            match (u64::try_from(n), i64::try_from(n)) {
+                (Ok(n), _) => n.into(),
+                (_, Ok(n)) => n.into(),
+                _ => serde_json::from_str(&n.to_string()).context("Parsing integer")?,
+            }
+        }
+        Cbor::Float(n) => serde_json::Number::from_f64(n)
+            .map(Value::Number)
+            .context("Float is not a finite number")?,
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bytes(value) => match bytes {
+            Bytes::Hex => Value::String(crate::dto::hex_str::bytes_to_hex_str_full(&value)),
+            Bytes::BinaryFelt => {
+                crate::dto::binary_felt(crate::dto::hex_str::bytes_to_hex_str_stripped(&value))
+            }
+        },
+        Cbor::Tag(_, value) => from_cbor(*value, bytes)?,
+        Cbor::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| from_cbor(value, bytes))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        Cbor::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| match key {
+                    Cbor::Text(key) => Ok((key, from_cbor(value, bytes)?)),
+                    _ => anyhow::bail!("Map keys must be strings"),
+                })
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        _ => anyhow::bail!("Unsupported CBOR value"),
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::{SerializeForVersion, Serializer};
+    use crate::RpcVersion;
+
+    #[test]
+    fn felts_are_encoded_as_bytes() {
+        let hash = block_hash!("0x1234");
+        let value = hash
+            .serialize(Serializer::new(RpcVersion::V07).with_binary_felts(true))
+            .unwrap();
+
+        // Other hex strings, such as resource bounds, are left as is. Keys are
+        // in sorted order, which objects keep with or without serde_json's
+        // `preserve_order` feature.
+        let encoded = json_to_cbor(&json!({
+            "block_hash": value,
+            "block_number": 5,
+            "max_amount": "0x1234",
+        }));
+        let decoded: ciborium::Value = ciborium::from_reader(encoded.as_slice()).unwrap();
+
+        let mut expected = [0u8; 32];
+        expected[30..].copy_from_slice(&[0x12, 0x34]);
+        assert_eq!(
+            decoded,
+            ciborium::Value::Map(vec![
+                (
+                    ciborium::Value::Text("block_hash".to_owned()),
+                    ciborium::Value::Bytes(expected.to_vec())
+                ),
+                (
+                    ciborium::Value::Text("block_number".to_owned()),
+                    ciborium::Value::Integer(5.into())
+                ),
+                (
+                    ciborium::Value::Text("max_amount".to_owned()),
+                    ciborium::Value::Text("0x1234".to_owned())
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn only_binary_felts_are_encoded_as_bytes() {
+        // Data such as ABIs can hold arbitrary objects, which must not be
+        // mistaken for felts.
+        let value = json!({"\0felt": "0x1"});
+        let encoded = json_to_cbor(&value);
+        let decoded: ciborium::Value = ciborium::from_reader(encoded.as_slice()).unwrap();
+        assert_eq!(
+            decoded,
+            ciborium::Value::Map(vec![(
+                ciborium::Value::Text("\0felt".to_owned()),
+                ciborium::Value::Text("0x1".to_owned())
+            )])
+        );
+    }
+
+    #[test]
+    fn requests_are_converted_to_json() {
+        let mut felt = [0u8; 32];
+        felt[31] = 0xab;
+        let request = ciborium::Value::Map(vec![
+            (
+                ciborium::Value::Text("jsonrpc".to_owned()),
+                ciborium::Value::Text("2.0".to_owned()),
+            ),
+            (
+                ciborium::Value::Text("id".to_owned()),
+                ciborium::Value::Integer(1.into()),
+            ),
+            (
+                ciborium::Value::Text("params".to_owned()),
+                ciborium::Value::Array(vec![ciborium::Value::Bytes(felt.to_vec())]),
+            ),
+        ]);
+        let mut body = Vec::new();
+        ciborium::into_writer(&request, &mut body).unwrap();
+
+        let json: Value = serde_json::from_str(&cbor_to_json(&body).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "params": [format!("0x{}ab", "0".repeat(62))],
+            })
+        );
+    }
+
+    #[test]
+    fn large_numbers() {
+        let value: Value = serde_json::from_str("340282366920938463463374607431768211455").unwrap();
+        let encoded = json_to_cbor(&value);
+        let decoded: ciborium::Value = ciborium::from_reader(encoded.as_slice()).unwrap();
+        assert_eq!(
+            decoded,
+            ciborium::Value::Tag(
+                TAG_UNSIGNED_BIGNUM,
+                Box::new(ciborium::Value::Bytes(vec![0xff; 16]))
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn cbor_over_http() {
+        use http::header::{ACCEPT, CONTENT_TYPE};
+
+        use crate::context::RpcContext;
+        use crate::RpcServer;
+
+        let server = RpcServer::new(
+            "127.0.0.1:0".parse().unwrap(),
+            RpcContext::for_tests(),
+            RpcVersion::V07,
+        );
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let url = format!("http://{}/rpc/v0_7", addresses[0]);
+
+        let request = ciborium::Value::Map(vec![
+            (
+                ciborium::Value::Text("jsonrpc".to_owned()),
+                ciborium::Value::Text("2.0".to_owned()),
+            ),
+            (
+                ciborium::Value::Text("id".to_owned()),
+                ciborium::Value::Integer(1.into()),
+            ),
+            (
+                ciborium::Value::Text("method".to_owned()),
+                ciborium::Value::Text("starknet_getBlockWithTxHashes".to_owned()),
+            ),
+            (
+                ciborium::Value::Text("params".to_owned()),
+                ciborium::Value::Array(vec![ciborium::Value::Map(vec![(
+                    ciborium::Value::Text("block_number".to_owned()),
+                    ciborium::Value::Integer(0.into()),
+                )])]),
+            ),
+        ]);
+        let mut body = Vec::new();
+        ciborium::into_writer(&request, &mut body).unwrap();
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header(CONTENT_TYPE, APPLICATION_CBOR)
+            .header(ACCEPT, APPLICATION_CBOR)
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], APPLICATION_CBOR);
+
+        let response = response.bytes().await.unwrap();
+        let response: ciborium::Value = ciborium::from_reader(response.as_ref()).unwrap();
+        let result = response
+            .as_map()
+            .unwrap()
+            .iter()
+            .find(|(key, _)| key.as_text() == Some("result"))
+            .map(|(_, value)| value)
+            .unwrap();
+        let block_hash = result
+            .as_map()
+            .unwrap()
+            .iter()
+            .find(|(key, _)| key.as_text() == Some("block_hash"))
+            .map(|(_, value)| value)
+            .unwrap();
+        assert_eq!(block_hash.as_bytes().map(Vec::len), Some(32));
+    }
+
+    #[test]
+    fn accept_header() {
+        let headers = |accept: &'static str| {
+            let mut headers = http::HeaderMap::new();
+            headers.insert(http::header::ACCEPT, HeaderValue::from_static(accept));
+            headers
+        };
+
+        assert_eq!(
+            Encoding::accepted(&headers("application/cbor")),
+            Encoding::Cbor
+        );
+        assert_eq!(
+            Encoding::accepted(&headers("application/json;q=0.5, application/cbor;q=0.9")),
+            Encoding::Cbor
+        );
+        assert_eq!(
+            Encoding::accepted(&headers("application/json, application/cbor;q=0.9")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::accepted(&headers("application/cbor;q=0")),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::accepted(&headers("application/cbor;q=2")),
+            Encoding::Json
+        );
+        assert_eq!(Encoding::accepted(&headers("*/*")), Encoding::Json);
+        assert_eq!(Encoding::accepted(&http::HeaderMap::new()), Encoding::Json);
+    }
+}
@@ -16,6 +16,20 @@ pub enum RpcError {
         subscription_id: u32,
         reason: String,
     },
+    /// An error returned by the archive fallback node, passed through as-is.
+    Upstream {
+        code: i32,
+        message: String,
+        data: Option<Value>,
+    },
+    /// The client's request quota is exhausted.
+    LimitExceeded {
+        retry_after: std::time::Duration,
+    },
+    /// The method did not complete within its configured timeout.
+    Timeout {
+        timeout: std::time::Duration,
+    },
 }
 
 impl PartialEq for RpcError {
@@ -38,6 +52,9 @@ impl RpcError {
             RpcError::InternalError(_) => -32603,
             RpcError::ApplicationError(err) => err.code(version),
             RpcError::WebsocketSubscriptionClosed { .. } => -32099,
+            RpcError::Upstream { code, .. } => *code,
+            RpcError::LimitExceeded { .. } => -32005,
+            RpcError::Timeout { .. } => -32098,
         }
     }
 
@@ -50,6 +67,9 @@ impl RpcError {
             RpcError::InternalError(_) => "Internal error".into(),
             RpcError::ApplicationError(e) => e.message(version).into(),
             RpcError::WebsocketSubscriptionClosed { .. } => "Websocket subscription closed".into(),
+            RpcError::Upstream { message, .. } => message.as_str().into(),
+            RpcError::LimitExceeded { .. } => "Limit exceeded".into(),
+            RpcError::Timeout { .. } => "Request timed out".into(),
         }
     }
 
@@ -63,6 +83,13 @@ impl RpcError {
                 "reason": reason,
             })),
             RpcError::ApplicationError(e) => e.data(version),
+            RpcError::Upstream { data, .. } => data.clone(),
+            RpcError::LimitExceeded { retry_after } => Some(json!({
+                "retry_after_ms": retry_after.as_millis() as u64,
+            })),
+            RpcError::Timeout { timeout } => Some(json!({
+                "timeout_ms": timeout.as_millis() as u64,
+            })),
             RpcError::InternalError(_) => None,
             RpcError::MethodNotFound => None,
             RpcError::ParseError(e) | RpcError::InvalidRequest(e) | RpcError::InvalidParams(e) => {
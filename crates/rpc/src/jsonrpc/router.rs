@@ -4,17 +4,23 @@ use std::num::NonZeroUsize;
 use axum::extract::{State, WebSocketUpgrade};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use axum::Extension;
 use futures::{Future, FutureExt, StreamExt};
-use http::HeaderValue;
 use method::RpcMethodEndpoint;
+pub(crate) use subscription::missed_reorgs;
 pub use subscription::{handle_json_rpc_socket, CatchUp, RpcSubscriptionFlow, SubscriptionMessage};
 use subscription::{split_ws, RpcSubscriptionEndpoint};
 
 use crate::context::RpcContext;
+use crate::dto::Serializer;
+use crate::error::ApplicationError;
+use crate::jsonrpc::encoding::{self, Encoding};
 use crate::jsonrpc::error::RpcError;
 use crate::jsonrpc::request::RpcRequest;
-use crate::jsonrpc::response::RpcResponse;
-use crate::RpcVersion;
+use crate::jsonrpc::response::{RpcResponse, RpcResult};
+use crate::middleware::rate_limit::Client;
+use crate::response_cache::Lookup;
+use crate::{MethodFilter, RpcVersion};
 
 mod method;
 mod subscription;
@@ -27,12 +33,21 @@ pub struct RpcRouter {
     method_endpoints: &'static HashMap<&'static str, Box<dyn RpcMethodEndpoint>>,
     subscription_endpoints: &'static HashMap<&'static str, Box<dyn RpcSubscriptionEndpoint>>,
     pub version: RpcVersion,
+    /// The client making the requests, if rate limiting is enabled.
+    client: Option<Client>,
+    method_filter: &'static MethodFilter,
+    /// The encoding of the responses.
+    encoding: Encoding,
+    /// When the HTTP request being served times out. Method calls are cut
+    /// short at this point even if their own timeout is longer.
+    deadline: Option<tokio::time::Instant>,
 }
 
 pub struct RpcRouterBuilder {
     method_endpoints: HashMap<&'static str, Box<dyn RpcMethodEndpoint>>,
     subscription_endpoints: HashMap<&'static str, Box<dyn RpcSubscriptionEndpoint>>,
     version: RpcVersion,
+    method_filter: MethodFilter,
 }
 
 impl RpcRouterBuilder {
@@ -69,6 +84,26 @@ impl RpcRouterBuilder {
         self
     }
 
+    /// Whether a method called `method_name` is registered. Subscriptions are
+    /// not methods.
+    pub(crate) fn has_method(&self, method_name: &str) -> bool {
+        self.method_endpoints.contains_key(method_name)
+    }
+
+    /// Removes the methods and subscriptions which are not allowed by
+    /// `filter`.
+    ///
+    /// The filter is also kept for methods which are not registered with the
+    /// router, see [RpcRouter::is_allowed].
+    pub fn with_method_filter(mut self, filter: &MethodFilter) -> Self {
+        self.method_endpoints
+            .retain(|method_name, _| filter.is_allowed(method_name));
+        self.subscription_endpoints
+            .retain(|method_name, _| filter.is_allowed(method_name));
+        self.method_filter = filter.clone();
+        self
+    }
+
     pub fn build(self, context: RpcContext) -> RpcRouter {
         // Intentionally leak the hashmaps to give them a static lifetime.
         // Since the router is expected to be long lived, this shouldn't be an issue.
@@ -76,11 +111,16 @@ impl RpcRouterBuilder {
         let methods = Box::leak(methods);
         let subscriptions = Box::new(self.subscription_endpoints);
         let subscriptions = Box::leak(subscriptions);
+        let method_filter = Box::leak(Box::new(self.method_filter));
         RpcRouter {
             context,
             method_endpoints: methods,
             subscription_endpoints: subscriptions,
             version: self.version,
+            client: None,
+            method_filter,
+            encoding: Encoding::Json,
+            deadline: None,
         }
     }
 
@@ -89,6 +129,7 @@ impl RpcRouterBuilder {
             method_endpoints: Default::default(),
             subscription_endpoints: Default::default(),
             version,
+            method_filter: MethodFilter::All,
         }
     }
 }
@@ -98,11 +139,44 @@ impl RpcRouter {
         RpcRouterBuilder::new(version)
     }
 
+    /// Charges the requests run by this router to `client`.
+    pub(crate) fn with_client(self, client: Option<Client>) -> Self {
+        Self { client, ..self }
+    }
+
+    /// Serializes the method outputs for responses in `encoding`.
+    pub(crate) fn with_encoding(self, encoding: Encoding) -> Self {
+        Self { encoding, ..self }
+    }
+
+    /// Caps the timeout of the method calls run by this router at `deadline`.
+    pub(crate) fn with_deadline(self, deadline: tokio::time::Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    fn serializer(&self) -> Serializer {
+        Serializer::new(self.version).with_binary_felts(self.encoding == Encoding::Cbor)
+    }
+
+    /// Whether the method filter of this router allows `method`. Only needed
+    /// for methods handled outside of the router, such as the legacy websocket
+    /// subscriptions, as filtered out endpoints are not registered at all.
+    pub(crate) fn is_allowed(&self, method: &str) -> bool {
+        self.method_filter.is_allowed(method)
+    }
+
     /// Parses and executes a request. Returns [None] if its a notification.
-    async fn run_request(&self, request: &str) -> Option<RpcResponse> {
-        tracing::trace!(%request, "Running request");
+    ///
+    /// Requests failing because they target pruned data are forwarded to the
+    /// archive fallback node, if one is configured. See
+    /// [is_forwardable](Self::is_forwardable).
+    async fn run_request(&self, raw_request: &str) -> Option<RpcResponse> {
+        tracing::trace!(request=%raw_request, "Running request");
 
-        let request = match serde_json::from_str::<RpcRequest<'_>>(request) {
+        let request = match serde_json::from_str::<RpcRequest<'_>>(raw_request) {
             Ok(request) => request,
             Err(e) => {
                 return Some(RpcResponse::invalid_request(e.to_string(), self.version));
@@ -124,12 +198,71 @@ impl RpcRouter {
 
         metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => self.version.to_str());
 
-        let method = method.invoke(self.context.clone(), request.params, self.version);
-        let result = std::panic::AssertUnwindSafe(method).catch_unwind().await;
+        if let Some(client) = &self.client {
+            if let Err(retry_after) = client.acquire(method_name) {
+                return Some(RpcResponse {
+                    output: Err(RpcError::LimitExceeded { retry_after }),
+                    id: request.id,
+                    version: self.version,
+                });
+            }
+        }
+
+        let cacheable = match &self.context.response_cache {
+            Some(cache) => match cache.get(
+                self.version,
+                self.encoding,
+                method_name,
+                &request.params,
+                *self.context.sync_status.l1_accepted.read().await,
+            ) {
+                Lookup::Hit(response) => {
+                    metrics::increment_counter!("rpc_response_cache_hits_total", "method" => method_name, "version" => self.version.to_str());
+                    return Some(RpcResponse {
+                        output: Ok(response),
+                        id: request.id,
+                        version: self.version,
+                    });
+                }
+                Lookup::Miss(cacheable) => {
+                    metrics::increment_counter!("rpc_response_cache_misses_total", "method" => method_name, "version" => self.version.to_str());
+                    cacheable
+                        .if_l1_accepted(self.context.storage.clone())
+                        .await
+                        .map(|cacheable| (cache, cacheable))
+                }
+                Lookup::Uncacheable => None,
+            },
+            None => None,
+        };
+
+        // Blocking executor work spawned by the method stops once this guard is
+        // dropped, i.e. when the method times out or the request is abandoned.
+        let cancellation = util::task::CancellationToken::new();
+        let _cancel_on_drop = cancellation.clone().drop_guard();
+
+        // The timeout covers the whole call, including forwarding it to the
+        // archive fallback node, and ends with the request at the latest.
+        let now = tokio::time::Instant::now();
+        let deadline = now + self.context.config.method_timeout(method_name);
+        let deadline = self.deadline.map_or(deadline, |request| request.min(deadline));
+        let timeout = deadline.saturating_duration_since(now);
+        let timed_out = || -> RpcResult {
+            metrics::increment_counter!("rpc_method_calls_timed_out_total", "method" => method_name, "version" => self.version.to_str());
+            tracing::debug!(method=%request.method, ?timeout, "RPC method timed out");
+            Err(RpcError::Timeout { timeout })
+        };
+
+        let method = method.invoke(self.context.clone(), request.params, self.serializer());
+        let method = crate::executor::REQUEST_CANCELLATION.scope(cancellation, method);
+        let result =
+            tokio::time::timeout_at(deadline, std::panic::AssertUnwindSafe(method).catch_unwind())
+                .await;
 
         let output = match result {
-            Ok(output) => output,
-            Err(e) => {
+            Ok(Ok(output)) => output,
+            Err(_elapsed) => timed_out(),
+            Ok(Err(e)) => {
                 tracing::warn!(method=%request.method, backtrace=?e, "RPC method panic'd");
                 Err(RpcError::InternalError(anyhow::anyhow!(
                     "RPC method panic'd"
@@ -137,6 +270,28 @@ impl RpcRouter {
             }
         };
 
+        if let (Some((cache, cacheable)), Ok(response)) = (cacheable, &output) {
+            cache.insert(cacheable, response);
+        }
+
+        let output = match &self.context.archive_fallback {
+            Some(fallback) if self.is_forwardable(&output) => {
+                metrics::increment_counter!("rpc_archive_fallback_calls_total", "method" => method_name, "version" => self.version.to_str());
+
+                let forward = fallback.forward(raw_request, self.version, self.encoding);
+                match tokio::time::timeout_at(deadline, forward).await {
+                    Ok(Ok(output)) => output,
+                    Ok(Err(e)) => {
+                        metrics::increment_counter!("rpc_archive_fallback_calls_failed_total", "method" => method_name, "version" => self.version.to_str());
+                        tracing::warn!(method=%request.method, error=?e, "Archive fallback request failed");
+                        output
+                    }
+                    Err(_elapsed) => timed_out(),
+                }
+            }
+            _ => output,
+        };
+
         if output.is_err() {
             metrics::increment_counter!("rpc_method_calls_failed_total", "method" => method_name, "version" => self.version.to_str());
         }
@@ -147,10 +302,22 @@ impl RpcRouter {
             version: self.version,
         })
     }
+
+    /// Whether `output` should be retried on the archive fallback node.
+    ///
+    /// Only requests the method found to target blocks older than the oldest
+    /// available block are forwarded. Not-found errors are not, as they are
+    /// also returned for future blocks and unknown hashes.
+    fn is_forwardable(&self, output: &RpcResult) -> bool {
+        matches!(
+            output,
+            Err(RpcError::ApplicationError(ApplicationError::BlockPruned(_)))
+        )
+    }
 }
 
 // A slight variation on the axum json extractor.
-fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
+fn is_utf8_encoded_json(headers: &http::HeaderMap) -> bool {
     let Some(content_type) = headers.get(http::header::CONTENT_TYPE) else {
         return false;
     };
@@ -181,11 +348,14 @@ fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
 #[axum::debug_handler]
 pub async fn rpc_handler(
     State(state): State<RpcRouter>,
+    client: Option<Extension<Client>>,
     headers: http::HeaderMap,
     method: http::Method,
     ws: Option<WebSocketUpgrade>,
     body: axum::body::Bytes,
 ) -> impl axum::response::IntoResponse {
+    let state = state.with_client(client.map(|Extension(client)| client));
+
     match ws {
         Some(ws) => ws.on_upgrade(|ws| async move {
             let (ws_tx, ws_rx) = split_ws(ws, state.version);
@@ -196,41 +366,59 @@ pub async fn rpc_handler(
                 return StatusCode::METHOD_NOT_ALLOWED.into_response();
             }
 
-            // Only utf8 json content allowed.
-            if !is_utf8_encoded_json(headers) {
+            // Only utf8 json and cbor content allowed.
+            let body = if is_utf8_encoded_json(&headers) {
+                Ok(std::borrow::Cow::Borrowed(body.as_ref()))
+            } else if encoding::is_cbor(&headers) {
+                encoding::cbor_to_json(&body)
+                    .map(|json| std::borrow::Cow::Owned(json.into_bytes()))
+                    .map_err(|e| RpcRequestError::ParseError(format!("{e:#}")))
+            } else {
                 return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
-            }
+            };
 
-            let mut response = match handle_json_rpc_body(&state, body.as_ref()).await {
-                Ok(responses) => match responses {
-                    RpcResponses::Empty => ().into_response(),
-                    RpcResponses::Single(response) => response.into_response(),
-                    RpcResponses::Multiple(responses) => {
-                        use crate::dto::SerializeForVersion;
-                        let values = responses
-                            .into_iter()
-                            .map(|response| {
-                                response
-                                    .serialize(crate::dto::Serializer::new(state.version))
-                                    .unwrap()
-                            })
-                            .collect::<Vec<_>>();
-                        serde_json::to_string(&values).unwrap().into_response()
-                    }
-                },
+            let response_encoding = Encoding::accepted(&headers);
+            let deadline = tokio::time::Instant::now() + state.context.config.request_timeout;
+            let state = state
+                .with_encoding(response_encoding)
+                .with_deadline(deadline);
+
+            let responses = match body {
+                Ok(body) => handle_json_rpc_body(&state, &body).await,
+                Err(e) => Err(e),
+            };
+            let responses = match responses {
+                Ok(responses) => responses,
                 Err(RpcRequestError::ParseError(e)) => {
-                    RpcResponse::parse_error(e, state.version).into_response()
+                    RpcResponses::Single(RpcResponse::parse_error(e, state.version))
                 }
                 Err(RpcRequestError::InvalidRequest(e)) => {
-                    RpcResponse::invalid_request(e, state.version).into_response()
+                    RpcResponses::Single(RpcResponse::invalid_request(e, state.version))
+                }
+            };
+
+            let mut response = match (responses, response_encoding) {
+                (RpcResponses::Empty, _) => return ().into_response(),
+                (RpcResponses::Single(response), Encoding::Json) => response.into_response(),
+                (RpcResponses::Multiple(responses), Encoding::Json) => {
+                    use crate::dto::SerializeForVersion;
+                    let values = responses
+                        .into_iter()
+                        .map(|response| response.serialize(Serializer::new(state.version)).unwrap())
+                        .collect::<Vec<_>>();
+                    serde_json::to_string(&values).unwrap().into_response()
+                }
+                (responses, Encoding::Cbor) => {
+                    use crate::dto::SerializeForVersion;
+                    let value = responses.serialize(state.serializer()).unwrap();
+                    encoding::json_to_cbor(&value).into_response()
                 }
             };
 
             use http::header::CONTENT_TYPE;
-            static APPLICATION_JSON: HeaderValue = HeaderValue::from_static("application/json");
             response
                 .headers_mut()
-                .insert(CONTENT_TYPE, APPLICATION_JSON.clone());
+                .insert(CONTENT_TYPE, response_encoding.content_type());
             response
         }
     }
@@ -737,6 +925,396 @@ mod tests {
         }
     }
 
+    mod archive_fallback {
+        use pathfinder_common::BlockNumber;
+
+        use super::*;
+        use crate::ArchiveFallback;
+
+        async fn pruned(_ctx: RpcContext) -> RpcResult {
+            Err(ApplicationError::BlockPruned(BlockNumber::new_or_panic(5)).into())
+        }
+
+        async fn spawn_upstream() -> reqwest::Url {
+            async fn pruned(_ctx: RpcContext) -> RpcResult {
+                Ok(json!("Upstream"))
+            }
+
+            async fn not_found(_ctx: RpcContext) -> RpcResult {
+                Err(ApplicationError::BlockNotFound.into())
+            }
+
+            async fn by_hash(_ctx: RpcContext) -> RpcResult {
+                Ok(json!({"transaction_hash": "0x1234", "type": "INVOKE"}))
+            }
+
+            async fn felt(
+                _ctx: RpcContext,
+            ) -> Result<pathfinder_common::BlockHash, ApplicationError> {
+                Ok(pathfinder_common::macro_prelude::block_hash!("0x1234"))
+            }
+
+            async fn slow(_ctx: RpcContext) -> RpcResult {
+                std::future::pending().await
+            }
+
+            let router = RpcRouter::builder(RpcVersion::V07)
+                .register("pruned", pruned)
+                .register("felt", felt)
+                .register("slow", slow)
+                .register("not_found", not_found)
+                .register("by_hash", by_hash)
+                .build(RpcContext::for_tests());
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+
+            tokio::spawn(async {
+                let router = axum::Router::new()
+                    .route("/rpc/v0_7", axum::routing::post(rpc_handler))
+                    .with_state(router);
+                axum::serve(listener, router.into_make_service()).await
+            });
+
+            format!("http://{addr}").parse().unwrap()
+        }
+
+        async fn txn_not_found(_ctx: RpcContext) -> RpcResult {
+            Err(ApplicationError::TxnHashNotFound.into())
+        }
+
+        fn local_router(upstream: reqwest::Url) -> RpcRouter {
+            local_router_with_context(upstream, RpcContext::for_tests())
+        }
+
+        fn local_router_with_context(upstream: reqwest::Url, context: RpcContext) -> RpcRouter {
+            let fallback = ArchiveFallback::new(upstream).unwrap();
+
+            RpcRouter::builder(RpcVersion::V07)
+                .register("pruned", pruned)
+                .register("felt", pruned)
+                .register("slow", pruned)
+                .register("not_found", pruned)
+                .register("by_hash", txn_not_found)
+                .build(context.with_archive_fallback(fallback))
+        }
+
+        /// A context whose history window starts after genesis.
+        fn pruned_history_context() -> RpcContext {
+            let storage =
+                pathfinder_storage::StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
+                    pathfinder_storage::pruning::BlockchainHistoryMode::Prune {
+                        num_blocks_kept: 1,
+                    },
+                    std::num::NonZeroU32::new(1).unwrap(),
+                )
+                .unwrap();
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            for number in 0..4 {
+                let header = pathfinder_common::BlockHeader::builder()
+                    .number(BlockNumber::new_or_panic(number))
+                    .finalize_with_hash(pathfinder_common::BlockHash(
+                        pathfinder_crypto::Felt::from_u64(number),
+                    ));
+                tx.insert_block_header(&header).unwrap();
+            }
+            tx.commit().unwrap();
+
+            RpcContext::for_tests().with_storage(storage)
+        }
+
+        #[tokio::test]
+        async fn not_found_is_not_forwarded_if_history_is_pruned() {
+            let upstream = spawn_upstream().await;
+
+            let response = serve_and_query(
+                local_router_with_context(upstream, pruned_history_context()),
+                json!({"jsonrpc": "2.0", "method": "by_hash", "id": 1}),
+            )
+            .await;
+            let expected = json!({
+                "jsonrpc": "2.0",
+                "error": {"code": 29, "message": "Transaction hash not found"},
+                "id": 1
+            });
+            assert_eq!(response, expected);
+        }
+
+        #[tokio::test]
+        async fn pruned_block_is_forwarded() {
+            let upstream = spawn_upstream().await;
+
+            let response = serve_and_query(
+                local_router(upstream.clone()),
+                json!({"jsonrpc": "2.0", "method": "pruned", "id": 1}),
+            )
+            .await;
+            let expected = json!({"jsonrpc": "2.0", "result": "Upstream", "id": 1});
+            assert_eq!(response, expected);
+
+            let response = serve_and_query_ws(
+                local_router(upstream),
+                json!({"jsonrpc": "2.0", "method": "pruned", "id": 1}),
+            )
+            .await;
+            assert_eq!(response, expected);
+        }
+
+        #[tokio::test]
+        async fn forwarded_response_is_encoded_as_cbor() {
+            use http::header::{ACCEPT, CONTENT_TYPE};
+
+            let upstream = spawn_upstream().await;
+            let url = spawn_server(local_router(upstream)).await;
+
+            let response = reqwest::Client::new()
+                .post(url)
+                .header(ACCEPT, "application/cbor")
+                .json(&json!({"jsonrpc": "2.0", "method": "pruned", "id": 1}))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.headers()[CONTENT_TYPE], "application/cbor");
+
+            let response = response.bytes().await.unwrap();
+            let response: ciborium::Value = ciborium::from_reader(response.as_ref()).unwrap();
+            let result = response
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(key, _)| key.as_text() == Some("result"))
+                .map(|(_, value)| value)
+                .unwrap();
+            assert_eq!(result, &ciborium::Value::Text("Upstream".to_owned()));
+        }
+
+        #[tokio::test]
+        async fn forwarded_felts_are_encoded_like_local_ones() {
+            use http::header::ACCEPT;
+
+            let upstream = spawn_upstream().await;
+
+            let response = serve_and_query(
+                local_router(upstream.clone()),
+                json!({"jsonrpc": "2.0", "method": "felt", "id": 1}),
+            )
+            .await;
+            let expected = json!({"jsonrpc": "2.0", "result": "0x1234", "id": 1});
+            assert_eq!(response, expected);
+
+            let url = spawn_server(local_router(upstream)).await;
+            let response = reqwest::Client::new()
+                .post(url)
+                .header(ACCEPT, "application/cbor")
+                .json(&json!({"jsonrpc": "2.0", "method": "felt", "id": 1}))
+                .send()
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap();
+            let response: ciborium::Value = ciborium::from_reader(response.as_ref()).unwrap();
+            let result = response
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(key, _)| key.as_text() == Some("result"))
+                .map(|(_, value)| value)
+                .unwrap();
+            let mut expected = [0u8; 32];
+            expected[30..].copy_from_slice(&[0x12, 0x34]);
+            assert_eq!(result, &ciborium::Value::Bytes(expected.to_vec()));
+        }
+
+        #[tokio::test]
+        async fn upstream_error_is_passed_through() {
+            let upstream = spawn_upstream().await;
+
+            let response = serve_and_query(
+                local_router(upstream),
+                json!({"jsonrpc": "2.0", "method": "not_found", "id": 1}),
+            )
+            .await;
+            let expected = json!({"jsonrpc": "2.0", "error": {"code": 24, "message": "Block not found"}, "id": 1});
+            assert_eq!(response, expected);
+        }
+
+        #[tokio::test]
+        async fn forwarded_call_times_out() {
+            let upstream = spawn_upstream().await;
+            let mut context = RpcContext::for_tests();
+            context
+                .config
+                .method_timeouts
+                .insert("slow".to_owned(), std::time::Duration::from_millis(50));
+
+            let response = serve_and_query(
+                local_router_with_context(upstream, context),
+                json!({"jsonrpc": "2.0", "method": "slow", "id": 1}),
+            )
+            .await;
+            let expected = json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32098, "message": "Request timed out", "data": {"timeout_ms": 50}},
+                "id": 1
+            });
+            assert_eq!(response, expected);
+        }
+
+        #[tokio::test]
+        async fn unreachable_upstream_returns_pruned_error() {
+            // Nothing is listening on the discard port.
+            let upstream = "http://127.0.0.1:9".parse().unwrap();
+
+            let response = serve_and_query(
+                local_router(upstream),
+                json!({"jsonrpc": "2.0", "method": "pruned", "id": 1}),
+            )
+            .await;
+            let expected = json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": 24,
+                    "message": "Block has been pruned",
+                    "data": {"oldest_available_block": 5}
+                },
+                "id": 1
+            });
+            assert_eq!(response, expected);
+        }
+    }
+
+    mod timeouts {
+        use std::sync::OnceLock;
+        use std::time::Duration;
+
+        use util::task::CancellationToken;
+
+        use super::*;
+
+        static SLOW_CANCELLATION: OnceLock<CancellationToken> = OnceLock::new();
+
+        async fn slow(_ctx: RpcContext) -> RpcResult {
+            SLOW_CANCELLATION
+                .set(crate::executor::request_cancellation())
+                .unwrap();
+            std::future::pending().await
+        }
+
+        async fn fast(_ctx: RpcContext) -> RpcResult {
+            Ok(json!("Success"))
+        }
+
+        #[tokio::test]
+        async fn method_timeout_cancels_execution() {
+            let mut context = RpcContext::for_tests();
+            context
+                .config
+                .method_timeouts
+                .insert("slow".to_owned(), Duration::from_millis(50));
+            let router = RpcRouter::builder(RpcVersion::V07)
+                .register("slow", slow)
+                .register("fast", fast)
+                .build(context);
+
+            let response = serve_and_query(
+                router,
+                json!([
+                    {"jsonrpc": "2.0", "method": "slow", "id": 1},
+                    {"jsonrpc": "2.0", "method": "fast", "id": 2},
+                ]),
+            )
+            .await;
+            let expected = json!([
+                {"jsonrpc": "2.0", "error": {"code": -32098, "message": "Request timed out", "data": {"timeout_ms": 50}}, "id": 1},
+                {"jsonrpc": "2.0", "result": "Success", "id": 2},
+            ]);
+            assert_eq!(response, expected);
+
+            assert!(SLOW_CANCELLATION.get().unwrap().is_cancelled());
+        }
+
+        #[tokio::test]
+        async fn calls_end_with_the_request() {
+            async fn pending(_ctx: RpcContext) -> RpcResult {
+                std::future::pending().await
+            }
+
+            let mut context = RpcContext::for_tests();
+            context.config.request_timeout = Duration::from_millis(50);
+            context
+                .config
+                .method_timeouts
+                .insert("pending".to_owned(), Duration::from_secs(60));
+            let router = RpcRouter::builder(RpcVersion::V07)
+                .register("pending", pending)
+                .build(context);
+
+            let response = tokio::time::timeout(
+                Duration::from_secs(10),
+                serve_and_query(
+                    router,
+                    json!({"jsonrpc": "2.0", "method": "pending", "id": 1}),
+                ),
+            )
+            .await
+            .expect("Call should end with the request");
+            assert_eq!(response["error"]["code"], json!(-32098), "{response}");
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_for_l1_accepted_blocks_are_cached() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use pathfinder_common::macro_prelude::block_hash_bytes;
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        async fn state_update(
+            _ctx: RpcContext,
+            _input: crate::method::get_state_update::Input,
+        ) -> RpcResult {
+            Ok(json!(CALLS.fetch_add(1, Ordering::Relaxed)))
+        }
+
+        let context = RpcContext::for_tests().with_response_cache(NonZeroUsize::new(1024).unwrap());
+        *context.sync_status.l1_accepted.write().await =
+            Some(pathfinder_common::BlockNumber::GENESIS);
+        let router = RpcRouter::builder(RpcVersion::V07)
+            .register("starknet_getStateUpdate", state_update)
+            .build(context);
+
+        let query = |block_id: Value| {
+            serve_and_query(
+                router.clone(),
+                json!({"jsonrpc": "2.0", "method": "starknet_getStateUpdate", "params": [block_id], "id": 1}),
+            )
+        };
+
+        // Block 0 is L1-accepted, block 1 is not.
+        assert_eq!(query(json!({"block_number": 0})).await["result"], json!(0));
+        assert_eq!(query(json!({"block_number": 0})).await["result"], json!(0));
+        // Named params share the entry of positional ones.
+        let named = serve_and_query(
+            router.clone(),
+            json!({"jsonrpc": "2.0", "method": "starknet_getStateUpdate", "params": {"block_id": {"block_number": 0}}, "id": 1}),
+        );
+        assert_eq!(named.await["result"], json!(0));
+        assert_eq!(query(json!({"block_number": 1})).await["result"], json!(1));
+        assert_eq!(query(json!({"block_number": 1})).await["result"], json!(2));
+        assert_eq!(query(json!("latest")).await["result"], json!(3));
+
+        // Blocks requested by hash are looked up in the database.
+        let genesis = json!({"block_hash": block_hash_bytes!(b"genesis")});
+        let block_1 = json!({"block_hash": block_hash_bytes!(b"block 1")});
+        assert_eq!(query(genesis.clone()).await["result"], json!(4));
+        assert_eq!(query(genesis).await["result"], json!(4));
+        assert_eq!(query(block_1.clone()).await["result"], json!(5));
+        assert_eq!(query(block_1).await["result"], json!(6));
+    }
+
     #[tokio::test]
     async fn rejects_non_json_content_header() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
@@ -29,7 +29,7 @@ pub(super) trait RpcMethodEndpoint: Send + Sync {
         &self,
         state: RpcContext,
         input: RawParams<'a>,
-        version: RpcVersion,
+        serializer: Serializer,
     ) -> RpcResult;
 }
 
@@ -127,13 +127,13 @@ where
                 &self,
                 state: RpcContext,
                 input: RawParams<'a>,
-                version: RpcVersion,
+                serializer: Serializer,
             ) -> RpcResult {
-                let input = input.deserialize_for_version(version)?;
-                (self.f)(state, input, version)
+                let input = input.deserialize_for_version(serializer.version)?;
+                (self.f)(state, input, serializer.version)
                     .await
                     .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
+                    .serialize(serializer)
                     .map_err(|e| RpcError::InternalError(e.into()))
             }
         }
@@ -176,13 +176,13 @@ where
                 &self,
                 state: RpcContext,
                 input: RawParams<'a>,
-                version: RpcVersion,
+                serializer: Serializer,
             ) -> RpcResult {
-                let input = input.deserialize_for_version(version)?;
+                let input = input.deserialize_for_version(serializer.version)?;
                 (self.f)(state, input)
                     .await
                     .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
+                    .serialize(serializer)
                     .map_err(|e| RpcError::InternalError(e.into()))
             }
         }
@@ -225,13 +225,13 @@ where
                 &self,
                 _state: RpcContext,
                 input: RawParams<'a>,
-                version: RpcVersion,
+                serializer: Serializer,
             ) -> RpcResult {
-                let input = input.deserialize_for_version(version)?;
+                let input = input.deserialize_for_version(serializer.version)?;
                 (self.f)(input)
                     .await
                     .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
+                    .serialize(serializer)
                     .map_err(|e| RpcError::InternalError(e.into()))
             }
         }
@@ -272,7 +272,7 @@ where
                 &self,
                 state: RpcContext,
                 input: RawParams<'a>,
-                version: RpcVersion,
+                serializer: Serializer,
             ) -> RpcResult {
                 if !input.is_empty() {
                     return Err(RpcError::InvalidParams(
@@ -282,7 +282,7 @@ where
                 (self.f)(state)
                     .await
                     .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
+                    .serialize(serializer)
                     .map_err(|e| RpcError::InternalError(e.into()))
             }
         }
@@ -323,7 +323,7 @@ where
                 &self,
                 _state: RpcContext,
                 input: RawParams<'a>,
-                version: RpcVersion,
+                serializer: Serializer,
             ) -> RpcResult {
                 if !input.is_empty() {
                     return Err(RpcError::InvalidParams(
@@ -333,7 +333,7 @@ where
                 (self.f)()
                     .await
                     .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
+                    .serialize(serializer)
                     .map_err(|e| RpcError::InternalError(e.into()))
             }
         }
@@ -367,7 +367,7 @@ where
                 &self,
                 _state: RpcContext,
                 input: RawParams<'a>,
-                version: RpcVersion,
+                serializer: Serializer,
             ) -> RpcResult {
                 if !input.is_empty() {
                     return Err(RpcError::InvalidParams(
@@ -375,7 +375,7 @@ where
                     ));
                 }
                 (self.f)()
-                    .serialize(Serializer::new(version))
+                    .serialize(serializer)
                     .map_err(|e| RpcError::InternalError(e.into()))
             }
         }
@@ -3,9 +3,9 @@ use std::sync::Arc;
 use axum::extract::ws::{Message, WebSocket};
 use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
-use pathfinder_common::BlockNumber;
+use pathfinder_common::{BlockHash, BlockNumber};
 use serde_json::value::RawValue;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::Instrument;
 
 use super::{run_concurrently, RpcRouter};
@@ -14,7 +14,7 @@ use crate::dto::{DeserializeForVersion, SerializeForVersion};
 use crate::error::ApplicationError;
 use crate::jsonrpc::{RpcError, RpcRequest, RpcResponse};
 use crate::types::request::SubscriptionBlockId;
-use crate::{RpcVersion, SubscriptionId};
+use crate::{Reorg, RpcVersion, SubscriptionId};
 
 /// See [`RpcSubscriptionFlow`].
 #[axum::async_trait]
@@ -58,6 +58,15 @@ pub(super) struct InvokeParams {
 ///   This is done to ensure that no blocks are missed between the previous
 ///   catch-up and the subscription.
 /// - Stream the first active update, and then keep streaming the rest.
+///
+/// Reorgs which happen before the first active update are reported with
+/// [`RpcSubscriptionFlow::reorg_notification`], and catching up resumes from
+/// the first reorged block. From then on `subscribe` reports reorgs itself.
+///
+/// Subscriptions resuming from a block the client has already received, see
+/// [`RpcSubscriptionFlow::resume_point`], which has since been reorged away
+/// are first sent the reorgs the client missed, and then catch up from the
+/// block where the client's chain forked off instead of the starting block.
 #[axum::async_trait]
 pub trait RpcSubscriptionFlow: Send + Sync {
     /// `params` field of the subscription request.
@@ -79,6 +88,13 @@ pub trait RpcSubscriptionFlow: Send + Sync {
         SubscriptionBlockId::Latest
     }
 
+    /// The number and hash of the last block the client has received, if it
+    /// is resuming a subscription. If the subscription endpoint does not
+    /// support resuming, leave this method unimplemented.
+    fn resume_point(_params: &Self::Params) -> Option<(BlockNumber, BlockHash)> {
+        None
+    }
+
     /// Fetch historical data from the `from` block to the `to` block. The
     /// range is inclusive on both ends. If there is no historical data in the
     /// range, return an empty vec. If the subscription endpoint does not
@@ -92,7 +108,18 @@ pub trait RpcSubscriptionFlow: Send + Sync {
         Ok(Default::default())
     }
 
+    /// The notification for a reorg which happens while catching up. If the
+    /// subscription endpoint does not report reorgs, leave this method
+    /// unimplemented.
+    fn reorg_notification(_reorg: Arc<Reorg>) -> Option<SubscriptionMessage<Self::Notification>> {
+        None
+    }
+
     /// Subscribe to active updates.
+    ///
+    /// Reorgs must be reported before any update from the blocks replacing the
+    /// reorged ones. If reorg notifications are missed, the subscription must
+    /// fail with [`missed_reorgs`] rather than carry on.
     async fn subscribe(
         state: RpcContext,
         params: Self::Params,
@@ -161,26 +188,52 @@ where
             _phantom: Default::default(),
         };
 
+        // Subscribe before catching up, so that no reorg can go unnoticed.
+        let mut reorgs = router.context.notifications.reorgs.subscribe();
+
         let first_block = T::starting_block(&params);
         let first_block = pathfinder_storage::BlockId::from(first_block);
+        let resume_point = T::resume_point(&params);
+        let reorg_log = router.context.notifications.reorg_log.clone();
         let storage = router.context.storage.clone();
-        let mut current_block = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
-            let mut conn = storage.connection().map_err(RpcError::InternalError)?;
-            let db = conn.transaction().map_err(RpcError::InternalError)?;
-
-            let pruned = db
-                .block_pruned(first_block)
-                .map_err(RpcError::InternalError)?;
-            if pruned {
-                return Err(ApplicationError::BlockNotFound.into());
-            }
+        let (mut current_block, missed) =
+            util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
+                let mut conn = storage.connection().map_err(RpcError::InternalError)?;
+                let db = conn.transaction().map_err(RpcError::InternalError)?;
+
+                if let Some((number, hash)) = resume_point {
+                    let current_hash = db
+                        .block_hash(number.into())
+                        .map_err(RpcError::InternalError)?;
+                    if current_hash != Some(hash) {
+                        // Reorged away while the client was gone, so catching up starts
+                        // where its chain forked off, which may not have been replaced yet.
+                        let (reorgs, fork) = reorg_log.since(hash).ok_or_else(missed_reorgs)?;
+                        if let Some(oldest_available) = db
+                            .block_pruned(fork.into())
+                            .map_err(RpcError::InternalError)?
+                        {
+                            return Err(ApplicationError::BlockPruned(oldest_available).into());
+                        }
+                        return Ok((fork, reorgs));
+                    }
+                }
 
-            db.block_number(first_block)
-                .map_err(RpcError::InternalError)?
-                .ok_or_else(|| ApplicationError::BlockNotFound.into())
-        })
-        .await
-        .map_err(|e| RpcError::InternalError(e.into()))??;
+                if let Some(oldest_available) = db
+                    .block_pruned(first_block)
+                    .map_err(RpcError::InternalError)?
+                {
+                    return Err(ApplicationError::BlockPruned(oldest_available).into());
+                }
+
+                let first_block = db
+                    .block_number(first_block)
+                    .map_err(RpcError::InternalError)?
+                    .ok_or(ApplicationError::BlockNotFound)?;
+                Ok((first_block, vec![]))
+            })
+            .await
+            .map_err(|e| RpcError::InternalError(e.into()))??;
 
         Ok(util::task::spawn(async move {
             let _subscription_guard = SubscriptionsGuard {
@@ -191,31 +244,8 @@ where
             // the caller sends the success response for the subscription request.
             let _lock_guard = lock.read().await;
 
-            // Catch up to the latest block in batches of BATCH_SIZE.
-            loop {
-                // -1 because the end is inclusive, otherwise we get batches of
-                // `CATCH_UP_BATCH_SIZE + 1` which probably doesn't really
-                // matter, but it's misleading.
-                let end = current_block + Self::CATCH_UP_BATCH_SIZE - 1;
-                let catch_up = match T::catch_up(&router.context, &params, current_block, end).await
-                {
-                    Ok(messages) => messages,
-                    Err(e) => {
-                        tx.send_err(e)
-                            .await
-                            // Could error if the subscription is closing.
-                            .ok();
-                        return;
-                    }
-                };
-                let last_block = match catch_up.last_block {
-                    Some(last_block) => last_block,
-                    None => {
-                        // `None` means that there were no messages for the given block range.
-                        break;
-                    }
-                };
-                for msg in catch_up.messages {
+            for reorg in missed {
+                if let Some(msg) = T::reorg_notification(reorg) {
                     if tx
                         .send(msg.notification, msg.subscription_name)
                         .await
@@ -225,33 +255,99 @@ where
                         return;
                     }
                 }
-                // Increment by 1 because the catch_up range is inclusive.
-                current_block = last_block + 1;
-                if last_block < end {
-                    // This was the last batch.
-                    break;
-                }
             }
 
-            // Subscribe to new blocks. Receive the first subscription message.
-            let (tx1, mut rx1) = mpsc::channel::<SubscriptionMessage<T::Notification>>(1024);
-            util::task::spawn({
-                let params = params.clone();
-                let context = router.context.clone();
-                let tx = tx.clone();
-                async move {
-                    if let Err(e) = T::subscribe(context, params, tx1).await {
-                        tx.send_err(e).await.ok();
+            let (first_msg, mut rx1) = 'catch_up: loop {
+                // Catch up to the latest block in batches of BATCH_SIZE.
+                loop {
+                    if report_reorgs::<T>(&mut reorgs, &tx, &mut current_block)
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+
+                    // -1 because the end is inclusive, otherwise we get batches of
+                    // `CATCH_UP_BATCH_SIZE + 1` which probably doesn't really
+                    // matter, but it's misleading.
+                    let end = current_block + Self::CATCH_UP_BATCH_SIZE - 1;
+                    let catch_up =
+                        match T::catch_up(&router.context, &params, current_block, end).await {
+                            Ok(messages) => messages,
+                            Err(e) => {
+                                tx.send_err(e)
+                                    .await
+                                    // Could error if the subscription is closing.
+                                    .ok();
+                                return;
+                            }
+                        };
+                    let last_block = match catch_up.last_block {
+                        Some(last_block) => last_block,
+                        None => {
+                            // `None` means that there were no messages for the given block
+                            // range.
+                            break;
+                        }
+                    };
+                    for msg in catch_up.messages {
+                        if tx
+                            .send(msg.notification, msg.subscription_name)
+                            .await
+                            .is_err()
+                        {
+                            // Subscription closing.
+                            return;
+                        }
+                    }
+                    // Increment by 1 because the catch_up range is inclusive.
+                    current_block = last_block + 1;
+                    if last_block < end {
+                        // This was the last batch.
+                        break;
                     }
                 }
-            });
-            let first_msg = match rx1.recv().await {
-                Some(msg) => msg,
-                None => {
-                    // Subscription closing.
-                    return;
+
+                // Subscribe to new blocks. Receive the first subscription message.
+                let (tx1, mut rx1) = mpsc::channel::<SubscriptionMessage<T::Notification>>(1024);
+                let subscription = util::task::spawn({
+                    let params = params.clone();
+                    let context = router.context.clone();
+                    let tx = tx.clone();
+                    async move {
+                        if let Err(e) = T::subscribe(context, params, tx1).await {
+                            tx.send_err(e).await.ok();
+                        }
+                    }
+                });
+                loop {
+                    tokio::select! {
+                        biased;
+                        reorg = reorgs.recv() => {
+                            // `subscribe` may or may not have seen this reorg, so it is
+                            // restarted after catching up again.
+                            match report_reorg::<T>(reorg, &tx, &mut current_block).await {
+                                Ok(true) => {
+                                    subscription.abort();
+                                    continue 'catch_up;
+                                }
+                                Ok(false) => {}
+                                Err(()) => {
+                                    subscription.abort();
+                                    return;
+                                }
+                            }
+                        }
+                        msg = rx1.recv() => match msg {
+                            Some(msg) => break 'catch_up (msg, rx1),
+                            // Subscription closing.
+                            None => return,
+                        }
+                    }
                 }
             };
+            // `subscribe` has subscribed to reorgs by the time it sends a message.
+            drop(reorgs);
 
             // Catch up from the latest block that we already caught up to, to the first
             // block that will be streamed from the subscription. This way we don't miss any
@@ -317,6 +413,65 @@ where
     }
 }
 
+/// The error a subscription fails with when it has missed reorg
+/// notifications, so that the client does not keep data from orphaned blocks.
+pub(crate) fn missed_reorgs() -> RpcError {
+    RpcError::ApplicationError(ApplicationError::Custom(anyhow::anyhow!(
+        "Missed reorg notifications, resubscribe and check for reorged blocks"
+    )))
+}
+
+/// Reports the reorgs received since the last call, see [`report_reorg`].
+async fn report_reorgs<T: RpcSubscriptionFlow>(
+    reorgs: &mut broadcast::Receiver<Arc<Reorg>>,
+    tx: &SubscriptionSender<T::Notification>,
+    current_block: &mut BlockNumber,
+) -> Result<(), ()> {
+    loop {
+        let reorg = match reorgs.try_recv() {
+            Ok(reorg) => Ok(reorg),
+            Err(broadcast::error::TryRecvError::Empty) => return Ok(()),
+            Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                Err(broadcast::error::RecvError::Lagged(n))
+            }
+            Err(broadcast::error::TryRecvError::Closed) => Err(broadcast::error::RecvError::Closed),
+        };
+        report_reorg::<T>(reorg, tx, current_block).await?;
+    }
+}
+
+/// Sends the notification for a reorg received while catching up, and moves
+/// `current_block` back to the first reorged block if needed.
+///
+/// Returns whether a notification was sent, or an error if the subscription
+/// must stop.
+async fn report_reorg<T: RpcSubscriptionFlow>(
+    reorg: Result<Arc<Reorg>, broadcast::error::RecvError>,
+    tx: &SubscriptionSender<T::Notification>,
+    current_block: &mut BlockNumber,
+) -> Result<bool, ()> {
+    let reorg = match reorg {
+        Ok(reorg) => reorg,
+        Err(broadcast::error::RecvError::Lagged(_)) => {
+            tx.send_err(missed_reorgs())
+                .await
+                // Could error if the subscription is closing.
+                .ok();
+            return Err(());
+        }
+        Err(broadcast::error::RecvError::Closed) => return Err(()),
+    };
+    let first_block = reorg.first_block_number;
+    let Some(msg) = T::reorg_notification(reorg) else {
+        return Ok(false);
+    };
+    tx.send(msg.notification, msg.subscription_name)
+        .await
+        .map_err(|_| ())?;
+    *current_block = std::cmp::min(*current_block, first_block);
+    Ok(true)
+}
+
 /// A guard to ensure that the subscription handle is removed when the
 /// subscription task corresponding to that handle returns.
 struct SubscriptionsGuard {
@@ -787,6 +942,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use axum::async_trait;
     use axum::extract::ws::Message;
     use pathfinder_common::{BlockHash, BlockHeader, BlockNumber};
@@ -805,7 +962,7 @@ mod tests {
         SubscriptionMessage,
     };
     use crate::types::request::SubscriptionBlockId;
-    use crate::Notifications;
+    use crate::{Notifications, Reorg};
 
     #[tokio::test]
     async fn test_error_returned_from_catch_up() {
@@ -961,6 +1118,129 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn test_reorg_before_first_update() {
+        struct ReorgBeforeFirstUpdate;
+
+        /// The latest block in storage, see [`setup`].
+        const LATEST: u64 = 4;
+
+        #[async_trait]
+        impl RpcSubscriptionFlow for ReorgBeforeFirstUpdate {
+            type Params = Params;
+            type Notification = serde_json::Value;
+
+            fn starting_block(_params: &Self::Params) -> SubscriptionBlockId {
+                SubscriptionBlockId::Number(BlockNumber::GENESIS)
+            }
+
+            async fn catch_up(
+                _state: &RpcContext,
+                _params: &Self::Params,
+                from: BlockNumber,
+                to: BlockNumber,
+            ) -> Result<CatchUp<Self::Notification>, crate::jsonrpc::RpcError> {
+                let messages: Vec<_> = (from.get()..=to.get().min(LATEST))
+                    .map(|block| SubscriptionMessage {
+                        notification: serde_json::json!(block),
+                        block_number: BlockNumber::new_or_panic(block),
+                        subscription_name: "test_block",
+                    })
+                    .collect();
+                let last_block = messages.last().map(|msg| msg.block_number);
+                Ok(CatchUp {
+                    messages,
+                    last_block,
+                })
+            }
+
+            fn reorg_notification(
+                reorg: Arc<Reorg>,
+            ) -> Option<SubscriptionMessage<Self::Notification>> {
+                Some(SubscriptionMessage {
+                    notification: serde_json::json!(reorg.first_block_number.get()),
+                    block_number: reorg.first_block_number,
+                    subscription_name: "test_reorg",
+                })
+            }
+
+            async fn subscribe(
+                _state: RpcContext,
+                _params: Self::Params,
+                tx: tokio::sync::mpsc::Sender<SubscriptionMessage<Self::Notification>>,
+            ) -> Result<(), crate::jsonrpc::RpcError> {
+                // No active updates.
+                tx.closed().await;
+                Ok(())
+            }
+        }
+
+        let router = setup(LATEST + 1, ReorgBeforeFirstUpdate).await;
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "test",
+                    "params": {}
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+
+        let mut received = Vec::new();
+        for _ in 0..=LATEST + 1 {
+            received.push(recv(&mut sender_rx).await);
+        }
+        assert_eq!(received[0]["id"], 1);
+        let blocks: Vec<_> = received[1..]
+            .iter()
+            .map(|msg| msg["params"]["result"].clone())
+            .collect();
+        assert_eq!(blocks, vec![0, 1, 2, 3, 4]);
+
+        router
+            .context
+            .notifications
+            .reorgs
+            .send(Arc::new(Reorg {
+                first_block_number: BlockNumber::new_or_panic(3),
+                first_block_hash: BlockHash(Felt::from_u64(3)),
+                last_block_number: BlockNumber::new_or_panic(LATEST),
+                last_block_hash: BlockHash(Felt::from_u64(LATEST)),
+                new_head: Some((BlockNumber::new_or_panic(2), BlockHash(Felt::from_u64(2)))),
+            }))
+            .unwrap();
+
+        // The reorg is reported, and catching up resumes from the first reorged block.
+        let reorg = recv(&mut sender_rx).await;
+        assert_eq!(reorg["method"], "test_reorg");
+        assert_eq!(reorg["params"]["result"], 3);
+        for block in 3..=LATEST {
+            let msg = recv(&mut sender_rx).await;
+            assert_eq!(msg["method"], "test_block");
+            assert_eq!(msg["params"]["result"], block);
+        }
+    }
+
+    async fn recv(
+        rx: &mut mpsc::Receiver<Result<Message, crate::jsonrpc::RpcResponse>>,
+    ) -> serde_json::Value {
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match msg {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        }
+    }
+
     #[derive(Debug, Clone)]
     struct Params;
 
@@ -10,6 +10,7 @@ use std::time::{Duration, Instant};
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{State, WebSocketUpgrade};
 use axum::response::IntoResponse;
+use axum::Extension;
 use futures::sink::Buffer;
 use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
@@ -35,6 +36,7 @@ use crate::jsonrpc::websocket::data::{
     SubscriptionItem,
 };
 use crate::jsonrpc::{RequestId, RpcError, RpcRequest, RpcRouter};
+use crate::middleware::rate_limit::Client;
 use crate::{BlockHeader, PendingData, RpcVersion};
 
 const SUBSCRIBE_METHOD: &str = "pathfinder_subscribe";
@@ -62,9 +64,11 @@ impl WebsocketContext {
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(router): State<RpcRouter>,
+    client: Option<Extension<Client>>,
 ) -> impl IntoResponse {
+    let router = router.with_client(client.map(|Extension(client)| client));
     let mut upgrade_response = ws
-        .max_message_size(crate::REQUEST_MAX_SIZE)
+        .max_message_size(router.context.config.request_max_size)
         .on_failed_upgrade(|error| tracing::debug!(%error, "Websocket upgrade failed"))
         .on_upgrade(|socket| handle_socket(socket, router));
 
@@ -185,22 +189,24 @@ async fn read(
             }
         };
 
-        // Handle request.
+        // Handle request. Legacy subscription methods which are filtered out are
+        // passed on to the router, which reports them as not found.
         let response = match parsed_request.method.as_ref() {
-            SUBSCRIBE_METHOD => match subscription_manager.subscribe(
-                parsed_request.id.clone(),
-                parsed_request.params,
-                response_sender.clone(),
-                source.clone(),
-                router.context.sequencer.clone(),
-            ) {
+            SUBSCRIBE_METHOD if router.is_allowed(SUBSCRIBE_METHOD) => match subscription_manager
+                .subscribe(
+                    parsed_request.id.clone(),
+                    parsed_request.params,
+                    response_sender.clone(),
+                    source.clone(),
+                    router.context.sequencer.clone(),
+                ) {
                 Ok(resp) => resp,
                 Err(e) => {
                     tracing::warn!(error=%e, "Failed to subscribe");
                     ResponseEvent::InternalError(parsed_request.id, e)
                 }
             },
-            UNSUBSCRIBE_METHOD => {
+            UNSUBSCRIBE_METHOD if router.is_allowed(UNSUBSCRIBE_METHOD) => {
                 subscription_manager
                     .unsubscribe(parsed_request.id, parsed_request.params)
                     .await
@@ -712,6 +718,36 @@ mod tests {
         client.destroy().await;
     }
 
+    #[tokio::test]
+    async fn filtered_out_subscription_methods_are_not_found() {
+        let mut client = Client::with_method_filter(&crate::MethodFilter::Deny(vec![
+            "pathfinder_*subscribe".to_owned(),
+        ]))
+        .await;
+
+        for method in [SUBSCRIBE_METHOD, UNSUBSCRIBE_METHOD] {
+            client
+                .send_request(&RpcRequest {
+                    method: Cow::from(method),
+                    params: RawParams(Some(
+                        &RawValue::from_string(r#"["newHeads"]"#.to_owned()).unwrap(),
+                    )),
+                    id: RequestId::Number(1),
+                })
+                .await;
+
+            client
+                .expect_response(&RpcResponse {
+                    output: Err(RpcError::MethodNotFound),
+                    id: RequestId::Number(1),
+                    version: RpcVersion::V07,
+                })
+                .await;
+        }
+
+        client.destroy().await;
+    }
+
     #[tokio::test]
     async fn subscribe_new_heads() {
         let mut client = Client::new().await;
@@ -1624,6 +1660,10 @@ mod tests {
 
     impl Client {
         async fn new() -> Client {
+            Self::with_method_filter(&crate::MethodFilter::All).await
+        }
+
+        async fn with_method_filter(method_filter: &crate::MethodFilter) -> Client {
             let (pending_data_tx, pending_data_rx) = watch::channel(PendingData {
                 block: Default::default(),
                 number: BlockNumber::new_or_panic(0),
@@ -1636,6 +1676,7 @@ mod tests {
             ));
             let router = RpcRouter::builder(crate::RpcVersion::V07)
                 .register("pathfinder_test", rpc_test_method)
+                .with_method_filter(method_filter)
                 .build(context.clone());
             let websocket_context = context.websocket.clone().unwrap();
             let head_sender = websocket_context.broadcasters.new_head.clone();
@@ -1,14 +1,17 @@
 //! Starknet node JSON-RPC related modules.
+mod archive_fallback;
 pub mod context;
 mod dto;
 mod error;
 mod executor;
 mod felt;
 mod jsonrpc;
+mod listener;
 pub(crate) mod method;
 pub mod middleware;
 mod pathfinder;
 mod pending;
+mod response_cache;
 #[cfg(test)]
 mod test_setup;
 pub mod types;
@@ -21,15 +24,20 @@ use std::result::Result;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use anyhow::Context;
+pub use archive_fallback::ArchiveFallback;
 use axum::error_handling::HandleErrorLayer;
 use axum::extract::DefaultBodyLimit;
 use axum::response::IntoResponse;
 use context::RpcContext;
-pub use executor::compose_executor_transaction;
-use http_body::Body;
+pub use executor::{
+    compose_executor_transaction,
+    VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
+};
 pub use jsonrpc::{Notifications, Reorg};
-use pathfinder_common::AllowedOrigins;
+pub use listener::{Listener, MethodFilter};
+use pathfinder_common::{AllowedOrigins, BlockNumber};
 pub use pending::PendingData;
+pub use response_cache::ResponseCache;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use tower_http::cors::CorsLayer;
@@ -62,26 +70,40 @@ impl RpcVersion {
     }
 }
 
-// TODO: make this configurable
-const REQUEST_MAX_SIZE: usize = 10 * 1024 * 1024;
-// TODO: make this configurable
-const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+/// Whether `method_name` is a method served by any of the API versions.
+/// Subscriptions don't count, as they are not method calls.
+pub fn is_rpc_method(method_name: &str) -> bool {
+    [
+        v06::register_routes(),
+        v07::register_routes(),
+        v08::register_routes(),
+        pathfinder::register_routes(),
+    ]
+    .iter()
+    .any(|routes| routes.has_method(method_name))
+}
 
 pub struct RpcServer {
-    addr: SocketAddr,
+    listeners: Vec<Listener>,
     context: RpcContext,
     max_connections: usize,
     cors: Option<CorsLayer>,
+    rate_limiter: Option<middleware::rate_limit::RateLimiter>,
+    compression: bool,
+    http2: bool,
     default_version: RpcVersion,
 }
 
 impl RpcServer {
     pub fn new(addr: SocketAddr, context: RpcContext, default_version: RpcVersion) -> Self {
         Self {
-            addr,
+            listeners: vec![Listener::new(addr)],
             context,
             max_connections: DEFAULT_MAX_CONNECTIONS,
             cors: None,
+            rate_limiter: None,
+            compression: false,
+            http2: false,
             default_version,
         }
     }
@@ -98,29 +120,90 @@ impl RpcServer {
         }
     }
 
-    /// Starts the HTTP-RPC server.
+    /// Identifies clients by API key or IP address and applies their request
+    /// quotas.
+    pub fn with_rate_limiter(self, rate_limiter: middleware::rate_limit::RateLimiter) -> Self {
+        Self {
+            rate_limiter: Some(rate_limiter),
+            ..self
+        }
+    }
+
+    /// Compresses responses and decompresses request bodies using gzip,
+    /// brotli or zstd, as negotiated with each client.
+    pub fn with_compression(self, compression: bool) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    /// Serves HTTP/2 in addition to HTTP/1.1. There is no TLS, so clients have
+    /// to use HTTP/2 with prior knowledge (h2c). Disabled by default.
+    pub fn with_http2(self, http2: bool) -> Self {
+        Self { http2, ..self }
+    }
+
+    /// Restricts the methods served on the address passed to [RpcServer::new].
+    pub fn with_method_filter(mut self, method_filter: MethodFilter) -> Self {
+        self.listeners[0].method_filter = method_filter;
+        self
+    }
+
+    /// Also serves the API on another address.
+    pub fn with_listener(mut self, listener: Listener) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Starts the HTTP-RPC server. Returns the local addresses of its
+    /// listeners, in the order they were added.
     pub async fn spawn(
         self,
-    ) -> Result<(JoinHandle<anyhow::Result<()>>, SocketAddr), anyhow::Error> {
-        use axum::routing::{get, post};
-
-        let listener = match tokio::net::TcpListener::bind(self.addr).await {
-            Ok(listener) => listener,
-            Err(e) => {
-                return Err(e).context(format!(
-                    "RPC address {} is already in use.
+    ) -> Result<(JoinHandle<anyhow::Result<()>>, Vec<SocketAddr>), anyhow::Error> {
+        let mut servers = Vec::with_capacity(self.listeners.len());
+        let mut addresses = Vec::with_capacity(self.listeners.len());
+
+        for listener in &self.listeners {
+            let tcp_listener = match tokio::net::TcpListener::bind(listener.addr).await {
+                Ok(tcp_listener) => tcp_listener,
+                Err(e) => {
+                    return Err(e).context(format!(
+                        "RPC address {} is already in use.
     
             Hint: This usually means you are already running another instance of pathfinder.
             Hint: If this happens when upgrading, make sure to shut down the first one first.
             Hint: If you are looking to run two instances of pathfinder, you must configure them \
-                     with different http rpc addresses.",
-                    self.addr
-                ))
-            }
-        };
-        let addr = listener
-            .local_addr()
-            .context("Getting local address from listener")?;
+                         with different http rpc addresses.",
+                        listener.addr
+                    ))
+                }
+            };
+            let addr = tcp_listener
+                .local_addr()
+                .context("Getting local address from listener")?;
+            addresses.push(addr);
+
+            let router = self.router(listener)?;
+            let http2 = self.http2;
+            servers.push(async move {
+                serve(tcp_listener, router, http2)
+                    .await
+                    .with_context(|| format!("Serving RPC on {addr}"))
+            });
+        }
+
+        let server_handle = util::task::spawn(async move {
+            futures::future::try_join_all(servers).await?;
+            Ok(())
+        });
+
+        Ok((server_handle, addresses))
+    }
+
+    /// Builds the routes and middleware of a listener.
+    fn router(&self, listener: &Listener) -> anyhow::Result<axum::Router> {
+        use axum::routing::{get, post};
 
         async fn handle_middleware_errors(err: axum::BoxError) -> (http::StatusCode, String) {
             use http::StatusCode;
@@ -148,26 +231,40 @@ impl RpcServer {
             // make sure to set request ids before the request reaches `TraceLayer`
             .set_x_request_id(middleware::request_id::RequestIdSource::default())
             .concurrency_limit(self.max_connections)
-            .layer(DefaultBodyLimit::max(REQUEST_MAX_SIZE))
-            .timeout(REQUEST_TIMEOUT)
+            .layer(DefaultBodyLimit::max(self.context.config.request_max_size))
+            // Bounds the request as a whole, including reading its body. Method
+            // calls time out individually within it.
+            .timeout(self.context.config.request_timeout)
             .layer(middleware::tracing::trace_layer())
-            .option_layer(self.cors)
+            .option_layer(self.cors.clone())
             .propagate_x_request_id();
 
         /// Returns success for requests with an empty body without reading
         /// the entire body.
+        ///
+        /// Reading stops at the first byte. The body can't be asked whether it
+        /// has ended instead, as request decompression hides that.
         async fn empty_body(request: axum::extract::Request) -> impl IntoResponse {
-            if request.body().is_end_stream() {
+            if axum::body::to_bytes(request.into_body(), 0).await.is_ok() {
                 axum::http::StatusCode::OK
             } else {
                 axum::http::StatusCode::METHOD_NOT_ALLOWED
             }
         }
 
-        let v06_routes = v06::register_routes().build(self.context.clone());
-        let v07_routes = v07::register_routes().build(self.context.clone());
-        let v08_routes = v08::register_routes().build(self.context.clone());
-        let pathfinder_routes = pathfinder::register_routes().build(self.context.clone());
+        // Methods which are filtered out are not registered at all, so that
+        // they are reported as not found.
+        let build = |version: RpcVersion, routes: jsonrpc::RpcRouterBuilder| {
+            listener.serves(version).then(|| {
+                routes
+                    .with_method_filter(&listener.method_filter)
+                    .build(self.context.clone())
+            })
+        };
+        let v06_routes = build(RpcVersion::V06, v06::register_routes());
+        let v07_routes = build(RpcVersion::V07, v07::register_routes());
+        let v08_routes = build(RpcVersion::V08, v08::register_routes());
+        let pathfinder_routes = build(RpcVersion::PathfinderV01, pathfinder::register_routes());
 
         let default_router = match self.default_version {
             RpcVersion::V06 => v06_routes.clone(),
@@ -178,46 +275,83 @@ impl RpcServer {
             }
         };
 
-        let router = axum::Router::new()
-            // Also return success for get's with an empty body. These are often
-            // used by monitoring bots to check service health.
-            .route("/", get(empty_body).post(rpc_handler))
-            .with_state(default_router.clone())
-            .route("/rpc/v0_6", post(rpc_handler))
-            .with_state(v06_routes.clone())
-            .route("/rpc/v0_7", post(rpc_handler))
-            .with_state(v07_routes.clone())
+        // Also return success for get's with an empty body. These are often
+        // used by monitoring bots to check service health.
+        let root = match &default_router {
+            Some(routes) => get(empty_body).post(rpc_handler).with_state(routes.clone()),
+            None => get(empty_body),
+        };
+        let mut router = axum::Router::new().route("/", root);
+
+        if let Some(routes) = &v06_routes {
+            router = router.route("/rpc/v0_6", post(rpc_handler).with_state(routes.clone()));
+        }
+        if let Some(routes) = &v07_routes {
+            router = router.route("/rpc/v0_7", post(rpc_handler).with_state(routes.clone()));
+        }
+        if let Some(routes) = &v08_routes {
             // TODO Uncomment once RPC 0.8 is ready.
-            .route("/rpc/v0_8", post(rpc_handler).get(rpc_handler))
-            .with_state(v08_routes.clone())
-            .route("/rpc/pathfinder/v0.1", post(rpc_handler))
-            .route("/rpc/pathfinder/v0_1", post(rpc_handler))
-            .with_state(pathfinder_routes.clone());
+            router = router.route(
+                "/rpc/v0_8",
+                post(rpc_handler)
+                    .get(rpc_handler)
+                    .with_state(routes.clone()),
+            );
+        }
+        if let Some(routes) = &pathfinder_routes {
+            router = router
+                .route(
+                    "/rpc/pathfinder/v0.1",
+                    post(rpc_handler).with_state(routes.clone()),
+                )
+                .route(
+                    "/rpc/pathfinder/v0_1",
+                    post(rpc_handler).with_state(routes.clone()),
+                );
+
+            if listener
+                .method_filter
+                .is_allowed(pathfinder::export_events::METHOD_NAME)
+            {
+                router = router.route(
+                    "/rpc/pathfinder/v0_1/events",
+                    post(pathfinder::export_events::export_events).with_state(self.context.clone()),
+                );
+            }
+        }
 
-        let router = if self.context.websocket.is_some() {
+        if self.context.websocket.is_some() {
+            let websocket_routes = [
+                ("/ws", default_router),
+                ("/ws/rpc/v0_6", v06_routes),
+                ("/ws/rpc/v0_7", v07_routes),
+                ("/ws/rpc/pathfinder/v0_1", pathfinder_routes),
+            ];
+            for (path, routes) in websocket_routes {
+                let Some(routes) = routes else {
+                    continue;
+                };
+                router = router.route(path, get(websocket_handler).with_state(routes));
+            }
+        }
+
+        let router = if self.compression {
             router
-                .route("/ws", get(websocket_handler))
-                .with_state(default_router)
-                .route("/ws/rpc/v0_6", get(websocket_handler))
-                .with_state(v06_routes)
-                .route("/ws/rpc/v0_7", get(websocket_handler))
-                .with_state(v07_routes)
-                .route("/ws/rpc/pathfinder/v0_1", get(websocket_handler))
-                .with_state(pathfinder_routes)
+                .layer(middleware::compression::decompression())
+                .layer(middleware::compression::compression())
         } else {
-            router.with_state(default_router)
+            router
         };
 
-        let router = router.layer(middleware);
-
-        let server_handle = util::task::spawn(async move {
-            axum::serve(listener, router.into_make_service())
-                .with_graceful_shutdown(util::task::cancellation_token().cancelled_owned())
-                .await
-                .map_err(Into::into)
-        });
+        let router = match &self.rate_limiter {
+            Some(rate_limiter) => router.layer(axum::middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                middleware::rate_limit::identify_client,
+            )),
+            None => router,
+        };
 
-        Ok((server_handle, addr))
+        Ok(router.layer(middleware))
     }
 
     pub fn get_topic_broadcasters(&self) -> Option<&TopicBroadcasters> {
@@ -228,14 +362,109 @@ impl RpcServer {
     }
 }
 
+/// Serves `router` on `tcp_listener` until shutdown is requested. Open
+/// connections are then shut down gracefully.
+///
+/// Connections are served with HTTP/1 only, unless `http2` is set.
+async fn serve(
+    tcp_listener: tokio::net::TcpListener,
+    router: axum::Router,
+    http2: bool,
+) -> std::io::Result<()> {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto;
+    use hyper_util::service::TowerToHyperService;
+    use tower::ServiceExt;
+
+    let cancellation = util::task::cancellation_token();
+
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = tcp_listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(error) => {
+                    // Usually caused by running out of file descriptors, so
+                    // give connections some time to close.
+                    tracing::debug!(%error, "Failed to accept connection");
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            _ = cancellation.cancelled() => return Ok(()),
+        };
+
+        // Provides what `into_make_service_with_connect_info` would.
+        let service =
+            router
+                .clone()
+                .map_request(move |mut request: http::Request<hyper::body::Incoming>| {
+                    request
+                        .extensions_mut()
+                        .insert(axum::extract::ConnectInfo(remote_addr));
+                    request.map(axum::body::Body::new)
+                });
+        let io = TokioIo::new(stream);
+        let service = TowerToHyperService::new(service);
+
+        // The auto builder can't be restricted to HTTP/1 while supporting
+        // upgrades to websockets, so HTTP/1 is served by hyper directly.
+        if http2 {
+            let connection = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(io, service)
+                .into_owned();
+            util::task::spawn(drive_connection(
+                connection,
+                auto::UpgradeableConnection::graceful_shutdown,
+            ));
+        } else {
+            let connection = hyper::server::conn::http1::Builder::new()
+                .serve_connection(io, service)
+                .with_upgrades();
+            util::task::spawn(drive_connection(
+                connection,
+                hyper::server::conn::http1::UpgradeableConnection::graceful_shutdown,
+            ));
+        }
+    }
+}
+
+/// Serves `connection` until it is closed, starting a graceful shutdown of the
+/// connection once shutdown is requested.
+async fn drive_connection<C, E>(connection: C, graceful_shutdown: fn(std::pin::Pin<&mut C>))
+where
+    C: std::future::Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    tokio::pin!(connection);
+    let cancellation = util::task::cancellation_token();
+
+    let result = tokio::select! {
+        result = connection.as_mut() => result,
+        _ = cancellation.cancelled() => {
+            graceful_shutdown(connection.as_mut());
+            connection.await
+        }
+    };
+
+    if let Err(error) = result {
+        tracing::trace!(%error, "Failed to serve connection");
+    }
+}
+
 pub struct SyncState {
     pub status: RwLock<Syncing>,
+    /// Set once sync has stopped at `--sync.stop-at-block`.
+    pub stopped_at: RwLock<Option<BlockNumber>>,
+    /// The latest block known to be accepted on L1.
+    pub l1_accepted: RwLock<Option<BlockNumber>>,
 }
 
 impl Default for SyncState {
     fn default() -> Self {
         Self {
             status: RwLock::new(Syncing::False),
+            stopped_at: RwLock::new(None),
+            l1_accepted: RwLock::new(None),
         }
     }
 }
@@ -902,16 +1131,49 @@ mod tests {
         }
     }
 
+    #[rstest::rstest]
+    #[case::enabled(true)]
+    #[case::disabled(false)]
+    #[tokio::test]
+    async fn h2c_requests(#[case] http2: bool) {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let (_jh, addresses) = RpcServer::new(addr, RpcContext::for_tests(), RpcVersion::V07)
+            .with_http2(http2)
+            .spawn()
+            .await
+            .unwrap();
+
+        let client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let response = client
+            .post(format!("http://{}/rpc/v0_7", addresses[0]))
+            .json(&serde_json::json!({"jsonrpc": "2.0", "method": "starknet_chainId", "id": 1}))
+            .send()
+            .await;
+
+        if http2 {
+            let response = response.unwrap();
+            assert_eq!(response.version(), http::Version::HTTP_2);
+            assert_eq!(response.status(), http::StatusCode::OK);
+        } else {
+            // The server only speaks HTTP/1, so the connection fails.
+            response.unwrap_err();
+        }
+    }
+
     #[tokio::test]
     async fn empty_get_on_root_is_ok() {
         // Monitoring bots often get query `/` with no body as a form
         // of health check. Test that we return success for such queries.
         let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
         let context = RpcContext::for_tests();
-        let (_jh, addr) = RpcServer::new(addr, context, RpcVersion::V07)
+        let (_jh, addresses) = RpcServer::new(addr, context, RpcVersion::V07)
             .spawn()
             .await
             .unwrap();
+        let addr = addresses[0];
 
         let url = format!("http://{addr}/");
 
@@ -939,6 +1201,20 @@ mod tests {
         assert!(!status.is_success());
     }
 
+    /// Methods of the Pathfinder specification which are only served by the
+    /// `pathfinder/v0_1` API, and not by the Starknet APIs.
+    ///
+    /// `pathfinder_getTransactionStatus` is now part of the official spec, so
+    /// we are phasing it out.
+    const PATHFINDER_ONLY_METHODS: &[&str] = &[
+        "pathfinder_version",
+        "pathfinder_getTransactionStatus",
+        "pathfinder_traceCall",
+        "pathfinder_simulateBlocks",
+        "pathfinder_getEventsByTransaction",
+        "pathfinder_getEventStats",
+    ];
+
     enum Api {
         HttpOnly,
         WebsocketOnly,
@@ -963,9 +1239,8 @@ mod tests {
     #[case::root_trace_websocket("/ws", "v07/starknet_trace_api_openrpc.json", &[], Api::WebsocketOnly)]
     #[case::root_write("/", "v07/starknet_write_api.json",         &[], Api::HttpOnly)]
     #[case::root_write_websocket("/ws", "v07/starknet_write_api.json",         &[], Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::root_pathfinder("/", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::HttpOnly)]
-    #[case::root_pathfinder_websocket("/ws", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::WebsocketOnly)]
+    #[case::root_pathfinder("/", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::HttpOnly)]
+    #[case::root_pathfinder_websocket("/ws", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::WebsocketOnly)]
 
     #[case::v0_8_api("/rpc/v0_8", "v08/starknet_api_openrpc.json", &[], Api::Both)]
     #[case::v0_8_executables("/rpc/v0_8", "v08/starknet_executables.json", &[], Api::Both)]
@@ -983,8 +1258,7 @@ mod tests {
             "starknet_subscriptionReorg"
         ],
         Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::v0_8_pathfinder("/rpc/v0_8", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::Both)]
+    #[case::v0_8_pathfinder("/rpc/v0_8", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::Both)]
 
     #[case::v0_7_api("/rpc/v0_7", "v07/starknet_api_openrpc.json", &[], Api::HttpOnly)]
     #[case::v0_7_api_websocket("/ws/rpc/v0_7", "v07/starknet_api_openrpc.json", &[], Api::WebsocketOnly)]
@@ -992,9 +1266,8 @@ mod tests {
     #[case::v0_7_trace_websocket("/ws/rpc/v0_7", "v07/starknet_trace_api_openrpc.json", &[], Api::WebsocketOnly)]
     #[case::v0_7_write("/rpc/v0_7", "v07/starknet_write_api.json", &[], Api::HttpOnly)]
     #[case::v0_7_write_websocket("/ws/rpc/v0_7", "v07/starknet_write_api.json", &[], Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::v0_7_pathfinder("/rpc/v0_7", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::HttpOnly)]
-    #[case::v0_7_pathfinder_websocket("/ws/rpc/v0_7", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::WebsocketOnly)]
+    #[case::v0_7_pathfinder("/rpc/v0_7", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::HttpOnly)]
+    #[case::v0_7_pathfinder_websocket("/ws/rpc/v0_7", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::WebsocketOnly)]
 
     #[case::v0_6_api(
         "/rpc/v0_6",
@@ -1010,12 +1283,11 @@ mod tests {
     #[case::v0_6_trace_websocket("/ws/rpc/v0_6", "v06/starknet_trace_api_openrpc.json", &[], Api::WebsocketOnly)]
     #[case::v0_6_write("/rpc/v0_6", "v06/starknet_write_api.json", &[], Api::HttpOnly)]
     #[case::v0_6_write_websocket("/ws/rpc/v0_6", "v06/starknet_write_api.json", &[], Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::v0_6_pathfinder("/rpc/v0_6", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::HttpOnly)]
-    #[case::v0_6_pathfinder_websocket("/ws/rpc/v0_6", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::WebsocketOnly)]
+    #[case::v0_6_pathfinder("/rpc/v0_6", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::HttpOnly)]
+    #[case::v0_6_pathfinder_websocket("/ws/rpc/v0_6", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::WebsocketOnly)]
 
-    #[case::pathfinder("/rpc/pathfinder/v0.1", "pathfinder_rpc_api.json", &[], Api::HttpOnly)]
-    #[case::pathfinder("/ws/rpc/pathfinder/v0_1", "pathfinder_rpc_api.json", &[], Api::WebsocketOnly)]
+    #[case::pathfinder("/rpc/pathfinder/v0.1", "pathfinder_rpc_api.json", pathfinder::ADMIN_METHODS, Api::HttpOnly)]
+    #[case::pathfinder("/ws/rpc/pathfinder/v0_1", "pathfinder_rpc_api.json", pathfinder::ADMIN_METHODS, Api::WebsocketOnly)]
 
     #[tokio::test]
     async fn rpc_routing(
@@ -1060,10 +1332,11 @@ mod tests {
                 rx_pending,
             ));
         }
-        let (_jh, addr) = RpcServer::new(addr, context, RpcVersion::V07)
+        let (_jh, addresses) = RpcServer::new(addr, context, RpcVersion::V07)
             .spawn()
             .await
             .unwrap();
+        let addr = addresses[0];
 
         let method_not_found = json!(-32601);
         let invalid_params = json!(-32602);
@@ -0,0 +1,220 @@
+//! Addresses the RPC server listens on, each exposing its own subset of the
+//! API.
+
+use std::net::SocketAddr;
+
+use crate::RpcVersion;
+
+/// An address the RPC server listens on, together with the API versions and
+/// methods served on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub(crate) addr: SocketAddr,
+    pub(crate) versions: Option<Vec<RpcVersion>>,
+    pub(crate) method_filter: MethodFilter,
+}
+
+impl Listener {
+    /// A listener serving all versions and methods.
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            versions: None,
+            method_filter: MethodFilter::All,
+        }
+    }
+
+    /// Only serves the given versions. The root path is only served if the
+    /// default version is among them.
+    pub fn with_versions(self, versions: Vec<RpcVersion>) -> Self {
+        Self {
+            versions: Some(versions),
+            ..self
+        }
+    }
+
+    pub fn with_method_filter(self, method_filter: MethodFilter) -> Self {
+        Self {
+            method_filter,
+            ..self
+        }
+    }
+
+    pub(crate) fn serves(&self, version: RpcVersion) -> bool {
+        match &self.versions {
+            Some(versions) => versions.contains(&version),
+            None => true,
+        }
+    }
+}
+
+/// Selects the methods served by a [Listener]. Patterns are method names in
+/// which `*` matches any sequence of characters, e.g.
+/// `starknet_add*Transaction`.
+///
+/// Calls to methods which are filtered out fail as if the method did not exist.
+///
+/// Admin methods, such as `pathfinder_getEventStats`, are only served if they
+/// are listed by name in an [Allow](MethodFilter::Allow) filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MethodFilter {
+    #[default]
+    All,
+    /// Only methods matching one of the patterns are served.
+    Allow(Vec<String>),
+    /// Methods matching one of the patterns are not served.
+    Deny(Vec<String>),
+}
+
+impl MethodFilter {
+    pub fn is_allowed(&self, method: &str) -> bool {
+        if crate::pathfinder::ADMIN_METHODS.contains(&method) {
+            return match self {
+                MethodFilter::Allow(patterns) => patterns.iter().any(|p| p == method),
+                _ => false,
+            };
+        }
+
+        match self {
+            MethodFilter::All => true,
+            MethodFilter::Allow(patterns) => patterns.iter().any(|p| matches(p, method)),
+            MethodFilter::Deny(patterns) => !patterns.iter().any(|p| matches(p, method)),
+        }
+    }
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // There is always at least one part, even for an empty pattern.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            // The last part has to match the end of the name.
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    // No wildcard in the pattern.
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::RpcContext;
+    use crate::RpcServer;
+
+    #[test]
+    fn patterns() {
+        assert!(matches("starknet_blockNumber", "starknet_blockNumber"));
+        assert!(!matches("starknet_blockNumber", "starknet_blockNumberX"));
+        assert!(!matches("starknet_block", "starknet_blockNumber"));
+
+        assert!(matches("starknet_*", "starknet_blockNumber"));
+        assert!(!matches("starknet_*", "pathfinder_version"));
+        assert!(matches("*", "pathfinder_version"));
+
+        assert!(matches(
+            "starknet_add*Transaction",
+            "starknet_addInvokeTransaction"
+        ));
+        assert!(matches(
+            "starknet_add*Transaction",
+            "starknet_addDeployAccountTransaction"
+        ));
+        assert!(!matches(
+            "starknet_add*Transaction",
+            "starknet_addInvokeTransactionX"
+        ));
+        assert!(matches("*get*Proof", "pathfinder_getClassProof"));
+        assert!(!matches("*get*Proof*x", "pathfinder_getClassProof"));
+    }
+
+    #[test]
+    fn filters() {
+        let deny = MethodFilter::Deny(vec![
+            "starknet_add*Transaction".to_owned(),
+            "pathfinder_getProof".to_owned(),
+        ]);
+        assert!(deny.is_allowed("starknet_blockNumber"));
+        assert!(!deny.is_allowed("starknet_addInvokeTransaction"));
+        assert!(!deny.is_allowed("pathfinder_getProof"));
+
+        let allow = MethodFilter::Allow(vec!["starknet_get*".to_owned()]);
+        assert!(allow.is_allowed("starknet_getEvents"));
+        assert!(!allow.is_allowed("starknet_call"));
+
+        assert!(MethodFilter::All.is_allowed("anything"));
+    }
+
+    #[test]
+    fn admin_methods_are_enabled_by_name() {
+        assert!(!MethodFilter::All.is_allowed("pathfinder_getEventStats"));
+        assert!(!MethodFilter::Deny(vec![]).is_allowed("pathfinder_getEventStats"));
+        assert!(!MethodFilter::Allow(vec!["pathfinder_*".to_owned()])
+            .is_allowed("pathfinder_getEventStats"));
+        assert!(
+            MethodFilter::Allow(vec!["pathfinder_getEventStats".to_owned()])
+                .is_allowed("pathfinder_getEventStats")
+        );
+    }
+
+    #[tokio::test]
+    async fn listeners_serve_their_own_api() {
+        let public = Listener::new("127.0.0.1:0".parse().unwrap())
+            .with_versions(vec![RpcVersion::V07])
+            .with_method_filter(MethodFilter::Deny(vec!["starknet_chainId".to_owned()]));
+        let server = RpcServer::new(
+            "127.0.0.1:0".parse().unwrap(),
+            RpcContext::for_tests(),
+            RpcVersion::V07,
+        )
+        .with_listener(public);
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let [internal, public] = addresses[..] else {
+            panic!("Expected two listeners");
+        };
+
+        let call = |address: SocketAddr, path: &'static str, method: &'static str| async move {
+            let response = reqwest::Client::new()
+                .post(format!("http://{address}{path}"))
+                .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method}))
+                .send()
+                .await
+                .unwrap();
+            if response.status() != reqwest::StatusCode::OK {
+                return Err(response.status());
+            }
+            Ok(response.json::<serde_json::Value>().await.unwrap())
+        };
+
+        let response = call(internal, "/rpc/v0_7", "starknet_chainId")
+            .await
+            .unwrap();
+        assert!(response.get("result").is_some(), "{response}");
+        let response = call(internal, "/rpc/v0_6", "starknet_chainId")
+            .await
+            .unwrap();
+        assert!(response.get("result").is_some(), "{response}");
+
+        let response = call(public, "/rpc/v0_7", "starknet_chainId").await.unwrap();
+        assert_eq!(response["error"]["code"], json!(-32601), "{response}");
+        let response = call(public, "/", "starknet_specVersion").await.unwrap();
+        assert!(response.get("result").is_some(), "{response}");
+        let status = call(public, "/rpc/v0_6", "starknet_specVersion")
+            .await
+            .unwrap_err();
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -1,15 +1,25 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_common::{
+    BlockId,
+    BlockNumber,
+    CallParam,
+    CallResultValue,
+    ContractAddress,
+    EntryPoint,
+};
 use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
+use crate::types::request::StateOverride;
+use crate::RpcVersion;
 
 #[derive(Debug)]
 pub enum CallError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     ContractNotFound,
     EntrypointNotFound,
     ContractError {
@@ -54,6 +64,9 @@ impl From<CallError> for ApplicationError {
     fn from(value: CallError) -> Self {
         match value {
             CallError::BlockNotFound => ApplicationError::BlockNotFound,
+            CallError::BlockPruned(oldest_available) => {
+                ApplicationError::BlockPruned(oldest_available)
+            }
             CallError::ContractNotFound => ApplicationError::ContractNotFound,
             CallError::EntrypointNotFound => ApplicationError::EntrypointNotFound,
             CallError::ContractError {
@@ -73,6 +86,7 @@ impl From<CallError> for ApplicationError {
 pub struct Input {
     pub request: FunctionCall,
     pub block_id: BlockId,
+    pub state_override: StateOverride,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -97,10 +111,18 @@ impl crate::dto::DeserializeForVersion for FunctionCall {
 
 impl crate::dto::DeserializeForVersion for Input {
     fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        let version = value.version;
         value.deserialize_map(|value| {
             Ok(Self {
                 request: value.deserialize("request")?,
                 block_id: value.deserialize("block_id")?,
+                state_override: if version >= RpcVersion::V08 {
+                    value
+                        .deserialize_optional("state_override")?
+                        .unwrap_or_default()
+                } else {
+                    Default::default()
+                },
             })
         })
     }
@@ -111,6 +133,7 @@ pub struct Output(pub Vec<CallResultValue>);
 
 pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError> {
     let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
     let result = util::task::spawn_blocking(move |_| {
         let _g = span.enter();
 
@@ -132,11 +155,11 @@ pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
 
-                let pruned = db
+                if let Some(oldest_available) = db
                     .block_pruned(block_id)
-                    .context("Querying block pruned status")?;
-                if pruned {
-                    return Err(CallError::BlockNotFound);
+                    .context("Querying block pruned status")?
+                {
+                    return Err(CallError::BlockPruned(oldest_available));
                 }
 
                 let header = db
@@ -158,7 +181,9 @@ pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError
             context.contract_addresses.eth_l2_token_address,
             context.contract_addresses.strk_l2_token_address,
             context.native_class_cache,
-        );
+        )
+        .with_state_overrides(input.state_override.0)
+        .with_cancellation(cancellation);
 
         let result = pathfinder_executor::call(
             state,
@@ -213,6 +238,7 @@ mod tests {
                     calldata: vec![call_param!("0x1234"), call_param!("0x2345")],
                 },
                 block_id: block_hash!("0xbbbbbbbb").into(),
+                state_override: Default::default(),
             };
             assert_eq!(input, expected);
         }
@@ -234,9 +260,76 @@ mod tests {
                     calldata: vec![call_param!("0x1234"), call_param!("0x2345")],
                 },
                 block_id: block_hash!("0xbbbbbbbb").into(),
+                state_override: Default::default(),
             };
             assert_eq!(input, expected);
         }
+
+        #[test]
+        fn state_override() {
+            let named_json = json!({
+                "request": { "contract_address": "0xabcde", "entry_point_selector": "0xee", "calldata": [] },
+                "block_id": "latest",
+                "state_override": [
+                    {
+                        "contract_address": "0xabcde",
+                        "storage": [{ "key": "0x1", "value": "0x2" }],
+                        "nonce": "0x3",
+                        "class_hash": "0x4",
+                        "strk_balance": "0x5"
+                    }
+                ]
+            });
+
+            let named = crate::dto::Value::new(named_json, crate::RpcVersion::V08);
+
+            let input = Input::deserialize(named).unwrap();
+            let expected = StateOverride(
+                [(
+                    contract_address!("0xabcde"),
+                    pathfinder_executor::types::ContractStateOverride {
+                        storage: [(storage_address!("0x1"), storage_value!("0x2"))].into(),
+                        nonce: Some(contract_nonce!("0x3")),
+                        class_hash: Some(class_hash!("0x4")),
+                        eth_balance: None,
+                        strk_balance: Some(5u64.into()),
+                    },
+                )]
+                .into(),
+            );
+            assert_eq!(input.state_override, expected);
+        }
+
+        #[test]
+        fn state_override_before_v08_is_rejected() {
+            let named_json = json!({
+                "request": { "contract_address": "0xabcde", "entry_point_selector": "0xee", "calldata": [] },
+                "block_id": "latest",
+                "state_override": []
+            });
+
+            let named = crate::dto::Value::new(named_json, crate::RpcVersion::V07);
+
+            let error = Input::deserialize(named).unwrap_err();
+            assert!(error.to_string().contains("state_override"), "{error}");
+        }
+
+        #[test]
+        fn state_override_out_of_range_address_is_rejected() {
+            // 2^251 is a valid felt, but too large for a contract address.
+            let named_json = json!({
+                "request": { "contract_address": "0xabcde", "entry_point_selector": "0xee", "calldata": [] },
+                "block_id": "latest",
+                "state_override": [
+                    { "contract_address": format!("0x8{}", "0".repeat(62)) }
+                ]
+            });
+
+            let named = crate::dto::Value::new(named_json, crate::RpcVersion::V08);
+
+            let error = Input::deserialize(named).unwrap_err();
+            assert!(error.to_string().contains("out of range"), "{error}");
+        }
     }
 
     mod in_memory {
@@ -327,11 +420,40 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Latest,
+                state_override: Default::default(),
             };
             let result = call(context, input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(test_value.0)]));
         }
 
+        #[tokio::test]
+        async fn storage_override() {
+            let (context, _last_block_header, contract_address, test_key, _test_value) =
+                test_context().await;
+
+            let new_value = storage_value!("0x09");
+            let input = Input {
+                request: FunctionCall {
+                    contract_address,
+                    entry_point_selector: EntryPoint::hashed(b"get_value"),
+                    calldata: vec![CallParam(*test_key.get())],
+                },
+                block_id: BlockId::Latest,
+                state_override: StateOverride(
+                    [(
+                        contract_address,
+                        pathfinder_executor::types::ContractStateOverride {
+                            storage: [(test_key, new_value)].into(),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                ),
+            };
+            let result = call(context, input).await.unwrap();
+            assert_eq!(result, Output(vec![CallResultValue(new_value.0)]));
+        }
+
         #[tokio::test]
         async fn storage_updated_in_pending() {
             let (context, last_block_header, contract_address, test_key, test_value) =
@@ -354,6 +476,7 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Latest,
+                state_override: Default::default(),
             };
             let result = call(context.clone(), input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(test_value.0)]));
@@ -366,6 +489,7 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Pending,
+                state_override: Default::default(),
             };
             let result = call(context, input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(new_value.0)]));
@@ -394,6 +518,7 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Pending,
+                state_override: Default::default(),
             };
             let result = call(context.clone(), input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(new_value.0)]));
@@ -439,6 +564,7 @@ mod tests {
                     calldata: vec![],
                 },
                 block_id: BlockId::Pending,
+                state_override: Default::default(),
             };
             let result = call(context.clone(), input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(storage_value.0)]));
@@ -518,6 +644,7 @@ mod tests {
                     calldata: vec![],
                 },
                 block_id: BlockId::Latest,
+                state_override: Default::default(),
             };
             let result = call(context, input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(storage_value.0)]));
@@ -545,6 +672,7 @@ mod tests {
                     calldata: vec![],
                 },
                 block_id: BlockId::Latest,
+                state_override: Default::default(),
             };
 
             let error = call(context, input).await;
@@ -574,6 +702,7 @@ mod tests {
                     calldata: vec![],
                 },
                 block_id: BlockId::Latest,
+                state_override: Default::default(),
             };
 
             let error = call(context, input).await;
@@ -696,6 +825,7 @@ mod tests {
                     ],
                 },
                 block_id: BlockId::Latest,
+                state_override: Default::default(),
             };
 
             let error = call(context, input).await;
@@ -785,6 +915,7 @@ mod tests {
             let input = Input {
                 request: valid_mainnet_call(),
                 block_id: BlockId::Hash(block_hash_bytes!(b"nonexistent")),
+                state_override: Default::default(),
             };
             let error = call(context, input).await;
             assert_matches::assert_matches!(error, Err(CallError::BlockNotFound));
@@ -800,6 +931,7 @@ mod tests {
                     ..valid_mainnet_call()
                 },
                 block_id: BLOCK_5,
+                state_override: Default::default(),
             };
             let error = call(context, input).await;
             assert_matches::assert_matches!(error, Err(CallError::ContractNotFound));
@@ -815,6 +947,7 @@ mod tests {
                     ..valid_mainnet_call()
                 },
                 block_id: BLOCK_5,
+                state_override: Default::default(),
             };
             let error = call(context, input).await;
             assert_matches::assert_matches!(error, Err(CallError::EntrypointNotFound));
@@ -827,6 +960,7 @@ mod tests {
             let input = Input {
                 request: valid_mainnet_call(),
                 block_id: BLOCK_5,
+                state_override: Default::default(),
             };
 
             let result = call(context, input).await.unwrap();
@@ -1,27 +1,37 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 use serde::de::Error;
 
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
-use crate::types::request::BroadcastedTransaction;
+use crate::types::request::{BroadcastedTransaction, StateOverride};
+use crate::RpcVersion;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
     pub request: Vec<BroadcastedTransaction>,
     pub simulation_flags: Vec<SimulationFlag>,
     pub block_id: BlockId,
+    pub state_override: StateOverride,
 }
 
 impl crate::dto::DeserializeForVersion for Input {
     fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        let version = value.version;
         value.deserialize_map(|value| {
             Ok(Self {
                 request: value.deserialize_array("request", BroadcastedTransaction::deserialize)?,
                 simulation_flags: value
                     .deserialize_array("simulation_flags", SimulationFlag::deserialize)?,
                 block_id: value.deserialize("block_id")?,
+                state_override: if version >= RpcVersion::V08 {
+                    value
+                        .deserialize_optional("state_override")?
+                        .unwrap_or_default()
+                } else {
+                    Default::default()
+                },
             })
         })
     }
@@ -47,6 +57,7 @@ pub struct Output(Vec<pathfinder_executor::types::FeeEstimate>);
 
 pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, EstimateFeeError> {
     let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
     let result = util::task::spawn_blocking(move |_| {
         let _g = span.enter();
         let mut db = context
@@ -67,11 +78,11 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
 
-                let pruned = db
+                if let Some(oldest_available) = db
                     .block_pruned(block_id)
-                    .context("Querying block pruned status")?;
-                if pruned {
-                    return Err(EstimateFeeError::BlockNotFound);
+                    .context("Querying block pruned status")?
+                {
+                    return Err(EstimateFeeError::BlockPruned(oldest_available));
                 }
 
                 let header = db
@@ -93,7 +104,9 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
             context.contract_addresses.eth_l2_token_address,
             context.contract_addresses.strk_l2_token_address,
             context.native_class_cache,
-        );
+        )
+        .with_state_overrides(input.state_override.0)
+        .with_cancellation(cancellation);
 
         let skip_validate = input
             .simulation_flags
@@ -132,6 +145,7 @@ pub enum EstimateFeeError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     TransactionExecutionError {
         transaction_index: usize,
         error: String,
@@ -178,6 +192,9 @@ impl From<EstimateFeeError> for ApplicationError {
     fn from(value: EstimateFeeError) -> Self {
         match value {
             EstimateFeeError::BlockNotFound => ApplicationError::BlockNotFound,
+            EstimateFeeError::BlockPruned(oldest_available) => {
+                ApplicationError::BlockPruned(oldest_available)
+            }
             EstimateFeeError::TransactionExecutionError {
                 transaction_index,
                 error,
@@ -404,10 +421,11 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = estimate_fee(context, input).await.unwrap();
 
-        let output_json = result.serialize(Serializer { version }).unwrap();
+        let output_json = result.serialize(Serializer::new(version)).unwrap();
         crate::assert_json_matches_fixture!(
             output_json,
             version,
@@ -449,10 +467,11 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = estimate_fee(context, input).await.unwrap();
 
-        let output_json = result.serialize(Serializer { version }).unwrap();
+        let output_json = result.serialize(Serializer::new(version)).unwrap();
         crate::assert_json_matches_fixture!(
             output_json,
             version,
@@ -494,10 +513,11 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = super::estimate_fee(context, input).await.unwrap();
 
-        let output_json = result.serialize(Serializer { version }).unwrap();
+        let output_json = result.serialize(Serializer::new(version)).unwrap();
         crate::assert_json_matches_fixture!(
             output_json,
             version,
@@ -539,10 +559,11 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = super::estimate_fee(context, input).await.unwrap();
 
-        let output_json = result.serialize(Serializer { version }).unwrap();
+        let output_json = result.serialize(Serializer::new(version)).unwrap();
         crate::assert_json_matches_fixture!(
             output_json,
             version,
@@ -715,10 +736,11 @@ mod tests {
             ],
             simulation_flags: vec![SimulationFlag::SkipValidate],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = super::estimate_fee(context, input).await.unwrap();
 
-        let output_json = result.serialize(Serializer { version }).unwrap();
+        let output_json = result.serialize(Serializer::new(version)).unwrap();
         crate::assert_json_matches_fixture!(
             output_json,
             version,
@@ -775,6 +797,101 @@ mod tests {
         ))
     }
 
+    /// Transfers 1000 STRK from the sender to `recipient`.
+    fn invoke_v3_strk_transfer(
+        sender_address: ContractAddress,
+        recipient: ContractAddress,
+    ) -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(
+            BroadcastedInvokeTransactionV3 {
+                version: TransactionVersion::THREE,
+                signature: vec![],
+                sender_address,
+                calldata: vec![
+                    // Number of calls
+                    call_param!("0x1"),
+                    CallParam(*crate::context::STRK_FEE_TOKEN_ADDRESS.get()),
+                    CallParam(EntryPoint::hashed(b"transfer").0),
+                    // Length of the call data: recipient and the low and high
+                    // parts of the amount
+                    call_param!("3"),
+                    CallParam(recipient.0),
+                    call_param!("1000"),
+                    call_param!("0"),
+                ],
+                nonce: transaction_nonce!("0x0"),
+                resource_bounds: ResourceBounds {
+                    l1_gas: ResourceBound {
+                        max_amount: ResourceAmount(50),
+                        max_price_per_unit: ResourcePricePerUnit(1000),
+                    },
+                    l1_data_gas: Some(ResourceBound {
+                        max_amount: ResourceAmount(100),
+                        max_price_per_unit: ResourcePricePerUnit(1000),
+                    }),
+                    l2_gas: ResourceBound {
+                        max_amount: ResourceAmount(800_000),
+                        max_price_per_unit: ResourcePricePerUnit(1000),
+                    },
+                },
+                tip: Tip(0),
+                paymaster_data: vec![],
+                account_deployment_data: vec![],
+                nonce_data_availability_mode: DataAvailabilityMode::L2,
+                fee_data_availability_mode: DataAvailabilityMode::L2,
+            },
+        ))
+    }
+
+    #[tokio::test]
+    async fn erc20_balance_override() {
+        let (context, last_block_header, account_contract_address, recipient) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 13, 1, 0,
+            ))
+            .await;
+
+        let input = |state_override| Input {
+            request: vec![invoke_v3_strk_transfer(account_contract_address, recipient)],
+            simulation_flags: vec![SimulationFlag::SkipValidate],
+            block_id: BlockId::Number(last_block_header.number),
+            state_override,
+        };
+
+        // The account's balance covers the transfer...
+        super::estimate_fee(context.clone(), input(Default::default()))
+            .await
+            .unwrap();
+
+        // ...until it is overridden.
+        let state_override = StateOverride(
+            [(
+                account_contract_address,
+                pathfinder_executor::types::ContractStateOverride {
+                    strk_balance: Some(0u64.into()),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+        );
+        let error = super::estimate_fee(context, input(state_override))
+            .await
+            .unwrap_err();
+        // The transfer fails in the fee token contract.
+        assert_matches::assert_matches!(error, EstimateFeeError::TransactionExecutionError { transaction_index, error_stack, .. } => {
+            assert_eq!(transaction_index, 0);
+            assert!(error_stack.0.iter().any(|frame| matches!(
+                frame,
+                pathfinder_executor::Frame::CallFrame(pathfinder_executor::CallFrame {
+                    storage_address,
+                    selector,
+                    ..
+                }) if *storage_address == crate::context::STRK_FEE_TOKEN_ADDRESS
+                    && *selector == Some(EntryPoint::hashed(b"transfer"))
+            )));
+        });
+    }
+
     #[tokio::test]
     async fn declare_deploy_and_invoke_sierra_class_reverts_on_starknet_0_13_4() {
         let (context, last_block_header, account_contract_address, universal_deployer_address) =
@@ -799,6 +916,7 @@ mod tests {
             request: vec![declare_transaction, deploy_transaction, invoke_transaction],
             simulation_flags: vec![SimulationFlag::SkipValidate],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let error = super::estimate_fee(context, input).await.unwrap_err();
 
@@ -858,6 +976,7 @@ mod tests {
             request: vec![declare_transaction, deploy_transaction, invoke_transaction],
             simulation_flags: vec![SimulationFlag::SkipValidate],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = super::estimate_fee(context, input).await;
         let expected_err = anyhow::anyhow!("Fee estimation failed, maximum gas limit exceeded");
@@ -889,6 +1008,7 @@ mod tests {
             request: vec![declare_transaction, deploy_transaction, invoke_transaction],
             simulation_flags: vec![SimulationFlag::SkipValidate],
             block_id: BlockId::Number(last_block_header.number),
+            state_override: Default::default(),
         };
         let result = super::estimate_fee(context, input).await.unwrap();
         let declare_expected = FeeEstimate {
@@ -58,6 +58,7 @@ pub async fn estimate_message_fee(
     input: EstimateMessageFeeInput,
 ) -> Result<Output, EstimateMessageFeeError> {
     let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
     let mut result = util::task::spawn_blocking(move |_| {
         let _g = span.enter();
         let mut db = context
@@ -78,11 +79,11 @@ pub async fn estimate_message_fee(
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
 
-                let pruned = db
+                if let Some(oldest_available) = db
                     .block_pruned(block_id)
-                    .context("Querying block pruned status")?;
-                if pruned {
-                    return Err(EstimateMessageFeeError::BlockNotFound);
+                    .context("Querying block pruned status")?
+                {
+                    return Err(EstimateMessageFeeError::BlockPruned(oldest_available));
                 }
 
                 let header = db
@@ -108,7 +109,8 @@ pub async fn estimate_message_fee(
             context.contract_addresses.eth_l2_token_address,
             context.contract_addresses.strk_l2_token_address,
             context.native_class_cache,
-        );
+        )
+        .with_cancellation(cancellation);
 
         let transaction = create_executor_transaction(input, context.chain_id)?;
 
@@ -194,6 +196,7 @@ impl crate::dto::SerializeForVersion for Output {
 pub enum EstimateMessageFeeError {
     Internal(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     ContractNotFound,
     ContractError {
         revert_error: String,
@@ -238,6 +241,9 @@ impl From<EstimateMessageFeeError> for ApplicationError {
     fn from(value: EstimateMessageFeeError) -> Self {
         match value {
             EstimateMessageFeeError::BlockNotFound => ApplicationError::BlockNotFound,
+            EstimateMessageFeeError::BlockPruned(oldest_available) => {
+                ApplicationError::BlockPruned(oldest_available)
+            }
             EstimateMessageFeeError::ContractNotFound => ApplicationError::ContractNotFound,
             EstimateMessageFeeError::ContractError {
                 revert_error,
@@ -366,7 +372,7 @@ mod tests {
             .await
             .expect("result");
 
-        let output_json = result.serialize(Serializer { version }).unwrap();
+        let output_json = result.serialize(Serializer::new(version)).unwrap();
         crate::assert_json_matches_fixture!(output_json, version, "fee_estimates/full.json");
     }
 }
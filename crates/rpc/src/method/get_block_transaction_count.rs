@@ -18,7 +18,10 @@ impl crate::dto::DeserializeForVersion for Input {
     }
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber)
+);
 
 #[derive(Debug)]
 pub struct Output(u64);
@@ -51,11 +54,11 @@ pub async fn get_block_transaction_count(
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = db
+        if let Some(oldest_available) = db
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         let exists = db
@@ -33,7 +33,10 @@ impl crate::dto::DeserializeForVersion for Input {
     }
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber)
+);
 
 pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
@@ -58,11 +61,11 @@ pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Resul
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = db
+        if let Some(oldest_available) = db
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         let header = db
@@ -208,7 +211,7 @@ mod tests {
         let output = get_block_with_receipts(context.clone(), input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "blocks/pending.json");
@@ -228,7 +231,7 @@ mod tests {
         let output = get_block_with_receipts(context.clone(), input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "blocks/latest.json");
@@ -5,7 +5,10 @@ use pathfinder_common::{BlockHeader, BlockId, TransactionHash};
 
 use crate::context::RpcContext;
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber)
+);
 
 pub struct Input {
     pub block_id: BlockId,
@@ -65,11 +68,11 @@ pub async fn get_block_with_tx_hashes(context: RpcContext, input: Input) -> Resu
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = transaction
+        if let Some(oldest_available) = transaction
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         let header = transaction
@@ -158,7 +161,7 @@ mod tests {
         };
 
         let output = get_block_with_tx_hashes(context, input).await.unwrap();
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
@@ -180,7 +183,7 @@ mod tests {
         };
 
         let output = get_block_with_tx_hashes(context, input).await.unwrap();
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
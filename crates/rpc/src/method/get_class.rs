@@ -6,7 +6,11 @@ use crate::dto;
 use crate::dto::SerializeForVersion;
 use crate::types::{CairoContractClass, ContractClass, SierraContractClass};
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ClassHashNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber),
+    ClassHashNotFound
+);
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
@@ -68,11 +72,11 @@ pub async fn get_class(context: RpcContext, input: Input) -> Result<Output, Erro
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         // Check that block exists
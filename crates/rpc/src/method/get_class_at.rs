@@ -6,7 +6,11 @@ use crate::dto;
 use crate::dto::SerializeForVersion;
 use crate::types::{CairoContractClass, ContractClass, SierraContractClass};
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber),
+    ContractNotFound
+);
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
@@ -78,11 +82,11 @@ pub async fn get_class_at(context: RpcContext, input: Input) -> Result<Output, E
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         if !tx.block_exists(block_id)? {
@@ -175,7 +179,7 @@ mod tests {
         let output = get_class_at(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "class_at/cairo0.json");
@@ -196,7 +200,7 @@ mod tests {
         let output = get_class_at(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "class_at/sierra.json");
@@ -3,7 +3,11 @@ use pathfinder_common::{BlockId, ClassHash, ContractAddress};
 
 use crate::context::RpcContext;
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber),
+    ContractNotFound
+);
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
@@ -55,11 +59,11 @@ pub async fn get_class_hash_at(context: RpcContext, input: Input) -> Result<Outp
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         // Check for block existence.
@@ -274,7 +278,7 @@ mod tests {
         let output = get_class_hash_at(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "class_hash/latest.json");
@@ -295,7 +299,7 @@ mod tests {
         let output = get_class_hash_at(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "class_hash/pending.json");
@@ -318,7 +322,7 @@ mod tests {
         let output = get_class_hash_at(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "class_hash/at_block.json");
@@ -3,7 +3,7 @@ use std::str::FromStr;
 use anyhow::Context;
 use pathfinder_common::prelude::*;
 use pathfinder_common::BlockId;
-use pathfinder_storage::{EventFilterError, EVENT_KEY_FILTER_LIMIT};
+use pathfinder_storage::{EventFilterError, EVENT_ADDRESS_FILTER_LIMIT, EVENT_KEY_FILTER_LIMIT};
 use starknet_gateway_types::reply::PendingBlock;
 use tokio::task::JoinHandle;
 
@@ -18,9 +18,11 @@ pub enum GetEventsError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     PageSizeTooBig,
     InvalidContinuationToken,
     TooManyKeysInFilter { limit: usize, requested: usize },
+    TooManyAddressesInFilter { limit: usize, requested: usize },
 }
 
 impl From<anyhow::Error> for GetEventsError {
@@ -35,11 +37,15 @@ impl From<GetEventsError> for crate::error::ApplicationError {
             GetEventsError::Internal(internal) => Self::Internal(internal),
             GetEventsError::Custom(internal) => Self::Custom(internal),
             GetEventsError::BlockNotFound => Self::BlockNotFound,
+            GetEventsError::BlockPruned(oldest_available) => Self::BlockPruned(oldest_available),
             GetEventsError::PageSizeTooBig => Self::PageSizeTooBig,
             GetEventsError::InvalidContinuationToken => Self::InvalidContinuationToken,
             GetEventsError::TooManyKeysInFilter { limit, requested } => {
                 Self::TooManyKeysInFilter { limit, requested }
             }
+            GetEventsError::TooManyAddressesInFilter { limit, requested } => {
+                Self::TooManyAddressesInEventFilter { limit, requested }
+            }
         }
     }
 }
@@ -65,6 +71,9 @@ pub struct EventFilter {
     pub from_block: Option<BlockId>,
     pub to_block: Option<BlockId>,
     pub address: Option<ContractAddress>,
+    /// Pathfinder extension: events emitted by any of these contracts match,
+    /// in addition to those emitted by `address`.
+    pub addresses: Vec<ContractAddress>,
     pub keys: Vec<Vec<EventKey>>,
     pub chunk_size: usize,
     /// Offset, measured in events, which points to the requested chunk
@@ -78,6 +87,11 @@ impl crate::dto::DeserializeForVersion for EventFilter {
                 from_block: value.deserialize_optional("from_block")?,
                 to_block: value.deserialize_optional("to_block")?,
                 address: value.deserialize_optional("address")?.map(ContractAddress),
+                addresses: value
+                    .deserialize_optional_array("addresses", |value| {
+                        value.deserialize().map(ContractAddress)
+                    })?
+                    .unwrap_or_default(),
                 keys: value
                     .deserialize_optional_array("keys", |value| {
                         value.deserialize_array(|value| value.deserialize().map(EventKey))
@@ -90,6 +104,21 @@ impl crate::dto::DeserializeForVersion for EventFilter {
     }
 }
 
+impl EventFilter {
+    /// The contracts whose events match the filter. Empty if events from all
+    /// contracts match.
+    pub fn contract_addresses(&self) -> Vec<ContractAddress> {
+        let mut addresses: Vec<_> = self
+            .address
+            .into_iter()
+            .chain(self.addresses.iter().copied())
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        addresses
+    }
+}
+
 /// Returns events matching the specified filter
 pub async fn get_events(
     context: RpcContext,
@@ -134,6 +163,13 @@ pub async fn get_events(
             requested: request.keys.len(),
         });
     }
+    let requested_addresses = request.contract_addresses().len();
+    if requested_addresses > EVENT_ADDRESS_FILTER_LIMIT {
+        return Err(GetEventsError::TooManyAddressesInFilter {
+            limit: EVENT_ADDRESS_FILTER_LIMIT,
+            requested: requested_addresses,
+        });
+    }
     if request.chunk_size > EVENT_PAGE_SIZE_LIMIT {
         return Err(GetEventsError::PageSizeTooBig);
     }
@@ -193,20 +229,12 @@ pub async fn get_events(
         let from_block = map_from_block_to_number(&transaction, request.from_block)?;
         let to_block = map_to_block_to_number(&transaction, request.to_block)?;
 
-        if let Some(from_block) = from_block {
-            let pruned = transaction
-                .block_pruned(from_block.into())
-                .context("Querying block pruned status")?;
-            if pruned {
-                return Err(GetEventsError::BlockNotFound);
-            }
-        }
-        if let Some(to_block) = from_block {
-            let pruned = transaction
-                .block_pruned(to_block.into())
-                .context("Querying block pruned status")?;
-            if pruned {
-                return Err(GetEventsError::BlockNotFound);
+        for block in [from_block, to_block].into_iter().flatten() {
+            if let Some(oldest_available) = transaction
+                .block_pruned(block.into())
+                .context("Querying block pruned status")?
+            {
+                return Err(GetEventsError::BlockPruned(oldest_available));
             }
         }
 
@@ -220,7 +248,7 @@ pub async fn get_events(
         let constraints = pathfinder_storage::EventConstraints {
             from_block,
             to_block,
-            contract_address: request.address,
+            contract_addresses: request.contract_addresses(),
             keys: keys.clone(),
             page_size: request.chunk_size,
             offset: requested_offset,
@@ -265,6 +293,7 @@ pub async fn get_events(
                     None => 0,
                 };
 
+                let contract_addresses = request.contract_addresses();
                 let keys: Vec<std::collections::HashSet<_>> = request
                     .keys
                     .into_iter()
@@ -276,7 +305,7 @@ pub async fn get_events(
                     &mut events.events,
                     current_offset,
                     amount,
-                    request.address,
+                    &contract_addresses,
                     keys,
                 );
 
@@ -335,7 +364,7 @@ fn get_pending_events(
         &mut events,
         current_offset,
         request.chunk_size,
-        request.address,
+        &request.contract_addresses(),
         keys,
     );
 
@@ -424,7 +453,7 @@ fn append_pending_events(
     dst: &mut Vec<EmittedEvent>,
     skip: usize,
     amount: usize,
-    address: Option<ContractAddress>,
+    addresses: &[ContractAddress],
     keys: Vec<std::collections::HashSet<EventKey>>,
 ) -> bool {
     let original_len = dst.len();
@@ -439,10 +468,7 @@ fn append_pending_events(
                 .iter()
                 .zip(std::iter::repeat(receipt.transaction_hash))
         })
-        .filter(|(event, _)| match address {
-            Some(address) => event.from_address == address,
-            None => true,
-        })
+        .filter(|(event, _)| addresses.is_empty() || addresses.contains(&event.from_address))
         .filter(|(event, _)| {
             if key_filter_is_empty {
                 return true;
@@ -648,6 +674,7 @@ mod tests {
                 from_block: Some(BlockId::Number(BlockNumber::new_or_panic(0))),
                 to_block: Some(BlockId::Latest),
                 address: Some(contract_address!("0x1")),
+                addresses: vec![],
                 keys: vec![vec![event_key!("0x2")], vec![]],
                 chunk_size: 3,
                 continuation_token: Some("4".to_string()),
@@ -751,6 +778,7 @@ mod tests {
                 from_block: Some(expected_event.block_number.unwrap().into()),
                 to_block: Some(expected_event.block_number.unwrap().into()),
                 address: Some(expected_event.from_address),
+                addresses: vec![],
                 // we're using a key which is present in _all_ events
                 keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
                 chunk_size: test_utils::NUM_EVENTS,
@@ -761,6 +789,29 @@ mod tests {
         assert_eq!(result, expected_result);
     }
 
+    #[tokio::test]
+    async fn get_events_from_several_addresses() {
+        let (context, events) = setup();
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                address: Some(events[1].from_address),
+                addresses: vec![events[20].from_address, events[33].from_address],
+                chunk_size: test_utils::NUM_EVENTS,
+                ..Default::default()
+            },
+        };
+        let result = get_events(context, input).await.unwrap();
+
+        assert_eq!(
+            result,
+            GetEventsResult {
+                events: vec![events[1].clone(), events[20].clone(), events[33].clone()],
+                continuation_token: None,
+            }
+        );
+    }
+
     #[tokio::test]
     async fn get_events_by_block() {
         let (context, events) = setup();
@@ -861,6 +912,34 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn get_events_with_too_many_addresses_in_filter() {
+        let (context, _) = setup();
+
+        let limit = EVENT_ADDRESS_FILTER_LIMIT;
+
+        let addresses = (0..=limit as u64)
+            .map(|i| ContractAddress(pathfinder_crypto::Felt::from_u64(i)))
+            .collect::<Vec<_>>();
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                addresses,
+                chunk_size: 10,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context, input).await.unwrap_err();
+
+        assert_eq!(
+            GetEventsError::TooManyAddressesInFilter {
+                limit,
+                requested: limit + 1
+            },
+            error
+        );
+    }
+
     #[tokio::test]
     async fn get_events_by_key_with_paging() {
         let (context, events) = setup();
@@ -1065,6 +1144,7 @@ mod tests {
                     from_block: None,
                     to_block: Some(BlockId::Pending),
                     address: None,
+                    addresses: vec![],
                     keys: vec![vec![
                         event_key_bytes!(b"event 0 key"),
                         event_key_bytes!(b"pending key 2"),
@@ -1096,6 +1176,7 @@ mod tests {
                     from_block: Some(BlockId::Pending),
                     to_block: Some(BlockId::Pending),
                     address: None,
+                    addresses: vec![],
                     keys: vec![],
                     chunk_size: 1024,
                     continuation_token: None,
@@ -23,7 +23,11 @@ impl crate::dto::DeserializeForVersion for Input {
 #[derive(Debug)]
 pub struct Output(ContractNonce);
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber),
+    ContractNotFound
+);
 
 pub async fn get_nonce(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
@@ -52,11 +56,11 @@ pub async fn get_nonce(context: RpcContext, input: Input) -> Result<Output, Erro
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         // Check that block exists. This should occur first as the block number
@@ -20,7 +20,10 @@ impl crate::dto::DeserializeForVersion for Input {
     }
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber)
+);
 
 #[derive(PartialEq, Debug)]
 pub enum Output {
@@ -63,11 +66,11 @@ pub async fn get_state_update(context: RpcContext, input: Input) -> Result<Outpu
             .try_into()
             .expect("Only pending cast should fail");
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         let state_update = tx
@@ -25,7 +25,11 @@ impl crate::dto::DeserializeForVersion for Input {
 #[derive(Debug)]
 pub struct Output(StorageValue);
 
-crate::error::generate_rpc_error_subset!(Error: ContractNotFound, BlockNotFound);
+crate::error::generate_rpc_error_subset!(
+    Error: ContractNotFound,
+    BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber)
+);
 
 /// Get the value of the storage at the given address and key.
 pub async fn get_storage_at(context: RpcContext, input: Input) -> Result<Output, Error> {
@@ -56,11 +60,11 @@ pub async fn get_storage_at(context: RpcContext, input: Input) -> Result<Output,
             other => other.try_into().expect("Only pending cast should fail"),
         };
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         // Check for block existence.
@@ -275,6 +279,52 @@ mod tests {
         assert_eq!(result.0, storage_value_bytes!(b"storage value 1"));
     }
 
+    #[tokio::test]
+    async fn pruned_block() {
+        let storage =
+            pathfinder_storage::StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
+                pathfinder_storage::pruning::BlockchainHistoryMode::Prune { num_blocks_kept: 1 },
+                std::num::NonZeroU32::new(1).unwrap(),
+            )
+            .unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        for number in 0..4 {
+            let header = pathfinder_common::BlockHeader::builder()
+                .number(BlockNumber::new_or_panic(number))
+                .finalize_with_hash(pathfinder_common::BlockHash(
+                    pathfinder_crypto::Felt::from_u64(number),
+                ));
+            tx.insert_block_header(&header).unwrap();
+        }
+        tx.commit().unwrap();
+        drop(db);
+        let ctx = RpcContext::for_tests().with_storage(storage);
+
+        let input = |block_id| Input {
+            contract_address: contract_address_bytes!(b"contract 1"),
+            key: storage_address_bytes!(b"storage addr 0"),
+            block_id,
+        };
+
+        let result = get_storage_at(
+            ctx.clone(),
+            input(BlockId::Number(BlockNumber::GENESIS + 1)),
+        )
+        .await;
+        assert_matches!(result, Err(Error::BlockPruned(oldest)) if oldest == BlockNumber::GENESIS + 2);
+
+        // The oldest available block and blocks which don't exist yet are not pruned.
+        let result = get_storage_at(
+            ctx.clone(),
+            input(BlockId::Number(BlockNumber::GENESIS + 2)),
+        )
+        .await;
+        assert_matches!(result, Err(Error::ContractNotFound));
+        let result = get_storage_at(ctx, input(BlockId::Number(BlockNumber::GENESIS + 10))).await;
+        assert_matches!(result, Err(Error::BlockNotFound));
+    }
+
     #[tokio::test]
     async fn unknown_contract() {
         let ctx = RpcContext::for_tests_with_pending().await;
@@ -35,6 +35,7 @@ impl DeserializeForVersion for ContractStorageKeys {
 pub enum Error {
     Internal(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     ProofLimitExceeded { limit: u32, requested: u32 },
     StorageProofNotSupported,
     ProofMissing,
@@ -67,6 +68,7 @@ impl From<Error> for crate::error::ApplicationError {
                 Self::ProofLimitExceeded { limit, requested }
             }
             Error::BlockNotFound => Self::BlockNotFound,
+            Error::BlockPruned(oldest_available) => Self::BlockPruned(oldest_available),
             Error::Internal(internal) => Self::Internal(internal),
             Error::StorageProofNotSupported => Self::StorageProofNotSupported,
             Error::ProofMissing => Self::ProofMissing,
@@ -296,11 +298,11 @@ pub async fn get_storage_proof(context: RpcContext, input: Input) -> Result<Outp
 
         let tx = db.transaction().context("Creating database transaction")?;
 
-        let pruned = tx
+        if let Some(oldest_available) = tx
             .block_pruned(block_id)
-            .context("Querying block pruned status")?;
-        if pruned {
-            return Err(Error::BlockNotFound);
+            .context("Querying block pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_available));
         }
 
         // Use internal error to indicate that the process of querying for a particular
@@ -311,6 +313,13 @@ pub async fn get_storage_proof(context: RpcContext, input: Input) -> Result<Outp
             .context("Fetching block header")?
             .ok_or(Error::BlockNotFound)?;
 
+        if let Some(oldest_trie_block) = tx
+            .trie_pruned(header.number)
+            .context("Querying trie pruned status")?
+        {
+            return Err(Error::BlockPruned(oldest_trie_block));
+        }
+
         let (class_root_hash, classes_proof) =
             get_class_proofs(&tx, header.number, &input.class_hashes)?;
         let (storage_root_hash, contract_proof_nodes, contract_leaves_data) =
@@ -815,6 +824,51 @@ mod tests {
         assert!(matches!(output, Err(Error::BlockNotFound)));
     }
 
+    #[tokio::test]
+    async fn trie_pruned() {
+        // Contract updates are applied to the tries using additional connections.
+        let storage =
+            pathfinder_storage::StorageBuilder::in_tempdir_with_trie_pruning_and_pool_size(
+                pathfinder_storage::TriePruneMode::Prune { num_blocks_kept: 1 },
+                NonZeroU32::new(32).unwrap(),
+            )
+            .unwrap();
+        let blocks = pathfinder_storage::fake::generate::with_config(
+            4,
+            Config {
+                update_tries: Box::new(update_starknet_state),
+                occurrence: OccurrencePerBlock {
+                    sierra: 1..=10,
+                    storage: 1..=10,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        pathfinder_storage::fake::fill(&storage, &blocks, Some(Box::new(update_starknet_state)));
+
+        let context = RpcContext::for_tests().with_storage(storage);
+        let input = |block_id| Input {
+            block_id,
+            class_hashes: Some(vec![class_hash!("0x1")]),
+            contract_addresses: Some(vec![contract_address!("0x2")]),
+            contracts_storage_keys: None,
+        };
+
+        let output = get_storage_proof(
+            context.clone(),
+            input(BlockId::Number(BlockNumber::GENESIS + 1)),
+        )
+        .await;
+        assert!(
+            matches!(output, Err(Error::BlockPruned(oldest)) if oldest == BlockNumber::GENESIS + 2)
+        );
+
+        get_storage_proof(context, input(BlockId::Number(BlockNumber::GENESIS + 2)))
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn chain_without_declarations_and_contract_updates() {
         let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
@@ -26,6 +26,7 @@ pub struct Output(Transaction);
 
 crate::error::generate_rpc_error_subset!(
     GetTransactionByBlockIdAndIndexError: BlockNotFound,
+    BlockPruned(pathfinder_common::BlockNumber),
     InvalidTxnIndex
 );
 
@@ -72,11 +73,13 @@ pub async fn get_transaction_by_block_id_and_index(
         {
             Some(transaction) => Ok(Output(transaction)),
             None => {
-                let pruned = db_tx
+                if let Some(oldest_available) = db_tx
                     .block_pruned(block_id)
-                    .context("Querying block pruned status")?;
-                if pruned {
-                    return Err(GetTransactionByBlockIdAndIndexError::BlockNotFound);
+                    .context("Querying block pruned status")?
+                {
+                    return Err(GetTransactionByBlockIdAndIndexError::BlockPruned(
+                        oldest_available,
+                    ));
                 }
 
                 // We now need to check whether it was the block hash or transaction index which
@@ -136,7 +136,7 @@ mod tests {
         };
         let output = get_transaction_by_hash(context, input).await.unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(output_json, version, "transactions/txn_1.json");
     }
@@ -154,7 +154,7 @@ mod tests {
         };
         let output = get_transaction_by_hash(context, input).await.unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
@@ -177,7 +177,7 @@ mod tests {
             .await
             .unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(output_json, version, "transactions/txn_reverted.json");
 
@@ -186,7 +186,7 @@ mod tests {
         };
         let output = get_transaction_by_hash(context, input).await.unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
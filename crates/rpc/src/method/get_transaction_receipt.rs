@@ -163,7 +163,7 @@ mod tests {
         };
         let output = get_transaction_receipt(context, input).await.unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
@@ -185,7 +185,7 @@ mod tests {
         };
         let output = get_transaction_receipt(context, input).await.unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
@@ -208,7 +208,7 @@ mod tests {
             .await
             .unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
@@ -221,7 +221,7 @@ mod tests {
         };
         let output = get_transaction_receipt(context, input).await.unwrap();
 
-        let output_json = output.serialize(Serializer { version }).unwrap();
+        let output_json = output.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
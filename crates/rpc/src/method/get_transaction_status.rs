@@ -230,7 +230,7 @@ mod tests {
         };
         let status = get_transaction_status(context, input).await.unwrap();
 
-        let output_json = status.serialize(Serializer { version }).unwrap();
+        let output_json = status.serialize(Serializer::new(version)).unwrap();
 
         let expected_status = include_str!("../../fixtures/status/l2_accepted.json");
         let expected_json: serde_json::Value =
@@ -252,7 +252,7 @@ mod tests {
         };
         let status = get_transaction_status(context, input).await.unwrap();
 
-        let output_json = status.serialize(Serializer { version }).unwrap();
+        let output_json = status.serialize(Serializer::new(version)).unwrap();
 
         let expected_status = include_str!("../../fixtures/status/pending.json");
         let expected_json: serde_json::Value =
@@ -310,7 +310,7 @@ mod tests {
             .await
             .unwrap();
 
-        let output_json = status.serialize(Serializer { version }).unwrap();
+        let output_json = status.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
@@ -323,7 +323,7 @@ mod tests {
         };
         let status = get_transaction_status(context, input).await.unwrap();
 
-        let output_json = status.serialize(Serializer { version }).unwrap();
+        let output_json = status.serialize(Serializer::new(version)).unwrap();
 
         crate::assert_json_matches_fixture!(
             output_json,
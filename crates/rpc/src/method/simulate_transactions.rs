@@ -1,20 +1,23 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 use pathfinder_executor::TransactionExecutionError;
 
 use crate::context::RpcContext;
 use crate::executor::ExecutionStateError;
-use crate::types::request::BroadcastedTransaction;
+use crate::types::request::{BroadcastedTransaction, StateOverride};
+use crate::RpcVersion;
 
 #[derive(Debug)]
 pub struct SimulateTransactionInput {
     pub block_id: BlockId,
     pub transactions: Vec<BroadcastedTransaction>,
     pub simulation_flags: crate::dto::SimulationFlags,
+    pub state_override: StateOverride,
 }
 
 impl crate::dto::DeserializeForVersion for SimulateTransactionInput {
     fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        let version = value.version;
         value.deserialize_map(|value| {
             Ok(Self {
                 block_id: value.deserialize("block_id")?,
@@ -22,6 +25,13 @@ impl crate::dto::DeserializeForVersion for SimulateTransactionInput {
                     BroadcastedTransaction::deserialize(value)
                 })?,
                 simulation_flags: value.deserialize("simulation_flags")?,
+                state_override: if version >= RpcVersion::V08 {
+                    value
+                        .deserialize_optional("state_override")?
+                        .unwrap_or_default()
+                } else {
+                    Default::default()
+                },
             })
         })
     }
@@ -34,6 +44,7 @@ pub async fn simulate_transactions(
     input: SimulateTransactionInput,
 ) -> Result<Output, SimulateTransactionError> {
     let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
     util::task::spawn_blocking(move |_| {
         let _g = span.enter();
 
@@ -67,11 +78,11 @@ pub async fn simulate_transactions(
             other => {
                 let block_id = other.try_into().expect("Only pending should fail");
 
-                let pruned = db
+                if let Some(oldest_available) = db
                     .block_pruned(block_id)
-                    .context("Querying block pruned status")?;
-                if pruned {
-                    return Err(SimulateTransactionError::BlockNotFound);
+                    .context("Querying block pruned status")?
+                {
+                    return Err(SimulateTransactionError::BlockPruned(oldest_available));
                 }
 
                 let header = db
@@ -93,7 +104,9 @@ pub async fn simulate_transactions(
             context.contract_addresses.eth_l2_token_address,
             context.contract_addresses.strk_l2_token_address,
             context.native_class_cache,
-        );
+        )
+        .with_state_overrides(input.state_override.0)
+        .with_cancellation(cancellation);
 
         let transactions = input
             .transactions
@@ -128,7 +141,9 @@ impl crate::dto::SerializeForVersion for Output {
     }
 }
 
-struct TransactionSimulation<'a>(&'a pathfinder_executor::types::TransactionSimulation);
+pub(crate) struct TransactionSimulation<'a>(
+    pub &'a pathfinder_executor::types::TransactionSimulation,
+);
 
 impl crate::dto::SerializeForVersion for TransactionSimulation<'_> {
     fn serialize(
@@ -153,6 +168,7 @@ pub enum SimulateTransactionError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     TransactionExecutionError {
         transaction_index: usize,
         error: String,
@@ -172,6 +188,9 @@ impl From<SimulateTransactionError> for crate::error::ApplicationError {
             SimulateTransactionError::Internal(internal) => Self::Internal(internal),
             SimulateTransactionError::Custom(internal) => Self::Custom(internal),
             SimulateTransactionError::BlockNotFound => Self::BlockNotFound,
+            SimulateTransactionError::BlockPruned(oldest_available) => {
+                Self::BlockPruned(oldest_available)
+            }
             SimulateTransactionError::TransactionExecutionError {
                 transaction_index,
                 error,
@@ -422,19 +441,70 @@ pub(crate) mod tests {
                     },
                 ),
             }
-        ]).serialize(Serializer {
-            version: RpcVersion::V07,
-        }).unwrap();
+        ]).serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
         let result = simulate_transactions(context, input).await.expect("result");
-        let result = result
-            .serialize(Serializer {
-                version: RpcVersion::V07,
-            })
-            .unwrap();
+        let result = result.serialize(Serializer::new(RpcVersion::V07)).unwrap();
         pretty_assertions_sorted::assert_eq!(result, expected);
     }
 
+    #[tokio::test]
+    async fn erc20_balance_override() {
+        let (context, _, account_contract_address, _) = crate::test_setup::test_context().await;
+
+        let input_json = serde_json::json!({
+            "block_id": {"block_number": 1},
+            "transactions": [
+                {
+                    "type": "INVOKE",
+                    "version": "0x1",
+                    "max_fee": "0x0",
+                    "signature": [],
+                    "nonce": "0x0",
+                    "sender_address": account_contract_address,
+                    "calldata": [
+                        "0x1",
+                        crate::context::STRK_FEE_TOKEN_ADDRESS,
+                        EntryPoint::hashed(b"balanceOf"),
+                        "0x1",
+                        account_contract_address
+                    ]
+                }
+            ],
+            "simulation_flags": ["SKIP_FEE_CHARGE"],
+            "state_override": [
+                {
+                    "contract_address": account_contract_address,
+                    "strk_balance": "0x100000000000000000000000000000007"
+                }
+            ]
+        });
+
+        let value = crate::dto::Value::new(input_json, RpcVersion::V08);
+        let input = SimulateTransactionInput::deserialize(value).unwrap();
+
+        let result = simulate_transactions(context, input).await.unwrap();
+        let pathfinder_executor::types::TransactionTrace::Invoke(trace) = &result.0[0].trace else {
+            panic!("Expected an invoke trace");
+        };
+        let pathfinder_executor::types::ExecuteInvocation::FunctionInvocation(Some(execute)) =
+            &trace.execute_invocation
+        else {
+            panic!("Expected a successful execution");
+        };
+
+        // The low and high parts of the overridden balance.
+        assert_eq!(
+            execute.internal_calls[0].result,
+            vec![felt!("0x7"), felt!("0x1")]
+        );
+        // Overrides are not part of the resulting state diff.
+        assert!(!trace
+            .state_diff
+            .storage_diffs
+            .contains_key(&crate::context::STRK_FEE_TOKEN_ADDRESS));
+    }
+
     #[tokio::test]
     async fn declare_cairo_v0_class() {
         pub const CAIRO0_DEFINITION: &[u8] =
@@ -469,6 +539,7 @@ pub(crate) mod tests {
             block_id: last_block_header.number.into(),
             transactions: vec![declare],
             simulation_flags: crate::dto::SimulationFlags(vec![]),
+            state_override: Default::default(),
         };
 
         const OVERALL_FEE: u64 = 15720;
@@ -589,18 +660,12 @@ pub(crate) mod tests {
                     unit: pathfinder_executor::types::PriceUnit::Wei,
                 }
             }
-        ]).serialize(Serializer {
-            version: RpcVersion::V07,
-        }).unwrap();
+        ]).serialize(Serializer::new(RpcVersion::V07)).unwrap();
 
         let result = simulate_transactions(context, input).await.unwrap();
 
         pretty_assertions_sorted::assert_eq!(
-            result
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
-                .unwrap(),
+            result.serialize(Serializer::new(RpcVersion::V07)).unwrap(),
             expected
         );
     }
@@ -1606,10 +1671,11 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: crate::dto::SimulationFlags(vec![]),
+            state_override: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
-        let serializer = crate::dto::Serializer { version };
+        let serializer = crate::dto::Serializer::new(version);
         let result_serializable = result.0.into_iter().collect::<Vec<_>>();
         let result_serialized = serializer
             .serialize_iter(
@@ -1650,10 +1716,11 @@ pub(crate) mod tests {
             simulation_flags: crate::dto::SimulationFlags(vec![
                 crate::dto::SimulationFlag::SkipFeeCharge,
             ]),
+            state_override: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
-        let serializer = crate::dto::Serializer { version };
+        let serializer = crate::dto::Serializer::new(version);
         let result_serializable = result.0.into_iter().collect::<Vec<_>>();
         let result_serialized = serializer
             .serialize_iter(
@@ -1694,11 +1761,12 @@ pub(crate) mod tests {
             simulation_flags: crate::dto::SimulationFlags(vec![
                 crate::dto::SimulationFlag::SkipValidate,
             ]),
+            state_override: Default::default(),
         };
 
         let result = simulate_transactions(context, input).await.unwrap();
 
-        let serializer = crate::dto::Serializer { version };
+        let serializer = crate::dto::Serializer::new(version);
         let result_serializable = result.0.into_iter().collect::<Vec<_>>();
         let result_serialized = serializer
             .serialize_iter(
@@ -1731,12 +1799,11 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: crate::dto::SimulationFlags(vec![]),
+            state_override: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
-        let serializer = crate::dto::Serializer {
-            version: RpcVersion::V08,
-        };
+        let serializer = crate::dto::Serializer::new(RpcVersion::V08);
         let result_serializable = result.0.into_iter().collect::<Vec<_>>();
         let result_serialized = serializer
             .serialize_iter(
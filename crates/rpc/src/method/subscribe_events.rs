@@ -2,14 +2,18 @@ use std::collections::HashSet;
 use std::sync::Arc;
 
 use axum::async_trait;
-use pathfinder_common::{BlockNumber, ContractAddress, EventKey};
-use pathfinder_storage::{AGGREGATE_BLOOM_BLOCK_RANGE_LEN, EVENT_KEY_FILTER_LIMIT};
-use tokio::sync::mpsc;
+use pathfinder_common::{BlockHash, BlockNumber, ContractAddress, EventKey};
+use pathfinder_storage::{
+    AGGREGATE_BLOOM_BLOCK_RANGE_LEN,
+    EVENT_ADDRESS_FILTER_LIMIT,
+    EVENT_KEY_FILTER_LIMIT,
+};
+use tokio::sync::{broadcast, mpsc};
 
 use super::REORG_SUBSCRIPTION_NAME;
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
-use crate::jsonrpc::{CatchUp, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::jsonrpc::{missed_reorgs, CatchUp, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
 use crate::method::get_events::EmittedEvent;
 use crate::types::request::SubscriptionBlockId;
 use crate::Reorg;
@@ -19,14 +23,54 @@ pub struct SubscribeEvents;
 #[derive(Debug, Clone, Default)]
 pub struct Params {
     from_address: Option<ContractAddress>,
+    /// Pathfinder extension: events emitted by any of these contracts match,
+    /// in addition to those emitted by `from_address`.
+    from_addresses: Vec<ContractAddress>,
     keys: Option<Vec<Vec<EventKey>>>,
     block_id: Option<SubscriptionBlockId>,
+    resume_from: Option<ResumeCursor>,
+}
+
+/// Position in the event stream to resume a subscription from, e.g. after a
+/// reconnect.
+///
+/// Like a `starknet_getEvents` continuation token, the cursor points at the
+/// `event_index`-th event in block `block_number` which matches the filter.
+/// Events before it have already been received and are not sent again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumeCursor {
+    block_number: BlockNumber,
+    /// The hash of the block the events were received from. If the block has
+    /// since been reorged away, the client is sent the reorgs it missed and
+    /// events are replayed from the block where its chain forked off.
+    block_hash: BlockHash,
+    event_index: usize,
+}
+
+impl From<ResumeCursor> for pathfinder_storage::ContinuationToken {
+    fn from(cursor: ResumeCursor) -> Self {
+        Self {
+            block_number: cursor.block_number,
+            offset: cursor.event_index,
+        }
+    }
 }
 
 impl Params {
+    /// The contracts whose events match. Empty if events from all contracts
+    /// match.
+    fn contract_addresses(&self) -> Vec<ContractAddress> {
+        self.from_address
+            .into_iter()
+            .chain(self.from_addresses.iter().copied())
+            .collect()
+    }
+
     fn matches(&self, event: &pathfinder_common::event::Event) -> bool {
-        if let Some(from_address) = self.from_address {
-            if event.from_address != from_address {
+        if self.from_address.is_some() || !self.from_addresses.is_empty() {
+            let matches_address = self.from_address == Some(event.from_address)
+                || self.from_addresses.contains(&event.from_address);
+            if !matches_address {
                 return false;
             }
         }
@@ -59,15 +103,41 @@ impl crate::dto::DeserializeForVersion for Option<Params> {
                 from_address: value
                     .deserialize_optional("from_address")?
                     .map(ContractAddress),
+                from_addresses: value
+                    .deserialize_optional_array("from_addresses", |value| {
+                        value.deserialize().map(ContractAddress)
+                    })?
+                    .unwrap_or_default(),
                 keys: value.deserialize_optional_array("keys", |value| {
                     value.deserialize_array(|value| Ok(EventKey(value.deserialize()?)))
                 })?,
                 block_id: value.deserialize_optional("block_id")?,
+                resume_from: value.deserialize_optional("resume_from")?,
             }))
         })
     }
 }
 
+impl crate::dto::DeserializeForVersion for ResumeCursor {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        value.deserialize_map(|value| {
+            let block_number = value.deserialize_serde::<u64>("block_number")?;
+            let event_index = value.deserialize_serde::<u64>("event_index")?;
+
+            Ok(Self {
+                block_number: BlockNumber::new(block_number)
+                    .ok_or_else(|| serde_json::Error::custom("Invalid block number"))?,
+                block_hash: BlockHash(value.deserialize("block_hash")?),
+                event_index: event_index
+                    .try_into()
+                    .map_err(|_| serde_json::Error::custom("Invalid event index"))?,
+            })
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum Notification {
     EmittedEvent(crate::method::get_events::EmittedEvent),
@@ -96,6 +166,11 @@ impl RpcSubscriptionFlow for SubscribeEvents {
 
     fn validate_params(params: &Self::Params) -> Result<(), RpcError> {
         if let Some(params) = params {
+            if params.block_id.is_some() && params.resume_from.is_some() {
+                return Err(RpcError::InvalidParams(
+                    "Only one of block_id and resume_from can be set".to_owned(),
+                ));
+            }
             if let Some(keys) = &params.keys {
                 if keys.len() > EVENT_KEY_FILTER_LIMIT {
                     return Err(RpcError::ApplicationError(
@@ -106,15 +181,36 @@ impl RpcSubscriptionFlow for SubscribeEvents {
                     ));
                 }
             }
+            let requested_addresses = params.contract_addresses().len();
+            if requested_addresses > EVENT_ADDRESS_FILTER_LIMIT {
+                return Err(RpcError::ApplicationError(
+                    ApplicationError::TooManyAddressesInEventFilter {
+                        limit: EVENT_ADDRESS_FILTER_LIMIT,
+                        requested: requested_addresses,
+                    },
+                ));
+            }
         }
         Ok(())
     }
 
     fn starting_block(params: &Self::Params) -> SubscriptionBlockId {
+        let Some(params) = params else {
+            return SubscriptionBlockId::Latest;
+        };
+
+        match params.resume_from {
+            // See `resume_point` for cursors of blocks which have been reorged away.
+            Some(cursor) => SubscriptionBlockId::Number(cursor.block_number),
+            None => params.block_id.unwrap_or(SubscriptionBlockId::Latest),
+        }
+    }
+
+    fn resume_point(params: &Self::Params) -> Option<(BlockNumber, BlockHash)> {
         params
-            .as_ref()
-            .and_then(|req| req.block_id)
-            .unwrap_or(SubscriptionBlockId::Latest)
+            .as_ref()?
+            .resume_from
+            .map(|cursor| (cursor.block_number, cursor.block_hash))
     }
 
     async fn catch_up(
@@ -124,20 +220,55 @@ impl RpcSubscriptionFlow for SubscribeEvents {
         to: BlockNumber,
     ) -> Result<CatchUp<Self::Notification>, RpcError> {
         let params = params.clone().unwrap_or_default();
+        let resume_from = params
+            .resume_from
+            .filter(|cursor| (from..=to).contains(&cursor.block_number))
+            .map(|cursor| {
+                (
+                    cursor.block_hash,
+                    pathfinder_storage::ContinuationToken::from(cursor),
+                )
+            });
         let storage = state.storage.clone();
         let (events, last_block) = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
             let mut conn = storage.connection().map_err(RpcError::InternalError)?;
             let db = conn.transaction().map_err(RpcError::InternalError)?;
-            let events = db
+
+            // None of the events of a block which replaced the cursor's one, e.g. in a
+            // reorg while catching up, have been received.
+            let skip_until = match resume_from {
+                Some((block_hash, token)) => {
+                    let current_hash = db
+                        .block_hash(token.block_number.into())
+                        .map_err(RpcError::InternalError)?;
+                    (current_hash == Some(block_hash)).then_some(token)
+                }
+                None => None,
+            };
+
+            let (mut events, last_block) = db
                 .events_in_range(
                     from,
                     to,
-                    params.from_address,
+                    params.contract_addresses(),
                     params.keys.unwrap_or_default(),
                 )
                 .map_err(RpcError::InternalError)?;
 
-            Ok(events)
+            // Skip the events which were received before the cursor.
+            if let Some(token) = skip_until {
+                let mut skip = token.offset;
+                events.retain(|event| {
+                    if skip > 0 && event.block_number == token.block_number {
+                        skip -= 1;
+                        false
+                    } else {
+                        true
+                    }
+                });
+            }
+
+            Ok((events, last_block))
         })
         .await
         .map_err(|e| RpcError::InternalError(e.into()))??;
@@ -158,6 +289,10 @@ impl RpcSubscriptionFlow for SubscribeEvents {
         })
     }
 
+    fn reorg_notification(reorg: Arc<Reorg>) -> Option<SubscriptionMessage<Self::Notification>> {
+        Some(reorg_message(reorg))
+    }
+
     async fn subscribe(
         state: RpcContext,
         params: Self::Params,
@@ -172,26 +307,21 @@ impl RpcSubscriptionFlow for SubscribeEvents {
         let mut current_block = BlockNumber::GENESIS;
         loop {
             tokio::select! {
+                // Reorgs are reported before the events of the blocks replacing the reorged
+                // ones.
+                biased;
                 reorg = reorgs.recv() => {
                     match reorg {
                         Ok(reorg) => {
-                            let block_number = reorg.first_block_number;
-                            if tx.send(SubscriptionMessage {
-                                notification: Notification::Reorg(reorg),
-                                block_number,
-                                subscription_name: REORG_SUBSCRIPTION_NAME,
-                            }).await.is_err() {
+                            if tx.send(reorg_message(reorg)).await.is_err() {
                                 break;
                             }
                         }
-                        Err(e) => {
-                            tracing::debug!(
-                                "Error receiving reorg from notifications channel, node might be \
-                                 lagging: {:?}",
-                                e
-                            );
-                            break;
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            tracing::debug!("Reorg notifications lagged, stopping subscription");
+                            return Err(missed_reorgs());
                         }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
                 }
                 block = blocks.recv() => {
@@ -285,6 +415,14 @@ impl RpcSubscriptionFlow for SubscribeEvents {
     }
 }
 
+fn reorg_message(reorg: Arc<Reorg>) -> SubscriptionMessage<Notification> {
+    SubscriptionMessage {
+        block_number: reorg.first_block_number,
+        notification: Notification::Reorg(reorg),
+        subscription_name: REORG_SUBSCRIPTION_NAME,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -297,7 +435,7 @@ mod tests {
     use pathfinder_common::receipt::Receipt;
     use pathfinder_common::transaction::{Transaction, TransactionVariant};
     use pathfinder_crypto::Felt;
-    use pathfinder_storage::StorageBuilder;
+    use pathfinder_storage::{StorageBuilder, EVENT_ADDRESS_FILTER_LIMIT};
     use starknet_gateway_types::reply::{Block, PendingBlock};
     use tokio::sync::mpsc;
 
@@ -434,6 +572,75 @@ mod tests {
         assert!(sender_rx.is_empty());
     }
 
+    #[tokio::test]
+    async fn filter_from_addresses() {
+        let (router, _pending_data_tx) = setup(SubscribeEvents::CATCH_UP_BATCH_SIZE + 10).await;
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        let params = serde_json::json!(
+            {
+                "block_id": {"block_number": 0},
+                "from_address": "0x3",
+                "from_addresses": ["0x46", "0x47"],
+            }
+        );
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "starknet_subscribeEvents",
+                    "params": params
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let res = sender_rx.recv().await.unwrap().unwrap();
+        let subscription_id = match res {
+            Message::Text(json) => {
+                let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+                assert_eq!(json["jsonrpc"], "2.0");
+                assert_eq!(json["id"], 1);
+                json["result"].as_u64().unwrap()
+            }
+            _ => panic!("Expected text message"),
+        };
+        for i in [0x3, 0x46, 0x47] {
+            let expected = sample_event_message(i, subscription_id);
+            let event = sender_rx.recv().await.unwrap().unwrap();
+            let json: serde_json::Value = match event {
+                Message::Text(json) => serde_json::from_str(&json).unwrap(),
+                _ => panic!("Expected text message"),
+            };
+            assert_eq!(json, expected);
+        }
+        retry(|| {
+            router
+                .context
+                .notifications
+                .l2_blocks
+                .send(sample_block(0x8f).into())
+        })
+        .await
+        .unwrap();
+        router
+            .context
+            .notifications
+            .l2_blocks
+            .send(sample_block(0x47).into())
+            .unwrap();
+        let expected = sample_event_message(0x47, subscription_id);
+        let event = sender_rx.recv().await.unwrap().unwrap();
+        let json: serde_json::Value = match event {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(json, expected);
+        assert!(sender_rx.is_empty());
+    }
+
     #[tokio::test]
     async fn filter_keys() {
         let (router, _pending_data_tx) = setup(SubscribeEvents::CATCH_UP_BATCH_SIZE + 10).await;
@@ -719,6 +926,58 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn too_many_addresses_filter() {
+        let (router, _pending_data_tx) = setup(SubscribeEvents::CATCH_UP_BATCH_SIZE + 10).await;
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        let from_addresses = (0..EVENT_ADDRESS_FILTER_LIMIT)
+            .map(|i| format!("{:#x}", i + 0x100))
+            .collect::<Vec<_>>();
+        let params = serde_json::json!(
+            {
+                "block_id": {"block_number": 0},
+                "from_address": "0x46",
+                "from_addresses": from_addresses,
+            }
+        );
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "starknet_subscribeEvents",
+                    "params": params
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let res = sender_rx.recv().await.unwrap().unwrap();
+        match res {
+            Message::Text(json) => {
+                let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+                assert_eq!(
+                    json,
+                    serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "error": {
+                            "code": -32602,
+                            "data": {
+                                "limit": EVENT_ADDRESS_FILTER_LIMIT,
+                                "requested": EVENT_ADDRESS_FILTER_LIMIT + 1
+                            },
+                            "message": "Too many contract addresses in event filter"
+                        }
+                    }),
+                );
+            }
+            _ => panic!("Expected text message"),
+        }
+    }
+
     #[test_log::test(tokio::test)]
     async fn reorg() {
         let (router, _pending_data_tx) = setup(1).await;
@@ -780,6 +1039,7 @@ mod tests {
                     first_block_hash: BlockHash(felt!("0x1")),
                     last_block_number: BlockNumber::new_or_panic(2),
                     last_block_hash: BlockHash(felt!("0x2")),
+                    new_head: Some((BlockNumber::GENESIS, BlockHash(felt!("0x0")))),
                 }
                 .into(),
             )
@@ -853,6 +1113,181 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn resume_from_cursor() {
+        let (router, _pending_data_tx) = setup(10).await;
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        // The only event of block 5 has already been received.
+        let params = serde_json::json!(
+            {"resume_from": {"block_number": 5, "block_hash": "0x5", "event_index": 1}}
+        );
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "starknet_subscribeEvents",
+                    "params": params
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let res = sender_rx.recv().await.unwrap().unwrap();
+        let subscription_id = match res {
+            Message::Text(json) => {
+                let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+                json["result"].as_u64().unwrap()
+            }
+            _ => panic!("Expected text message"),
+        };
+        for i in 6..10 {
+            let expected = sample_event_message(i, subscription_id);
+            let event = sender_rx.recv().await.unwrap().unwrap();
+            let json: serde_json::Value = match event {
+                Message::Text(json) => serde_json::from_str(&json).unwrap(),
+                _ => panic!("Expected text message"),
+            };
+            assert_eq!(json, expected);
+        }
+        retry(|| {
+            router
+                .context
+                .notifications
+                .l2_blocks
+                .send(sample_block(10).into())
+        })
+        .await
+        .unwrap();
+        let expected = sample_event_message(10, subscription_id);
+        let event = sender_rx.recv().await.unwrap().unwrap();
+        let json: serde_json::Value = match event {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(json, expected);
+        assert!(sender_rx.is_empty());
+    }
+
+    #[tokio::test]
+    async fn resume_from_reorged_block() {
+        let (router, _pending_data_tx) = setup(10).await;
+        // The client received blocks 4 and 5 of a chain which was reorged away while
+        // it was disconnected.
+        router.context.notifications.send_reorg(
+            Reorg {
+                first_block_number: BlockNumber::new_or_panic(4),
+                first_block_hash: BlockHash(felt!("0x444")),
+                last_block_number: BlockNumber::new_or_panic(5),
+                last_block_hash: BlockHash(felt!("0x555")),
+                new_head: Some((BlockNumber::new_or_panic(3), BlockHash(felt!("0x3")))),
+            },
+            vec![BlockHash(felt!("0x444")), BlockHash(felt!("0x555"))],
+        );
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        let params = serde_json::json!(
+            {"resume_from": {"block_number": 5, "block_hash": "0x555", "event_index": 1}}
+        );
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "starknet_subscribeEvents",
+                    "params": params
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let res = sender_rx.recv().await.unwrap().unwrap();
+        let subscription_id = match res {
+            Message::Text(json) => {
+                let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+                json["result"].as_u64().unwrap()
+            }
+            _ => panic!("Expected text message"),
+        };
+
+        let res = sender_rx.recv().await.unwrap().unwrap();
+        let json: serde_json::Value = match res {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "starknet_subscriptionReorg",
+                "params": {
+                    "result": {
+                        "first_block_hash": "0x444",
+                        "first_block_number": 4,
+                        "last_block_hash": "0x555",
+                        "last_block_number": 5
+                    },
+                    "subscription_id": subscription_id
+                }
+            })
+        );
+
+        // Events are replayed from the fork point, including all events of the
+        // block which replaced the cursor's one.
+        for i in 4..10 {
+            let expected = sample_event_message(i, subscription_id);
+            let event = sender_rx.recv().await.unwrap().unwrap();
+            let json: serde_json::Value = match event {
+                Message::Text(json) => serde_json::from_str(&json).unwrap(),
+                _ => panic!("Expected text message"),
+            };
+            assert_eq!(json, expected);
+        }
+        assert!(sender_rx.is_empty());
+    }
+
+    #[tokio::test]
+    async fn resume_from_unknown_block() {
+        let (router, _pending_data_tx) = setup(10).await;
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        // Not part of the chain, and not known to have been reorged away either.
+        let params = serde_json::json!(
+            {"resume_from": {"block_number": 5, "block_hash": "0x555", "event_index": 0}}
+        );
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "starknet_subscribeEvents",
+                    "params": params
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let res = sender_rx.recv().await.unwrap().unwrap();
+        let json: serde_json::Value = match res {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(json["error"]["code"], -32603);
+    }
+
+    #[test]
+    fn resume_cursor_requires_block_hash() {
+        let value = crate::dto::Value::new(
+            serde_json::json!({"block_number": 5, "event_index": 0}),
+            crate::RpcVersion::V08,
+        );
+        value.deserialize::<super::ResumeCursor>().unwrap_err();
+    }
+
     async fn setup(num_blocks: u64) -> (RpcRouter, tokio::sync::watch::Sender<crate::PendingData>) {
         assert!(num_blocks > 0);
 
@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use axum::async_trait;
 use pathfinder_common::BlockNumber;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 
 use super::REORG_SUBSCRIPTION_NAME;
 use crate::context::RpcContext;
-use crate::jsonrpc::{CatchUp, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::jsonrpc::{missed_reorgs, CatchUp, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
 use crate::types::request::SubscriptionBlockId;
 use crate::Reorg;
 
@@ -95,6 +95,10 @@ impl RpcSubscriptionFlow for SubscribeNewHeads {
         })
     }
 
+    fn reorg_notification(reorg: Arc<Reorg>) -> Option<SubscriptionMessage<Self::Notification>> {
+        Some(reorg_message(reorg))
+    }
+
     async fn subscribe(
         state: RpcContext,
         _params: Self::Params,
@@ -104,26 +108,20 @@ impl RpcSubscriptionFlow for SubscribeNewHeads {
         let mut reorgs = state.notifications.reorgs.subscribe();
         loop {
             tokio::select! {
+                // Reorgs are reported before the headers replacing the reorged blocks.
+                biased;
                 reorg = reorgs.recv() => {
                     match reorg {
                         Ok(reorg) => {
-                            let block_number = reorg.first_block_number;
-                            if tx.send(SubscriptionMessage {
-                                notification: Notification::Reorg(reorg),
-                                block_number,
-                                subscription_name: REORG_SUBSCRIPTION_NAME,
-                            }).await.is_err() {
+                            if tx.send(reorg_message(reorg)).await.is_err() {
                                 break;
                             }
                         }
-                        Err(e) => {
-                            tracing::debug!(
-                                "Error receiving reorg from notifications channel, node might be \
-                                 lagging: {:?}",
-                                e
-                            );
-                            break;
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            tracing::debug!("Reorg notifications lagged, stopping subscription");
+                            return Err(missed_reorgs());
                         }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
                 }
                 header = headers.recv() => {
@@ -154,6 +152,14 @@ impl RpcSubscriptionFlow for SubscribeNewHeads {
     }
 }
 
+fn reorg_message(reorg: Arc<Reorg>) -> SubscriptionMessage<Notification> {
+    SubscriptionMessage {
+        block_number: reorg.first_block_number,
+        notification: Notification::Reorg(reorg),
+        subscription_name: REORG_SUBSCRIPTION_NAME,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -199,6 +205,7 @@ mod tests {
                     first_block_hash: BlockHash(felt!("0x1")),
                     last_block_number: BlockNumber::new_or_panic(2),
                     last_block_hash: BlockHash(felt!("0x2")),
+                    new_head: Some((BlockNumber::GENESIS, BlockHash(felt!("0x0")))),
                 }
                 .into(),
             )
@@ -12,7 +12,7 @@ use tokio::time::MissedTickBehavior;
 
 use super::REORG_SUBSCRIPTION_NAME;
 use crate::context::RpcContext;
-use crate::jsonrpc::{RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::jsonrpc::{missed_reorgs, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
 use crate::Reorg;
 
 pub struct SubscribeTransactionStatus;
@@ -121,6 +121,14 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
     type Params = Params;
     type Notification = Notification;
 
+    fn reorg_notification(reorg: Arc<Reorg>) -> Option<SubscriptionMessage<Self::Notification>> {
+        Some(SubscriptionMessage {
+            block_number: reorg.first_block_number,
+            notification: Notification::Reorg(reorg),
+            subscription_name: REORG_SUBSCRIPTION_NAME,
+        })
+    }
+
     #[allow(clippy::collapsible_if)]
     async fn subscribe(
         state: RpcContext,
@@ -221,6 +229,32 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
             interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
             loop {
                 tokio::select! {
+                    // Reorgs are reported before the blocks replacing the reorged ones.
+                    biased;
+                    reorg = reorgs.recv() => {
+                        match reorg {
+                            Ok(reorg) => {
+                                let block_number = sender.last_block_number;
+                                if tx.send(SubscriptionMessage {
+                                    notification: Notification::Reorg(reorg),
+                                    block_number,
+                                    subscription_name: REORG_SUBSCRIPTION_NAME,
+                                }).await.is_err() {
+                                    // Subscription closing.
+                                    break;
+                                }
+                                continue 'reorg;
+                            }
+                            Err(broadcast::error::RecvError::Closed) => {
+                                tracing::debug!("Reorg channel closed, stopping subscription");
+                                break 'reorg;
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => {
+                                tracing::debug!("Reorg channel lagged, stopping subscription");
+                                return Err(missed_reorgs());
+                            }
+                        }
+                    }
                     _ = interval.tick() => {
                         match state.sequencer.transaction_status(params.transaction_hash).await {
                             Ok(status) => {
@@ -245,29 +279,6 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
                             }
                         }
                     }
-                    reorg = reorgs.recv() => {
-                        match reorg {
-                            Ok(reorg) => {
-                                let block_number = sender.last_block_number;
-                                if tx.send(SubscriptionMessage {
-                                    notification: Notification::Reorg(reorg),
-                                    block_number,
-                                    subscription_name: REORG_SUBSCRIPTION_NAME,
-                                }).await.is_err() {
-                                    // Subscription closing.
-                                    break;
-                                }
-                                continue 'reorg;
-                            }
-                            Err(broadcast::error::RecvError::Closed) => {
-                                tracing::debug!("Reorg channel closed, stopping subscription");
-                                break 'reorg;
-                            }
-                            Err(broadcast::error::RecvError::Lagged(_)) => {
-                                tracing::warn!("Reorg channel lagged");
-                            }
-                        }
-                    }
                     r = pending_data.changed() => {
                         if r.is_err() {
                             tracing::debug!("Pending data channel closed, stopping subscription");
@@ -858,6 +869,7 @@ mod tests {
                     first_block_hash: BlockHash(Felt::from_u64(4)),
                     last_block_number: BlockNumber::GENESIS + 5,
                     last_block_hash: BlockHash(Felt::from_u64(5)),
+                    new_head: Some((BlockNumber::GENESIS + 3, BlockHash(Felt::from_u64(3)))),
                 }),
                 TestEvent::Message(serde_json::json!({
                     "jsonrpc": "2.0",
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 use pathfinder_executor::types::InnerCallExecutionResources;
 use pathfinder_executor::TransactionExecutionError;
 use starknet_gateway_client::GatewayApi;
@@ -46,6 +46,7 @@ pub async fn trace_block_transactions(
     let span = tracing::Span::current();
 
     let storage = context.execution_storage.clone();
+    let cancellation = crate::executor::request_cancellation();
     let traces = util::task::spawn_blocking(move |_| {
         let _g = span.enter();
 
@@ -72,17 +73,24 @@ pub async fn trace_block_transactions(
             other => {
                 let block_id = other.try_into().expect("Only pending should fail");
 
-                let pruned = db
+                if let Some(oldest_available) = db
                     .block_pruned(block_id)
-                    .context("Querying block pruned status")?;
-                if pruned {
-                    return Err(TraceBlockTransactionsError::BlockNotFound);
+                    .context("Querying block pruned status")?
+                {
+                    return Err(TraceBlockTransactionsError::BlockPruned(oldest_available));
                 }
 
                 let header = db
                     .block_header(block_id)?
                     .ok_or(TraceBlockTransactionsError::BlockNotFound)?;
 
+                if let Some(traces) = context.cache.get(&db, header.number, header.hash) {
+                    return Ok(LocalExecution::Success(TraceBlockTransactionsOutput {
+                        traces,
+                        include_state_diffs: true,
+                    }));
+                }
+
                 let transactions = db
                     .transactions_for_block(block_id)?
                     .context("Transaction data missing")?
@@ -125,7 +133,8 @@ pub async fn trace_block_transactions(
             context.contract_addresses.eth_l2_token_address,
             context.contract_addresses.strk_l2_token_address,
             context.native_class_cache,
-        );
+        )
+        .with_cancellation(cancellation);
         let traces = match pathfinder_executor::trace(state, cache, hash, executor_transactions) {
             Ok(traces) => traces,
             Err(TransactionExecutionError::ExecutionError { .. }) => {
@@ -599,6 +608,7 @@ pub enum TraceBlockTransactionsError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
 }
 
 impl From<anyhow::Error> for TraceBlockTransactionsError {
@@ -612,6 +622,9 @@ impl From<TraceBlockTransactionsError> for crate::error::ApplicationError {
         match value {
             TraceBlockTransactionsError::Internal(e) => Self::Internal(e),
             TraceBlockTransactionsError::BlockNotFound => Self::BlockNotFound,
+            TraceBlockTransactionsError::BlockPruned(oldest_available) => {
+                Self::BlockPruned(oldest_available)
+            }
             TraceBlockTransactionsError::Custom(e) => Self::Custom(e),
         }
     }
@@ -784,7 +797,7 @@ pub(crate) mod tests {
         let output = trace_block_transactions(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "traces/multiple_txs.json");
@@ -792,6 +805,81 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn traces_are_served_from_the_store() -> anyhow::Result<()> {
+        let (context, next_block_header, traces) = setup_multi_tx_trace_test().await?;
+        let context = context.with_trace_store(context.storage.clone());
+
+        // Store only the first trace, so that the output can't have been computed.
+        let stored = vec![(traces[0].transaction_hash, traces[0].trace_root.clone())];
+        {
+            let mut db = context.storage.connection()?;
+            let tx = db.transaction()?;
+            tx.insert_block_traces(
+                next_block_header.number,
+                next_block_header.hash,
+                pathfinder_executor::STORED_TRACES_VERSION,
+                &stored,
+            )?;
+            tx.commit()?;
+        }
+
+        let input = TraceBlockTransactionsInput {
+            block_id: next_block_header.hash.into(),
+        };
+        let output = trace_block_transactions(context, input).await.unwrap();
+
+        let expected = TraceBlockTransactionsOutput {
+            traces: stored,
+            include_state_diffs: true,
+        };
+        pretty_assertions_sorted::assert_eq!(
+            output.serialize(Serializer::new(RpcVersion::V07)).unwrap(),
+            expected
+                .serialize(Serializer::new(RpcVersion::V07))
+                .unwrap(),
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stored_traces_are_ignored_without_trace_store() -> anyhow::Result<()> {
+        let (context, next_block_header, traces) = setup_multi_tx_trace_test().await?;
+
+        let stored = vec![(traces[0].transaction_hash, traces[0].trace_root.clone())];
+        {
+            let mut db = context.storage.connection()?;
+            let tx = db.transaction()?;
+            tx.insert_block_traces(
+                next_block_header.number,
+                next_block_header.hash,
+                pathfinder_executor::STORED_TRACES_VERSION,
+                &stored,
+            )?;
+            tx.commit()?;
+        }
+
+        let input = TraceBlockTransactionsInput {
+            block_id: next_block_header.hash.into(),
+        };
+        let output = trace_block_transactions(context, input).await.unwrap();
+
+        let expected = TraceBlockTransactionsOutput {
+            traces: traces
+                .iter()
+                .map(|t| (t.transaction_hash, t.trace_root.clone()))
+                .collect(),
+            include_state_diffs: true,
+        };
+        pretty_assertions_sorted::assert_eq!(
+            output.serialize(Serializer::new(RpcVersion::V07)).unwrap(),
+            expected
+                .serialize(Serializer::new(RpcVersion::V07))
+                .unwrap(),
+        );
+        Ok(())
+    }
+
     /// Test that multiple requests for the same block return correctly. This
     /// checks that the trace request coalescing doesn't do anything
     /// unexpected.
@@ -815,9 +903,7 @@ pub(crate) mod tests {
             outputs.push(
                 output
                     .unwrap()
-                    .serialize(Serializer {
-                        version: RpcVersion::V07,
-                    })
+                    .serialize(Serializer::new(RpcVersion::V07))
                     .unwrap(),
             );
         }
@@ -831,9 +917,7 @@ pub(crate) mod tests {
                         .collect(),
                     include_state_diffs: true,
                 }
-                .serialize(Serializer {
-                    version: RpcVersion::V07,
-                })
+                .serialize(Serializer::new(RpcVersion::V07))
                 .unwrap(),
             );
         }
@@ -975,7 +1059,7 @@ pub(crate) mod tests {
         let output = trace_block_transactions(context, input)
             .await
             .unwrap()
-            .serialize(Serializer { version })
+            .serialize(Serializer::new(version))
             .unwrap();
 
         crate::assert_json_matches_fixture!(output, version, "traces/multiple_pending_txs.json");
@@ -51,6 +51,7 @@ pub async fn trace_transaction(
     }
 
     let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
     let local =
         util::task::spawn_blocking(move |_| -> Result<LocalExecution, TraceTransactionError> {
             let _g = span.enter();
@@ -97,6 +98,11 @@ pub async fn trace_transaction(
                     .context("Fetching block header")?
                     .context("Block header is missing")?;
 
+                if let Some(traces) = context.cache.get(&db, header.number, header.hash) {
+                    let trace = find_trace(traces, input.transaction_hash)?;
+                    return Ok(LocalExecution::Success(trace));
+                }
+
                 if header.starknet_version
                     < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
                 {
@@ -128,7 +134,8 @@ pub async fn trace_transaction(
                 context.contract_addresses.eth_l2_token_address,
                 context.contract_addresses.strk_l2_token_address,
                 context.native_class_cache,
-            );
+            )
+            .with_cancellation(cancellation);
 
             let executor_transactions = transactions
                 .iter()
@@ -137,21 +144,7 @@ pub async fn trace_transaction(
 
             match pathfinder_executor::trace(state, cache, hash, executor_transactions) {
                 Ok(txs) => {
-                    let trace = txs
-                        .into_iter()
-                        .find_map(|(tx_hash, trace)| {
-                            if tx_hash == input.transaction_hash {
-                                Some(trace)
-                            } else {
-                                None
-                            }
-                        })
-                        .ok_or_else(|| {
-                            TraceTransactionError::Internal(anyhow::anyhow!(
-                                "Transaction trace missing from block: {}",
-                                input.transaction_hash
-                            ))
-                        })?;
+                    let trace = find_trace(txs, input.transaction_hash)?;
                     Ok(LocalExecution::Success(trace))
                 }
                 Err(TransactionExecutionError::ExecutionError { .. }) => {
@@ -194,12 +187,32 @@ pub async fn trace_transaction(
     }))
 }
 
+/// Picks the trace of `transaction_hash` from the traces of its block.
+fn find_trace(
+    traces: Vec<(
+        TransactionHash,
+        pathfinder_executor::types::TransactionTrace,
+    )>,
+    transaction_hash: TransactionHash,
+) -> Result<pathfinder_executor::types::TransactionTrace, TraceTransactionError> {
+    traces
+        .into_iter()
+        .find_map(|(tx_hash, trace)| (tx_hash == transaction_hash).then_some(trace))
+        .ok_or_else(|| {
+            TraceTransactionError::Internal(anyhow::anyhow!(
+                "Transaction trace missing from block: {}",
+                transaction_hash
+            ))
+        })
+}
+
 #[derive(Debug)]
 pub enum TraceTransactionError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     TxnHashNotFound,
     NoTraceAvailable(TraceError),
+    BlockPruned(pathfinder_common::BlockNumber),
 }
 
 impl From<ExecutionStateError> for TraceTransactionError {
@@ -242,6 +255,7 @@ impl From<super::trace_block_transactions::TraceBlockTransactionsError> for Trac
         match e {
             Internal(e) => Self::Internal(e),
             BlockNotFound => Self::Custom(anyhow::anyhow!("Block not found")),
+            BlockPruned(oldest_available) => Self::BlockPruned(oldest_available),
             Custom(e) => Self::Custom(e),
         }
     }
@@ -254,6 +268,9 @@ impl From<TraceTransactionError> for ApplicationError {
             TraceTransactionError::NoTraceAvailable(status) => {
                 ApplicationError::NoTraceAvailable(status)
             }
+            TraceTransactionError::BlockPruned(oldest_available) => {
+                ApplicationError::BlockPruned(oldest_available)
+            }
             TraceTransactionError::Internal(e) => ApplicationError::Internal(e),
             TraceTransactionError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -285,15 +302,9 @@ pub mod tests {
                 include_state_diff: false,
             });
             pretty_assertions_sorted::assert_eq!(
-                output
-                    .serialize(Serializer {
-                        version: RpcVersion::V07
-                    })
-                    .unwrap(),
+                output.serialize(Serializer::new(RpcVersion::V07)).unwrap(),
                 expected
-                    .serialize(Serializer {
-                        version: RpcVersion::V07
-                    })
+                    .serialize(Serializer::new(RpcVersion::V07))
                     .unwrap()
             );
         }
@@ -315,15 +326,9 @@ pub mod tests {
                 include_state_diff: false,
             });
             pretty_assertions_sorted::assert_eq!(
-                output
-                    .serialize(Serializer {
-                        version: RpcVersion::V07
-                    })
-                    .unwrap(),
+                output.serialize(Serializer::new(RpcVersion::V07)).unwrap(),
                 expected
-                    .serialize(Serializer {
-                        version: RpcVersion::V07
-                    })
+                    .serialize(Serializer::new(RpcVersion::V07))
                     .unwrap()
             );
         }
@@ -1,3 +1,5 @@
+pub(crate) mod compression;
 pub mod cors;
+pub mod rate_limit;
 pub(crate) mod request_id;
 pub(crate) mod tracing;
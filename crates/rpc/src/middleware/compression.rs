@@ -0,0 +1,106 @@
+use tower_http::compression::CompressionLayer;
+use tower_http::decompression::RequestDecompressionLayer;
+
+/// Compresses responses with gzip, brotli or zstd, as negotiated with the
+/// client's `Accept-Encoding` header.
+pub(crate) fn compression() -> CompressionLayer {
+    CompressionLayer::new()
+}
+
+/// Decompresses request bodies according to their `Content-Encoding` header.
+pub(crate) fn decompression() -> RequestDecompressionLayer {
+    RequestDecompressionLayer::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE};
+    use serde_json::json;
+
+    use crate::context::RpcContext;
+    use crate::{RpcServer, RpcVersion};
+
+    async fn spawn(compression: bool) -> String {
+        let server = RpcServer::new(
+            "127.0.0.1:0".parse().unwrap(),
+            RpcContext::for_tests(),
+            RpcVersion::V07,
+        )
+        .with_compression(compression);
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        format!("http://{}/rpc/v0_7", addresses[0])
+    }
+
+    fn gzip(body: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(body).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // Large enough for the response to be compressed.
+    fn request() -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_getBlockWithTxHashes",
+            "params": [{"block_number": 0}]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn responses_are_compressed() {
+        let url = spawn(true).await;
+
+        for encoding in ["gzip", "br", "zstd"] {
+            let response = reqwest::Client::new()
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .header(ACCEPT_ENCODING, encoding)
+                .body(request())
+                .send()
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            assert_eq!(response.headers()[CONTENT_ENCODING], encoding);
+        }
+    }
+
+    #[tokio::test]
+    async fn compressed_requests_are_accepted() {
+        let url = spawn(true).await;
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_ENCODING, "gzip")
+            .body(gzip(&request()))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response: serde_json::Value = response.json().await.unwrap();
+        assert!(response.get("result").is_some(), "{response}");
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let url = spawn(false).await;
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(request())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+}
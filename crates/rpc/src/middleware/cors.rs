@@ -41,7 +41,8 @@ mod tests {
                 None => server,
             };
 
-            let (_server_handle, address) = server.spawn().await.unwrap();
+            let (_server_handle, addresses) = server.spawn().await.unwrap();
+            let address = addresses[0];
 
             let resp = reqwest::Client::new()
                 .request(reqwest::Method::OPTIONS, format!("http://{address}"))
@@ -0,0 +1,427 @@
+//! Per-client request quotas.
+//!
+//! Clients are identified by the API key they send in the [API_KEY_HEADER]
+//! header, or by their IP address if they don't send one. Each client gets a
+//! token bucket which is drained by the [cost](method_cost) of the methods it
+//! calls, and refilled at the rate of its [Quota].
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use dashmap::DashMap;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Buckets of clients which haven't made requests for a while are full, and
+/// are dropped at most this often to keep the number of tracked IP addresses
+/// in check.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The label used in metrics for clients without an API key.
+const ANONYMOUS: &str = "anonymous";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Cost units refilled per second.
+    pub rate: NonZeroU32,
+    /// Maximum number of cost units which can be spent at once.
+    pub burst: NonZeroU32,
+}
+
+#[derive(Debug, Clone)]
+struct ApiKeyClient {
+    name: Arc<str>,
+    quota: Option<Quota>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientId {
+    ApiKey(Arc<str>),
+    Ip(IpAddr),
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    anonymous: Option<Quota>,
+    require_api_key: bool,
+    api_keys: Arc<HashMap<String, ApiKeyClient>>,
+    buckets: Arc<DashMap<ClientId, Bucket>>,
+    last_eviction: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter applying `anonymous` to each IP address which
+    /// doesn't send an API key. [None] means no limit.
+    pub fn new(anonymous: Option<Quota>) -> Self {
+        Self {
+            anonymous,
+            require_api_key: false,
+            api_keys: Default::default(),
+            buckets: Default::default(),
+            last_eviction: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Rejects requests which don't send a known API key.
+    pub fn with_required_api_key(self, require_api_key: bool) -> Self {
+        Self {
+            require_api_key,
+            ..self
+        }
+    }
+
+    /// Registers an API key. `name` identifies the client in logs and metrics,
+    /// and `quota` is its limit, [None] meaning no limit.
+    ///
+    /// Panics if the key was already registered.
+    pub fn with_api_key(mut self, name: String, key: String, quota: Option<Quota>) -> Self {
+        let client = ApiKeyClient {
+            name: name.into(),
+            quota,
+        };
+        if Arc::make_mut(&mut self.api_keys)
+            .insert(key, client)
+            .is_some()
+        {
+            panic!("API key is already registered");
+        }
+        self
+    }
+
+    /// Returns [None] if the API key is unknown, or if it's missing and one is
+    /// required.
+    fn identify(&self, api_key: Option<&str>, ip: Option<IpAddr>) -> Option<Client> {
+        let (id, name, quota) = match api_key {
+            Some(api_key) => {
+                let client = self.api_keys.get(api_key)?;
+                (
+                    ClientId::ApiKey(client.name.clone()),
+                    client.name.clone(),
+                    client.quota,
+                )
+            }
+            None if self.require_api_key => return None,
+            None => (
+                ClientId::Ip(ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))),
+                ANONYMOUS.into(),
+                self.anonymous,
+            ),
+        };
+
+        Some(Client {
+            id,
+            name,
+            quota,
+            buckets: self.buckets.clone(),
+        })
+    }
+
+    fn evict_full_buckets(&self, now: Instant) {
+        let mut last_eviction = self.last_eviction.lock().unwrap();
+        if now.saturating_duration_since(*last_eviction) < EVICTION_INTERVAL {
+            return;
+        }
+        *last_eviction = now;
+        drop(last_eviction);
+
+        self.buckets.retain(|_, bucket| !bucket.is_full(now));
+    }
+}
+
+/// A client identified by [identify_client], available as a request
+/// extension.
+#[derive(Clone)]
+pub struct Client {
+    id: ClientId,
+    name: Arc<str>,
+    quota: Option<Quota>,
+    buckets: Arc<DashMap<ClientId, Bucket>>,
+}
+
+impl Client {
+    /// Charges the cost of `method` to the client's quota. On exhaustion
+    /// returns the time after which the call can be retried.
+    pub(crate) fn acquire(&self, method: &str) -> Result<(), Duration> {
+        self.acquire_at(method, Instant::now())
+    }
+
+    fn acquire_at(&self, method: &str, now: Instant) -> Result<(), Duration> {
+        let cost = method_cost(method);
+        let name = self.name.to_string();
+
+        metrics::increment_counter!("rpc_client_requests_total", "client" => name.clone());
+        metrics::counter!("rpc_client_cost_total", cost.into(), "client" => name.clone());
+
+        let Some(quota) = self.quota else {
+            return Ok(());
+        };
+
+        let result = self
+            .buckets
+            .entry(self.id.clone())
+            .or_insert_with(|| Bucket::new(quota, now))
+            .take(cost, now);
+
+        if result.is_err() {
+            metrics::increment_counter!("rpc_client_rate_limited_total", "client" => name);
+        }
+
+        result
+    }
+}
+
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(quota: Quota, now: Instant) -> Self {
+        Self {
+            quota,
+            tokens: quota.burst.get().into(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(self.quota.rate.get()))
+            .min(self.quota.burst.get().into());
+        self.updated = now;
+    }
+
+    /// Methods costing more than the burst size are charged the burst size, so
+    /// that they can still be called.
+    fn take(&mut self, cost: u32, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+
+        let cost = f64::from(cost.min(self.quota.burst.get()));
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            Ok(())
+        } else {
+            let missing = cost - self.tokens;
+            Err(Duration::from_secs_f64(
+                missing / f64::from(self.quota.rate.get()),
+            ))
+        }
+    }
+
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * f64::from(self.quota.rate.get())
+            >= f64::from(self.quota.burst.get())
+    }
+}
+
+/// The number of cost units charged for a call, based on how expensive the
+/// method is to serve. The method namespace is ignored.
+pub fn method_cost(method: &str) -> u32 {
+    let name = method
+        .split_once('_')
+        .map(|(_, name)| name)
+        .unwrap_or(method);
+
+    match name {
+        "traceBlockTransactions" | "simulateBlocks" | "getEventStats" | "exportEvents" => 20,
+        "getEvents"
+        | "traceTransaction"
+        | "traceCall"
+        | "simulateTransactions"
+        | "estimateFee"
+        | "estimateMessageFee"
+        | "getProof"
+        | "getClassProof"
+        | "getStorageProof" => 10,
+        "call" | "getBlockWithReceipts" | "getStateUpdate" => 5,
+        _ => 1,
+    }
+}
+
+/// Identifies the client making the request and adds it to the request
+/// extensions. Requests with an unknown API key are rejected.
+pub async fn identify_client(
+    State(limiter): State<RateLimiter>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let api_key = match request.headers().get(API_KEY_HEADER) {
+        Some(api_key) => match api_key.to_str() {
+            Ok(api_key) => Some(api_key),
+            Err(_) => return http::StatusCode::UNAUTHORIZED.into_response(),
+        },
+        None => None,
+    };
+
+    let Some(client) = limiter.identify(api_key, ip) else {
+        return http::StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    limiter.evict_full_buckets(Instant::now());
+
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::RpcContext;
+    use crate::{RpcServer, RpcVersion};
+
+    fn quota(rate: u32, burst: u32) -> Quota {
+        Quota {
+            rate: NonZeroU32::new(rate).unwrap(),
+            burst: NonZeroU32::new(burst).unwrap(),
+        }
+    }
+
+    #[test]
+    fn bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(quota(2, 10), start);
+
+        bucket.take(10, start).unwrap();
+        assert_eq!(bucket.take(1, start), Err(Duration::from_millis(500)));
+
+        let later = start + Duration::from_secs(1);
+        bucket.take(2, later).unwrap();
+        assert!(bucket.take(1, later).is_err());
+
+        // Never refilled past the burst size.
+        let much_later = start + Duration::from_secs(60);
+        assert!(bucket.is_full(much_later));
+        bucket.take(10, much_later).unwrap();
+        assert!(bucket.take(1, much_later).is_err());
+    }
+
+    #[test]
+    fn cost_is_capped_to_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(quota(1, 5), start);
+
+        bucket
+            .take(method_cost("starknet_traceBlockTransactions"), start)
+            .unwrap();
+        assert!(bucket.take(1, start).is_err());
+    }
+
+    #[test]
+    fn method_costs() {
+        assert_eq!(method_cost("starknet_blockNumber"), 1);
+        assert_eq!(method_cost("starknet_getEvents"), 10);
+        assert_eq!(method_cost("pathfinder_getProof"), 10);
+        assert_eq!(method_cost("starknet_traceBlockTransactions"), 20);
+        assert_eq!(method_cost("pathfinder_exportEvents"), 20);
+    }
+
+    #[test]
+    fn clients_are_identified_by_key_or_ip() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let limiter = RateLimiter::new(Some(quota(1, 1))).with_api_key(
+            "alice".to_owned(),
+            "secret".to_owned(),
+            None,
+        );
+
+        let alice = limiter.identify(Some("secret"), Some(ip)).unwrap();
+        assert_eq!(alice.id, ClientId::ApiKey("alice".into()));
+        assert_eq!(alice.quota, None);
+
+        let anonymous = limiter.identify(None, Some(ip)).unwrap();
+        assert_eq!(anonymous.id, ClientId::Ip(ip));
+        assert_eq!(anonymous.quota, Some(quota(1, 1)));
+
+        assert!(limiter.identify(Some("unknown"), Some(ip)).is_none());
+
+        let limiter = limiter.with_required_api_key(true);
+        assert!(limiter.identify(None, Some(ip)).is_none());
+        assert!(limiter.identify(Some("secret"), Some(ip)).is_some());
+    }
+
+    #[test]
+    fn full_buckets_are_evicted() {
+        let limiter = RateLimiter::new(Some(quota(1, 2)));
+        let start = *limiter.last_eviction.lock().unwrap();
+
+        let first = limiter
+            .identify(None, Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))))
+            .unwrap();
+        let second = limiter
+            .identify(None, Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))))
+            .unwrap();
+        let now = start + EVICTION_INTERVAL;
+        first.acquire_at("starknet_blockNumber", now).unwrap();
+        second.acquire_at("starknet_getEvents", now).unwrap();
+
+        // The first bucket is full again, the second one is still refilling.
+        limiter.evict_full_buckets(now + Duration::from_millis(1500));
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(limiter.buckets.contains_key(&second.id));
+    }
+
+    #[tokio::test]
+    async fn exhausted_quota_is_a_json_rpc_error() {
+        let limiter = RateLimiter::new(Some(quota(1, 1))).with_api_key(
+            "alice".to_owned(),
+            "secret".to_owned(),
+            None,
+        );
+        let server = RpcServer::new(
+            "127.0.0.1:0".parse().unwrap(),
+            RpcContext::for_tests(),
+            RpcVersion::V07,
+        )
+        .with_rate_limiter(limiter);
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let address = addresses[0];
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_chainId",
+        });
+        let send = |api_key: Option<&'static str>| {
+            let request = request.clone();
+            async move {
+                let client = reqwest::Client::new()
+                    .post(format!("http://{address}/rpc/v0_7"))
+                    .json(&request);
+                let client = match api_key {
+                    Some(api_key) => client.header(API_KEY_HEADER, api_key),
+                    None => client,
+                };
+                client.send().await.unwrap()
+            }
+        };
+
+        let response: serde_json::Value = send(None).await.json().await.unwrap();
+        assert!(response.get("result").is_some(), "{response}");
+
+        let response: serde_json::Value = send(None).await.json().await.unwrap();
+        assert_eq!(response["error"]["code"], json!(-32005), "{response}");
+
+        // Clients with an API key have their own quota.
+        for _ in 0..3 {
+            let response: serde_json::Value = send(Some("secret")).await.json().await.unwrap();
+            assert!(response.get("result").is_some(), "{response}");
+        }
+
+        let response = send(Some("unknown")).await;
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+}
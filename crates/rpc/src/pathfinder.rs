@@ -1,12 +1,23 @@
 use crate::jsonrpc::{RpcRouter, RpcRouterBuilder};
 
+pub(crate) mod export_events;
 pub(crate) mod methods;
 
+/// Methods meant for node operators rather than the public, which are only
+/// served by listeners enabling them by name, see
+/// [MethodFilter](crate::MethodFilter).
+pub(crate) const ADMIN_METHODS: &[&str] = &["pathfinder_getEventStats"];
+
 #[rustfmt::skip]
 pub fn register_routes() -> RpcRouterBuilder {
     RpcRouter::builder(crate::RpcVersion::PathfinderV01)
-        .register("pathfinder_version",              || { pathfinder_version::VERSION })
-        .register("pathfinder_getProof",             methods::get_proof)
-        .register("pathfinder_getClassProof",        methods::get_class_proof)
-        .register("pathfinder_getTransactionStatus", methods::get_transaction_status)
+        .register("pathfinder_version",                || { pathfinder_version::VERSION })
+        .register("pathfinder_getProof",               methods::get_proof)
+        .register("pathfinder_getClassProof",          methods::get_class_proof)
+        .register("pathfinder_getTransactionStatus",   methods::get_transaction_status)
+        .register("pathfinder_traceCall",              methods::trace_call)
+        .register("pathfinder_simulateBlocks",         methods::simulate_blocks)
+        .register("pathfinder_getEventsByTransaction", methods::get_events_by_transaction)
+        .register("pathfinder_getEventStats",          methods::get_event_stats)
+        .register("pathfinder_subscribeReorgs",        methods::SubscribeReorgs)
 }
@@ -0,0 +1,577 @@
+//! Streams all events matching a filter as newline delimited JSON, for bulk
+//! exports which would take far too many `starknet_getEvents` pages.
+
+use anyhow::Context;
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use http::StatusCode;
+use pathfinder_common::prelude::*;
+use pathfinder_common::BlockId;
+use pathfinder_storage::{
+    AGGREGATE_BLOOM_BLOCK_RANGE_LEN,
+    EVENT_ADDRESS_FILTER_LIMIT,
+    EVENT_KEY_FILTER_LIMIT,
+};
+use tokio::sync::mpsc;
+
+use crate::context::RpcContext;
+use crate::dto::{SerializeForVersion, Serializer};
+use crate::error::ApplicationError;
+use crate::jsonrpc::RpcError;
+use crate::method::get_events::EmittedEvent;
+use crate::middleware::rate_limit::Client;
+use crate::RpcVersion;
+
+/// The name under which the export is subject to listener method filters.
+pub(crate) const METHOD_NAME: &str = "pathfinder_exportEvents";
+
+/// Number of events buffered for a client before the export waits for it.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Number of events sent for each charge to the client's quota.
+#[cfg(not(test))]
+const EVENTS_PER_CHARGE: usize = 1024;
+
+/// Charge more often in tests, which export only a few events.
+#[cfg(test)]
+const EVENTS_PER_CHARGE: usize = 2;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExportEventsInput {
+    from_block: Option<BlockId>,
+    to_block: Option<BlockId>,
+    address: Option<ContractAddress>,
+    addresses: Vec<ContractAddress>,
+    keys: Vec<Vec<EventKey>>,
+}
+
+impl crate::dto::DeserializeForVersion for ExportEventsInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                from_block: value.deserialize_optional("from_block")?,
+                to_block: value.deserialize_optional("to_block")?,
+                address: value.deserialize_optional("address")?.map(ContractAddress),
+                addresses: value
+                    .deserialize_optional_array("addresses", |value| {
+                        value.deserialize().map(ContractAddress)
+                    })?
+                    .unwrap_or_default(),
+                keys: value
+                    .deserialize_optional_array("keys", |value| {
+                        value.deserialize_array(|value| value.deserialize().map(EventKey))
+                    })?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
+
+/// Handles `POST /rpc/pathfinder/v0_1/events`.
+///
+/// The request body holds the same filter as `starknet_getEvents`, including
+/// the `addresses` extension but without `chunk_size` and
+/// `continuation_token`. Matching events are streamed in
+/// order, one JSON object per line. The export reads from the database only as
+/// fast as the client consumes the response.
+///
+/// The export is charged to the client's quota like a call to [METHOD_NAME]
+/// when it starts, and again for every further range of
+/// [AGGREGATE_BLOOM_BLOCK_RANGE_LEN] blocks scanned and every
+/// [EVENTS_PER_CHARGE] events sent. Running out of quota aborts the response.
+///
+/// Errors detected before streaming starts are returned as a JSON-RPC error
+/// object with a non-success status code. Later errors abort the response, so
+/// that clients can tell a truncated export from a complete one.
+pub(crate) async fn export_events(
+    State(context): State<RpcContext>,
+    client: Option<Extension<Client>>,
+    body: Bytes,
+) -> Response {
+    let client = client.map(|Extension(client)| client);
+    if let Some(client) = &client {
+        if let Err(retry_after) = client.acquire(METHOD_NAME) {
+            return error_response(RpcError::LimitExceeded { retry_after });
+        }
+    }
+
+    let input = match parse_input(&body) {
+        Ok(input) => input,
+        Err(e) => return error_response(e),
+    };
+
+    match stream_events(context, client, input).await {
+        Ok(body) => ([(http::header::CONTENT_TYPE, "application/x-ndjson")], body).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+fn parse_input(body: &[u8]) -> Result<ExportEventsInput, RpcError> {
+    let value: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| RpcError::ParseError(e.to_string()))?;
+    crate::dto::Value::new(value, RpcVersion::PathfinderV01)
+        .deserialize()
+        .map_err(|e| RpcError::InvalidParams(e.to_string()))
+}
+
+fn error_response(error: RpcError) -> Response {
+    let status = match &error {
+        RpcError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        RpcError::LimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    let body = error
+        .serialize(Serializer::new(RpcVersion::PathfinderV01))
+        .expect("Serializing an error cannot fail");
+
+    (status, axum::Json(body)).into_response()
+}
+
+async fn stream_events(
+    context: RpcContext,
+    client: Option<Client>,
+    input: ExportEventsInput,
+) -> Result<Body, RpcError> {
+    if input.keys.len() > EVENT_KEY_FILTER_LIMIT {
+        return Err(ApplicationError::TooManyKeysInFilter {
+            limit: EVENT_KEY_FILTER_LIMIT,
+            requested: input.keys.len(),
+        }
+        .into());
+    }
+    let requested_addresses = usize::from(input.address.is_some()) + input.addresses.len();
+    if requested_addresses > EVENT_ADDRESS_FILTER_LIMIT {
+        return Err(ApplicationError::TooManyAddressesInEventFilter {
+            limit: EVENT_ADDRESS_FILTER_LIMIT,
+            requested: requested_addresses,
+        }
+        .into());
+    }
+    if matches!(input.from_block, Some(BlockId::Pending))
+        || matches!(input.to_block, Some(BlockId::Pending))
+    {
+        return Err(RpcError::InvalidParams(
+            "Pending events cannot be exported".to_owned(),
+        ));
+    }
+
+    // Truncate empty key lists from the end of the key filter.
+    let mut keys = input.keys;
+    if let Some(last_non_empty) = keys.iter().rposition(|keys| !keys.is_empty()) {
+        keys.truncate(last_non_empty + 1);
+    }
+
+    let storage = context.storage.clone();
+    let range = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
+        let mut connection = storage
+            .connection()
+            .context("Opening database connection")
+            .map_err(RpcError::InternalError)?;
+        let transaction = connection
+            .transaction()
+            .context("Creating database transaction")
+            .map_err(RpcError::InternalError)?;
+
+        let Some(latest) = transaction
+            .block_number(pathfinder_storage::BlockId::Latest)
+            .context("Querying latest block number")
+            .map_err(RpcError::InternalError)?
+        else {
+            return Ok(None);
+        };
+
+        let resolve = |block: Option<BlockId>, default: BlockNumber| -> Result<_, RpcError> {
+            let Some(block) = block else {
+                return Ok(default);
+            };
+            let block = pathfinder_storage::BlockId::try_from(block)
+                .map_err(|e| RpcError::InvalidParams(e.to_owned()))?;
+            let number = transaction
+                .block_number(block)
+                .context("Querying block number")
+                .map_err(RpcError::InternalError)?
+                .ok_or(ApplicationError::BlockNotFound)?;
+            if let Some(oldest_available) = transaction
+                .block_pruned(number.into())
+                .context("Querying block pruned status")
+                .map_err(RpcError::InternalError)?
+            {
+                return Err(ApplicationError::BlockPruned(oldest_available).into());
+            }
+            Ok(number)
+        };
+
+        // Without an explicit start, the export starts at the oldest block that
+        // has not been pruned.
+        let oldest_available = transaction
+            .oldest_available_block()
+            .context("Querying oldest available block")
+            .map_err(RpcError::InternalError)?
+            .unwrap_or(BlockNumber::GENESIS);
+
+        let from_block = resolve(input.from_block, oldest_available)?;
+        let to_block = resolve(input.to_block, latest)?;
+
+        Ok(Some((from_block, to_block)))
+    })
+    .await
+    .context("Joining blocking task")
+    .map_err(RpcError::InternalError)??;
+
+    let (tx, mut rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+    if let Some((from_block, to_block)) = range {
+        let storage = context.storage.clone();
+        let addresses = input.address.into_iter().chain(input.addresses).collect();
+        let span = tracing::Span::current();
+        util::task::spawn_blocking(move |cancellation| {
+            let _g = span.enter();
+            let result = export(
+                storage,
+                from_block,
+                to_block,
+                addresses,
+                keys,
+                client,
+                &tx,
+                cancellation,
+            );
+            if let Err(e) = result {
+                tracing::warn!(error=?e, "Event export failed");
+                // The client may be gone already.
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+    }
+
+    let stream = futures::stream::poll_fn(move |cx| rx.poll_recv(cx));
+    Ok(Body::from_stream(stream))
+}
+
+/// Sends the matching events in `from_block..=to_block`, one line each, until
+/// the receiving end is dropped or the client runs out of quota.
+///
+/// The aggregate Bloom filters select the blocks to read, whose events are
+/// then read and sent one block at a time, each in its own database
+/// transaction. This way the export only reads as far ahead of the client as
+/// the channel allows, and a long running export does not pin an old snapshot
+/// of the database.
+fn export(
+    storage: pathfinder_storage::Storage,
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+    addresses: Vec<ContractAddress>,
+    keys: Vec<Vec<EventKey>>,
+    client: Option<Client>,
+    tx: &mpsc::Sender<anyhow::Result<Bytes>>,
+    cancellation: util::task::CancellationToken,
+) -> anyhow::Result<()> {
+    let mut connection = storage
+        .connection()
+        .context("Opening database connection")?;
+
+    // The first block range and batch of events are covered by the charge for
+    // starting the export.
+    let mut events_sent = 0;
+    let mut from = from_block.get();
+    while from <= to_block.get() {
+        if from != from_block.get() && !charge(client.as_ref(), tx) {
+            return Ok(());
+        }
+
+        let to = std::cmp::min(
+            (from / AGGREGATE_BLOOM_BLOCK_RANGE_LEN + 1) * AGGREGATE_BLOOM_BLOCK_RANGE_LEN - 1,
+            to_block.get(),
+        );
+
+        let (blocks, last_block) = connection
+            .transaction()
+            .context("Creating database transaction")?
+            .event_blocks_in_range(
+                BlockNumber::new_or_panic(from),
+                BlockNumber::new_or_panic(to),
+                &addresses,
+                &keys,
+            )
+            .context("Querying blocks with events")?;
+
+        for block in blocks {
+            if cancellation.is_cancelled() {
+                anyhow::bail!("Shutting down");
+            }
+
+            let events = connection
+                .transaction()
+                .context("Creating database transaction")?
+                .matching_events_in_block(block, &addresses, &keys)
+                .context("Querying events")?;
+            let Some(events) = events else {
+                // The database has no more blocks.
+                return Ok(());
+            };
+
+            for event in events {
+                if events_sent > 0
+                    && events_sent % EVENTS_PER_CHARGE == 0
+                    && !charge(client.as_ref(), tx)
+                {
+                    return Ok(());
+                }
+
+                let event = EmittedEvent::from(event)
+                    .serialize(Serializer::new(RpcVersion::PathfinderV01))
+                    .context("Serializing event")?;
+                let mut line = serde_json::to_vec(&event).context("Serializing event")?;
+                line.push(b'\n');
+
+                if tx.blocking_send(Ok(line.into())).is_err() {
+                    tracing::debug!("Client disconnected from event export");
+                    return Ok(());
+                }
+                events_sent += 1;
+            }
+        }
+
+        match last_block {
+            Some(last_block) if last_block.get() == to => from = to + 1,
+            // The database has no more blocks.
+            _ => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Charges the export to the client's quota. Returns false if the quota has
+/// run out, in which case the response has been aborted.
+fn charge(client: Option<&Client>, tx: &mpsc::Sender<anyhow::Result<Bytes>>) -> bool {
+    let Some(client) = client else {
+        return true;
+    };
+    match client.acquire(METHOD_NAME) {
+        Ok(()) => true,
+        Err(retry_after) => {
+            tracing::debug!(?retry_after, "Event export ran out of quota");
+            // The client may be gone already.
+            let _ = tx.blocking_send(Err(anyhow::anyhow!("Quota exceeded")));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_storage::test_utils;
+    use serde_json::json;
+
+    use super::*;
+    use crate::RpcServer;
+
+    async fn setup() -> (String, Vec<serde_json::Value>) {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let events = test_data
+            .events
+            .into_iter()
+            .map(|event| {
+                EmittedEvent::from(event)
+                    .serialize(Serializer::new(RpcVersion::PathfinderV01))
+                    .unwrap()
+            })
+            .collect();
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let server = RpcServer::new("127.0.0.1:0".parse().unwrap(), context, RpcVersion::V07);
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let url = format!("http://{}/rpc/pathfinder/v0_1/events", addresses[0]);
+
+        (url, events)
+    }
+
+    async fn export(url: &str, filter: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(url)
+            .json(&filter)
+            .send()
+            .await
+            .unwrap()
+    }
+
+    async fn lines(response: reqwest::Response) -> Vec<serde_json::Value> {
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        response
+            .text()
+            .await
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn all_events() {
+        let (url, events) = setup().await;
+
+        let exported = lines(export(&url, json!({})).await).await;
+        assert_eq!(exported, events);
+    }
+
+    #[tokio::test]
+    async fn filtered() {
+        let (url, events) = setup().await;
+        let expected = events[1].clone();
+
+        let exported = lines(
+            export(
+                &url,
+                json!({
+                    "from_block": {"block_number": expected["block_number"]},
+                    "to_block": "latest",
+                    "address": expected["from_address"],
+                }),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(exported, vec![expected]);
+    }
+
+    #[tokio::test]
+    async fn pending_is_rejected() {
+        let (url, _) = setup().await;
+
+        let response = export(&url, json!({"to_block": "pending"})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn pruned_blocks() {
+        let storage =
+            pathfinder_storage::StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
+                pathfinder_storage::pruning::BlockchainHistoryMode::Prune { num_blocks_kept: 1 },
+                std::num::NonZeroU32::new(1).unwrap(),
+            )
+            .unwrap();
+        let test_data = test_utils::fill_test_storage(
+            &storage,
+            test_utils::NUM_BLOCKS,
+            test_utils::TRANSACTIONS_PER_BLOCK,
+        );
+        let context = RpcContext::for_tests().with_storage(storage);
+        let server = RpcServer::new("127.0.0.1:0".parse().unwrap(), context, RpcVersion::V07);
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let url = format!("http://{}/rpc/pathfinder/v0_1/events", addresses[0]);
+
+        let latest = test_data.headers.last().unwrap().number;
+        let oldest_available = latest.get() - 1;
+
+        // Only events of the blocks which are still available are exported.
+        let exported = lines(export(&url, json!({})).await).await;
+        let expected = test_data
+            .events
+            .into_iter()
+            .filter(|event| event.block_number.get() >= oldest_available)
+            .map(|event| {
+                EmittedEvent::from(event)
+                    .serialize(Serializer::new(RpcVersion::PathfinderV01))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(exported, expected);
+
+        let response = export(&url, json!({"from_block": {"block_number": 0}})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["code"], 10002);
+        assert_eq!(error["data"]["oldest_available_block"], oldest_available);
+    }
+
+    #[tokio::test]
+    async fn charged_to_the_client_quota() {
+        use crate::middleware::rate_limit::{Quota, RateLimiter};
+
+        let (storage, _) = test_utils::setup_test_storage();
+        let quota = Quota {
+            rate: std::num::NonZeroU32::new(1).unwrap(),
+            burst: std::num::NonZeroU32::new(1).unwrap(),
+        };
+        let server = RpcServer::new(
+            "127.0.0.1:0".parse().unwrap(),
+            RpcContext::for_tests().with_storage(storage),
+            RpcVersion::V07,
+        )
+        .with_rate_limiter(RateLimiter::new(Some(quota)));
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let url = format!("http://{}/rpc/pathfinder/v0_1/events", addresses[0]);
+
+        let response = export(&url, json!({})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let response = export(&url, json!({})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["code"], -32005);
+    }
+
+    #[tokio::test]
+    async fn aborted_when_the_quota_runs_out() {
+        use crate::middleware::rate_limit::{Quota, RateLimiter};
+
+        let (storage, _) = test_utils::setup_test_storage();
+        // Enough for starting the export and one more batch of events.
+        let quota = Quota {
+            rate: std::num::NonZeroU32::new(1).unwrap(),
+            burst: std::num::NonZeroU32::new(40).unwrap(),
+        };
+        let server = RpcServer::new(
+            "127.0.0.1:0".parse().unwrap(),
+            RpcContext::for_tests().with_storage(storage),
+            RpcVersion::V07,
+        )
+        .with_rate_limiter(RateLimiter::new(Some(quota)));
+        let (_server_handle, addresses) = server.spawn().await.unwrap();
+        let url = format!("http://{}/rpc/pathfinder/v0_1/events", addresses[0]);
+
+        let mut response = export(&url, json!({})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let mut body = Vec::new();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                Ok(None) => panic!("Export should have been aborted"),
+                Err(_) => break,
+            }
+        }
+        let lines = String::from_utf8(body).unwrap().lines().count();
+        assert_eq!(lines, 2 * EVENTS_PER_CHARGE);
+    }
+
+    #[tokio::test]
+    async fn too_many_addresses() {
+        let (url, _) = setup().await;
+
+        let addresses = (0..=EVENT_ADDRESS_FILTER_LIMIT)
+            .map(|i| format!("{i:#x}"))
+            .collect::<Vec<_>>();
+        let response = export(&url, json!({"addresses": addresses})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["code"], 10003);
+        assert_eq!(error["data"]["requested"], EVENT_ADDRESS_FILTER_LIMIT + 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_block() {
+        let (url, _) = setup().await;
+
+        let response = export(&url, json!({"from_block": {"block_hash": "0x1234"}})).await;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["code"], 24);
+    }
+}
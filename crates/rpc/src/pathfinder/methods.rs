@@ -1,5 +1,15 @@
+mod get_event_stats;
+mod get_events_by_transaction;
 mod get_proof;
 mod get_transaction_status;
+mod simulate_blocks;
+mod subscribe_reorgs;
+mod trace_call;
 
+pub(crate) use get_event_stats::get_event_stats;
+pub(crate) use get_events_by_transaction::get_events_by_transaction;
 pub(crate) use get_proof::{get_class_proof, get_proof};
 pub(crate) use get_transaction_status::get_transaction_status;
+pub(crate) use simulate_blocks::simulate_blocks;
+pub(crate) use subscribe_reorgs::SubscribeReorgs;
+pub(crate) use trace_call::trace_call;
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, EventKey};
+
+use crate::context::RpcContext;
+use crate::error::ApplicationError;
+
+/// The largest number of blocks a single request may cover.
+const MAX_BLOCK_RANGE: u64 = 1000;
+
+/// The number of contracts and selectors returned by default.
+const DEFAULT_LIMIT: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetEventStatsInput {
+    from_block: BlockId,
+    to_block: BlockId,
+    limit: usize,
+}
+
+#[derive(Debug)]
+pub enum GetEventStatsError {
+    BlockNotFound,
+    BlockPruned(BlockNumber),
+    InvalidParams(String),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for GetEventStatsError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Internal(error)
+    }
+}
+
+impl From<GetEventStatsError> for crate::jsonrpc::RpcError {
+    fn from(error: GetEventStatsError) -> Self {
+        match error {
+            GetEventStatsError::BlockNotFound => {
+                Self::ApplicationError(ApplicationError::BlockNotFound)
+            }
+            GetEventStatsError::BlockPruned(oldest_available) => {
+                Self::ApplicationError(ApplicationError::BlockPruned(oldest_available))
+            }
+            GetEventStatsError::InvalidParams(message) => Self::InvalidParams(message),
+            GetEventStatsError::Internal(e) => Self::InternalError(e),
+        }
+    }
+}
+
+impl crate::dto::DeserializeForVersion for GetEventStatsInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                from_block: value.deserialize("from_block")?,
+                to_block: value.deserialize("to_block")?,
+                limit: value
+                    .deserialize_optional::<u64>("limit")?
+                    .map(|limit| limit as usize)
+                    .unwrap_or(DEFAULT_LIMIT),
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetEventStatsOutput {
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+    total_events: usize,
+    /// Number of events in each block of the range, in ascending order.
+    blocks: Vec<(BlockNumber, usize)>,
+    /// Contracts emitting the most events, in descending order of event count.
+    contracts: Vec<(ContractAddress, usize)>,
+    /// The most frequent first event keys, in descending order of event count.
+    selectors: Vec<(EventKey, usize)>,
+}
+
+/// Counts events per block, per emitting contract and per selector (the first
+/// event key) over a range of blocks.
+///
+/// Only the `limit` most active contracts and selectors are returned, ties are
+/// broken by address and selector so that the output is deterministic.
+pub async fn get_event_stats(
+    context: RpcContext,
+    input: GetEventStatsInput,
+) -> Result<GetEventStatsOutput, GetEventStatsError> {
+    let (from_block, to_block): (pathfinder_storage::BlockId, pathfinder_storage::BlockId) =
+        match (input.from_block, input.to_block) {
+            (BlockId::Pending, _) | (_, BlockId::Pending) => {
+                return Err(GetEventStatsError::InvalidParams(
+                    "'pending' is not supported by this method".to_owned(),
+                ))
+            }
+            (from, to) => (
+                from.try_into().expect("Only pending cast should fail"),
+                to.try_into().expect("Only pending cast should fail"),
+            ),
+        };
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking(move |_| {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        let resolve = |block: pathfinder_storage::BlockId| -> Result<_, GetEventStatsError> {
+            let number = db_tx
+                .block_number(block)
+                .context("Querying block number")?
+                .ok_or(GetEventStatsError::BlockNotFound)?;
+            if let Some(oldest_available) = db_tx
+                .block_pruned(number.into())
+                .context("Querying block pruned status")?
+            {
+                return Err(GetEventStatsError::BlockPruned(oldest_available));
+            }
+            Ok(number)
+        };
+        let from_block = resolve(from_block)?;
+        let to_block = resolve(to_block)?;
+
+        if from_block > to_block {
+            return Err(GetEventStatsError::InvalidParams(
+                "from_block must not be after to_block".to_owned(),
+            ));
+        }
+        if to_block.get() - from_block.get() >= MAX_BLOCK_RANGE {
+            return Err(GetEventStatsError::InvalidParams(format!(
+                "Block range is limited to {MAX_BLOCK_RANGE} blocks"
+            )));
+        }
+
+        // Counts are taken from the events themselves rather than from the block
+        // headers, which lack them for blocks synced by older versions.
+        let mut blocks = Vec::new();
+        let mut contracts = HashMap::<_, usize>::new();
+        let mut selectors = HashMap::<_, usize>::new();
+        for block in from_block.get()..=to_block.get() {
+            let block = BlockNumber::new_or_panic(block);
+            // Events of a block can be missing even though its header exists,
+            // e.g. while the block is still being stored.
+            let events = db_tx
+                .events_for_block(block.into())
+                .context("Querying events")?
+                .ok_or(GetEventStatsError::BlockNotFound)?;
+
+            let mut event_count = 0;
+            for event in events.iter().flat_map(|(_, events)| events) {
+                event_count += 1;
+                *contracts.entry(event.from_address).or_default() += 1;
+                if let Some(selector) = event.keys.first() {
+                    *selectors.entry(*selector).or_default() += 1;
+                }
+            }
+            blocks.push((block, event_count));
+        }
+        let total_events = blocks.iter().map(|(_, count)| count).sum();
+
+        Ok(GetEventStatsOutput {
+            from_block,
+            to_block,
+            total_events,
+            blocks,
+            contracts: top(contracts, input.limit),
+            selectors: top(selectors, input.limit),
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+fn top<T: Ord>(counts: HashMap<T, usize>, limit: usize) -> Vec<(T, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts.truncate(limit);
+    counts
+}
+
+impl crate::dto::SerializeForVersion for GetEventStatsOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("from_block", &self.from_block)?;
+        serializer.serialize_field("to_block", &self.to_block)?;
+        serializer.serialize_field("total_events", &self.total_events)?;
+        serializer.serialize_iter(
+            "blocks",
+            self.blocks.len(),
+            &mut self.blocks.iter().map(|(block_number, event_count)| Count {
+                name: "block_number",
+                value: *block_number,
+                event_count: *event_count,
+            }),
+        )?;
+        serializer.serialize_iter(
+            "contracts",
+            self.contracts.len(),
+            &mut self.contracts.iter().map(|(address, event_count)| Count {
+                name: "address",
+                value: *address,
+                event_count: *event_count,
+            }),
+        )?;
+        serializer.serialize_iter(
+            "selectors",
+            self.selectors.len(),
+            &mut self.selectors.iter().map(|(selector, event_count)| Count {
+                name: "selector",
+                value: selector.0,
+                event_count: *event_count,
+            }),
+        )?;
+        serializer.end()
+    }
+}
+
+struct Count<T> {
+    name: &'static str,
+    value: T,
+    event_count: usize,
+}
+
+impl<T: crate::dto::SerializeForVersion> crate::dto::SerializeForVersion for Count<T> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(self.name, &self.value)?;
+        serializer.serialize_field("event_count", &self.event_count)?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_storage::test_utils;
+
+    use super::*;
+
+    fn input(from: u64, to: u64) -> GetEventStatsInput {
+        GetEventStatsInput {
+            from_block: BlockId::Number(BlockNumber::new_or_panic(from)),
+            to_block: BlockId::Number(BlockNumber::new_or_panic(to)),
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    #[tokio::test]
+    async fn counts() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let output = get_event_stats(context, input(1, 2)).await.unwrap();
+
+        let events: Vec<_> = test_data
+            .events
+            .iter()
+            .filter(|event| (1..=2).contains(&event.block_number.get()))
+            .collect();
+        assert_eq!(output.from_block, BlockNumber::new_or_panic(1));
+        assert_eq!(output.to_block, BlockNumber::new_or_panic(2));
+        assert_eq!(output.total_events, events.len());
+        assert_eq!(
+            output.blocks,
+            vec![
+                (BlockNumber::new_or_panic(1), test_utils::EVENTS_PER_BLOCK),
+                (BlockNumber::new_or_panic(2), test_utils::EVENTS_PER_BLOCK),
+            ]
+        );
+        assert_eq!(output.contracts.len(), events.len());
+        assert!(output.contracts.iter().all(|(_, count)| *count == 1));
+        assert_eq!(
+            output
+                .selectors
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>(),
+            events.iter().filter(|event| !event.keys.is_empty()).count()
+        );
+    }
+
+    #[tokio::test]
+    async fn limit() {
+        let (storage, _) = test_utils::setup_test_storage();
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let output = get_event_stats(
+            context,
+            GetEventStatsInput {
+                limit: 3,
+                ..input(0, 3)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output.total_events, test_utils::NUM_EVENTS);
+        assert_eq!(output.contracts.len(), 3);
+        assert!(output.selectors.len() <= 3);
+    }
+
+    #[tokio::test]
+    async fn unknown_block() {
+        let context = RpcContext::for_tests();
+
+        let error = get_event_stats(context, input(0, 100)).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetEventStatsError::BlockNotFound);
+    }
+
+    #[tokio::test]
+    async fn missing_events() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        let header = pathfinder_common::BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .finalize_with_hash(pathfinder_common::BlockHash(pathfinder_crypto::Felt::ZERO));
+        tx.insert_block_header(&header).unwrap();
+        tx.commit().unwrap();
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let error = get_event_stats(context, input(0, 0)).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetEventStatsError::BlockNotFound);
+    }
+
+    #[tokio::test]
+    async fn pending_is_rejected() {
+        let context = RpcContext::for_tests();
+
+        let error = get_event_stats(
+            context,
+            GetEventStatsInput {
+                to_block: BlockId::Pending,
+                ..input(0, 0)
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches::assert_matches!(error, GetEventStatsError::InvalidParams(_));
+    }
+
+    #[tokio::test]
+    async fn reversed_range() {
+        let context = RpcContext::for_tests();
+
+        let error = get_event_stats(context, input(2, 1)).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetEventStatsError::InvalidParams(_));
+
+        let error = crate::jsonrpc::RpcError::from(error);
+        assert_eq!(error.code(crate::RpcVersion::PathfinderV01), -32602);
+    }
+}
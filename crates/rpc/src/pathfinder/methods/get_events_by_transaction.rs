@@ -0,0 +1,206 @@
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, TransactionHash};
+
+use crate::context::RpcContext;
+use crate::method::get_events::EmittedEvent;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetEventsByTransactionInput {
+    transaction_hash: TransactionHash,
+}
+
+crate::error::generate_rpc_error_subset!(
+    GetEventsByTransactionError: TxnHashNotFound,
+    BlockPruned(BlockNumber)
+);
+
+impl crate::dto::DeserializeForVersion for GetEventsByTransactionInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: TransactionHash(value.deserialize("transaction_hash")?),
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetEventsByTransactionOutput(Vec<EmittedEvent>);
+
+/// Returns the events emitted by a transaction, in the order they were
+/// emitted, without fetching the transaction or its receipt.
+pub async fn get_events_by_transaction(
+    context: RpcContext,
+    input: GetEventsByTransactionInput,
+) -> Result<GetEventsByTransactionOutput, GetEventsByTransactionError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking(move |_| {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        // Check pending transactions first.
+        let pending = context
+            .pending_data
+            .get(&db_tx)
+            .context("Querying pending data")?;
+        if let Some((_, events)) = pending
+            .block
+            .transaction_receipts
+            .iter()
+            .find(|(receipt, _)| receipt.transaction_hash == input.transaction_hash)
+        {
+            let events = events
+                .iter()
+                .map(|event| EmittedEvent {
+                    data: event.data.clone(),
+                    keys: event.keys.clone(),
+                    from_address: event.from_address,
+                    block_hash: None,
+                    block_number: None,
+                    transaction_hash: input.transaction_hash,
+                })
+                .collect();
+            return Ok(GetEventsByTransactionOutput(events));
+        }
+
+        let (block_number, events) = db_tx
+            .events_for_transaction(input.transaction_hash)
+            .context("Querying events")?
+            .ok_or(GetEventsByTransactionError::TxnHashNotFound)?;
+        // Pruned blocks may not have been removed from the database yet.
+        if let Some(oldest_available) = db_tx
+            .block_pruned(block_number.into())
+            .context("Querying pruned blocks")?
+        {
+            return Err(GetEventsByTransactionError::BlockPruned(oldest_available));
+        }
+        let events = events.context("Events missing")?;
+        let block_hash = db_tx
+            .block_hash(block_number.into())
+            .context("Querying block hash")?
+            .context("Block hash missing")?;
+
+        let events = events
+            .into_iter()
+            .map(|event| EmittedEvent {
+                data: event.data,
+                keys: event.keys,
+                from_address: event.from_address,
+                block_hash: Some(block_hash),
+                block_number: Some(block_number),
+                transaction_hash: input.transaction_hash,
+            })
+            .collect();
+
+        Ok(GetEventsByTransactionOutput(events))
+    })
+    .await
+    .context("Joining database task")?
+}
+
+impl crate::dto::SerializeForVersion for GetEventsByTransactionOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockNumber;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn confirmed() {
+        let context = RpcContext::for_tests();
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"txn 0"),
+        };
+        let output = get_events_by_transaction(context, input).await.unwrap();
+
+        assert_eq!(
+            output.0,
+            vec![EmittedEvent {
+                data: vec![event_data_bytes!(b"event 0 data")],
+                keys: vec![event_key_bytes!(b"event 0 key")],
+                from_address: contract_address_bytes!(b"event 0 from addr"),
+                block_hash: Some(block_hash_bytes!(b"genesis")),
+                block_number: Some(BlockNumber::GENESIS),
+                transaction_hash: transaction_hash_bytes!(b"txn 0"),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn without_events() {
+        let context = RpcContext::for_tests();
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"txn 1"),
+        };
+        let output = get_events_by_transaction(context, input).await.unwrap();
+
+        assert!(output.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"pending tx hash 0"),
+        };
+        let output = get_events_by_transaction(context, input).await.unwrap();
+
+        assert_eq!(output.0.len(), 3);
+        assert!(output
+            .0
+            .iter()
+            .all(|event| event.block_hash.is_none() && event.block_number.is_none()));
+        assert_eq!(output.0[2].keys, vec![event_key_bytes!(b"pending key 2")]);
+    }
+
+    #[tokio::test]
+    async fn pruned_block() {
+        let storage =
+            pathfinder_storage::StorageBuilder::in_memory_with_blockchain_pruning_and_pool_size(
+                pathfinder_storage::pruning::BlockchainHistoryMode::Prune { num_blocks_kept: 1 },
+                std::num::NonZeroU32::new(1).unwrap(),
+            )
+            .unwrap();
+        let test_data = pathfinder_storage::test_utils::fill_test_storage(
+            &storage,
+            pathfinder_storage::test_utils::NUM_BLOCKS,
+            pathfinder_storage::test_utils::TRANSACTIONS_PER_BLOCK,
+        );
+        let context = RpcContext::for_tests().with_storage(storage);
+        let input = GetEventsByTransactionInput {
+            transaction_hash: test_data.transactions[0].hash,
+        };
+        let error = get_events_by_transaction(context, input).await.unwrap_err();
+
+        let latest = test_data.headers.last().unwrap().number;
+        assert_matches::assert_matches!(
+            error,
+            GetEventsByTransactionError::BlockPruned(oldest) if oldest == latest - 1
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_transaction() {
+        let context = RpcContext::for_tests();
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"non-existent"),
+        };
+        let error = get_events_by_transaction(context, input).await.unwrap_err();
+
+        assert_matches::assert_matches!(error, GetEventsByTransactionError::TxnHashNotFound);
+    }
+}
@@ -55,6 +55,7 @@ impl crate::dto::DeserializeForVersion for GetClassProofInput {
 pub enum GetProofError {
     Internal(anyhow::Error),
     BlockNotFound,
+    BlockPruned(BlockNumber),
     ProofLimitExceeded { limit: u32, requested: u32 },
     ProofMissing,
 }
@@ -84,6 +85,7 @@ impl From<GetProofError> for crate::error::ApplicationError {
                 Self::ProofLimitExceeded { limit, requested }
             }
             GetProofError::BlockNotFound => Self::BlockNotFound,
+            GetProofError::BlockPruned(oldest_available) => Self::BlockPruned(oldest_available),
             GetProofError::Internal(internal) => Self::Internal(internal),
             GetProofError::ProofMissing => Self::ProofMissing,
         }
@@ -297,6 +299,13 @@ pub async fn get_proof(
 
         let tx = db.transaction().context("Creating database transaction")?;
 
+        if let Some(oldest_available) = tx
+            .block_pruned(block_id)
+            .context("Querying block pruned status")?
+        {
+            return Err(GetProofError::BlockPruned(oldest_available));
+        }
+
         // Use internal error to indicate that the process of querying for a particular
         // block failed, which is not the same as being sure that the block is
         // not in the db.
@@ -305,6 +314,13 @@ pub async fn get_proof(
             .context("Fetching block header")?
             .ok_or(GetProofError::BlockNotFound)?;
 
+        if let Some(oldest_trie_block) = tx
+            .trie_pruned(header.number)
+            .context("Querying trie pruned status")?
+        {
+            return Err(GetProofError::BlockPruned(oldest_trie_block));
+        }
+
         let state_commitment = match header.state_commitment {
             StateCommitment::ZERO => None,
             other => Some(other),
@@ -445,6 +461,13 @@ pub async fn get_class_proof(
 
         let tx = db.transaction().context("Creating database transaction")?;
 
+        if let Some(oldest_available) = tx
+            .block_pruned(block_id)
+            .context("Querying block pruned status")?
+        {
+            return Err(GetProofError::BlockPruned(oldest_available));
+        }
+
         // Use internal error to indicate that the process of querying for a particular
         // block failed, which is not the same as being sure that the block is
         // not in the db.
@@ -453,6 +476,13 @@ pub async fn get_class_proof(
             .context("Fetching block header")?
             .ok_or(GetProofError::BlockNotFound)?;
 
+        if let Some(oldest_trie_block) = tx
+            .trie_pruned(header.number)
+            .context("Querying trie pruned status")?
+        {
+            return Err(GetProofError::BlockPruned(oldest_trie_block));
+        }
+
         let class_root_idx = tx
             .class_root_index(header.number)
             .context("Querying class root index")?;
@@ -524,9 +554,7 @@ mod tests {
                 },
             ]);
             let actual = nodes
-                .serialize(crate::dto::Serializer {
-                    version: crate::RpcVersion::default(),
-                })
+                .serialize(crate::dto::Serializer::new(crate::RpcVersion::default()))
                 .unwrap();
             let expected = serde_json::json!(
                 [
@@ -625,6 +653,53 @@ mod tests {
             assert_matches::assert_matches!(err, GetProofError::ProofMissing);
         }
 
+        #[tokio::test]
+        async fn trie_pruned() {
+            // Contract updates are applied to the tries using additional connections.
+            let storage =
+                pathfinder_storage::StorageBuilder::in_tempdir_with_trie_pruning_and_pool_size(
+                    pathfinder_storage::TriePruneMode::Prune { num_blocks_kept: 1 },
+                    NonZeroU32::new(32).unwrap(),
+                )
+                .unwrap();
+            let blocks = pathfinder_storage::fake::generate::with_config(
+                4,
+                pathfinder_storage::fake::Config {
+                    update_tries: Box::new(update_starknet_state),
+                    occurrence: pathfinder_storage::fake::OccurrencePerBlock {
+                        sierra: 1..=10,
+                        storage: 1..=10,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            );
+            pathfinder_storage::fake::fill(
+                &storage,
+                &blocks,
+                Some(Box::new(update_starknet_state)),
+            );
+
+            let context = RpcContext::for_tests().with_storage(storage);
+            let input = |block_id| GetProofInput {
+                block_id,
+                contract_address: contract_address!("0xabcd"),
+                keys: vec![storage_address!("0x1234")],
+            };
+
+            let err = get_proof(
+                context.clone(),
+                input(BlockId::Number(BlockNumber::GENESIS + 1)),
+            )
+            .await
+            .unwrap_err();
+            assert_matches::assert_matches!(err, GetProofError::BlockPruned(oldest) if oldest == BlockNumber::GENESIS + 2);
+
+            get_proof(context, input(BlockId::Number(BlockNumber::GENESIS + 2)))
+                .await
+                .unwrap();
+        }
+
         #[tokio::test]
         async fn chain_without_contract_updates() {
             let storage =
@@ -719,6 +794,52 @@ mod tests {
             assert_matches::assert_matches!(err, GetProofError::ProofMissing);
         }
 
+        #[tokio::test]
+        async fn trie_pruned() {
+            // Contract updates are applied to the tries using additional connections.
+            let storage =
+                pathfinder_storage::StorageBuilder::in_tempdir_with_trie_pruning_and_pool_size(
+                    TriePruneMode::Prune { num_blocks_kept: 1 },
+                    NonZeroU32::new(32).unwrap(),
+                )
+                .unwrap();
+            let blocks = pathfinder_storage::fake::generate::with_config(
+                4,
+                Config {
+                    update_tries: Box::new(update_starknet_state),
+                    occurrence: OccurrencePerBlock {
+                        sierra: 1..=10,
+                        storage: 1..=10,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            );
+            pathfinder_storage::fake::fill(
+                &storage,
+                &blocks,
+                Some(Box::new(update_starknet_state)),
+            );
+
+            let context = RpcContext::for_tests().with_storage(storage);
+            let input = |block_id| GetClassProofInput {
+                block_id,
+                class_hash: class_hash!("0x1"),
+            };
+
+            let err = get_class_proof(
+                context.clone(),
+                input(BlockId::Number(BlockNumber::GENESIS + 1)),
+            )
+            .await
+            .unwrap_err();
+            assert_matches::assert_matches!(err, GetProofError::BlockPruned(oldest) if oldest == BlockNumber::GENESIS + 2);
+
+            get_class_proof(context, input(BlockId::Number(BlockNumber::GENESIS + 2)))
+                .await
+                .unwrap();
+        }
+
         #[tokio::test]
         async fn chain_without_class_declarations() {
             let storage =
@@ -0,0 +1,357 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, BlockTimestamp, GasPrice};
+use pathfinder_executor::types::{BlockSimulation, BlocksSimulation, VirtualBlock};
+use serde::de::Error;
+
+use crate::context::RpcContext;
+use crate::method::simulate_transactions::{SimulateTransactionError, TransactionSimulation};
+use crate::types::request::{BroadcastedTransaction, StateOverride};
+
+#[derive(Debug)]
+pub struct SimulateBlocksInput {
+    pub block_id: BlockId,
+    pub blocks: Vec<VirtualBlockInput>,
+    pub simulation_flags: crate::dto::SimulationFlags,
+    pub state_override: StateOverride,
+}
+
+/// A block to simulate on top of the base block. Fields which are not set are
+/// taken from the previous block: the block number is incremented by one, the
+/// timestamp and gas prices are left unchanged.
+#[derive(Debug)]
+pub struct VirtualBlockInput {
+    pub block_number: Option<BlockNumber>,
+    pub timestamp: Option<BlockTimestamp>,
+    pub l1_gas_price: Option<ResourcePrice>,
+    pub l1_data_gas_price: Option<ResourcePrice>,
+    pub l2_gas_price: Option<ResourcePrice>,
+    pub transactions: Vec<BroadcastedTransaction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourcePrice {
+    pub price_in_wei: GasPrice,
+    pub price_in_fri: GasPrice,
+}
+
+impl crate::dto::DeserializeForVersion for SimulateBlocksInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                blocks: value.deserialize_array("blocks", |value| value.deserialize())?,
+                simulation_flags: value.deserialize("simulation_flags")?,
+                state_override: value
+                    .deserialize_optional("state_override")?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
+
+impl crate::dto::DeserializeForVersion for VirtualBlockInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_number: value
+                    .deserialize_optional("block_number")?
+                    .map(|number| {
+                        BlockNumber::new(number)
+                            .ok_or_else(|| serde_json::Error::custom("Invalid block number"))
+                    })
+                    .transpose()?,
+                timestamp: value
+                    .deserialize_optional("timestamp")?
+                    .map(|timestamp| {
+                        BlockTimestamp::new(timestamp)
+                            .ok_or_else(|| serde_json::Error::custom("Invalid timestamp"))
+                    })
+                    .transpose()?,
+                l1_gas_price: value.deserialize_optional("l1_gas_price")?,
+                l1_data_gas_price: value.deserialize_optional("l1_data_gas_price")?,
+                l2_gas_price: value.deserialize_optional("l2_gas_price")?,
+                transactions: value
+                    .deserialize_array("transactions", |value| value.deserialize())?,
+            })
+        })
+    }
+}
+
+impl crate::dto::DeserializeForVersion for ResourcePrice {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                price_in_wei: GasPrice(value.deserialize::<crate::dto::U128Hex>("price_in_wei")?.0),
+                price_in_fri: GasPrice(value.deserialize::<crate::dto::U128Hex>("price_in_fri")?.0),
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulateBlocksOutput(BlocksSimulation);
+
+/// Simulates transactions in a sequence of virtual blocks on top of the given
+/// block, carrying the state over from one virtual block to the next.
+pub async fn simulate_blocks(
+    context: RpcContext,
+    input: SimulateBlocksInput,
+) -> Result<SimulateBlocksOutput, SimulateTransactionError> {
+    let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
+    util::task::spawn_blocking(move |_| {
+        let _g = span.enter();
+
+        let skip_validate = input
+            .simulation_flags
+            .0
+            .iter()
+            .any(|flag| flag == &crate::dto::SimulationFlag::SkipValidate);
+
+        let skip_fee_charge = input
+            .simulation_flags
+            .0
+            .iter()
+            .any(|flag| flag == &crate::dto::SimulationFlag::SkipFeeCharge);
+
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending should fail");
+
+                if let Some(oldest_available) = db
+                    .block_pruned(block_id)
+                    .context("Querying block pruned status")?
+                {
+                    return Err(SimulateTransactionError::BlockPruned(oldest_available));
+                }
+
+                let header = db
+                    .block_header(block_id)
+                    .context("Fetching block header")?
+                    .ok_or(SimulateTransactionError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let mut parent = header.clone();
+        let mut blocks = Vec::with_capacity(input.blocks.len());
+        for block in input.blocks {
+            let mut block_header = parent.clone();
+            block_header.number = match block.block_number {
+                Some(block_number) => block_number,
+                None => parent.number + 1,
+            };
+            if let Some(timestamp) = block.timestamp {
+                block_header.timestamp = timestamp;
+            }
+            if let Some(price) = block.l1_gas_price {
+                block_header.eth_l1_gas_price = price.price_in_wei;
+                block_header.strk_l1_gas_price = price.price_in_fri;
+            }
+            if let Some(price) = block.l1_data_gas_price {
+                block_header.eth_l1_data_gas_price = price.price_in_wei;
+                block_header.strk_l1_data_gas_price = price.price_in_fri;
+            }
+            if let Some(price) = block.l2_gas_price {
+                block_header.eth_l2_gas_price = price.price_in_wei;
+                block_header.strk_l2_gas_price = price.price_in_fri;
+            }
+
+            let transactions = block
+                .transactions
+                .into_iter()
+                .map(|tx| {
+                    crate::executor::map_broadcasted_transaction(
+                        &tx,
+                        context.chain_id,
+                        skip_validate,
+                        skip_fee_charge,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            parent = block_header.clone();
+            blocks.push(VirtualBlock {
+                header: block_header,
+                transactions,
+            });
+        }
+
+        let state = pathfinder_executor::ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            pathfinder_executor::L1BlobDataAvailability::Enabled,
+            context.config.versioned_constants_map,
+            context.contract_addresses.eth_l2_token_address,
+            context.contract_addresses.strk_l2_token_address,
+            context.native_class_cache,
+        )
+        .with_state_overrides(input.state_override.0)
+        .with_cancellation(cancellation);
+
+        let simulation = pathfinder_executor::simulate_blocks(
+            state,
+            blocks,
+            context.config.fee_estimation_epsilon,
+        )?;
+        Ok(SimulateBlocksOutput(simulation))
+    })
+    .await
+    .context("Simulating blocks")?
+}
+
+impl crate::dto::SerializeForVersion for SimulateBlocksOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter(
+            "blocks",
+            self.0.blocks.len(),
+            &mut self.0.blocks.iter().map(BlockSimulationWrapper),
+        )?;
+        serializer.serialize_field("state_diff", &self.0.state_diff)?;
+        serializer.end()
+    }
+}
+
+struct BlockSimulationWrapper<'a>(&'a BlockSimulation);
+
+impl crate::dto::SerializeForVersion for BlockSimulationWrapper<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.0.block_number)?;
+        serializer.serialize_iter(
+            "simulated_transactions",
+            self.0.transactions.len(),
+            &mut self.0.transactions.iter().map(TransactionSimulation),
+        )?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::prelude::*;
+
+    use super::*;
+    use crate::method::simulate_transactions::tests::{
+        fixtures,
+        setup_storage_with_starknet_version,
+    };
+
+    fn virtual_block(
+        block_number: Option<BlockNumber>,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> VirtualBlockInput {
+        VirtualBlockInput {
+            block_number,
+            timestamp: None,
+            l1_gas_price: None,
+            l1_data_gas_price: None,
+            l2_gas_price: None,
+            transactions,
+        }
+    }
+
+    #[tokio::test]
+    async fn state_is_carried_over_between_blocks() {
+        let (storage, last_block_header, account_contract_address, universal_deployer_address, _) =
+            setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let input = SimulateBlocksInput {
+            block_id: BlockId::Number(last_block_header.number),
+            blocks: vec![
+                virtual_block(
+                    None,
+                    vec![
+                        fixtures::input::declare(account_contract_address),
+                        fixtures::input::universal_deployer(
+                            account_contract_address,
+                            universal_deployer_address,
+                        ),
+                    ],
+                ),
+                VirtualBlockInput {
+                    timestamp: Some(BlockTimestamp::new_or_panic(
+                        last_block_header.timestamp.get() + 3600,
+                    )),
+                    ..virtual_block(
+                        Some(last_block_header.number + 5),
+                        vec![fixtures::input::invoke(account_contract_address)],
+                    )
+                },
+            ],
+            simulation_flags: crate::dto::SimulationFlags(vec![]),
+            state_override: Default::default(),
+        };
+        let SimulateBlocksOutput(simulation) = simulate_blocks(context, input).await.unwrap();
+
+        let block_numbers = simulation
+            .blocks
+            .iter()
+            .map(|block| (block.block_number, block.transactions.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            block_numbers,
+            vec![
+                (last_block_header.number + 1, 2),
+                (last_block_header.number + 5, 1)
+            ]
+        );
+        for block in &simulation.blocks {
+            for transaction in &block.transactions {
+                assert_eq!(transaction.revert_reason(), None);
+            }
+        }
+
+        assert_eq!(simulation.state_diff.declared_classes.len(), 1);
+        assert_eq!(
+            simulation.state_diff.nonces.get(&account_contract_address),
+            Some(&contract_nonce!("0x3"))
+        );
+    }
+
+    #[tokio::test]
+    async fn block_numbers_must_increase() {
+        let (storage, last_block_header, account_contract_address, _, _) =
+            setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let input = SimulateBlocksInput {
+            block_id: BlockId::Number(last_block_header.number),
+            blocks: vec![virtual_block(
+                Some(last_block_header.number),
+                vec![fixtures::input::declare(account_contract_address)],
+            )],
+            simulation_flags: crate::dto::SimulationFlags(vec![]),
+            state_override: Default::default(),
+        };
+        let error = simulate_blocks(context, input).await.unwrap_err();
+
+        assert_matches::assert_matches!(error, SimulateTransactionError::Custom(_));
+    }
+}
@@ -0,0 +1,250 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use pathfinder_common::{BlockHash, BlockNumber};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::context::RpcContext;
+use crate::jsonrpc::{missed_reorgs, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::Reorg;
+
+/// Notifies the client of every reorg, with the range of orphaned blocks and
+/// the block the replacing blocks build on.
+pub struct SubscribeReorgs;
+
+#[derive(Debug, Clone)]
+pub struct Params;
+
+impl crate::dto::DeserializeForVersion for Params {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        if value.is_null() {
+            // Params are optional.
+            return Ok(Self);
+        }
+        value.deserialize_map(|_| Ok(Self))
+    }
+}
+
+#[derive(Debug)]
+pub struct Notification(Arc<Reorg>);
+
+impl crate::dto::SerializeForVersion for Notification {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("first_block_number", &self.0.first_block_number)?;
+        serializer.serialize_field("first_block_hash", &self.0.first_block_hash)?;
+        serializer.serialize_field("last_block_number", &self.0.last_block_number)?;
+        serializer.serialize_field("last_block_hash", &self.0.last_block_hash)?;
+        serializer.serialize_optional_with_null(
+            "new_head",
+            self.0
+                .new_head
+                .map(|(number, hash)| NewHead { number, hash }),
+        )?;
+        serializer.end()
+    }
+}
+
+struct NewHead {
+    number: BlockNumber,
+    hash: BlockHash,
+}
+
+impl crate::dto::SerializeForVersion for NewHead {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.number)?;
+        serializer.serialize_field("block_hash", &self.hash)?;
+        serializer.end()
+    }
+}
+
+const SUBSCRIPTION_NAME: &str = "pathfinder_subscriptionReorg";
+
+#[async_trait]
+impl RpcSubscriptionFlow for SubscribeReorgs {
+    type Params = Params;
+    type Notification = Notification;
+
+    fn reorg_notification(reorg: Arc<Reorg>) -> Option<SubscriptionMessage<Self::Notification>> {
+        Some(message(reorg))
+    }
+
+    async fn subscribe(
+        state: RpcContext,
+        _params: Self::Params,
+        tx: mpsc::Sender<SubscriptionMessage<Self::Notification>>,
+    ) -> Result<(), RpcError> {
+        let mut reorgs = state.notifications.reorgs.subscribe();
+        loop {
+            tokio::select! {
+                reorg = reorgs.recv() => {
+                    match reorg {
+                        Ok(reorg) => {
+                            if tx.send(message(reorg)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            tracing::debug!("Reorg notifications lagged, stopping subscription");
+                            return Err(missed_reorgs());
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+                _ = tx.closed() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn message(reorg: Arc<Reorg>) -> SubscriptionMessage<Notification> {
+    SubscriptionMessage {
+        block_number: reorg.first_block_number,
+        notification: Notification(reorg),
+        subscription_name: SUBSCRIPTION_NAME,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::extract::ws::Message;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockHeader;
+    use pathfinder_storage::StorageBuilder;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::jsonrpc::{handle_json_rpc_socket, RpcResponse, RpcRouter};
+    use crate::Notifications;
+
+    async fn setup() -> RpcRouter {
+        let storage = StorageBuilder::in_memory().unwrap();
+        tokio::task::spawn_blocking({
+            let storage = storage.clone();
+            move || {
+                let mut conn = storage.connection().unwrap();
+                let db = conn.transaction().unwrap();
+                db.insert_block_header(&BlockHeader {
+                    hash: block_hash!("0x1"),
+                    ..Default::default()
+                })
+                .unwrap();
+                db.commit().unwrap();
+            }
+        })
+        .await
+        .unwrap();
+        let ctx = RpcContext::for_tests()
+            .with_storage(storage)
+            .with_notifications(Notifications::default());
+        crate::pathfinder::register_routes().build(ctx)
+    }
+
+    async fn recv(rx: &mut mpsc::Receiver<Result<Message, RpcResponse>>) -> serde_json::Value {
+        let msg = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match msg {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reorgs_are_notified() {
+        let router = setup().await;
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "pathfinder_subscribeReorgs",
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let response = recv(&mut sender_rx).await;
+        assert_eq!(response["id"], 1);
+        let subscription_id = response["result"].as_u64().unwrap();
+
+        let reorgs = [
+            Reorg {
+                first_block_number: BlockNumber::new_or_panic(3),
+                first_block_hash: block_hash!("0x3"),
+                last_block_number: BlockNumber::new_or_panic(5),
+                last_block_hash: block_hash!("0x5"),
+                new_head: Some((BlockNumber::new_or_panic(2), block_hash!("0x2"))),
+            },
+            Reorg {
+                first_block_number: BlockNumber::GENESIS,
+                first_block_hash: block_hash!("0x10"),
+                last_block_number: BlockNumber::new_or_panic(4),
+                last_block_hash: block_hash!("0x14"),
+                new_head: None,
+            },
+        ];
+        let expected = [
+            serde_json::json!({
+                "first_block_number": 3,
+                "first_block_hash": "0x3",
+                "last_block_number": 5,
+                "last_block_hash": "0x5",
+                "new_head": {"block_number": 2, "block_hash": "0x2"},
+            }),
+            serde_json::json!({
+                "first_block_number": 0,
+                "first_block_hash": "0x10",
+                "last_block_number": 4,
+                "last_block_hash": "0x14",
+                "new_head": null,
+            }),
+        ];
+
+        for (reorg, expected) in reorgs.into_iter().zip(expected) {
+            router
+                .context
+                .notifications
+                .reorgs
+                .send(Arc::new(reorg))
+                .unwrap();
+            assert_eq!(
+                recv(&mut sender_rx).await,
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "pathfinder_subscriptionReorg",
+                    "params": {
+                        "result": expected,
+                        "subscription_id": subscription_id,
+                    }
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_params_are_rejected() {
+        use crate::dto::DeserializeForVersion;
+
+        let value = crate::dto::Value::new(
+            serde_json::json!({"unknown": 1}),
+            crate::RpcVersion::PathfinderV01,
+        );
+        assert!(Params::deserialize(value).is_err());
+    }
+}
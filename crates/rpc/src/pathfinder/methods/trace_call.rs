@@ -0,0 +1,244 @@
+use anyhow::Context;
+use pathfinder_common::BlockId;
+use pathfinder_executor::types::{CallTrace, StorageAccess};
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+
+use crate::context::RpcContext;
+use crate::method::call::{CallError, FunctionCall};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TraceCallInput {
+    pub request: FunctionCall,
+    pub block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for TraceCallInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                request: value.deserialize("request")?,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceCallOutput(CallTrace);
+
+/// Executes a call like `starknet_call` and returns its full invocation tree
+/// together with every storage slot it read or wrote.
+pub async fn trace_call(
+    context: RpcContext,
+    input: TraceCallInput,
+) -> Result<TraceCallOutput, CallError> {
+    let span = tracing::Span::current();
+    let cancellation = crate::executor::request_cancellation();
+    let trace = util::task::spawn_blocking(move |_| {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+
+                if let Some(oldest_available) = db
+                    .block_pruned(block_id)
+                    .context("Querying block pruned status")?
+                {
+                    return Err(CallError::BlockPruned(oldest_available));
+                }
+
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(CallError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            L1BlobDataAvailability::Disabled,
+            context.config.versioned_constants_map,
+            context.contract_addresses.eth_l2_token_address,
+            context.contract_addresses.strk_l2_token_address,
+            context.native_class_cache,
+        )
+        .with_cancellation(cancellation);
+
+        let trace = pathfinder_executor::trace_call(
+            state,
+            input.request.contract_address,
+            input.request.entry_point_selector,
+            input.request.calldata,
+        )?;
+
+        Ok(trace)
+    })
+    .await
+    .context("Executing call")??;
+
+    Ok(TraceCallOutput(trace))
+}
+
+impl crate::dto::SerializeForVersion for TraceCallOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("function_invocation", &&self.0.invocation)?;
+        serializer.serialize_optional("revert_reason", self.0.revert_reason.as_deref())?;
+        serializer.serialize_iter(
+            "storage_accesses",
+            self.0.storage_accesses.len(),
+            &mut self.0.storage_accesses.iter().map(StorageAccessWrapper),
+        )?;
+        serializer.end()
+    }
+}
+
+struct StorageAccessWrapper<'a>(&'a StorageAccess);
+
+impl crate::dto::SerializeForVersion for StorageAccessWrapper<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", &self.0.contract_address)?;
+        serializer.serialize_field("key", &self.0.key)?;
+        serializer.serialize_field("pre_value", &self.0.pre_value)?;
+        serializer.serialize_field("post_value", &self.0.post_value)?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::prelude::*;
+    use pathfinder_common::{felt, CallParam};
+    use starknet_gateway_test_fixtures::class_definitions::{
+        CONTRACT_DEFINITION,
+        CONTRACT_DEFINITION_CLASS_HASH,
+    };
+
+    use super::*;
+
+    /// A context at block 1 with the test contract deployed at the returned
+    /// address, and its storage slot `0x123` set to 3.
+    fn setup() -> (RpcContext, ContractAddress, StorageAddress, StorageValue) {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .timestamp(BlockTimestamp::new_or_panic(0))
+            .finalize_with_hash(BlockHash(felt!("0xb00")));
+        tx.insert_block_header(&header).unwrap();
+
+        tx.insert_cairo_class(CONTRACT_DEFINITION_CLASS_HASH, CONTRACT_DEFINITION)
+            .unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::new_or_panic(1))
+            .timestamp(BlockTimestamp::new_or_panic(1))
+            .eth_l1_gas_price(GasPrice(1))
+            .finalize_with_hash(BlockHash(felt!("0xb01")));
+        tx.insert_block_header(&header).unwrap();
+
+        let contract_address = ContractAddress::new_or_panic(felt!("0xc01"));
+        let key = StorageAddress::new_or_panic(felt!("0x123"));
+        let value = StorageValue(felt!("0x3"));
+
+        let state_update = StateUpdate::default()
+            .with_block_hash(header.hash)
+            .with_declared_cairo_class(CONTRACT_DEFINITION_CLASS_HASH)
+            .with_deployed_contract(contract_address, CONTRACT_DEFINITION_CLASS_HASH)
+            .with_storage_update(contract_address, key, value);
+        tx.insert_state_update(header.number, &state_update)
+            .unwrap();
+
+        tx.commit().unwrap();
+        drop(db);
+
+        let context =
+            RpcContext::for_tests_on(pathfinder_common::Chain::Mainnet).with_storage(storage);
+
+        (context, contract_address, key, value)
+    }
+
+    #[tokio::test]
+    async fn records_storage_reads() {
+        let (context, contract_address, key, value) = setup();
+
+        let input = TraceCallInput {
+            request: FunctionCall {
+                contract_address,
+                entry_point_selector: EntryPoint::hashed(b"get_value"),
+                calldata: vec![CallParam(*key.get())],
+            },
+            block_id: BlockId::Latest,
+        };
+        let TraceCallOutput(trace) = trace_call(context, input).await.unwrap();
+
+        assert_eq!(trace.invocation.contract_address, contract_address);
+        assert_eq!(trace.invocation.result, vec![value.0]);
+        assert!(!trace.invocation.is_reverted);
+        assert_eq!(trace.revert_reason, None);
+        assert_eq!(
+            trace.storage_accesses,
+            vec![StorageAccess {
+                contract_address,
+                key,
+                pre_value: value,
+                post_value: value,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_storage_writes() {
+        let (context, contract_address, key, value) = setup();
+
+        let input = TraceCallInput {
+            request: FunctionCall {
+                contract_address,
+                entry_point_selector: EntryPoint::hashed(b"increase_value"),
+                calldata: vec![CallParam(*key.get()), CallParam(felt!("0x2"))],
+            },
+            block_id: BlockId::Latest,
+        };
+        let TraceCallOutput(trace) = trace_call(context, input).await.unwrap();
+
+        assert!(!trace.invocation.is_reverted);
+        assert_eq!(
+            trace.storage_accesses,
+            vec![StorageAccess {
+                contract_address,
+                key,
+                pre_value: value,
+                post_value: StorageValue(felt!("0x5")),
+            }]
+        );
+    }
+}
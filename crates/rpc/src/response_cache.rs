@@ -0,0 +1,620 @@
+//! Caching of responses to requests for finalized data.
+//!
+//! Blocks which have been accepted on L1 no longer change, so responses to
+//! requests for such blocks, or for transactions in them, can be served from
+//! memory when the same request is repeated. Requests are only cached if they
+//! identify a specific block or transaction, and their response is only stored
+//! if the block was known to be L1-accepted when the request was received,
+//! according to [SyncState::l1_accepted](crate::SyncState::l1_accepted).
+//! Blocks identified by hash, and the blocks of transactions, are looked up in
+//! the database to find their number.
+//!
+//! The whole cache is cleared on every reorg. L1-accepted blocks are not
+//! expected to be reorged, but a stale response is worse than a cache miss.
+
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, TransactionHash};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::Storage;
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::jsonrpc::{Encoding, RawParams, Reorg};
+use crate::RpcVersion;
+
+/// Methods whose responses are cached, and the names of their params in
+/// order. Each of them has either a `block_id` or a `transaction_hash` param
+/// identifying what the response is about.
+const CACHEABLE_METHODS: &[(&str, &[&str])] = &[
+    ("starknet_getBlockWithTxHashes", &["block_id"]),
+    ("starknet_getBlockWithTxs", &["block_id"]),
+    ("starknet_getBlockWithReceipts", &["block_id"]),
+    ("starknet_getBlockTransactionCount", &["block_id"]),
+    ("starknet_getStateUpdate", &["block_id"]),
+    (
+        "starknet_getTransactionByBlockIdAndIndex",
+        &["block_id", "index"],
+    ),
+    ("starknet_getClass", &["block_id", "class_hash"]),
+    ("starknet_getClassAt", &["block_id", "contract_address"]),
+    ("starknet_getClassHashAt", &["block_id", "contract_address"]),
+    ("starknet_getNonce", &["block_id", "contract_address"]),
+    (
+        "starknet_getStorageAt",
+        &["contract_address", "key", "block_id"],
+    ),
+    ("starknet_getTransactionReceipt", &["transaction_hash"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Block(BlockId),
+    Transaction(TransactionHash),
+}
+
+/// Method, version, response encoding and canonicalized params of a request.
+type Key = (&'static str, &'static str, Encoding, String);
+
+#[derive(Debug)]
+pub(crate) enum Lookup {
+    Hit(Value),
+    /// The response can be cached by passing the request to
+    /// [ResponseCache::insert].
+    Miss(CacheableRequest),
+    Uncacheable,
+}
+
+#[derive(Debug)]
+pub(crate) struct CacheableRequest {
+    key: Key,
+    target: Target,
+    /// The latest L1-accepted block when the request was received.
+    l1_accepted: BlockNumber,
+    generation: u64,
+}
+
+impl CacheableRequest {
+    /// Returns the request if the block it is about had been accepted on L1
+    /// when it was received, i.e. if its response can be cached.
+    ///
+    /// This has to be checked before executing the request, so that a response
+    /// computed from data which changed before the block was accepted is never
+    /// cached.
+    pub(crate) async fn if_l1_accepted(self, storage: Storage) -> Option<Self> {
+        let block_number = match self.target {
+            Target::Block(BlockId::Number(number)) => Some(number),
+            target => {
+                let result = util::task::spawn_blocking(move |_| block_number(&storage, target))
+                    .await
+                    .context("Joining database task")
+                    .and_then(|result| result);
+                match result {
+                    Ok(block_number) => block_number,
+                    Err(error) => {
+                        tracing::debug!(?error, "Looking up block of cacheable request");
+                        None
+                    }
+                }
+            }
+        };
+
+        block_number
+            .is_some_and(|number| number <= self.l1_accepted)
+            .then_some(self)
+    }
+}
+
+/// The number of the block `target` refers to, if it is known.
+fn block_number(storage: &Storage, target: Target) -> anyhow::Result<Option<BlockNumber>> {
+    let mut db = storage
+        .connection()
+        .context("Opening database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let block_hash = match target {
+        Target::Block(BlockId::Number(number)) => return Ok(Some(number)),
+        Target::Block(BlockId::Hash(hash)) => hash,
+        Target::Block(BlockId::Latest | BlockId::Pending) => return Ok(None),
+        Target::Transaction(hash) => match db
+            .transaction_block_hash(hash)
+            .context("Querying transaction block")?
+        {
+            Some(hash) => hash,
+            None => return Ok(None),
+        },
+    };
+
+    db.block_number(block_hash.into())
+        .context("Querying block number")
+}
+
+#[derive(Clone)]
+pub struct ResponseCache(Arc<Mutex<Inner>>);
+
+struct Inner {
+    entries: HashMap<Key, Entry>,
+    /// Keys by the time they were last used, oldest first.
+    last_used: BTreeMap<u64, Key>,
+    clock: u64,
+    /// Total serialized size of the cached responses.
+    size: usize,
+    capacity: usize,
+    reorgs: broadcast::Receiver<Arc<Reorg>>,
+    /// Incremented whenever the cache is cleared, so that responses computed
+    /// before a reorg are not inserted after it.
+    generation: u64,
+}
+
+struct Entry {
+    response: Value,
+    size: usize,
+    last_used: u64,
+}
+
+impl ResponseCache {
+    /// `capacity` is the maximum total size of the cached responses, in bytes
+    /// of serialized JSON.
+    pub fn new(capacity: NonZeroUsize, reorgs: broadcast::Receiver<Arc<Reorg>>) -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            entries: Default::default(),
+            last_used: Default::default(),
+            clock: 0,
+            size: 0,
+            capacity: capacity.get(),
+            reorgs,
+            generation: 0,
+        })))
+    }
+
+    /// `l1_accepted` is the latest block currently known to be accepted on L1.
+    pub(crate) fn get(
+        &self,
+        version: RpcVersion,
+        encoding: Encoding,
+        method: &'static str,
+        params: &RawParams<'_>,
+        l1_accepted: Option<BlockNumber>,
+    ) -> Lookup {
+        let Some(l1_accepted) = l1_accepted else {
+            return Lookup::Uncacheable;
+        };
+        let Some((target, params)) = parse_request(version, method, params) else {
+            return Lookup::Uncacheable;
+        };
+        if matches!(target, Target::Block(BlockId::Number(number)) if number > l1_accepted) {
+            return Lookup::Uncacheable;
+        }
+        let key = (version.to_str(), method, encoding, params);
+
+        let mut inner = self.lock();
+        inner.handle_reorgs();
+
+        match inner.get(&key) {
+            Some(response) => Lookup::Hit(response),
+            None => Lookup::Miss(CacheableRequest {
+                key,
+                target,
+                l1_accepted,
+                generation: inner.generation,
+            }),
+        }
+    }
+
+    /// Stores the response to `request`, which has to be about an
+    /// L1-accepted block, see [CacheableRequest::if_l1_accepted].
+    pub(crate) fn insert(&self, request: CacheableRequest, response: &Value) {
+        self.lock().insert(request, response.clone());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        // The cache is always left in a consistent state, so it is still usable if
+        // another thread panicked while holding the lock.
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Inner {
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.last_used.remove(&entry.last_used);
+        self.last_used.insert(self.clock, key.clone());
+        entry.last_used = self.clock;
+
+        Some(entry.response.clone())
+    }
+
+    fn insert(&mut self, request: CacheableRequest, response: Value) {
+        self.handle_reorgs();
+        if request.generation != self.generation {
+            return;
+        }
+
+        let size = serialized_size(&response);
+        if size > self.capacity {
+            return;
+        }
+
+        if let Some(entry) = self.entries.remove(&request.key) {
+            self.last_used.remove(&entry.last_used);
+            self.size -= entry.size;
+        }
+
+        while self.size + size > self.capacity {
+            let Some((_, key)) = self.last_used.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.size -= entry.size;
+            }
+        }
+
+        self.clock += 1;
+        self.last_used.insert(self.clock, request.key.clone());
+        self.entries.insert(
+            request.key,
+            Entry {
+                response,
+                size,
+                last_used: self.clock,
+            },
+        );
+        self.size += size;
+
+        metrics::gauge!("rpc_response_cache_size_bytes", self.size as f64);
+    }
+
+    fn handle_reorgs(&mut self) {
+        loop {
+            match self.reorgs.try_recv() {
+                Ok(_) | Err(TryRecvError::Lagged(_)) => self.clear(),
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.last_used.clear();
+        self.size = 0;
+        self.generation += 1;
+
+        metrics::gauge!("rpc_response_cache_size_bytes", 0.0);
+    }
+}
+
+/// Returns the block or transaction the request is about, and its params in
+/// canonical form, if the request can be cached.
+fn parse_request(
+    version: RpcVersion,
+    method: &str,
+    params: &RawParams<'_>,
+) -> Option<(Target, String)> {
+    let (_, names) = CACHEABLE_METHODS.iter().find(|(name, _)| *name == method)?;
+    let params: Value = serde_json::from_str(params.0?.get()).ok()?;
+    let params = named_params(names, params)?;
+
+    let target = if let Some(block_id) = params.get("block_id") {
+        match crate::dto::Value::new(block_id.clone(), version)
+            .deserialize::<BlockId>()
+            .ok()?
+        {
+            // These move with the chain.
+            BlockId::Latest | BlockId::Pending => return None,
+            block_id => Target::Block(block_id),
+        }
+    } else {
+        let hash = params.get("transaction_hash")?.clone();
+        Target::Transaction(TransactionHash(
+            crate::dto::Value::new(hash, version)
+                .deserialize::<Felt>()
+                .ok()?,
+        ))
+    };
+
+    // Objects are serialized with their keys sorted.
+    Some((target, Value::Object(params).to_string()))
+}
+
+/// Converts positional params to named ones, so that requests using either
+/// form share their cache entries.
+fn named_params(names: &[&str], params: Value) -> Option<Map<String, Value>> {
+    match params {
+        Value::Object(params) => Some(params),
+        Value::Array(params) if params.len() <= names.len() => Some(
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .zip(params)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn serialized_size(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    // Writing to the counter cannot fail.
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::transaction_hash_bytes;
+    use serde_json::json;
+
+    use super::*;
+
+    fn params(value: Value) -> Box<serde_json::value::RawValue> {
+        serde_json::value::to_raw_value(&value).unwrap()
+    }
+
+    /// Looks up the request with genesis being the latest L1-accepted block.
+    fn lookup(cache: &ResponseCache, method: &'static str, value: Value) -> Lookup {
+        let raw = params(value);
+        cache.get(
+            RpcVersion::V07,
+            Encoding::Json,
+            method,
+            &RawParams(Some(&raw)),
+            Some(BlockNumber::GENESIS),
+        )
+    }
+
+    fn setup(capacity: usize) -> (ResponseCache, broadcast::Sender<Arc<Reorg>>) {
+        let (reorgs, rx) = broadcast::channel(8);
+        let cache = ResponseCache::new(NonZeroUsize::new(capacity).unwrap(), rx);
+        (cache, reorgs)
+    }
+
+    fn fill(cache: &ResponseCache, method: &'static str, params: Value, response: Value) {
+        let Lookup::Miss(request) = lookup(cache, method, params) else {
+            panic!("Expected a cache miss");
+        };
+        cache.insert(request, &response);
+    }
+
+    #[test]
+    fn only_l1_accepted_blocks_are_cached() {
+        let (cache, _reorgs) = setup(1024);
+        let genesis = json!({"block_id": {"block_number": 0}});
+
+        fill(
+            &cache,
+            "starknet_getBlockWithTxHashes",
+            genesis.clone(),
+            json!("genesis"),
+        );
+
+        assert_matches::assert_matches!(
+            lookup(&cache, "starknet_getBlockWithTxHashes", genesis.clone()),
+            Lookup::Hit(response) if response == json!("genesis")
+        );
+        assert_matches::assert_matches!(
+            lookup(
+                &cache,
+                "starknet_getBlockWithTxHashes",
+                json!({"block_id": {"block_number": 1}})
+            ),
+            Lookup::Uncacheable
+        );
+        // Nothing is known to be L1-accepted.
+        let raw = params(genesis);
+        assert_matches::assert_matches!(
+            cache.get(
+                RpcVersion::V07,
+                Encoding::Json,
+                "starknet_getBlockWithTxHashes",
+                &RawParams(Some(&raw)),
+                None
+            ),
+            Lookup::Uncacheable
+        );
+    }
+
+    /// Whether the request's response can be cached according to `storage`.
+    async fn is_l1_accepted(
+        cache: &ResponseCache,
+        storage: &Storage,
+        method: &'static str,
+        params: Value,
+    ) -> bool {
+        let Lookup::Miss(request) = lookup(cache, method, params) else {
+            panic!("Expected a cache miss");
+        };
+        request.if_l1_accepted(storage.clone()).await.is_some()
+    }
+
+    #[tokio::test]
+    async fn blocks_by_hash_are_looked_up() {
+        let (cache, _reorgs) = setup(1024);
+        let (storage, test_data) = pathfinder_storage::test_utils::setup_test_storage();
+        let method = "starknet_getStateUpdate";
+
+        let genesis = json!([{"block_hash": test_data.headers[0].hash}]);
+        assert!(is_l1_accepted(&cache, &storage, method, genesis).await);
+        let block_1 = json!([{"block_hash": test_data.headers[1].hash}]);
+        assert!(!is_l1_accepted(&cache, &storage, method, block_1).await);
+        let unknown = json!([{"block_hash": "0xdead"}]);
+        assert!(!is_l1_accepted(&cache, &storage, method, unknown).await);
+    }
+
+    #[tokio::test]
+    async fn transactions_are_cached_by_their_block() {
+        let (cache, _reorgs) = setup(1024);
+        let (storage, test_data) = pathfinder_storage::test_utils::setup_test_storage();
+        let method = "starknet_getTransactionReceipt";
+
+        let in_genesis = json!([test_data.transactions.first().unwrap().hash]);
+        assert!(is_l1_accepted(&cache, &storage, method, in_genesis).await);
+        let in_latest = json!([test_data.transactions.last().unwrap().hash]);
+        assert!(!is_l1_accepted(&cache, &storage, method, in_latest).await);
+        let unknown = json!([transaction_hash_bytes!(b"unknown")]);
+        assert!(!is_l1_accepted(&cache, &storage, method, unknown).await);
+    }
+
+    #[test]
+    fn uncacheable_requests() {
+        let (cache, _reorgs) = setup(1024);
+
+        assert_matches::assert_matches!(
+            lookup(&cache, "starknet_getBlockWithTxHashes", json!(["latest"])),
+            Lookup::Uncacheable
+        );
+        assert_matches::assert_matches!(
+            lookup(
+                &cache,
+                "starknet_getNonce",
+                json!({"block_id": "pending", "contract_address": "0x1"})
+            ),
+            Lookup::Uncacheable
+        );
+        assert_matches::assert_matches!(
+            lookup(&cache, "starknet_call", json!([{}, {"block_number": 0}])),
+            Lookup::Uncacheable
+        );
+        assert_matches::assert_matches!(
+            lookup(&cache, "starknet_getStorageAt", json!(["0x1", "0x2"])),
+            Lookup::Uncacheable
+        );
+        assert_matches::assert_matches!(
+            lookup(
+                &cache,
+                "starknet_getBlockWithTxs",
+                json!([{"block_number": 0}, "extra"])
+            ),
+            Lookup::Uncacheable
+        );
+    }
+
+    #[test]
+    fn params_are_canonicalized() {
+        let (cache, _reorgs) = setup(1024);
+        let key = |value: Value| {
+            let Lookup::Miss(request) = lookup(&cache, "starknet_getNonce", value) else {
+                panic!("Expected a cache miss");
+            };
+            request.key
+        };
+
+        let named = key(json!({"contract_address": "0x1", "block_id": {"block_number": 0}}));
+        let reordered = key(json!({"block_id": {"block_number": 0}, "contract_address": "0x1"}));
+        let positional = key(json!([{"block_number": 0}, "0x1"]));
+
+        assert_eq!(named, reordered);
+        assert_eq!(named, positional);
+    }
+
+    #[test]
+    fn cleared_on_reorg() {
+        let (cache, reorgs) = setup(1024);
+        let genesis = json!([{"block_number": 0}]);
+
+        fill(
+            &cache,
+            "starknet_getStateUpdate",
+            genesis.clone(),
+            json!("state update"),
+        );
+        assert_matches::assert_matches!(
+            lookup(&cache, "starknet_getStateUpdate", genesis.clone()),
+            Lookup::Hit(_)
+        );
+
+        let Lookup::Miss(in_flight) = lookup(
+            &cache,
+            "starknet_getStateUpdate",
+            json!([{"block_hash": "0xb00"}]),
+        ) else {
+            panic!("Expected a cache miss");
+        };
+
+        reorgs
+            .send(Arc::new(Reorg {
+                first_block_number: BlockNumber::GENESIS,
+                first_block_hash: Default::default(),
+                last_block_number: BlockNumber::GENESIS,
+                last_block_hash: Default::default(),
+                new_head: None,
+            }))
+            .unwrap();
+
+        assert_matches::assert_matches!(
+            lookup(&cache, "starknet_getStateUpdate", genesis),
+            Lookup::Miss(_)
+        );
+
+        // Responses computed before the reorg are discarded.
+        cache.insert(in_flight, &json!({"block_number": 0}));
+        assert!(cache.lock().entries.is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let (cache, _reorgs) = setup(10);
+        let request = |hash: &str| {
+            let Lookup::Miss(request) = lookup(
+                &cache,
+                "starknet_getBlockWithTxs",
+                json!([{ "block_hash": hash }]),
+            ) else {
+                panic!("Expected a cache miss");
+            };
+            request
+        };
+
+        // Each response is 4 bytes.
+        let (a, b, c) = (request("0xa"), request("0xb"), request("0xc"));
+        cache.lock().insert(a, json!("aa"));
+        cache.lock().insert(b, json!("bb"));
+        // Make block a the most recently used.
+        assert_matches::assert_matches!(
+            lookup(
+                &cache,
+                "starknet_getBlockWithTxs",
+                json!([{"block_hash": "0xa"}])
+            ),
+            Lookup::Hit(_)
+        );
+        cache.lock().insert(c, json!("cc"));
+
+        let inner = cache.lock();
+        assert_eq!(inner.entries.len(), 2);
+        assert_eq!(inner.size, 8);
+        drop(inner);
+        assert_matches::assert_matches!(
+            lookup(
+                &cache,
+                "starknet_getBlockWithTxs",
+                json!([{"block_hash": "0xb"}])
+            ),
+            Lookup::Miss(_)
+        );
+
+        // Too large to be cached at all.
+        let d = request("0xd");
+        cache.lock().insert(d, json!("too large"));
+        assert_eq!(cache.lock().entries.len(), 2);
+    }
+}
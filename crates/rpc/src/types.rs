@@ -1265,6 +1265,61 @@ pub mod request {
         }
     }
 
+    /// State overrides accepted by the execution methods (`starknet_call`,
+    /// `starknet_estimateFee` and `starknet_simulateTransactions`) as an
+    /// optional trailing parameter.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    pub struct StateOverride(pub pathfinder_executor::types::StateOverrides);
+
+    impl crate::dto::DeserializeForVersion for StateOverride {
+        fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+            let contracts = value.deserialize_array(|value| {
+                value.deserialize_map(|value| {
+                    let contract_address = value.deserialize("contract_address")?;
+                    let contract_address = ContractAddress::new(contract_address)
+                        .ok_or_else(|| serde_json::Error::custom("Contract address out of range"))?;
+                    let storage = value
+                        .deserialize_optional_array("storage", |value| {
+                            value.deserialize_map(|value| {
+                                Ok((
+                                    StorageAddress::new(value.deserialize("key")?).ok_or_else(
+                                        || serde_json::Error::custom("Storage key out of range"),
+                                    )?,
+                                    value.deserialize("value").map(StorageValue)?,
+                                ))
+                            })
+                        })?
+                        .unwrap_or_default();
+
+                    Ok((
+                        contract_address,
+                        pathfinder_executor::types::ContractStateOverride {
+                            storage: storage.into_iter().collect(),
+                            nonce: value.deserialize_optional("nonce")?.map(ContractNonce),
+                            class_hash: value.deserialize_optional("class_hash")?.map(ClassHash),
+                            eth_balance: value.deserialize_optional_serde("eth_balance")?,
+                            strk_balance: value.deserialize_optional_serde("strk_balance")?,
+                        },
+                    ))
+                })
+            })?;
+
+            let mut overrides = pathfinder_executor::types::StateOverrides::new();
+            for (contract_address, contract_override) in contracts {
+                if overrides
+                    .insert(contract_address, contract_override)
+                    .is_some()
+                {
+                    return Err(serde_json::Error::custom(format!(
+                        "duplicate state override for contract {contract_address}"
+                    )));
+                }
+            }
+
+            Ok(Self(overrides))
+        }
+    }
+
     #[cfg(test)]
     mod tests {
 
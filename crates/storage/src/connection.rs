@@ -8,11 +8,13 @@ pub mod pruning;
 mod reference;
 mod signature;
 mod state_update;
+mod trace;
 pub(crate) mod transaction;
 mod trie;
 
 use event::RunningEventFilter;
 pub use event::{
+    ContinuationToken,
     EmittedEvent,
     EventConstraints,
     EventFilterError,
@@ -131,6 +133,10 @@ impl Transaction<'_> {
         matches!(self.trie_prune_mode, TriePruneMode::Prune { .. })
     }
 
+    pub fn trie_prune_mode(&self) -> TriePruneMode {
+        self.trie_prune_mode
+    }
+
     /// Store the in-memory [`Storage`](crate::Storage) state in the database.
     /// To be performed on shutdown.
     pub fn store_in_memory_state(self) -> anyhow::Result<()> {
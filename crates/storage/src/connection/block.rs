@@ -120,6 +120,13 @@ impl Transaction<'_> {
             )
             .context("Deleting transactions")?;
 
+        self.inner()
+            .execute(
+                "DELETE FROM block_traces WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting block traces")?;
+
         self.inner()
             .execute(
                 "DELETE FROM canonical_blocks WHERE number = ?",
@@ -16,13 +16,18 @@ use crate::prelude::*;
 // This allows for the maximum of 16 keys per event to be stored in the
 // filter.
 pub const EVENT_KEY_FILTER_LIMIT: usize = 16;
+// Each contract address is looked up in the Bloom filters separately, so the
+// number of addresses in a single query is bounded.
+pub const EVENT_ADDRESS_FILTER_LIMIT: usize = 64;
 pub const PAGE_SIZE_LIMIT: usize = 1_024;
 
 #[derive(Debug, Default)]
 pub struct EventConstraints {
     pub from_block: Option<BlockNumber>,
     pub to_block: Option<BlockNumber>,
-    pub contract_address: Option<ContractAddress>,
+    /// Events emitted by any of these contracts match. Empty matches all
+    /// contracts.
+    pub contract_addresses: Vec<ContractAddress>,
     pub keys: Vec<Vec<EventKey>>,
     pub page_size: usize,
     pub offset: usize,
@@ -146,16 +151,43 @@ impl Transaction<'_> {
     }
 
     /// Return all of the events in the given block range, filtered by the given
-    /// keys and contract address. Along with the events, return the last
+    /// keys and contract addresses. Along with the events, return the last
     /// block number that was scanned, which may be smaller than `to_block`
     /// if there are no more blocks in the database.
     pub fn events_in_range(
         &self,
         from_block: BlockNumber,
         to_block: BlockNumber,
-        contract_address: Option<ContractAddress>,
+        contract_addresses: Vec<ContractAddress>,
         keys: Vec<Vec<EventKey>>,
     ) -> anyhow::Result<(Vec<EmittedEvent>, Option<BlockNumber>)> {
+        let (blocks_to_scan, last_block) =
+            self.event_blocks_in_range(from_block, to_block, &contract_addresses, &keys)?;
+
+        let mut emitted_events = vec![];
+
+        for block in blocks_to_scan {
+            match self.matching_events_in_block(block, &contract_addresses, &keys)? {
+                Some(events) => emitted_events.extend(events),
+                None => break,
+            }
+        }
+
+        Ok((emitted_events, last_block))
+    }
+
+    /// Return the blocks in the given block range which may hold events
+    /// matching the given keys and contract addresses, according to the
+    /// aggregate Bloom filters. Along with the blocks, return the last block
+    /// number that was scanned, which may be smaller than `to_block` if there
+    /// are no more blocks in the database.
+    pub fn event_blocks_in_range(
+        &self,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        contract_addresses: &[ContractAddress],
+        keys: &[Vec<EventKey>],
+    ) -> anyhow::Result<(Vec<BlockNumber>, Option<BlockNumber>)> {
         let Some(latest_block) = self.block_number(crate::BlockId::Latest)? else {
             // No blocks in the database.
             return Ok((vec![], None));
@@ -166,76 +198,79 @@ impl Transaction<'_> {
         let to_block = std::cmp::min(to_block, latest_block);
 
         let constraints = EventConstraints {
-            contract_address,
-            keys,
+            contract_addresses: contract_addresses.to_vec(),
+            keys: keys.to_vec(),
             page_size: usize::MAX - 1,
             ..Default::default()
         };
 
         let (event_filters, _) = self.load_event_filter_range(from_block, to_block, None)?;
 
-        let blocks_to_scan = event_filters
+        let blocks = event_filters
             .iter()
             .flat_map(|filter| filter.check(&constraints))
-            .filter(|&block| (from_block..=to_block).contains(&block));
-
-        let no_key_constraints = constraints.keys.iter().flatten().count() == 0;
-        let keys: Vec<std::collections::HashSet<_>> = constraints
-            .keys
-            .iter()
-            .map(|keys| keys.iter().collect())
+            .filter(|&block| (from_block..=to_block).contains(&block))
             .collect();
 
-        let mut emitted_events = vec![];
+        Ok((blocks, Some(to_block)))
+    }
 
-        for block in blocks_to_scan {
-            let Some(block_header) = self.block_header(crate::BlockId::Number(block))? else {
-                break;
-            };
+    /// Return the events of the given block matching the given keys and
+    /// contract addresses, or `None` if the block or its events are not in the
+    /// database.
+    pub fn matching_events_in_block(
+        &self,
+        block: BlockNumber,
+        contract_addresses: &[ContractAddress],
+        keys: &[Vec<EventKey>],
+    ) -> anyhow::Result<Option<Vec<EmittedEvent>>> {
+        let Some(block_header) = self.block_header(crate::BlockId::Number(block))? else {
+            return Ok(None);
+        };
 
-            let events = match self.events_for_block(block.into())? {
-                Some(events) => events,
-                // Reached the end of P2P (checkpoint) synced events.
-                None => break,
-            };
+        let events = match self.events_for_block(block.into())? {
+            Some(events) => events,
+            // Reached the end of P2P (checkpoint) synced events.
+            None => return Ok(None),
+        };
 
-            let events = events
-                .into_iter()
-                .flat_map(|(transaction_hash, events)| {
-                    events.into_iter().zip(std::iter::repeat(transaction_hash))
-                })
-                .filter(|(event, _)| match constraints.contract_address {
-                    Some(address) => event.from_address == address,
-                    None => true,
-                })
-                .filter(|(event, _)| {
-                    if no_key_constraints {
-                        return true;
-                    }
+        let no_key_constraints = keys.iter().flatten().count() == 0;
+        let keys: Vec<std::collections::HashSet<_>> =
+            keys.iter().map(|keys| keys.iter().collect()).collect();
+        let addresses: std::collections::HashSet<_> = contract_addresses.iter().collect();
 
-                    if event.keys.len() < keys.len() {
-                        return false;
-                    }
+        let events = events
+            .into_iter()
+            .flat_map(|(transaction_hash, events)| {
+                events.into_iter().zip(std::iter::repeat(transaction_hash))
+            })
+            .filter(|(event, _)| addresses.is_empty() || addresses.contains(&event.from_address))
+            .filter(|(event, _)| {
+                if no_key_constraints {
+                    return true;
+                }
 
-                    event
-                        .keys
-                        .iter()
-                        .zip(keys.iter())
-                        .all(|(key, filter)| filter.is_empty() || filter.contains(key))
-                })
-                .map(|(event, tx_hash)| EmittedEvent {
-                    data: event.data.clone(),
-                    keys: event.keys.clone(),
-                    from_address: event.from_address,
-                    block_hash: block_header.hash,
-                    block_number: block_header.number,
-                    transaction_hash: tx_hash,
-                });
+                if event.keys.len() < keys.len() {
+                    return false;
+                }
 
-            emitted_events.extend(events);
-        }
+                event
+                    .keys
+                    .iter()
+                    .zip(keys.iter())
+                    .all(|(key, filter)| filter.is_empty() || filter.contains(key))
+            })
+            .map(|(event, tx_hash)| EmittedEvent {
+                data: event.data.clone(),
+                keys: event.keys.clone(),
+                from_address: event.from_address,
+                block_hash: block_header.hash,
+                block_number: block_header.number,
+                transaction_hash: tx_hash,
+            })
+            .collect();
 
-        Ok((emitted_events, Some(to_block)))
+        Ok(Some(events))
     }
 
     #[tracing::instrument(skip(self))]
@@ -276,6 +311,8 @@ impl Transaction<'_> {
             .iter()
             .map(|keys| keys.iter().collect())
             .collect();
+        let addresses: std::collections::HashSet<_> =
+            constraints.contract_addresses.iter().collect();
 
         let no_key_constraints = constraints.keys.iter().flatten().count() == 0;
         let mut offset = constraints.offset;
@@ -317,9 +354,8 @@ impl Transaction<'_> {
                 .flat_map(|(transaction_hash, events)| {
                     events.into_iter().zip(std::iter::repeat(transaction_hash))
                 })
-                .filter(|(event, _)| match constraints.contract_address {
-                    Some(address) => event.from_address == address,
-                    None => true,
+                .filter(|(event, _)| {
+                    addresses.is_empty() || addresses.contains(&event.from_address)
                 })
                 .filter(|(event, _)| {
                     if no_key_constraints {
@@ -506,7 +542,7 @@ impl Transaction<'_> {
 impl AggregateBloom {
     /// Returns the block numbers that match the given constraints.
     pub fn check(&self, constraints: &EventConstraints) -> Vec<BlockNumber> {
-        let addr_blocks = self.check_address(constraints.contract_address);
+        let addr_blocks = self.check_addresses(&constraints.contract_addresses);
         let keys_blocks = self.check_keys(&constraints.keys);
 
         let block_matches = addr_blocks & keys_blocks;
@@ -517,11 +553,13 @@ impl AggregateBloom {
             .collect()
     }
 
-    fn check_address(&self, address: Option<ContractAddress>) -> BlockRange {
-        match address {
-            Some(addr) => self.blocks_for_keys(&[addr.0]),
-            None => BlockRange::FULL,
+    fn check_addresses(&self, addresses: &[ContractAddress]) -> BlockRange {
+        if addresses.is_empty() {
+            return BlockRange::FULL;
         }
+
+        let addresses: Vec<_> = addresses.iter().map(|address| address.0).collect();
+        self.blocks_for_keys(&addresses)
     }
 
     fn check_keys(&self, keys: &[Vec<EventKey>]) -> BlockRange {
@@ -834,7 +872,7 @@ mod tests {
             let constraints = EventConstraints {
                 from_block: None,
                 to_block: None,
-                contract_address: Some(contract_address!("0x1234")),
+                contract_addresses: vec![contract_address!("0x1234")],
                 keys: vec![vec![event_key!("0xdeadbeef")]],
                 page_size: 1024,
                 offset: 0,
@@ -859,7 +897,7 @@ mod tests {
             let constraints = EventConstraints {
                 from_block: None,
                 to_block: None,
-                contract_address: Some(contract_address!("0x4321")),
+                contract_addresses: vec![contract_address!("0x4321")],
                 keys: vec![vec![event_key!("0xdeadbeef")]],
                 page_size: 1024,
                 offset: 0,
@@ -868,6 +906,37 @@ mod tests {
             assert_eq!(aggregate.check(&constraints), Vec::<BlockNumber>::new());
         }
 
+        #[test]
+        fn any_of_several_addresses() {
+            let mut aggregate = AggregateBloom::new(BlockNumber::GENESIS);
+
+            let mut filter = BloomFilter::new();
+            filter.set_address(&contract_address!("0x1234"));
+            aggregate.insert(&filter, BlockNumber::GENESIS);
+
+            let mut filter = BloomFilter::new();
+            filter.set_address(&contract_address!("0x5678"));
+            aggregate.insert(&filter, BlockNumber::GENESIS + 1);
+
+            let mut filter = BloomFilter::new();
+            filter.set_address(&contract_address!("0x9abc"));
+            aggregate.insert(&filter, BlockNumber::GENESIS + 2);
+
+            let constraints = EventConstraints {
+                from_block: None,
+                to_block: None,
+                contract_addresses: vec![contract_address!("0x1234"), contract_address!("0x9abc")],
+                keys: vec![],
+                page_size: 1024,
+                offset: 0,
+            };
+
+            assert_eq!(
+                aggregate.check(&constraints),
+                vec![BlockNumber::GENESIS, BlockNumber::GENESIS + 2]
+            );
+        }
+
         #[test]
         fn correct_address_wrong_key() {
             let mut aggregate = AggregateBloom::new(BlockNumber::GENESIS);
@@ -881,7 +950,7 @@ mod tests {
             let constraints = EventConstraints {
                 from_block: None,
                 to_block: None,
-                contract_address: Some(contract_address!("0x1234")),
+                contract_addresses: vec![contract_address!("0x1234")],
                 keys: vec![vec![event_key!("0xfeebdaed"), event_key!("0x4321")]],
                 page_size: 1024,
                 offset: 0,
@@ -903,7 +972,7 @@ mod tests {
             let constraints = EventConstraints {
                 from_block: None,
                 to_block: None,
-                contract_address: None,
+                contract_addresses: vec![],
                 keys: vec![
                     // Key present in both blocks as the first key.
                     vec![event_key!("0xdeadbeef")],
@@ -936,7 +1005,7 @@ mod tests {
             let constraints = EventConstraints {
                 from_block: None,
                 to_block: None,
-                contract_address: None,
+                contract_addresses: vec![],
                 keys: vec![],
                 page_size: 1024,
                 offset: 0,
@@ -957,7 +1026,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(expected_event.block_number),
             to_block: Some(expected_event.block_number),
-            contract_address: Some(expected_event.from_address),
+            contract_addresses: vec![expected_event.from_address],
             // We're using a key which is present in _all_ events as the 2nd key.
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: test_utils::NUM_EVENTS,
@@ -980,6 +1049,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_events_from_several_addresses() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let expected_events = vec![emitted_events[1].clone(), emitted_events[20].clone()];
+        let constraints = EventConstraints {
+            from_block: None,
+            to_block: None,
+            contract_addresses: expected_events
+                .iter()
+                .map(|event| event.from_address)
+                .collect(),
+            keys: vec![],
+            page_size: test_utils::NUM_EVENTS,
+            offset: 0,
+        };
+
+        let events = tx
+            .events(
+                &constraints,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_EVENT_FILTERS_TO_LOAD,
+            )
+            .unwrap();
+        assert_eq!(
+            events,
+            PageOfEvents {
+                events: expected_events,
+                continuation_token: None,
+            }
+        );
+    }
+
     #[test]
     fn events_are_ordered() {
         // This is a regression test where events were incorrectly ordered by
@@ -1067,7 +1172,7 @@ mod tests {
                 &EventConstraints {
                     from_block: None,
                     to_block: None,
-                    contract_address: None,
+                    contract_addresses: vec![],
                     keys: vec![],
                     page_size: 1024,
                     offset: 0,
@@ -1100,7 +1205,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(BlockNumber::new_or_panic(BLOCK_NUMBER as u64)),
             to_block: Some(BlockNumber::new_or_panic(BLOCK_NUMBER as u64)),
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
@@ -1135,7 +1240,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: Some(BlockNumber::new_or_panic(UNTIL_BLOCK_NUMBER as u64)),
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
@@ -1169,7 +1274,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: Some(BlockNumber::new_or_panic(1)),
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: test_utils::EVENTS_PER_BLOCK + 1,
             offset: 0,
@@ -1198,7 +1303,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(events.continuation_token.unwrap().block_number),
             to_block: Some(BlockNumber::new_or_panic(1)),
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: test_utils::EVENTS_PER_BLOCK + 1,
             offset: events.continuation_token.unwrap().offset,
@@ -1233,7 +1338,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(BlockNumber::new_or_panic(FROM_BLOCK_NUMBER as u64)),
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
@@ -1268,7 +1373,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: Some(expected_event.from_address),
+            contract_addresses: vec![expected_event.from_address],
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
@@ -1290,6 +1395,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn matching_events_by_block() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let expected_event = &emitted_events[33];
+        let addresses = [expected_event.from_address];
+        let latest = test_data.headers.last().unwrap().number;
+
+        let (blocks, last_block) = tx
+            .event_blocks_in_range(BlockNumber::GENESIS, latest + 10, &addresses, &[])
+            .unwrap();
+        assert_eq!(last_block, Some(latest));
+        assert!(blocks.contains(&expected_event.block_number));
+
+        let events = blocks
+            .into_iter()
+            .flat_map(|block| {
+                tx.matching_events_in_block(block, &addresses, &[])
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![expected_event.clone()]);
+
+        assert_eq!(
+            tx.matching_events_in_block(latest + 1, &addresses, &[])
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn get_events_by_key() {
         let (storage, test_data) = test_utils::setup_test_storage();
@@ -1301,7 +1440,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![vec![expected_event.keys[0]], vec![expected_event.keys[1]]],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
@@ -1354,7 +1493,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
@@ -1386,7 +1525,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: 10,
             offset: 0,
@@ -1413,7 +1552,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: 10,
             offset: 10,
@@ -1440,7 +1579,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: 10,
             offset: 30,
@@ -1472,7 +1611,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: PAGE_SIZE,
             // _after_ the last one
@@ -1511,7 +1650,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 0,
@@ -1539,7 +1678,7 @@ mod tests {
         let constraints: EventConstraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 2,
@@ -1567,7 +1706,7 @@ mod tests {
         let constraints: EventConstraints = EventConstraints {
             from_block: Some(BlockNumber::new_or_panic(0)),
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 2,
@@ -1595,7 +1734,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 4,
@@ -1620,7 +1759,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(BlockNumber::new_or_panic(3)),
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: keys_for_expected_events,
             page_size: 2,
             offset: 1,
@@ -1652,7 +1791,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: 20,
             offset: 0,
@@ -1679,7 +1818,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(BlockNumber::new_or_panic(1)),
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: 20,
             offset: 0,
@@ -1735,7 +1874,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             // We're using a key which is present in _all_ events as the 2nd key.
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: emitted_events.len(),
@@ -1773,7 +1912,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             // We're using a key which is present in _all_ events as the 2nd key...
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: emitted_events.len(),
@@ -1802,7 +1941,7 @@ mod tests {
             // Use the provided continuation token.
             from_block: Some(events.continuation_token.unwrap().block_number),
             to_block: None,
-            contract_address: None,
+            contract_addresses: vec![],
             // We're using a key which is present in _all_ events as the 2nd key...
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: emitted_events.len(),
@@ -1886,7 +2025,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: Some(BlockNumber::new_or_panic(u64::try_from(from_block).unwrap())),
             to_block: Some(BlockNumber::new_or_panic(u64::try_from(to_block).unwrap())),
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: emitted_events.len(),
             offset: 0,
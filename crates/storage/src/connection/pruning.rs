@@ -21,13 +21,15 @@
 //! enable pruning on a database that was created with it disabled (and vice
 //! versa). These restrictions are there to make it possible to prune block and
 //! (contract/nonce/storage) update data without breaking reorg handling.
+//! The only exception is converting an archive database offline using
+//! [`Transaction::convert_to_pruned_blockchain`].
 
 use anyhow::Context;
 use pathfinder_common::BlockNumber;
 
 use super::Transaction;
 use crate::prelude::{named_params, params, RowExt};
-use crate::BlockId;
+use crate::{BlockId, TriePruneMode};
 
 #[derive(Debug, Clone, Copy)]
 pub enum BlockchainHistoryMode {
@@ -61,6 +63,144 @@ impl Transaction<'_> {
         Ok(())
     }
 
+    /// Converts an archive database into one with blockchain history pruning
+    /// enabled, as if it had been created with
+    /// [`BlockchainHistoryMode::Prune`] in the first place.
+    ///
+    /// All data that [`prune_blockchain`](Self::prune_blockchain) would have
+    /// removed by now is deleted in bulk and the pruning setting is recorded
+    /// in the database. This is an offline operation which can take a long
+    /// time on large databases.
+    pub fn convert_to_pruned_blockchain(&self, num_blocks_kept: u64) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            matches!(self.blockchain_history_mode, BlockchainHistoryMode::Archive),
+            "Blockchain history pruning is already enabled"
+        );
+
+        self.inner()
+            .execute(
+                "INSERT INTO storage_options (option, value) VALUES ('prune_blockchain', ?)",
+                [num_blocks_kept],
+            )
+            .context("Enabling blockchain history pruning")?;
+
+        let Some(last_pruned_block) = self
+            .block_number(BlockId::Latest)?
+            .and_then(|latest| latest.checked_sub(num_blocks_kept + 1))
+        else {
+            return Ok(());
+        };
+
+        tracing::info!(%last_pruned_block, "Pruning transaction data");
+        self.inner()
+            .execute(
+                "DELETE FROM transactions WHERE block_number <= ?",
+                params![&last_pruned_block],
+            )
+            .context("Deleting transactions")?;
+        self.inner()
+            .execute(
+                "DELETE FROM transaction_hashes WHERE block_number <= ?",
+                params![&last_pruned_block],
+            )
+            .context("Deleting transaction hashes")?;
+        self.inner()
+            .execute(
+                "DELETE FROM block_traces WHERE block_number <= ?",
+                params![&last_pruned_block],
+            )
+            .context("Deleting block traces")?;
+
+        // An update is obsolete once there is a newer one for the same key at or
+        // before the last kept block. The first contract update is the deployment
+        // and must be kept for state update reconstruction, see `prune_block_data`.
+        tracing::info!(%last_pruned_block, "Pruning state updates");
+        self.inner()
+            .execute(
+                r"
+                DELETE FROM contract_updates
+                WHERE block_number <= :last_pruned_block
+                AND block_number > (
+                    SELECT MIN(block_number)
+                    FROM contract_updates AS deployment
+                    WHERE deployment.contract_address = contract_updates.contract_address
+                )
+                AND EXISTS (
+                    SELECT 1
+                    FROM contract_updates AS newer
+                    WHERE newer.contract_address = contract_updates.contract_address
+                    AND newer.block_number > contract_updates.block_number
+                    AND newer.block_number <= :last_pruned_block + 1
+                )
+                ",
+                named_params![":last_pruned_block": &last_pruned_block],
+            )
+            .context("Deleting contract updates")?;
+        self.inner()
+            .execute(
+                r"
+                DELETE FROM nonce_updates
+                WHERE block_number <= :last_pruned_block
+                AND EXISTS (
+                    SELECT 1
+                    FROM nonce_updates AS newer
+                    WHERE newer.contract_address_id = nonce_updates.contract_address_id
+                    AND newer.block_number > nonce_updates.block_number
+                    AND newer.block_number <= :last_pruned_block + 1
+                )
+                ",
+                named_params![":last_pruned_block": &last_pruned_block],
+            )
+            .context("Deleting nonce updates")?;
+        self.inner()
+            .execute(
+                r"
+                DELETE FROM storage_updates
+                WHERE block_number <= :last_pruned_block
+                AND EXISTS (
+                    SELECT 1
+                    FROM storage_updates AS newer
+                    WHERE newer.contract_address_id = storage_updates.contract_address_id
+                    AND newer.storage_address_id = storage_updates.storage_address_id
+                    AND newer.block_number > storage_updates.block_number
+                    AND newer.block_number <= :last_pruned_block + 1
+                )
+                ",
+                named_params![":last_pruned_block": &last_pruned_block],
+            )
+            .context("Deleting storage updates")?;
+
+        tracing::info!(%last_pruned_block, "Pruning blocks");
+        for (table, column) in [
+            ("block_signatures", "block_number"),
+            ("canonical_blocks", "number"),
+            ("block_headers", "number"),
+        ] {
+            self.inner()
+                .execute(
+                    &format!(
+                        r"
+                        DELETE FROM {table}
+                        WHERE {column} <= ?1
+                        AND NOT EXISTS (
+                            SELECT 1 FROM contract_updates WHERE block_number = {table}.{column}
+                        )
+                        AND NOT EXISTS (
+                            SELECT 1 FROM nonce_updates WHERE block_number = {table}.{column}
+                        )
+                        AND NOT EXISTS (
+                            SELECT 1 FROM storage_updates WHERE block_number = {table}.{column}
+                        )
+                        "
+                    ),
+                    params![&last_pruned_block],
+                )
+                .with_context(|| format!("Deleting pruned blocks from {table}"))?;
+        }
+
+        Ok(())
+    }
+
     fn prune_transaction_data(&self, block: BlockNumber) -> anyhow::Result<()> {
         let mut transaction_stmt = self.inner().prepare_cached(
             r"
@@ -74,8 +214,15 @@ impl Transaction<'_> {
             WHERE block_number = ?
             ",
         )?;
+        let mut block_traces_stmt = self.inner().prepare_cached(
+            r"
+            DELETE FROM block_traces
+            WHERE block_number = ?
+            ",
+        )?;
         transaction_stmt.execute(params![&block])?;
         transaction_hashes_stmt.execute(params![&block])?;
+        block_traces_stmt.execute(params![&block])?;
 
         Ok(())
     }
@@ -317,7 +464,50 @@ impl Transaction<'_> {
         Ok(())
     }
 
-    /// Checks if a block has been pruned.
+    /// Returns the oldest block that has not been pruned, or `None` if
+    /// blockchain history pruning is disabled or there are no blocks yet.
+    pub fn oldest_available_block(&self) -> anyhow::Result<Option<BlockNumber>> {
+        let BlockchainHistoryMode::Prune { num_blocks_kept } = self.blockchain_history_mode else {
+            return Ok(None);
+        };
+        let Some(latest) = self.block_number(BlockId::Latest)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(
+            latest
+                .checked_sub(num_blocks_kept)
+                .unwrap_or(BlockNumber::GENESIS),
+        ))
+    }
+
+    /// Returns the oldest block whose Merkle trie state has not been pruned, or
+    /// `None` if trie pruning is disabled or there are no blocks yet.
+    pub fn oldest_trie_block(&self) -> anyhow::Result<Option<BlockNumber>> {
+        let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode else {
+            return Ok(None);
+        };
+        let Some(latest) = self.block_number(BlockId::Latest)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(
+            latest
+                .checked_sub(num_blocks_kept)
+                .unwrap_or(BlockNumber::GENESIS),
+        ))
+    }
+
+    /// Checks if the Merkle trie state of a block has been pruned and returns
+    /// the [oldest block with trie state](Self::oldest_trie_block) if so.
+    pub fn trie_pruned(&self, block: BlockNumber) -> anyhow::Result<Option<BlockNumber>> {
+        Ok(self
+            .oldest_trie_block()?
+            .filter(|&oldest_trie_block| block < oldest_trie_block))
+    }
+
+    /// Checks if a block has been pruned and returns the [oldest available
+    /// block](Self::oldest_available_block) if so.
     ///
     /// But not really, because there can be blocks that have had *some* of
     /// their data pruned but still have state updates tied to them that can't
@@ -325,20 +515,21 @@ impl Transaction<'_> {
     /// [`block_exists`](Self::block_exists) would be the correct check). Still,
     /// in order to avoid providing misleading information, we consider those
     /// blocks as pruned.
-    pub fn block_pruned(&self, block_id: BlockId) -> anyhow::Result<bool> {
-        let BlockchainHistoryMode::Prune { num_blocks_kept } = self.blockchain_history_mode else {
-            return Ok(false);
-        };
-        let Some(latest) = self.block_number(BlockId::Latest)? else {
-            return Ok(false);
+    ///
+    /// Unknown block hashes are not considered pruned since there is no way
+    /// to tell them apart from blocks that never existed.
+    pub fn block_pruned(&self, block_id: BlockId) -> anyhow::Result<Option<BlockNumber>> {
+        let Some(oldest_available) = self.oldest_available_block()? else {
+            return Ok(None);
         };
-        // If `latest` exists and the provided block doesn't, it has been pruned.
-        let Some(block) = self.block_number(block_id)? else {
-            return Ok(true);
+        let block = match block_id {
+            BlockId::Number(number) => number,
+            other => match self.block_number(other)? {
+                Some(number) => number,
+                None => return Ok(None),
+            },
         };
 
-        Ok(latest
-            .checked_sub(num_blocks_kept + 1)
-            .is_some_and(|prune_point| block < prune_point))
+        Ok((block < oldest_available).then_some(oldest_available))
     }
 }
@@ -0,0 +1,184 @@
+//! Persisted block traces.
+//!
+//! Traces are stored as an opaque, compressed blob per block. Their format is
+//! owned by the caller, so that this crate does not have to depend on the
+//! executor. Callers tag the blob with a format version, and traces stored
+//! with any other version are treated as not stored.
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber};
+
+use super::transaction::compression;
+use crate::prelude::*;
+
+impl Transaction<'_> {
+    /// Stores the traces of a block, replacing any traces already stored for
+    /// the same block number.
+    ///
+    /// Nothing is stored if the block is no longer part of the canonical chain,
+    /// which can happen if a reorg raced with tracing the block.
+    pub fn insert_block_traces<T: serde::Serialize>(
+        &self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        version: u32,
+        traces: &T,
+    ) -> anyhow::Result<()> {
+        let traces = bincode::serde::encode_to_vec(traces, bincode::config::standard())
+            .context("Serializing traces")?;
+        let traces = compression::compress_traces(&traces).context("Compressing traces")?;
+
+        self.inner()
+            .execute(
+                r"
+                INSERT OR REPLACE INTO block_traces (block_number, block_hash, version, traces)
+                SELECT :block_number, :block_hash, :version, :traces
+                WHERE EXISTS (
+                    SELECT 1 FROM block_headers WHERE number = :block_number AND hash = :block_hash
+                )
+                ",
+                named_params! {
+                    ":block_number": &block_number,
+                    ":block_hash": &block_hash,
+                    ":version": &version,
+                    ":traces": &traces,
+                },
+            )
+            .context("Inserting block traces")?;
+
+        Ok(())
+    }
+
+    /// Returns the traces stored for the given block.
+    ///
+    /// The hash has to match as well so that traces of a block which has since
+    /// been reorged away are never returned. Traces stored with a different
+    /// format `version` are not returned either, since they may well decode
+    /// into garbage instead of failing to decode.
+    pub fn block_traces<T: serde::de::DeserializeOwned>(
+        &self,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        version: u32,
+    ) -> anyhow::Result<Option<T>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"
+                SELECT traces FROM block_traces
+                WHERE block_number = ? AND block_hash = ? AND version = ?
+                ",
+            )
+            .context("Preparing block traces query")?;
+        let mut rows = stmt
+            .query(params![&block_number, &block_hash, &version])
+            .context("Querying block traces")?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+
+        let traces = row.get_blob(0)?;
+        let traces = compression::decompress_traces(traces).context("Decompressing traces")?;
+        let traces = bincode::serde::decode_from_slice(&traces, bincode::config::standard())
+            .context("Deserializing traces")?
+            .0;
+
+        Ok(Some(traces))
+    }
+
+    /// Returns whether traces are stored for the given block number.
+    pub fn block_traces_exist(&self, block_number: BlockNumber) -> anyhow::Result<bool> {
+        self.inner()
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM block_traces WHERE block_number = ?)",
+                params![&block_number],
+                |row| row.get(0),
+            )
+            .context("Querying block traces existence")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockHeader;
+
+    use super::*;
+
+    fn setup() -> crate::Storage {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash!("0xb0"));
+        tx.insert_block_header(&header).unwrap();
+
+        tx.commit().unwrap();
+        storage
+    }
+
+    #[test]
+    fn insert_and_query() {
+        let storage = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let traces = vec![(transaction_hash!("0x1"), "trace".to_owned())];
+        tx.insert_block_traces(BlockNumber::GENESIS, block_hash!("0xb0"), 1, &traces)
+            .unwrap();
+
+        let stored: Option<Vec<(pathfinder_common::TransactionHash, String)>> = tx
+            .block_traces(BlockNumber::GENESIS, block_hash!("0xb0"), 1)
+            .unwrap();
+        assert_eq!(stored, Some(traces));
+        assert!(tx.block_traces_exist(BlockNumber::GENESIS).unwrap());
+
+        // A different block at the same height, e.g. after a reorg.
+        let stored: Option<Vec<(pathfinder_common::TransactionHash, String)>> = tx
+            .block_traces(BlockNumber::GENESIS, block_hash!("0xb1"), 1)
+            .unwrap();
+        assert_eq!(stored, None);
+    }
+
+    #[test]
+    fn version_mismatch_is_not_stored() {
+        let storage = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        tx.insert_block_traces(BlockNumber::GENESIS, block_hash!("0xb0"), 1, &"trace")
+            .unwrap();
+
+        let stored: Option<String> = tx
+            .block_traces(BlockNumber::GENESIS, block_hash!("0xb0"), 2)
+            .unwrap();
+        assert_eq!(stored, None);
+    }
+
+    #[test]
+    fn not_stored_for_non_canonical_block() {
+        let storage = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        tx.insert_block_traces(BlockNumber::GENESIS, block_hash!("0xb1"), 1, &Vec::<u8>::new())
+            .unwrap();
+
+        assert!(!tx.block_traces_exist(BlockNumber::GENESIS).unwrap());
+    }
+
+    #[test]
+    fn purged_with_block() {
+        let storage = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        tx.insert_block_traces(BlockNumber::GENESIS, block_hash!("0xb0"), 1, &Vec::<u8>::new())
+            .unwrap();
+        tx.purge_block(BlockNumber::GENESIS).unwrap();
+
+        assert!(!tx.block_traces_exist(BlockNumber::GENESIS).unwrap());
+    }
+}
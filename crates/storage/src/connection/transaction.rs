@@ -23,6 +23,9 @@ pub(crate) mod compression {
     const MAX_TRANSACTIONS_UNCOMPRESSED_SIZE: usize = 128usize * 1024 * 1024;
     /// The maximum allowed uncompressed size of a serialized blob of events.
     const MAX_EVENTS_UNCOMPRESSED_SIZE: usize = 128usize * 1024 * 1024;
+    /// The maximum allowed uncompressed size of a serialized blob of block
+    /// traces.
+    const MAX_TRACES_UNCOMPRESSED_SIZE: usize = 512usize * 1024 * 1024;
 
     static ZSTD_TXS_ENCODER_DICTIONARY: LazyLock<zstd::dict::EncoderDictionary<'static>> =
         LazyLock::new(|| {
@@ -81,6 +84,20 @@ pub(crate) mod compression {
     fn new_events_decompressor() -> std::io::Result<zstd::bulk::Decompressor<'static>> {
         zstd::bulk::Decompressor::with_prepared_dictionary(&ZSTD_EVENTS_DECODER_DICTIONARY)
     }
+
+    // Unlike transactions and events, traces are compressed without a
+    // dictionary. A dictionary helps small inputs most, whereas the traces of a
+    // block are compressed as a single large blob which zstd learns enough from
+    // on its own. Training a representative one would also need a corpus of
+    // mainnet traces. Since stored traces are tagged with a format version, a
+    // dictionary can still be introduced later under a new version.
+    pub(crate) fn compress_traces(input: &[u8]) -> std::io::Result<Vec<u8>> {
+        zstd::bulk::compress(input, ZSTD_COMPRESSION_LEVEL)
+    }
+
+    pub(crate) fn decompress_traces(input: &[u8]) -> std::io::Result<Vec<u8>> {
+        zstd::bulk::decompress(input, MAX_TRACES_UNCOMPRESSED_SIZE)
+    }
 }
 
 type TransactionsAndEventsByBlock = (Vec<(StarknetTransaction, Receipt)>, Vec<Vec<Event>>);
@@ -237,6 +254,50 @@ impl Transaction<'_> {
         Ok(Some((transaction, receipt, events, block_number)))
     }
 
+    /// Returns the events emitted by a transaction and the number of the block
+    /// it is in. Unlike [Self::transaction_with_receipt], this does not decode
+    /// the block's transactions.
+    ///
+    /// The events are `None` if they are not stored for the block.
+    pub fn events_for_transaction(
+        &self,
+        hash: TransactionHash,
+    ) -> anyhow::Result<Option<(BlockNumber, Option<Vec<Event>>)>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT transactions.block_number, events, idx
+            FROM transactions
+            JOIN transaction_hashes ON transactions.block_number = transaction_hashes.block_number
+            WHERE hash = ?
+            ",
+        )?;
+        let mut rows = stmt.query(params![&hash])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let block_number = row.get_block_number(0)?;
+        let idx: usize = row.get_i64(2)?.try_into()?;
+        let Some(events) = row.get_optional_blob(1)? else {
+            return Ok(Some((block_number, None)));
+        };
+
+        let events = compression::decompress_events(events).context("Decompressing events")?;
+        let events: dto::EventsForBlock =
+            bincode::serde::decode_from_slice(&events, bincode::config::standard())
+                .context("Deserializing events")?
+                .0;
+        let events = events
+            .events()
+            .into_iter()
+            .nth(idx)
+            .context("Transaction events missing")?;
+
+        Ok(Some((
+            block_number,
+            Some(events.into_iter().map(Into::into).collect()),
+        )))
+    }
+
     pub fn transaction_at_block(
         &self,
         block: BlockId,
@@ -3206,6 +3267,53 @@ mod tests {
         assert_eq!(invalid, None);
     }
 
+    #[test]
+    fn events_for_transaction() {
+        use crate::test_utils;
+
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        for (i, transaction) in test_data.transactions.iter().enumerate() {
+            let expected: Vec<_> = test_data
+                .events
+                .iter()
+                .filter(|event| event.transaction_hash == transaction.hash)
+                .map(|event| Event {
+                    data: event.data.clone(),
+                    from_address: event.from_address,
+                    keys: event.keys.clone(),
+                })
+                .collect();
+            let block_number =
+                BlockNumber::new_or_panic((i / test_utils::TRANSACTIONS_PER_BLOCK) as u64);
+
+            let result = tx.events_for_transaction(transaction.hash).unwrap();
+            assert_eq!(result, Some((block_number, Some(expected))));
+        }
+
+        let invalid = tx
+            .events_for_transaction(transaction_hash_bytes!(b"invalid"))
+            .unwrap();
+        assert_eq!(invalid, None);
+    }
+
+    #[test]
+    fn events_for_transaction_without_stored_events() {
+        let (_, header, body) = setup();
+
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_transaction_data(header.number, &body, None)
+            .unwrap();
+
+        let result = tx.events_for_transaction(body[0].0.hash).unwrap();
+        assert_eq!(result, Some((header.number, None)));
+    }
+
     #[test]
     fn transaction_at_block() {
         let (mut db, header, body) = setup();
@@ -389,6 +389,193 @@ impl Transaction<'_> {
         Ok(())
     }
 
+    /// Converts an archive database into one with Merkle trie pruning enabled,
+    /// keeping the trie state of the last `num_blocks_kept` blocks as well as
+    /// the latest one.
+    ///
+    /// Archive databases don't record which nodes were removed by each block,
+    /// so every node that isn't reachable from a kept root is deleted. Nodes
+    /// that are reachable from older kept roots only are marked as removed in
+    /// the latest block so that they are pruned once they fall out of the
+    /// window. This is an offline operation which can take a long time on
+    /// large databases.
+    pub fn convert_to_pruned_tries(&self, num_blocks_kept: u64) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !self.trie_pruning_enabled(),
+            "Merkle trie pruning is already enabled"
+        );
+
+        self.inner()
+            .execute(
+                "INSERT INTO storage_options (option) VALUES ('prune_tries')",
+                [],
+            )
+            .context("Enabling Merkle trie pruning")?;
+
+        let Some(latest) = self.block_number(BlockId::Latest)? else {
+            return Ok(());
+        };
+        let oldest_kept = latest
+            .checked_sub(num_blocks_kept)
+            .unwrap_or(BlockNumber::GENESIS);
+
+        tracing::info!(%oldest_kept, "Removing old trie roots");
+        self.delete_class_roots(oldest_kept)?;
+        self.delete_storage_roots(oldest_kept)?;
+        for table in ["contract_roots", "contract_state_hashes"] {
+            self.inner()
+                .execute(
+                    &format!(
+                        r"
+                        DELETE FROM {table}
+                        WHERE block_number < (
+                            SELECT MAX(block_number)
+                            FROM {table} AS kept
+                            WHERE kept.contract_address = {table}.contract_address
+                            AND kept.block_number <= ?
+                        )
+                        "
+                    ),
+                    params![&oldest_kept],
+                )
+                .with_context(|| format!("Deleting old rows from {table}"))?;
+        }
+
+        // Only roots at or after `oldest_kept` are left at this point.
+        let class_roots = self.trie_roots("SELECT root_index FROM class_roots")?;
+        let storage_roots = self.trie_roots("SELECT root_index FROM storage_roots")?;
+        let contract_roots = self.trie_roots("SELECT root_index FROM contract_roots")?;
+        let latest_contract_roots = self.trie_roots(
+            r"
+            SELECT root_index
+            FROM contract_roots
+            WHERE block_number = (
+                SELECT MAX(block_number)
+                FROM contract_roots AS latest
+                WHERE latest.contract_address = contract_roots.contract_address
+            )
+            ",
+        )?;
+
+        self.sweep_trie(
+            latest,
+            self.class_root_index(latest)?.into_iter().collect(),
+            class_roots,
+            "trie_class",
+        )?;
+        self.sweep_trie(
+            latest,
+            self.storage_root_index(latest)?.into_iter().collect(),
+            storage_roots,
+            "trie_storage",
+        )?;
+        self.sweep_trie(
+            latest,
+            latest_contract_roots,
+            contract_roots,
+            "trie_contracts",
+        )?;
+
+        Ok(())
+    }
+
+    fn trie_roots(&self, query: &str) -> anyhow::Result<Vec<TrieStorageIndex>> {
+        let mut stmt = self.inner().prepare(query).context("Creating root query")?;
+        let roots = stmt
+            .query_map([], |row| row.get::<_, Option<u64>>(0))
+            .context("Querying roots")?
+            .filter_map(|root| root.map(|x| x.map(TrieStorageIndex)).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(roots)
+    }
+
+    /// Deletes all nodes of a trie that are not reachable from any of the
+    /// given roots. Nodes reachable from `kept_roots` but not from
+    /// `latest_roots` are marked as removed in the `latest` block.
+    fn sweep_trie(
+        &self,
+        latest: BlockNumber,
+        latest_roots: Vec<TrieStorageIndex>,
+        kept_roots: Vec<TrieStorageIndex>,
+        table: &'static str,
+    ) -> anyhow::Result<()> {
+        let max_index: Option<u64> = self
+            .inner()
+            .query_row(&format!("SELECT MAX(idx) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .context("Querying largest node index")?;
+        let Some(max_index) = max_index else {
+            return Ok(());
+        };
+
+        tracing::info!(%table, "Marking reachable trie nodes");
+        let mut reachable = BitVec::<u8, Msb0>::repeat(false, max_index as usize + 1);
+        self.mark_reachable(latest_roots, &mut reachable, table)?;
+        let kept = self.mark_reachable(kept_roots, &mut reachable, table)?;
+        self.remove_trie(&kept, latest, table)?;
+
+        tracing::info!(%table, "Deleting unreachable trie nodes");
+        let mut delete_stmt = self
+            .inner()
+            .prepare_cached(&format!("DELETE FROM {table} WHERE idx BETWEEN ? AND ?"))
+            .context("Creating delete statement")?;
+        let mut unreachable = reachable.iter_zeros().map(|idx| idx as u64).peekable();
+        let mut deleted = 0;
+        while let Some(first) = unreachable.next() {
+            let mut last = first;
+            while let Some(idx) = unreachable.next_if_eq(&(last + 1)) {
+                last = idx;
+            }
+            deleted += delete_stmt
+                .execute(params![&first, &last])
+                .context("Deleting nodes")?;
+        }
+        metrics::counter!(METRIC_TRIE_NODES_REMOVED, deleted as u64, "table" => table);
+        tracing::info!(%table, %deleted, "Deleted unreachable trie nodes");
+
+        Ok(())
+    }
+
+    /// Marks all nodes reachable from `roots` and returns the ones that
+    /// weren't marked before.
+    fn mark_reachable(
+        &self,
+        roots: Vec<TrieStorageIndex>,
+        reachable: &mut BitVec<u8, Msb0>,
+        table: &'static str,
+    ) -> anyhow::Result<Vec<TrieStorageIndex>> {
+        let mut marked = Vec::new();
+        let mut to_visit = roots;
+
+        while let Some(index) = to_visit.pop() {
+            anyhow::ensure!(
+                (index.0 as usize) < reachable.len(),
+                "Node index {} is out of range",
+                index.0
+            );
+            if reachable.replace(index.0 as usize, true) {
+                continue;
+            }
+            marked.push(index);
+
+            let node = self
+                .trie_node(index, table)?
+                .with_context(|| format!("Node {} is missing from {table}", index.0))?;
+            match node {
+                StoredNode::Binary { left, right } => {
+                    to_visit.push(left);
+                    to_visit.push(right);
+                }
+                StoredNode::Edge { child, .. } => to_visit.push(child),
+                StoredNode::LeafBinary | StoredNode::LeafEdge { .. } => {}
+            }
+        }
+
+        Ok(marked)
+    }
+
     pub fn coalesce_trie_removals(&self, target_block: BlockNumber) -> anyhow::Result<()> {
         self.coalesce_removed_trie_nodes(target_block, "trie_contracts")?;
         self.coalesce_removed_trie_nodes(target_block, "trie_storage")?;
@@ -1919,4 +2106,77 @@ mod tests {
             Some(2.into())
         );
     }
+
+    #[test]
+    fn convert_to_pruned_tries() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        let mut roots = Vec::new();
+        for block in 0..4 {
+            let block_number = BlockNumber::new_or_panic(block);
+            tx.insert_block_header(
+                &BlockHeader::builder()
+                    .number(block_number)
+                    .finalize_with_hash(BlockHash(Felt::from_u64(block))),
+            )
+            .unwrap();
+
+            let update = tx
+                .insert_class_trie(
+                    &TrieUpdate {
+                        nodes_added: vec![
+                            (
+                                Felt::from_u64(3 * block),
+                                Node::Binary {
+                                    left: NodeRef::Index(1),
+                                    right: NodeRef::Index(2),
+                                },
+                            ),
+                            (Felt::from_u64(3 * block + 1), Node::LeafBinary),
+                            (Felt::from_u64(3 * block + 2), Node::LeafBinary),
+                        ],
+                        nodes_removed: vec![],
+                        root_commitment: Felt::ZERO,
+                    },
+                    block_number,
+                )
+                .unwrap();
+            let RootIndexUpdate::Updated(root) = update else {
+                panic!("Expected a new root, got {update:?}");
+            };
+            tx.insert_class_root(block_number, update).unwrap();
+            roots.push(root);
+        }
+
+        tx.convert_to_pruned_tries(1).unwrap();
+
+        // Roots and nodes of blocks 0 and 1 are gone, the last two are kept.
+        assert!(!tx.class_root_exists(BlockNumber::new_or_panic(1)).unwrap());
+        assert!(tx.class_root_exists(BlockNumber::new_or_panic(2)).unwrap());
+        for root in &roots[..2] {
+            assert!(tx.class_trie_node(*root).unwrap().is_none());
+            assert!(tx.class_trie_node(*root - 1).unwrap().is_none());
+        }
+        for root in &roots[2..] {
+            assert!(tx.class_trie_node(*root).unwrap().is_some());
+            assert!(tx.class_trie_node(*root - 1).unwrap().is_some());
+        }
+
+        // The nodes only used by block 2 are removed from the latest block on.
+        let removal_blocks = tx
+            .inner()
+            .prepare("SELECT block_number FROM trie_class_removals")
+            .unwrap()
+            .query_map([], |row| row.get_block_number(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(removal_blocks, vec![BlockNumber::new_or_panic(3)]);
+
+        tx.convert_to_pruned_tries(1).unwrap_err();
+    }
 }
@@ -22,7 +22,7 @@ use std::sync::{Arc, Mutex};
 use anyhow::Context;
 pub use connection::*;
 use event::RunningEventFilter;
-pub use event::EVENT_KEY_FILTER_LIMIT;
+pub use event::{EVENT_ADDRESS_FILTER_LIMIT, EVENT_KEY_FILTER_LIMIT};
 use pathfinder_common::{BlockHash, BlockNumber};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
@@ -222,18 +222,7 @@ impl StorageBuilder {
         trie_prune_mode: TriePruneMode,
         pool_size: NonZeroU32,
     ) -> anyhow::Result<Storage> {
-        // Create a unique database name so that they are not shared between
-        // concurrent tests. i.e. Make every in-mem Storage unique.
-        static COUNT: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
-        let unique_mem_db = {
-            let mut count = COUNT.lock().unwrap();
-            // &cache=shared allows other threads to see and access the inmemory database
-            let unique_mem_db = format!("file:memdb{count}?mode=memory&cache=shared");
-            *count += 1;
-            unique_mem_db
-        };
-
-        let database_path = PathBuf::from(unique_mem_db);
+        let database_path = Self::unique_mem_db();
         // This connection must be held until a pool has been created, since an
         // in-memory database is dropped once all its connections are. This connection
         // therefore holds the database in-place until the pool is established.
@@ -254,22 +243,22 @@ impl StorageBuilder {
         storage.create_pool(pool_size)
     }
 
+    /// Creates a unique database name so that they are not shared between
+    /// concurrent tests. i.e. Make every in-mem Storage unique.
+    fn unique_mem_db() -> PathBuf {
+        static COUNT: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
+        let mut count = COUNT.lock().unwrap();
+        // &cache=shared allows other threads to see and access the inmemory database
+        let unique_mem_db = format!("file:memdb{count}?mode=memory&cache=shared");
+        *count += 1;
+        PathBuf::from(unique_mem_db)
+    }
+
     pub fn in_memory_with_blockchain_pruning_and_pool_size(
         blockchain_history_mode: BlockchainHistoryMode,
         pool_size: NonZeroU32,
     ) -> anyhow::Result<Storage> {
-        // Create a unique database name so that they are not shared between
-        // concurrent tests. i.e. Make every in-mem Storage unique.
-        static COUNT: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
-        let unique_mem_db = {
-            let mut count = COUNT.lock().unwrap();
-            // &cache=shared allows other threads to see and access the inmemory database
-            let unique_mem_db = format!("file:memdb{count}?mode=memory&cache=shared");
-            *count += 1;
-            unique_mem_db
-        };
-
-        let database_path = PathBuf::from(unique_mem_db);
+        let database_path = Self::unique_mem_db();
         // This connection must be held until a pool has been created, since an
         // in-memory database is dropped once all its connections are. This connection
         // therefore holds the database in-place until the pool is established.
@@ -391,6 +380,47 @@ impl StorageBuilder {
         })
     }
 
+    /// Opens an existing database without migrating it or touching its
+    /// journal mode and returns a [storage manager](StorageManager).
+    ///
+    /// This is safe to use on a database that is concurrently in use by a
+    /// running node, but only [read-only
+    /// pools](StorageManager::create_read_only_pool) should be created from
+    /// the result. Fails if the database schema is not at the latest revision.
+    pub fn open_read_only(self) -> anyhow::Result<StorageManager> {
+        let open_flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX
+            | OpenFlags::SQLITE_OPEN_URI;
+        let mut connection = rusqlite::Connection::open_with_flags(&self.database_path, open_flags)
+            .context("Opening DB")?;
+
+        let current_revision = schema_version(&connection)?;
+        let latest_revision = latest_schema_revision();
+        anyhow::ensure!(
+            current_revision == latest_revision,
+            "Database schema revision {current_revision} does not match the expected revision \
+             {latest_revision}. Start the node once to migrate the database."
+        );
+
+        let blockchain_history_mode =
+            self.determine_blockchain_history_mode(&mut connection, false)?;
+        let trie_prune_mode = self.determine_trie_prune_mode(&mut connection, false)?;
+
+        let running_event_filter = event::RunningEventFilter::load(&connection.transaction()?)
+            .context("Loading running event filter")?;
+
+        Ok(StorageManager {
+            database_path: self.database_path,
+            journal_mode: self.journal_mode,
+            event_filter_cache: Arc::new(AggregateBloomCache::with_size(
+                self.event_filter_cache_size,
+            )),
+            running_event_filter: Arc::new(Mutex::new(running_event_filter)),
+            trie_prune_mode,
+            blockchain_history_mode,
+        })
+    }
+
     /// - If there is no explicitly requested configuration, assumes the user
     ///   wants to archive. If this doesn't match the database setting, errors.
     /// - If there's an explicitly requested setting: uses it if matches DB
@@ -537,6 +567,54 @@ impl Storage {
     pub fn path(&self) -> &Path {
         &self.0.database_path
     }
+
+    /// Writes a transactionally consistent copy of the database to
+    /// `destination` using SQLite's `VACUUM INTO`.
+    ///
+    /// The copy is taken from a single read transaction so writers using
+    /// other connections are not blocked (in WAL mode) and the copy reflects
+    /// the database state at the moment the read started. `destination` must
+    /// not exist.
+    pub fn vacuum_into(&self, destination: &Path) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !destination.exists(),
+            "Destination {} already exists",
+            destination.display()
+        );
+        let destination = destination
+            .to_str()
+            .context("Destination path is not valid UTF-8")?;
+
+        let conn = self.0.pool.get()?;
+        conn.execute("VACUUM INTO ?", [destination])
+            .context("Copying database")?;
+
+        Ok(())
+    }
+
+    /// Rebuilds the database file, returning free pages to the operating
+    /// system. Requires exclusive access to the database and free space for a
+    /// full copy of it.
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        let conn = self.0.pool.get()?;
+        conn.execute("VACUUM", []).context("Vacuuming database")?;
+
+        Ok(())
+    }
+}
+
+/// Returns the schema revision of the database at `path` without migrating
+/// it, or `0` if the database is empty.
+pub fn database_schema_revision(path: &Path) -> anyhow::Result<usize> {
+    let connection = rusqlite::Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .context("Opening DB")?;
+    schema_version(&connection)
+}
+
+/// The schema revision a database has after
+/// [migration](StorageBuilder::migrate) by this version of pathfinder.
+pub fn latest_schema_revision() -> usize {
+    schema::BASE_SCHEMA_REVISION + schema::migrations().len()
 }
 
 fn setup_journal_mode(
@@ -598,7 +676,7 @@ fn migrate_database(connection: &mut rusqlite::Connection) -> anyhow::Result<()>
 
     // The target version is the number of null migrations which have been replaced
     // by the base schema + the new migrations built on top of that.
-    let latest_revision = schema::BASE_SCHEMA_REVISION + migrations.len();
+    let latest_revision = latest_schema_revision();
 
     // Apply the base schema if the database is new.
     if current_revision == 0 {
@@ -816,6 +894,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn vacuum_into_creates_readable_copy() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let storage = StorageBuilder::file(db_dir.path().join("db.sqlite"))
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let headers = create_blocks(3);
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        for header in &headers {
+            tx.insert_block_header(header).unwrap();
+        }
+        tx.commit().unwrap();
+
+        let copy_path = db_dir.path().join("copy.sqlite");
+        storage.vacuum_into(&copy_path).unwrap();
+        // The destination must not be overwritten.
+        storage.vacuum_into(&copy_path).unwrap_err();
+
+        assert_eq!(
+            database_schema_revision(&copy_path).unwrap(),
+            latest_schema_revision()
+        );
+
+        let copy = StorageBuilder::file(copy_path)
+            .open_read_only()
+            .unwrap()
+            .create_read_only_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut conn = copy.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        let latest = headers.last().unwrap();
+
+        assert_eq!(
+            tx.block_id(BlockId::Latest).unwrap(),
+            Some((latest.number, latest.hash))
+        );
+    }
+
     #[test]
     fn running_event_filter_rebuilt_after_shutdown() {
         let n_blocks = 6;
@@ -1004,7 +1124,7 @@ mod tests {
         let constraints = EventConstraints {
             from_block: None,
             to_block: Some(to_block),
-            contract_address: None,
+            contract_addresses: vec![],
             keys: vec![],
             page_size: 1024,
             offset: 0,
@@ -0,0 +1,20 @@
+use anyhow::Context;
+
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Creating block_traces table");
+
+    tx.execute(
+        r"
+        CREATE TABLE block_traces (
+            block_number INTEGER PRIMARY KEY,
+            block_hash   BLOB NOT NULL,
+            version      INTEGER NOT NULL,
+            traces       BLOB NOT NULL
+        )
+        ",
+        [],
+    )
+    .context("Creating block_traces table")?;
+
+    Ok(())
+}
@@ -210,6 +210,17 @@ pub fn setup_custom_test_storage(
     transactions_per_block: usize,
 ) -> (crate::Storage, TestData) {
     let storage = crate::StorageBuilder::in_memory().unwrap();
+    let test_data = fill_test_storage(&storage, n_blocks, transactions_per_block);
+    (storage, test_data)
+}
+
+/// Inserts N blocks with a custom number of transactions per block into an
+/// existing storage instance, returning the expected emitted events.
+pub fn fill_test_storage(
+    storage: &crate::Storage,
+    n_blocks: usize,
+    transactions_per_block: usize,
+) -> TestData {
     let mut connection = storage.connection().unwrap();
     let tx = connection.transaction().unwrap();
 
@@ -247,13 +258,10 @@ pub fn setup_custom_test_storage(
         .map(|(transaction, receipts, _)| (transaction, receipts))
         .unzip();
 
-    (
-        storage,
-        TestData {
-            headers: headers.to_vec(),
-            transactions,
-            receipts,
-            events,
-        },
-    )
+    TestData {
+        headers: headers.to_vec(),
+        transactions,
+        receipts,
+        events,
+    }
 }
@@ -1,7 +1,7 @@
 use std::future::Future;
 use std::sync::LazyLock;
 
-use tokio_util::sync::CancellationToken;
+pub use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
 pub trait FutureOutputExt {
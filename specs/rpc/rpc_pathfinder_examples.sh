@@ -26,3 +26,19 @@ rpc_call '{
     },
     "id": 0
 }'
+
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_traceCall",
+    "params": {
+        "request": {
+            "contract_address": "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+            "entry_point_selector": "0x2e4263afad30923c891518314c3c95dbe830a16874e8abc5777a9a20b54c76e",
+            "calldata": [
+                "0x1"
+            ]
+        },
+        "block_id": "latest"
+    },
+    "id": 0
+}'